@@ -0,0 +1,55 @@
+//! Deep vs wide hierarchy ticking under each `TickMode`.
+//!
+//! Run with `cargo bench --bench hierarchy_tick`.
+
+use sptl_spi::agents::Agent;
+use sptl_spi::recursion::{CategoryObject, RecursionLevel, TickMode};
+use std::time::Instant;
+
+fn node(id: usize) -> CategoryObject {
+    let mut obj = CategoryObject::new(RecursionLevel::Atom, &format!("n{}", id));
+    obj.agents.push(Agent::new(format!("a{}", id), 16, 0.1));
+    obj
+}
+
+/// A single chain `depth` nodes long.
+fn deep(depth: usize) -> CategoryObject {
+    let mut root = node(0);
+    for i in 1..depth {
+        let mut parent = node(i);
+        parent.subobjects.push(Box::new(root));
+        root = parent;
+    }
+    root
+}
+
+/// A root with `width` leaf children.
+fn wide(width: usize) -> CategoryObject {
+    let mut root = node(0);
+    for i in 1..=width {
+        root.subobjects.push(Box::new(node(i)));
+    }
+    root
+}
+
+fn bench(label: &str, obj: &mut CategoryObject, mode: TickMode, iters: usize) {
+    let start = Instant::now();
+    for _ in 0..iters {
+        obj.tick(mode);
+    }
+    let per_tick = start.elapsed() / iters as u32;
+    println!("{:<6} {:<28} {:>12?}/tick", label, format!("{:?}", mode), per_tick);
+}
+
+fn main() {
+    let modes = [
+        TickMode::Recursive { grain: 0 },
+        TickMode::Recursive { grain: 64 },
+        TickMode::Recursive { grain: 1024 },
+        TickMode::Flattened,
+    ];
+    for mode in modes {
+        bench("deep", &mut deep(2_000), mode, 200);
+        bench("wide", &mut wide(20_000), mode, 200);
+    }
+}
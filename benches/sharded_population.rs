@@ -0,0 +1,34 @@
+//! Throughput of the sharded population mode across sync intervals.
+//!
+//! Run with `cargo bench --bench sharded_population`.
+
+use sptl_spi::agents::Agent;
+use sptl_spi::sharding::ShardedPopulation;
+use sptl_spi::substrate::Pattern;
+use std::time::Instant;
+
+const AGENTS: usize = 10_000;
+const TAUS: usize = 200;
+
+fn main() {
+    for shards in [1, 4, 16] {
+        for sync_interval in [1, 4, 16, 64] {
+            let agents = (0..AGENTS).map(|i| Agent::new(format!("a{}", i), 32, 0.1)).collect();
+            let mut pop = ShardedPopulation::new(agents, shards, sync_interval);
+            let start = Instant::now();
+            for tau in 0..TAUS {
+                let speaker = format!("a{}", (tau * 7919) % AGENTS);
+                pop.express(&speaker, &format!("w{}", tau % 50), Pattern::new("1011")).unwrap();
+                pop.step();
+            }
+            let elapsed = start.elapsed();
+            println!(
+                "shards={:<3} sync={:<3} {:>10.1} τ/s  ({} syncs)",
+                shards,
+                sync_interval,
+                TAUS as f64 / elapsed.as_secs_f64(),
+                pop.syncs
+            );
+        }
+    }
+}
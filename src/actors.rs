@@ -0,0 +1,199 @@
+//! Actor-style agent runtime.
+//!
+//! Each agent runs as a lightweight actor owning a mailbox. A small pool of
+//! worker threads only visits actors that have pending messages, so very large
+//! populations of mostly idle agents cost nothing between messages — unlike
+//! lockstep parallel iteration, which touches every agent every τ.
+
+use crate::agents::Agent;
+use crate::substrate::Pattern;
+use crate::symbol::Symbol;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// Messages an agent actor understands.
+#[derive(Debug, Clone)]
+pub enum AgentMessage {
+    /// Express a symbol, then deliver it for interpretation to each agent in `to`.
+    Express { token: String, pattern: Pattern, tau: usize, to: Vec<String> },
+    /// Interpret a symbol heard from another agent.
+    Interpret { symbol: Symbol, tau: usize },
+    /// Decay memory by `decay`.
+    Tick { decay: f64 },
+}
+
+/// Messages handled per actor visit before yielding the worker to other actors.
+const BATCH: usize = 64;
+
+/// Ready-queue entry telling a worker to exit.
+const STOP: usize = usize::MAX;
+
+struct Actor {
+    agent: Mutex<Agent>,
+    mailbox: Receiver<AgentMessage>,
+    /// True while the actor sits in the ready queue or is being run.
+    scheduled: AtomicBool,
+}
+
+struct Inner {
+    actors: Vec<Actor>,
+    senders: Vec<Sender<AgentMessage>>,
+    index: HashMap<String, usize>,
+    ready: Sender<usize>,
+    /// Messages sent but not yet processed.
+    in_flight: AtomicUsize,
+    /// Notified, under `idle_lock`, when `in_flight` drops to zero.
+    idle: Condvar,
+    idle_lock: Mutex<()>,
+}
+
+impl Inner {
+    fn send(&self, idx: usize, msg: AgentMessage) {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        self.senders[idx].send(msg).expect("actor mailbox closed");
+        if !self.actors[idx].scheduled.swap(true, Ordering::SeqCst) {
+            let _ = self.ready.send(idx);
+        }
+    }
+
+    fn run(&self, idx: usize) {
+        let actor = &self.actors[idx];
+        let mut processed = 0;
+        {
+            let mut agent = actor.agent.lock().unwrap();
+            while processed < BATCH {
+                let Ok(msg) = actor.mailbox.try_recv() else { break };
+                // A panicking handler loses its message, not the worker or the count.
+                if panic::catch_unwind(AssertUnwindSafe(|| self.handle(&mut agent, msg))).is_err() {
+                    eprintln!("⚠️ Agent {} panicked handling a message", agent.id);
+                }
+                processed += 1;
+            }
+        }
+        actor.scheduled.store(false, Ordering::SeqCst);
+        if !actor.mailbox.is_empty() && !actor.scheduled.swap(true, Ordering::SeqCst) {
+            let _ = self.ready.send(idx);
+        }
+        self.finish(processed);
+    }
+
+    /// Count `n` messages as processed, waking `wait_idle` when none remain.
+    fn finish(&self, n: usize) {
+        if n > 0 && self.in_flight.fetch_sub(n, Ordering::SeqCst) == n {
+            let _idle = self.idle_lock.lock().unwrap();
+            self.idle.notify_all();
+        }
+    }
+
+    fn handle(&self, agent: &mut Agent, msg: AgentMessage) {
+        match msg {
+            AgentMessage::Express { token, pattern, tau, to } => {
+                let symbol = agent.express_symbol(&token, pattern, tau);
+                for listener in to {
+                    match self.index.get(&listener) {
+                        Some(&idx) => self.send(idx, AgentMessage::Interpret { symbol: symbol.clone(), tau }),
+                        None => eprintln!("⚠️ Agent {} has no listener '{}'", agent.id, listener),
+                    }
+                }
+            }
+            AgentMessage::Interpret { symbol, tau } => {
+                agent.interpret_symbol(&symbol, tau);
+            }
+            AgentMessage::Tick { decay } => agent.decay_memory(decay),
+        }
+    }
+}
+
+/// A population of agent actors served by a fixed pool of worker threads.
+pub struct ActorRuntime {
+    inner: Arc<Inner>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl ActorRuntime {
+    /// Start actors for `agents` on `workers` threads.
+    pub fn start(agents: Vec<Agent>, workers: usize) -> Self {
+        let (ready, ready_rx) = unbounded();
+        let mut actors = Vec::with_capacity(agents.len());
+        let mut senders = Vec::with_capacity(agents.len());
+        let mut index = HashMap::new();
+        for (i, agent) in agents.into_iter().enumerate() {
+            let (tx, rx) = unbounded();
+            index.insert(agent.id.clone(), i);
+            actors.push(Actor { agent: Mutex::new(agent), mailbox: rx, scheduled: AtomicBool::new(false) });
+            senders.push(tx);
+        }
+        let inner = Arc::new(Inner {
+            actors,
+            senders,
+            index,
+            ready,
+            in_flight: AtomicUsize::new(0),
+            idle: Condvar::new(),
+            idle_lock: Mutex::new(()),
+        });
+        let workers = (0..workers.max(1))
+            .map(|_| {
+                let inner = Arc::clone(&inner);
+                let ready_rx: Receiver<usize> = ready_rx.clone();
+                thread::spawn(move || {
+                    while let Ok(idx) = ready_rx.recv() {
+                        if idx == STOP {
+                            break;
+                        }
+                        inner.run(idx);
+                    }
+                })
+            })
+            .collect();
+        ActorRuntime { inner, workers }
+    }
+
+    /// Number of actors in the runtime.
+    pub fn len(&self) -> usize {
+        self.inner.actors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.actors.is_empty()
+    }
+
+    /// Post a message to one agent's mailbox.
+    pub fn send(&self, agent: &str, msg: AgentMessage) -> Result<(), String> {
+        let idx = *self.inner.index.get(agent).ok_or_else(|| format!("unknown agent '{}'", agent))?;
+        self.inner.send(idx, msg);
+        Ok(())
+    }
+
+    /// Post a tick to every agent.
+    pub fn tick_all(&self, decay: f64) {
+        for idx in 0..self.inner.actors.len() {
+            self.inner.send(idx, AgentMessage::Tick { decay });
+        }
+    }
+
+    /// Block until every message sent so far (including replies) has been processed.
+    pub fn wait_idle(&self) {
+        let mut idle = self.inner.idle_lock.lock().unwrap();
+        while self.inner.in_flight.load(Ordering::SeqCst) > 0 {
+            idle = self.inner.idle.wait(idle).unwrap();
+        }
+    }
+
+    /// Drain all mailboxes, stop the workers, and hand the agents back.
+    pub fn shutdown(self) -> Vec<Agent> {
+        self.wait_idle();
+        for _ in &self.workers {
+            let _ = self.inner.ready.send(STOP);
+        }
+        for worker in self.workers {
+            let _ = worker.join();
+        }
+        let inner = Arc::try_unwrap(self.inner).ok().expect("workers hold no runtime references after join");
+        inner.actors.into_iter().map(|a| a.agent.into_inner().unwrap()).collect()
+    }
+}
@@ -1,14 +1,157 @@
 //! Agent (⟁) — See SPT Section IV, VII.
 //! Identity enacted through recursive sign cycles.
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::similarity::SymbolIndex;
 use crate::substrate::{Substrate, Pattern};
-use crate::symbol::{Symbol, Meaning};
-// ... other use statements unchanged
+use crate::symbol::{Classification, Meaning, Symbol};
+use serde::{Deserialize, Serialize};
 
-// ... MemoryTrace, MemoryField unchanged
+/// Represents a memory trace for a symbol, with stability and interpretants.
+/// A memory trace is not static: its stability emerges through feedback cycles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryTrace {
+    /// The symbol this trace refers to.
+    pub symbol: Symbol,
+    /// The recursion/time index when it was created.
+    pub tau_index: usize,
+    /// Stability [0,1] of the trace.
+    pub stability: f64,
+    /// All meanings/interpretations of the symbol for this trace.
+    pub interpretants: Vec<Meaning>,
+}
+
+impl MemoryTrace {
+    /// Reinforces the trace, increasing stability.
+    pub fn reinforce(&mut self, delta: f64) {
+        self.stability = (self.stability + delta).clamp(0.0, 1.0);
+    }
+    /// Decays the trace, decreasing stability.
+    pub fn decay(&mut self, rate: f64) {
+        self.stability = (self.stability - rate).max(0.0);
+    }
+}
+
+/// MemoryField stores a queue of memory traces for an agent.
+/// Memory is always dynamic, subject to decay and feedback.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct MemoryField {
+    /// The traces currently stored.
+    pub traces: VecDeque<MemoryTrace>,
+    /// Maximum number of traces to store.
+    pub max_traces: usize,
+    /// Tokens whose traces neither decay nor are evicted: innate or
+    /// hard-wired signs.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub pinned: BTreeSet<String>,
+}
 
-#[derive(Debug)]
+impl MemoryField {
+    /// Admit a new trace if stability ≥ eta, evicting the oldest unpinned
+    /// trace if at capacity. A full memory of pinned traces admits nothing.
+    pub fn admit(&mut self, trace: MemoryTrace, eta: f64) {
+        if trace.stability >= eta {
+            if self.traces.len() >= self.max_traces {
+                match self.traces.iter().position(|t| !self.is_pinned(&t.symbol.token)) {
+                    Some(oldest) => {
+                        self.traces.remove(oldest);
+                    }
+                    None if !self.traces.is_empty() => return,
+                    None => {}
+                }
+            }
+            self.traces.push_back(trace);
+        }
+    }
+    /// Exempt `token`'s traces, present and future, from decay and
+    /// eviction; false if it already was.
+    pub fn pin(&mut self, token: &str) -> bool {
+        self.pinned.insert(token.to_string())
+    }
+    /// Subject `token`'s traces to decay and eviction again; false if it was not pinned.
+    pub fn unpin(&mut self, token: &str) -> bool {
+        self.pinned.remove(token)
+    }
+    pub fn is_pinned(&self, token: &str) -> bool {
+        self.pinned.contains(token)
+    }
+    /// Reinforce stability for a matching symbol.
+    pub fn reinforce_symbol(&mut self, symbol: &Symbol, delta: f64) {
+        for t in &mut self.traces {
+            if &t.symbol == symbol {
+                t.reinforce(delta);
+            }
+        }
+    }
+    /// Decay all unpinned traces, removing those below threshold.
+    pub fn decay_all(&mut self, rate: f64) {
+        let pinned = &self.pinned;
+        for t in self.traces.iter_mut().filter(|t| !pinned.contains(&t.symbol.token)) {
+            t.decay(rate);
+        }
+        self.traces.retain(|t| t.stability > 0.0 || pinned.contains(&t.symbol.token));
+    }
+    /// Find a trace by symbol.
+    pub fn find(&self, symbol: &Symbol) -> Option<&MemoryTrace> {
+        self.traces.iter().find(|t| &t.symbol == symbol)
+    }
+}
+
+/// How an agent scores its interpretations. A sign is understood when its
+/// pattern is at least `min_similarity` similar to the known one; the trace is
+/// then reinforced by `base` scaled by similarity, plus a bonus for
+/// interpreting soon after the sign was expressed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReinforcementPolicy {
+    /// Reinforcement of an exact match.
+    pub base: f64,
+    /// Smallest pattern similarity in [0,1] still counted as understood.
+    pub min_similarity: f64,
+    /// Extra reinforcement for an interpretation at the τ of expression.
+    pub recency_bonus: f64,
+    /// τ steps after which the recency bonus has halved.
+    pub recency_half_life: f64,
+}
+
+impl Default for ReinforcementPolicy {
+    /// Exact matches only, flat +0.1: the original binary behavior.
+    fn default() -> Self {
+        ReinforcementPolicy { base: 0.1, min_similarity: 1.0, recency_bonus: 0.0, recency_half_life: 10.0 }
+    }
+}
+
+impl ReinforcementPolicy {
+    /// Reinforcement for an interpretation with pattern `similarity`, `age` τ
+    /// steps after expression; `None` if the sign is not understood.
+    pub fn score(&self, similarity: f64, age: usize) -> Option<f64> {
+        if similarity < self.min_similarity {
+            return None;
+        }
+        let recency = if self.recency_half_life > 0.0 {
+            0.5f64.powf(age as f64 / self.recency_half_life)
+        } else {
+            0.0
+        };
+        Some(self.base * similarity + self.recency_bonus * recency)
+    }
+}
+
+/// Fraction of positions where two patterns agree, over the longer length.
+pub fn pattern_similarity(a: &Pattern, b: &Pattern) -> f64 {
+    let len = a.0.chars().count().max(b.0.chars().count());
+    if len == 0 {
+        return 1.0;
+    }
+    let same = a.0.chars().zip(b.0.chars()).filter(|(x, y)| x == y).count();
+    same as f64 / len as f64
+}
+
+/// Serializes with its symbol table and memory, so an agent trained in one run
+/// can be saved and loaded into another.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Agent {
     /// Agent identifier.
     pub id: String,
@@ -18,10 +161,161 @@ pub struct Agent {
     pub memory: MemoryField,
     /// Minimum stability required for memory admission.
     pub coherence_threshold: f64,
+    /// How interpretations are scored and reinforced.
+    #[serde(default)]
+    pub reinforcement: ReinforcementPolicy,
+    /// Similarity index over `symbol_table`, kept current by `learn_symbol`.
+    #[serde(skip)]
+    index: SymbolIndex,
 }
 
 impl Agent {
-    // ... existing methods unchanged
+    /// Construct a new agent with given memory and coherence.
+    pub fn new(id: impl Into<String>, max_memory: usize, coherence_threshold: f64) -> Self {
+        Agent {
+            id: id.into(),
+            symbol_table: HashMap::new(),
+            memory: MemoryField {
+                traces: VecDeque::with_capacity(max_memory),
+                max_traces: max_memory,
+                pinned: BTreeSet::new(),
+            },
+            coherence_threshold,
+            reinforcement: ReinforcementPolicy::default(),
+            index: SymbolIndex::default(),
+        }
+    }
+
+    /// Use `policy` to score this agent's interpretations.
+    pub fn with_reinforcement(mut self, policy: ReinforcementPolicy) -> Self {
+        self.reinforcement = policy;
+        self
+    }
+
+    /// Write the agent as JSON.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    /// Read an agent written by `save`.
+    pub fn load(path: &Path) -> io::Result<Agent> {
+        let text = fs::read_to_string(path)?;
+        let mut agent: Agent = serde_json::from_str(&text).map_err(io::Error::other)?;
+        agent.reindex();
+        Ok(agent)
+    }
+
+    /// Add or replace a symbol table entry, keeping the similarity index current.
+    pub fn learn_symbol(&mut self, token: &str, pattern: Pattern) {
+        self.index.insert(token, &pattern);
+        self.symbol_table.insert(token.to_string(), pattern);
+    }
+
+    /// Rebuild the similarity index after `symbol_table` was changed directly.
+    pub fn reindex(&mut self) {
+        self.index = SymbolIndex::from_table(&self.symbol_table);
+    }
+
+    /// Up to `k` known symbols whose patterns are most similar to `pattern`,
+    /// with their similarity, most similar first.
+    pub fn nearest_symbols(&self, pattern: &Pattern, k: usize) -> Vec<(String, f64)> {
+        self.index.nearest(&self.symbol_table, pattern, k)
+    }
+
+    /// The known token a sign is understood as: its own token if known, else,
+    /// under a policy accepting inexact matches, the token whose pattern is
+    /// most similar to the sign's.
+    fn ground(&self, symbol: &Symbol) -> Option<String> {
+        if self.symbol_table.contains_key(&symbol.token) {
+            return Some(symbol.token.clone());
+        }
+        if self.reinforcement.min_similarity >= 1.0 {
+            return None;
+        }
+        self.nearest_symbols(&symbol.pattern, 1).pop().map(|(token, _)| token)
+    }
+
+    /// Express a symbol (token, pattern), adding a trace if stable.
+    /// In SPTL, expression is an act that can recursively reinforce or mutate the system.
+    pub fn express_symbol(&mut self, token: &str, pattern: Pattern, tau: usize) -> Symbol {
+        let symbol = Symbol::new(token, pattern.clone());
+        self.learn_symbol(token, pattern);
+        let trace = MemoryTrace {
+            symbol: symbol.clone(),
+            tau_index: tau,
+            stability: 1.0,
+            interpretants: Vec::new(),
+        };
+        self.memory.admit(trace, self.coherence_threshold);
+        symbol
+    }
+
+    /// Project a symbol into the substrate.
+    pub fn project_symbol(&self, symbol: &Symbol, substrate: &mut Substrate) {
+        substrate.project(symbol);
+    }
+
+    /// Score an interpretation of `symbol` at `tau` under this agent's policy
+    /// without changing memory; `None` if the sign is not understood.
+    pub fn score_interpretation(&self, symbol: &Symbol, tau: usize) -> Option<f64> {
+        let token = self.ground(symbol)?;
+        self.score_as(&token, symbol, tau)
+    }
+
+    fn score_as(&self, token: &str, symbol: &Symbol, tau: usize) -> Option<f64> {
+        let known = self.symbol_table.get(token)?;
+        let age = self
+            .memory
+            .traces
+            .iter()
+            .find(|t| t.symbol.token == token)
+            .map_or(0, |t| tau.saturating_sub(t.tau_index));
+        self.reinforcement.score(pattern_similarity(known, &symbol.pattern), age)
+    }
+
+    /// Attempt to interpret a symbol, reinforcing the memory of its token by
+    /// the policy's score if it is understood. A sign with an unknown token is
+    /// understood as the most similar known symbol when the policy accepts
+    /// inexact matches. Inexact matches are classified as approximate; a sign
+    /// that is not understood is recorded as unrecognized by the traces
+    /// sharing its pattern.
+    pub fn interpret_symbol(&mut self, symbol: &Symbol, tau: usize) -> Option<Meaning> {
+        let understood = self.ground(symbol).and_then(|token| Some((self.score_as(&token, symbol, tau)?, token)));
+        let Some((delta, token)) = understood else {
+            self.record_unrecognized(symbol, tau);
+            return None;
+        };
+        let known = Symbol::new(&token, self.symbol_table[&token].clone());
+        let classification =
+            if known == *symbol { Classification::Recognized } else { Classification::Approximate };
+        let meaning = Meaning::new(&known, tau, classification);
+        if let Some(trace) = self.memory.traces.iter_mut().find(|t| t.symbol == known) {
+            trace.reinforce(delta);
+            trace.interpretants.push(meaning.clone());
+        }
+        Some(meaning)
+    }
+
+    /// A sign the agent cannot interpret still registers: the traces of
+    /// symbols with its pattern record it as unrecognized, so a drifted sign
+    /// breaks the symmetry of their interpretant history.
+    fn record_unrecognized(&mut self, symbol: &Symbol, tau: usize) {
+        let meaning = Meaning::new(symbol, tau, Classification::Unrecognized);
+        for trace in self.memory.traces.iter_mut().filter(|t| t.symbol.pattern == symbol.pattern) {
+            trace.interpretants.push(meaning.clone());
+        }
+    }
+
+    /// Return a mutated version of the symbol.
+    pub fn mutate_symbol(&self, symbol: &Symbol) -> Symbol {
+        symbol.mutate()
+    }
+
+    /// Decay all memory traces.
+    pub fn decay_memory(&mut self, rate: f64) {
+        self.memory.decay_all(rate);
+    }
 
     /// Returns true if all memory traces have stabilized their interpretants (symmetry/attractor).
     /// See SPT Section VII.
@@ -38,4 +332,4 @@ impl Agent {
 
 // Make Agent Send + Sync for Rayon/threads
 unsafe impl Send for Agent {}
-unsafe impl Sync for Agent {}
\ No newline at end of file
+unsafe impl Sync for Agent {}
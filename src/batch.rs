@@ -0,0 +1,269 @@
+//! Batch evaluation: many configured runs in one process.
+//!
+//! For pipelines that run hundreds of small scripts, starting a process per
+//! run costs more than the runs themselves. `run_all` executes every
+//! `RunConfig` on the rayon thread pool instead. Each run gets its own
+//! `Runtime` (fields, variables, hierarchies and RNG) and its own
+//! `RunReport`, so runs never see each other's state.
+//!
+//! Runs of the same script that differ only in numeric parameters used as
+//! numbers (`alpha: $alpha`, `if d > $limit`) share one compiled program:
+//! the script is parsed, checked and compiled once with each `$name` left as
+//! a variable, and every run binds its own values (`vm::Vm::run_bound`).
+//! Scripts that use a parameter elsewhere, such as a step count or a field
+//! name, are parsed once per run.
+//!
+//! `differential` runs one configuration on both engines under the same seed
+//! and lists where their reports differ, so tests can hold the bytecode
+//! engine to the AST walker's semantics.
+
+use crate::capability;
+use crate::cli::Engine;
+use crate::config::Config;
+use crate::report::RunReport;
+use crate::rng::NoiseRng;
+use crate::runtime::Runtime;
+use crate::sptl::{self, optimize, vm, Statement};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// One run: a script, its parameters and how to execute it.
+#[derive(Debug, Clone, Default)]
+pub struct RunConfig {
+    pub source: String,
+    /// File the source was read from; `include`s resolve relative to it.
+    /// Without one they resolve relative to the working directory.
+    pub script: Option<PathBuf>,
+    /// Values bound to `$name` in the source.
+    pub params: BTreeMap<String, String>,
+    /// Seeds the run's noise RNG, as `seed <n>` at the top of the script would.
+    pub seed: Option<u64>,
+    /// Count noise RNG draws per call site into `RunReport::rng_draws`.
+    pub audit_rng: bool,
+    pub engine: Engine,
+    pub config: Config,
+}
+
+impl RunConfig {
+    pub fn new(source: impl Into<String>) -> Self {
+        RunConfig { source: source.into(), ..Default::default() }
+    }
+
+    /// The script at `path`, configured by the `sptl.toml` next to it.
+    pub fn from_file(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let source = std::fs::read_to_string(&path)?;
+        let config = Config::for_script(&path);
+        Ok(RunConfig { source, script: Some(path), config, ..Default::default() })
+    }
+
+    pub fn param(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.params.insert(name.into(), value.to_string());
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn audit_rng(mut self) -> Self {
+        self.audit_rng = true;
+        self
+    }
+
+    pub fn engine(mut self, engine: Engine) -> Self {
+        self.engine = engine;
+        self
+    }
+}
+
+/// Execute every configured run across the thread pool. Reports come back in
+/// the order of `configs`; a run whose script does not parse or fails the
+/// static check reports its errors in `RunReport::errors` and executes nothing.
+pub fn run_all(configs: Vec<RunConfig>) -> Vec<RunReport> {
+    let mut compiled = Vec::new();
+    let mut shared = vec![None; configs.len()];
+    for i in 0..configs.len() {
+        if shared[i].is_some() {
+            continue;
+        }
+        let group: Vec<usize> = (i..configs.len()).filter(|&j| same_program(&configs[i], &configs[j])).collect();
+        if group.len() < 2 {
+            continue;
+        }
+        if let Some(code) = compile_shared(&configs[i]) {
+            for j in group {
+                shared[j] = Some(compiled.len());
+            }
+            compiled.push(code);
+        }
+    }
+    configs
+        .into_par_iter()
+        .zip(shared)
+        .map(|(config, shared)| match shared {
+            Some(k) => run_compiled(&compiled[k], config),
+            None => run(config),
+        })
+        .collect()
+}
+
+/// Execute one configured run on the calling thread.
+pub fn run(config: RunConfig) -> RunReport {
+    let mut report = new_report(&config);
+    let script = config.script.as_deref().unwrap_or(Path::new(""));
+    let program = sptl::parse_source_with(&config.source, &config.params, &config.config)
+        .and_then(|program| sptl::resolve_includes(program, script, &config.params, &config.config));
+    let optimized = match program {
+        Ok(program) => optimize::optimize(program),
+        Err(errors) => {
+            report.errors = errors.iter().map(|e| e.to_string()).collect();
+            return report;
+        }
+    };
+    if optimized.has_errors() {
+        let errors = optimized.diagnostics.iter().filter(|d| d.severity == optimize::Severity::Error);
+        report.errors = errors.map(|d| d.to_string()).collect();
+        if !optimized.missing.is_empty() {
+            report.errors.push(capability::rebuild_hint(&optimized.missing));
+        }
+        return report;
+    }
+    let mut rt = runtime(&config);
+    match config.engine {
+        Engine::Ast => sptl::execute_program_in(optimized.program, &mut rt, &mut report),
+        Engine::Vm => vm::Vm::new(&vm::compile_in(optimized.program, &rt)).run_in(&mut rt, &mut report),
+    }
+    report
+}
+
+/// Seed of differential runs whose configuration sets none.
+pub const DIFFERENTIAL_SEED: u64 = 0;
+
+/// Run `config` on the AST walker and on the bytecode engine under the same
+/// seed, auditing RNG draws, and describe every difference between the two
+/// reports: traces, meanings, assertions, errors, final fields, RNG draws
+/// per site and the sequence of telemetry values. Empty when they agree.
+/// Journals are not compared; each engine words its own.
+pub fn differential(config: RunConfig) -> Vec<String> {
+    let seed = config.seed.unwrap_or(DIFFERENTIAL_SEED);
+    let config = config.seed(seed).audit_rng();
+    let ast = run(config.clone().engine(Engine::Ast));
+    let vm = run(config.engine(Engine::Vm));
+
+    let mut differences = Vec::new();
+    let mut differ = |what: String, a: String, b: String| {
+        if a != b {
+            differences.push(format!("{}: ast {}, vm {}", what, a, b));
+        }
+    };
+    for name in ast.traces.keys().chain(vm.traces.keys()).collect::<BTreeSet<_>>() {
+        differ(format!("trace {}", name), format!("{:?}", ast.traces.get(name)), format!("{:?}", vm.traces.get(name)));
+    }
+    for name in ast.meanings.keys().chain(vm.meanings.keys()).collect::<BTreeSet<_>>() {
+        let (a, b) = (ast.meanings.get(name), vm.meanings.get(name));
+        differ(format!("meaning {}", name), format!("{:?}", a), format!("{:?}", b));
+    }
+    for name in ast.fields.keys().chain(vm.fields.keys()).collect::<BTreeSet<_>>() {
+        differ(format!("field {}", name), format!("{:?}", ast.fields.get(name)), format!("{:?}", vm.fields.get(name)));
+    }
+    differ("assertions".to_string(), format!("{:?}", ast.assertions), format!("{:?}", vm.assertions));
+    differ("errors".to_string(), format!("{:?}", ast.errors), format!("{:?}", vm.errors));
+    differ("rng draws".to_string(), format!("{:?}", ast.rng_draws), format!("{:?}", vm.rng_draws));
+    let values = |report: &RunReport| -> Vec<String> {
+        report.telemetry.iter().map(|row| format!("{}={:?}", row.name, row.value)).collect()
+    };
+    differ("telemetry".to_string(), values(&ast).join(", "), values(&vm).join(", "));
+    differences
+}
+
+/// Execute a parsed program in a fresh runtime with the selected engine,
+/// using the levels `config` defines; `audit_rng` counts its RNG draws.
+pub fn execute(program: Vec<Statement>, report: &mut RunReport, engine: Engine, config: &Config, audit_rng: bool) {
+    let mut rt = Runtime { ontology: config.levels.clone(), ..Default::default() };
+    if audit_rng {
+        rt.rng.audit();
+    }
+    match engine {
+        Engine::Ast => sptl::execute_program_in(program, &mut rt, report),
+        Engine::Vm => vm::Vm::new(&vm::compile_in(program, &rt)).run_in(&mut rt, report),
+    }
+}
+
+fn new_report(config: &RunConfig) -> RunReport {
+    RunReport { script: config.script.as_ref().map(|p| p.display().to_string()), ..Default::default() }
+}
+
+fn runtime(config: &RunConfig) -> Runtime {
+    let mut rt = Runtime { ontology: config.config.levels.clone(), ..Default::default() };
+    if let Some(seed) = config.seed {
+        rt.rng = NoiseRng::seed_from_u64(seed);
+    }
+    if config.audit_rng {
+        rt.rng.audit();
+    }
+    rt
+}
+
+/// Whether `a` and `b` can share one compiled program: the same script on
+/// the bytecode engine, with the same parameter names, all bound to numbers.
+fn same_program(a: &RunConfig, b: &RunConfig) -> bool {
+    a.engine == Engine::Vm
+        && b.engine == Engine::Vm
+        && a.source == b.source
+        && a.script == b.script
+        && a.config == b.config
+        && a.params.keys().eq(b.params.keys())
+        && b.params.values().all(|v| v.trim().parse::<f64>().is_ok())
+}
+
+/// `config`'s script compiled with its parameters as variables, if it parses
+/// and checks that way and uses every parameter only as a number.
+fn compile_shared(config: &RunConfig) -> Option<vm::Bytecode> {
+    let unbound = BTreeMap::new();
+    let script = config.script.as_deref().unwrap_or(Path::new(""));
+    let program = sptl::parse_source_with(&config.source, &unbound, &config.config)
+        .and_then(|program| sptl::resolve_includes(program, script, &unbound, &config.config))
+        .ok()?;
+    let vars: Vec<String> = config.params.keys().map(|name| format!("${}", name)).collect();
+    // A parameter read as a number appears once per use as an expression
+    // variable; any other mention of it (a field name, a message) also shows
+    // up in the serialized program, and the program would mean something else.
+    let json = serde_json::to_string(&program).ok()?;
+    for var in &vars {
+        let uses = program.iter().map(|s| variable_uses(s, var)).sum::<usize>();
+        if json.matches(&format!("\"{}\"", var)).count() != uses {
+            return None;
+        }
+    }
+    let optimized = optimize::optimize_bound(program, &vars);
+    if optimized.has_errors() {
+        return None;
+    }
+    Some(vm::compile_bound(optimized.program, &runtime(config), &vars))
+}
+
+/// Times `var` is read in the numeric options of `statement` and its blocks.
+fn variable_uses(statement: &Statement, var: &str) -> usize {
+    let here = statement.numbers().into_iter().flat_map(|e| e.fields()).filter(|f| *f == var).count();
+    let nested: Vec<&Statement> = match statement {
+        Statement::Repeat { body, .. } | Statement::Level { body, .. } => body.iter().collect(),
+        Statement::If { then, otherwise, .. } => then.iter().chain(otherwise).collect(),
+        _ => Vec::new(),
+    };
+    here + nested.into_iter().map(|s| variable_uses(s, var)).sum::<usize>()
+}
+
+fn run_compiled(code: &vm::Bytecode, config: RunConfig) -> RunReport {
+    let mut report = new_report(&config);
+    let bindings = config
+        .params
+        .iter()
+        .filter_map(|(name, value)| Some((format!("${}", name), value.trim().parse().ok()?)))
+        .collect();
+    vm::Vm::new(code).run_bound(&mut runtime(&config), &mut report, &bindings);
+    report
+}
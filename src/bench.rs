@@ -0,0 +1,132 @@
+//! `:bench` in the REPL: time one operation on the session's runtime.
+//!
+//! `:bench project F I steps=10000` projects a copy of field `F` toward
+//! interpretation `I`; `:bench tick population=1000 n=100` ticks a
+//! population of agents, the session's own unless `population` asks for a
+//! fresh one of that size. Both work on copies, so the session is left as it
+//! was, and report steps per second to estimate what a long run will cost.
+
+use crate::agents::Agent;
+use crate::projection::project;
+use crate::runtime::Runtime;
+use crate::substrate::Pattern;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_PROJECT_STEPS: usize = 10_000;
+pub const DEFAULT_TICKS: usize = 100;
+
+/// Memory of agents in a fresh population, and the traces each starts with.
+const FRESH_MEMORY: usize = 64;
+const FRESH_TRACES: usize = 8;
+
+pub const USAGE: &str = "Usage: :bench project <field> <interp> [steps=N] [alpha=A] [noise=X] or :bench tick [population=N] [n=N]";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bench {
+    Project { field: String, interp: String, steps: usize, alpha: f64, noise: f64 },
+    /// `population` of `None` ticks the runtime's own agents.
+    Tick { population: Option<usize>, n: usize },
+}
+
+/// How long a benchmark took.
+#[derive(Debug, Clone)]
+pub struct Measurement {
+    pub label: String,
+    pub steps: usize,
+    pub elapsed: Duration,
+}
+
+impl Measurement {
+    pub fn per_second(&self) -> f64 {
+        self.steps as f64 / self.elapsed.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+}
+
+impl fmt::Display for Measurement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "⏱ {}: {} steps in {:.3} ms ({:.0} steps/s)",
+            self.label,
+            self.steps,
+            self.elapsed.as_secs_f64() * 1000.0,
+            self.per_second()
+        )
+    }
+}
+
+impl Bench {
+    /// Parse the words after `:bench`; options left out take `alpha` and `noise` as given.
+    pub fn parse(words: &[&str], alpha: f64, noise: f64) -> Result<Bench, String> {
+        let (positional, options): (Vec<&str>, Vec<&str>) = words.iter().copied().partition(|w| !w.contains('='));
+        let mut options: BTreeMap<&str, &str> = options.iter().filter_map(|o| o.split_once('=')).collect();
+        let bench = match positional.as_slice() {
+            ["project", field, interp] => Bench::Project {
+                field: field.to_string(),
+                interp: interp.to_string(),
+                steps: take(&mut options, "steps")?.unwrap_or(DEFAULT_PROJECT_STEPS),
+                alpha: take(&mut options, "alpha")?.unwrap_or(alpha),
+                noise: take(&mut options, "noise")?.unwrap_or(noise),
+            },
+            ["tick"] => Bench::Tick {
+                population: take(&mut options, "population")?,
+                n: take(&mut options, "n")?.unwrap_or(DEFAULT_TICKS),
+            },
+            _ => return Err(USAGE.to_string()),
+        };
+        match options.keys().next() {
+            Some(unknown) => Err(format!("bench: unknown option {}", unknown)),
+            None => Ok(bench),
+        }
+    }
+
+    /// Time the operation on copies of what it needs from `rt`.
+    pub fn run(&self, rt: &Runtime) -> Result<Measurement, String> {
+        match self {
+            Bench::Project { field, interp, steps, alpha, noise } => {
+                let mut state = rt.fields.get(field).map(|f| (**f).clone()).ok_or_else(|| format!("No field named {}.", field))?;
+                let target = rt.interps.get(interp).ok_or_else(|| format!("No interpretation named {}.", interp))?;
+                let mut rng = rt.rng.clone();
+                let start = Instant::now();
+                for _ in 0..*steps {
+                    project(&mut state, target, *alpha, *noise, rng.at("bench"));
+                }
+                let label = format!("project {} <- {}", field, interp);
+                Ok(Measurement { label, steps: *steps, elapsed: start.elapsed() })
+            }
+            Bench::Tick { population, n } => {
+                let mut agents: Vec<Agent> = match population {
+                    Some(size) => (0..*size).map(fresh_agent).collect(),
+                    None => rt.agents.values().filter_map(|state| state.agent.clone()).collect(),
+                };
+                if agents.is_empty() {
+                    return Err("No agents to tick; give population=N for a fresh population.".to_string());
+                }
+                let start = Instant::now();
+                for _ in 0..*n {
+                    agents.par_iter_mut().for_each(Agent::tick_parallel);
+                }
+                let label = format!("tick {} agents", agents.len());
+                Ok(Measurement { label, steps: *n, elapsed: start.elapsed() })
+            }
+        }
+    }
+}
+
+fn take<T: std::str::FromStr>(options: &mut BTreeMap<&str, &str>, key: &str) -> Result<Option<T>, String> {
+    match options.remove(key) {
+        Some(v) => v.parse().map(Some).map_err(|_| format!("bench: invalid {} '{}'", key, v)),
+        None => Ok(None),
+    }
+}
+
+fn fresh_agent(i: usize) -> Agent {
+    let mut agent = Agent::new(format!("bench{}", i), FRESH_MEMORY, 0.1);
+    for t in 0..FRESH_TRACES {
+        agent.express_symbol(&format!("w{}", t), Pattern::new("1011"), 0);
+    }
+    agent
+}
@@ -0,0 +1,69 @@
+//! Optional subsystems and whether this build includes them.
+//!
+//! Plot rendering, GPU projection, SQLite export and WebSocket streaming
+//! each sit behind a cargo feature. A script that needs one names it, with
+//! `require gpu` or by exporting to a destination only that subsystem can
+//! write (`"d.png"`, `"runs.sqlite"`, `"ws://..."`). The checker consults
+//! this registry, so a build without the feature rejects the script at
+//! `--check` time, listing the features to enable, instead of failing
+//! partway through a run.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Capability {
+    Plots,
+    Gpu,
+    Sqlite,
+    WebSocket,
+}
+
+/// Capabilities compiled into this build. A subsystem adds itself here,
+/// under `#[cfg(feature = "...")]`, when it lands.
+const BUILT: &[Capability] = &[];
+
+impl Capability {
+    pub const ALL: [Capability; 4] = [Capability::Plots, Capability::Gpu, Capability::Sqlite, Capability::WebSocket];
+
+    /// The capability a script calls `name`, as in `require gpu`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Capability::ALL.into_iter().find(|c| c.feature() == name.to_lowercase())
+    }
+
+    /// Cargo feature that builds it in; also its name in scripts.
+    pub fn feature(self) -> &'static str {
+        match self {
+            Capability::Plots => "plots",
+            Capability::Gpu => "gpu",
+            Capability::Sqlite => "sqlite",
+            Capability::WebSocket => "websocket",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Capability::Plots => "plot rendering",
+            Capability::Gpu => "GPU projection",
+            Capability::Sqlite => "SQLite export",
+            Capability::WebSocket => "WebSocket streaming",
+        }
+    }
+
+    /// Whether this build includes it.
+    pub fn available(self) -> bool {
+        BUILT.contains(&self)
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.feature())
+    }
+}
+
+/// How to get a build with every one of `missing`.
+pub fn rebuild_hint(missing: &[Capability]) -> String {
+    let features: Vec<&str> = missing.iter().map(|c| c.feature()).collect();
+    format!("rebuild with `cargo build --features {}`", features.join(","))
+}
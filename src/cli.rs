@@ -0,0 +1,249 @@
+//! Command-line options for the interpreter binary.
+
+use crate::console::Throttle;
+use crate::rundir::RunDir;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Prefix of environment variables bound into script variables (`SPTL_VAR_alpha=0.3`).
+pub const ENV_VAR_PREFIX: &str = "SPTL_VAR_";
+
+/// Subcommand selected by the first positional argument.
+#[derive(Debug, Default, PartialEq)]
+pub enum Command {
+    /// No subcommand: behave according to the flags alone.
+    #[default]
+    Default,
+    /// `sptl run <script|package>`
+    Run(String),
+    /// `sptl pack <dir> -o <file>`
+    Pack { dir: String },
+    /// `sptl sensitivity <script> --param <name> --range start:end:count`
+    Sensitivity(String),
+    /// `sptl new experiment [dir] --template <name>`
+    NewExperiment { dir: Option<String> },
+    /// `sptl ingest <corpus.txt> [--agents N] [--tokens-per-tick N]`
+    Ingest(String),
+    /// `sptl fmt <script> [--check]`
+    Fmt(String),
+    /// `sptl repl`
+    Repl,
+    /// `sptl serve [address]`
+    Serve(Option<String>),
+}
+
+/// How SPTL programs are executed (`--engine ast|vm`).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Engine {
+    /// Walk the parsed AST directly.
+    Ast,
+    /// Compile to bytecode first; faster when a script runs many times.
+    #[default]
+    Vm,
+}
+
+#[derive(Debug, Default)]
+pub struct CliOptions {
+    pub command: Command,
+    /// SPTL script to execute (`--script <path>`).
+    pub script: Option<String>,
+    /// Narrative DSL script to execute (`--narrative <path>`).
+    pub narrative: Option<String>,
+    /// Directory collecting every artifact of the run (`--run-dir [path]`).
+    pub run_dir: Option<PathBuf>,
+    /// Swept parameters (`--sweep name=v1,v2,...`, repeatable).
+    pub sweep: Vec<(String, Vec<String>)>,
+    /// Script variable overrides (`--set name=value`, repeatable).
+    pub vars: BTreeMap<String, String>,
+    /// Output path for `pack` (`-o <path>`).
+    pub output: Option<PathBuf>,
+    /// Entry script recorded by `pack` (`--entry <file>`).
+    pub entry: Option<String>,
+    /// Seed for projection and perturbation noise, recorded with the run (`--seed <n>`).
+    pub seed: Option<u64>,
+    /// Parse the script and report problems without executing it (`--check`);
+    /// with `fmt`, report whether the script is already formatted.
+    pub check: bool,
+    /// Directory for cached parsed ASTs (`--cache-dir <dir>`, or `SPTL_CACHE_DIR`).
+    pub cache_dir: Option<PathBuf>,
+    /// Execute the script this many times and summarize (`--repeat N`).
+    pub repeat: usize,
+    /// Use seed, seed+1, ... for successive repetitions (`--vary-seed`).
+    pub vary_seed: bool,
+    /// Count noise RNG draws per call site and report them (`--audit-rng`).
+    pub audit_rng: bool,
+    /// Parameter analyzed by `sensitivity` (`--param <name>`).
+    pub param: Option<String>,
+    /// Values analyzed by `sensitivity` (`--range start:end:count`).
+    pub range: Option<String>,
+    /// Template used by `new experiment` (`--template <name>`).
+    pub template: Option<String>,
+    /// Print the parsed program as JSON instead of running it (`--emit-json`).
+    pub emit_json: bool,
+    pub engine: Engine,
+    /// Append every interpreter event to this file as JSON lines (`--events <path>`).
+    pub events: Option<PathBuf>,
+    /// Verify the say → project → interpret loop of a narrative run (`--check-protocol`).
+    pub check_protocol: bool,
+    /// Readers replaying a corpus with `ingest` (`--agents N`).
+    pub agents: Option<usize>,
+    /// Corpus words replayed per τ by `ingest` (`--tokens-per-tick N`).
+    pub tokens_per_tick: Option<usize>,
+    /// Console throttling (`--throttle N`, `--max-lines-per-tau M`).
+    pub throttle: Throttle,
+    /// Every console line, throttled or not, is written here (`--console-log <path>`).
+    pub console_log: Option<PathBuf>,
+}
+
+impl CliOptions {
+    /// Initial script variables: `SPTL_VAR_*` environment variables, overridden by `--set`.
+    pub fn bindings(&self) -> BTreeMap<String, String> {
+        let mut vars = env_bindings(std::env::vars());
+        vars.extend(self.vars.clone());
+        vars
+    }
+}
+
+/// Collect `SPTL_VAR_name=value` pairs as `name -> value`.
+pub fn env_bindings(env: impl IntoIterator<Item = (String, String)>) -> BTreeMap<String, String> {
+    env.into_iter()
+        .filter_map(|(k, v)| k.strip_prefix(ENV_VAR_PREFIX).map(|name| (name.to_string(), v)))
+        .filter(|(name, _)| !name.is_empty())
+        .collect()
+}
+
+/// Parse arguments (without the program name).
+/// `--run-dir` without a value uses `out/run-<timestamp>`.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<CliOptions, String> {
+    let mut opts = CliOptions::default();
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "run" if opts.command == Command::Default => {
+                opts.command = Command::Run(args.next().ok_or("run requires a script or package")?);
+            }
+            "pack" if opts.command == Command::Default => {
+                opts.command = Command::Pack { dir: args.next().ok_or("pack requires a directory")? };
+            }
+            "ingest" if opts.command == Command::Default => {
+                opts.command = Command::Ingest(args.next().ok_or("ingest requires a text file")?);
+            }
+            "fmt" if opts.command == Command::Default => {
+                opts.command = Command::Fmt(args.next().ok_or("fmt requires a script")?);
+            }
+            "repl" if opts.command == Command::Default => opts.command = Command::Repl,
+            "serve" if opts.command == Command::Default => {
+                let address = match args.peek() {
+                    Some(v) if !v.starts_with("--") => args.next(),
+                    _ => None,
+                };
+                opts.command = Command::Serve(address);
+            }
+            "--agents" => {
+                let v = args.next().ok_or("--agents requires a count")?;
+                opts.agents = Some(v.parse().map_err(|_| format!("invalid agent count '{}'", v))?);
+            }
+            "--tokens-per-tick" => {
+                let v = args.next().ok_or("--tokens-per-tick requires a count")?;
+                opts.tokens_per_tick = Some(v.parse().map_err(|_| format!("invalid token count '{}'", v))?);
+            }
+            "--throttle" => {
+                let v = args.next().ok_or("--throttle requires a count")?;
+                match v.parse() {
+                    Ok(n) if n > 0 => opts.throttle.repeat_every = Some(n),
+                    _ => return Err(format!("invalid throttle count '{}'", v)),
+                }
+            }
+            "--max-lines-per-tau" => {
+                let v = args.next().ok_or("--max-lines-per-tau requires a count")?;
+                opts.throttle.lines_per_tau = Some(v.parse().map_err(|_| format!("invalid line count '{}'", v))?);
+            }
+            "--console-log" => {
+                opts.console_log = Some(PathBuf::from(args.next().ok_or("--console-log requires a path")?));
+            }
+            "sensitivity" if opts.command == Command::Default => {
+                opts.command = Command::Sensitivity(args.next().ok_or("sensitivity requires a script")?);
+            }
+            "new" if opts.command == Command::Default => {
+                match args.next().as_deref() {
+                    Some("experiment") => {}
+                    _ => return Err("usage: new experiment [dir] --template <name>".to_string()),
+                }
+                let dir = match args.peek() {
+                    Some(v) if !v.starts_with("--") => args.next(),
+                    _ => None,
+                };
+                opts.command = Command::NewExperiment { dir };
+            }
+            "--template" => {
+                opts.template = Some(args.next().ok_or("--template requires a name")?);
+            }
+            "--param" => {
+                opts.param = Some(args.next().ok_or("--param requires a name")?);
+            }
+            "--range" => {
+                opts.range = Some(args.next().ok_or("--range requires start:end:count")?);
+            }
+            "-o" | "--output" => {
+                opts.output = Some(PathBuf::from(args.next().ok_or("-o requires a path")?));
+            }
+            "--entry" => {
+                opts.entry = Some(args.next().ok_or("--entry requires a file name")?);
+            }
+            "--seed" => {
+                let v = args.next().ok_or("--seed requires a number")?;
+                opts.seed = Some(v.parse().map_err(|_| format!("invalid seed '{}'", v))?);
+            }
+            "--check" => opts.check = true,
+            "--vary-seed" => opts.vary_seed = true,
+            "--audit-rng" => opts.audit_rng = true,
+            "--emit-json" => opts.emit_json = true,
+            "--check-protocol" => opts.check_protocol = true,
+            "--engine" => {
+                opts.engine = match args.next().as_deref() {
+                    Some("ast") => Engine::Ast,
+                    Some("vm") => Engine::Vm,
+                    _ => return Err("--engine requires ast or vm".to_string()),
+                };
+            }
+            "--repeat" => {
+                let v = args.next().ok_or("--repeat requires a count")?;
+                opts.repeat = v.parse().map_err(|_| format!("invalid repeat count '{}'", v))?;
+            }
+            "--cache-dir" => {
+                opts.cache_dir = Some(PathBuf::from(args.next().ok_or("--cache-dir requires a path")?));
+            }
+            "--events" => {
+                opts.events = Some(PathBuf::from(args.next().ok_or("--events requires a path")?));
+            }
+            "--script" => {
+                opts.script = Some(args.next().ok_or("--script requires a path")?);
+            }
+            "--narrative" => {
+                opts.narrative = Some(args.next().ok_or("--narrative requires a path")?);
+            }
+            "--set" => {
+                let spec = args.next().ok_or("--set requires name=value")?;
+                let (name, value) = spec.split_once('=')
+                    .ok_or_else(|| format!("invalid binding '{}', expected name=value", spec))?;
+                opts.vars.insert(name.trim().to_string(), value.to_string());
+            }
+            "--run-dir" => {
+                let dir = match args.peek() {
+                    Some(v) if !v.starts_with("--") => PathBuf::from(args.next().unwrap()),
+                    _ => RunDir::default_path(),
+                };
+                opts.run_dir = Some(dir);
+            }
+            "--sweep" => {
+                let spec = args.next().ok_or("--sweep requires name=v1,v2,...")?;
+                let (name, values) = spec.split_once('=')
+                    .ok_or_else(|| format!("invalid sweep '{}', expected name=v1,v2,...", spec))?;
+                let values = values.split(',').map(|v| v.trim().to_string()).collect();
+                opts.sweep.push((name.trim().to_string(), values));
+            }
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+    Ok(opts)
+}
@@ -0,0 +1,382 @@
+//! Conditions shared by SPTL `if` and the narrative `if`, `while` and `assert`.
+//!
+//! Both languages parse condition text with `Condition::parse` and evaluate
+//! it against a `Scope`: their view of the running state, giving numbers by
+//! name (variables, traces, meanings, measurements) and agents' memories.
+//! A condition naming something the scope does not have fails with
+//! `Unknown` rather than quietly being false.
+
+use crate::sptl::expr::{self, FieldExpr};
+use crate::sptl::format::{condition_source, expr_source};
+use crate::symmetry::Consensus;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// `F` names a number: a `String` as parsed, or a slot once compiled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(serialize = "FieldExpr<F>: Serialize", deserialize = "FieldExpr<F>: Deserialize<'de>"))]
+pub enum Condition<F = String> {
+    /// `always`
+    Always,
+    /// `d < 0.1`, `a * 2 >= b` or `tau == 10`
+    Compare { left: FieldExpr<F>, cmp: Relation, right: FieldExpr<F> },
+    /// `alice knows fire`, also written `alice memory contains fire`
+    Knows { agent: String, token: String },
+    /// `consensus on "fire" among group learners quorum 0.8`: at least
+    /// `quorum` of the group stably interprets the token with one shared pattern.
+    Consensus { token: String, group: String, quorum: f64 },
+    /// `alice activation fire > 0.5`: how strongly the agent holds the token
+    /// (see `AgentState::activation_of`), compared with a number.
+    Activation { agent: String, token: String, cmp: Relation, right: FieldExpr<F> },
+    /// `a and b`: both hold. Evaluation stops at the first that does not.
+    And(Box<Condition<F>>, Box<Condition<F>>),
+    /// `a or b`: either holds. Evaluation stops at the first that does.
+    Or(Box<Condition<F>>, Box<Condition<F>>),
+    /// `not a`
+    Not(Box<Condition<F>>),
+}
+
+/// How a comparison relates its two sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Relation {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Relation {
+    /// Operators in the order they are looked for, two-character ones first.
+    const OPERATORS: [(&'static str, Relation); 6] = [
+        ("<=", Relation::LessOrEqual),
+        (">=", Relation::GreaterOrEqual),
+        ("==", Relation::Equal),
+        ("!=", Relation::NotEqual),
+        ("<", Relation::Less),
+        (">", Relation::Greater),
+    ];
+
+    pub fn symbol(self) -> &'static str {
+        Relation::OPERATORS.iter().find(|(_, r)| *r == self).map(|(s, _)| *s).unwrap_or("?")
+    }
+
+    pub fn holds(self, left: f64, right: f64) -> bool {
+        match self {
+            Relation::Less => left < right,
+            Relation::LessOrEqual => left <= right,
+            Relation::Greater => left > right,
+            Relation::GreaterOrEqual => left >= right,
+            Relation::Equal => left == right,
+            Relation::NotEqual => left != right,
+        }
+    }
+
+    /// The first operator in `text`: where it starts, its length and the relation.
+    fn find(text: &str) -> Option<(usize, usize, Relation)> {
+        let at = text.find(['<', '>', '=', '!'])?;
+        Relation::OPERATORS
+            .iter()
+            .find(|(symbol, _)| text[at..].starts_with(symbol))
+            .map(|(symbol, relation)| (at, symbol.len(), *relation))
+    }
+}
+
+/// The running state a condition is evaluated against.
+pub trait Scope<F = String> {
+    /// The number `name` stands for, if it has one.
+    fn value(&self, name: &F) -> Option<f64>;
+
+    /// Whether `agent` remembers `token`; `None` if there is no such agent.
+    fn knows(&self, _agent: &str, _token: &str) -> Option<bool> {
+        None
+    }
+
+    /// How far `group` agrees on `token`; `None` if there is no such group.
+    fn consensus(&self, _group: &str, _token: &str) -> Option<Consensus> {
+        None
+    }
+
+    /// How strongly `agent` holds `token`; `None` if there is no such agent.
+    fn activation(&self, _agent: &str, _token: &str) -> Option<f64> {
+        None
+    }
+}
+
+/// A variable lookup is a scope without agents.
+impl<F, T: Fn(&F) -> Option<f64>> Scope<F> for T {
+    fn value(&self, name: &F) -> Option<f64> {
+        self(name)
+    }
+}
+
+/// Something a condition names that its scope does not have.
+#[derive(Debug, PartialEq)]
+pub enum Unknown<'a, F> {
+    Value(&'a F),
+    Agent(&'a str),
+    Group(&'a str),
+}
+
+impl<F: fmt::Display> fmt::Display for Unknown<'_, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unknown::Value(name) => write!(f, "variable {}", name),
+            Unknown::Agent(name) => write!(f, "agent {}", name),
+            Unknown::Group(name) => write!(f, "group {}", name),
+        }
+    }
+}
+
+impl Condition {
+    /// Parse `always`, `<agent> knows <token>`, `<agent> memory contains <token>`,
+    /// `consensus on <token> among group <group> quorum <share>`, a
+    /// comparison `<expr> <op> <expr>` with `<op>` one of `<`, `<=`, `>`,
+    /// `>=`, `==` and `!=`, or `<agent> activation <token> <op> <expr>`;
+    /// or conditions combined with `and`, `or`, `not` and parentheses.
+    /// `not` binds tightest and `or` loosest; `and` and `or` group to the left.
+    pub fn parse(text: &str) -> Option<Condition> {
+        let text = text.trim();
+        if let Some((left, right)) = split_last(text, "or") {
+            return Some(Condition::Or(Box::new(Condition::parse(left)?), Box::new(Condition::parse(right)?)));
+        }
+        if let Some((left, right)) = split_last(text, "and") {
+            return Some(Condition::And(Box::new(Condition::parse(left)?), Box::new(Condition::parse(right)?)));
+        }
+        if let Some(rest) = text.strip_prefix("not").filter(|rest| rest.starts_with(|c: char| c.is_whitespace() || c == '(')) {
+            return Some(Condition::Not(Box::new(Condition::parse(rest)?)));
+        }
+        if let Some(condition) = parenthesized(text).and_then(Condition::parse) {
+            return Some(condition);
+        }
+        let words: Vec<&str> = text.split_whitespace().collect();
+        match words.as_slice() {
+            ["always"] => return Some(Condition::Always),
+            [agent, "knows", token] | [agent, "memory", "contains", token] => {
+                return Some(Condition::Knows { agent: agent.to_string(), token: token.to_string() })
+            }
+            ["consensus", "on", token, "among", "group", group, "quorum", quorum] => {
+                let token = token.trim_matches('"').to_string();
+                return Some(Condition::Consensus { token, group: group.to_string(), quorum: quorum.parse().ok()? });
+            }
+            _ => {}
+        }
+        let (at, len, cmp) = Relation::find(text)?;
+        let right = expr::parse_str(&text[at + len..])?;
+        if let [agent, "activation", token] = text[..at].split_whitespace().collect::<Vec<_>>()[..] {
+            return Some(Condition::Activation { agent: agent.to_string(), token: token.to_string(), cmp, right });
+        }
+        Some(Condition::Compare { left: expr::parse_str(&text[..at])?, cmp, right })
+    }
+
+    /// The condition with `single` writing each part that is not `and`, `or`
+    /// or `not`, in parentheses where precedence needs them.
+    pub fn source_with(&self, single: &impl Fn(&Condition) -> String) -> String {
+        let operand = |condition: &Condition, precedence| {
+            let text = condition.source_with(single);
+            if condition.precedence() < precedence {
+                format!("({})", text)
+            } else {
+                text
+            }
+        };
+        match self {
+            Condition::Or(a, b) => format!("{} or {}", operand(a, 0), operand(b, 1)),
+            Condition::And(a, b) => format!("{} and {}", operand(a, 1), operand(b, 2)),
+            Condition::Not(a) => format!("not {}", operand(a, 2)),
+            other => single(other),
+        }
+    }
+}
+
+/// `text` before and after the last whole word `word` outside parentheses
+/// and quotes.
+fn split_last<'a>(text: &'a str, word: &str) -> Option<(&'a str, &'a str)> {
+    let (mut depth, mut quoted, mut found) = (0, false, None);
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth -= 1,
+            _ if depth == 0 && !quoted && text[i..].starts_with(word) => {
+                let before = text[..i].chars().next_back().is_some_and(|b| b.is_whitespace() || b == ')');
+                let after = text[i + word.len()..].chars().next().is_some_and(|a| a.is_whitespace() || a == '(');
+                if before && after {
+                    found = Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    found.map(|i| (&text[..i], &text[i + word.len()..]))
+}
+
+/// The inside of `text` if one pair of parentheses encloses all of it.
+fn parenthesized(text: &str) -> Option<&str> {
+    let inner = text.strip_prefix('(')?.strip_suffix(')')?;
+    let mut depth = 0;
+    for c in inner.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return None,
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    Some(inner)
+}
+
+impl Condition {
+    /// Evaluate against `scope` like `eval`, describing how: the verdict
+    /// first, then every sub-expression with the value it had and the values
+    /// read from the scope, indented under the expression reading them.
+    pub fn explain(&self, scope: &impl Scope) -> Vec<String> {
+        let verdict = match self.eval(scope) {
+            Ok(true) => "holds".to_string(),
+            Ok(false) => "does not hold".to_string(),
+            Err(unknown) => format!("cannot be evaluated: unknown {}", unknown),
+        };
+        let mut out = vec![format!("{} {}", condition_source(self), verdict)];
+        match self {
+            Condition::Always => {}
+            Condition::Compare { left, cmp, right } => {
+                explain_expr(left, scope, 1, &mut out);
+                explain_expr(right, scope, 1, &mut out);
+                let value_of = |name: &String| scope.value(name);
+                if let (Ok(l), Ok(r)) = (left.scalar(&value_of), right.scalar(&value_of)) {
+                    let outcome = if cmp.holds(l, r) { "true" } else { "false" };
+                    out.push(format!("  {} {} {} is {}", l, cmp.symbol(), r, outcome));
+                }
+            }
+            Condition::Knows { agent, token } => out.push(match scope.knows(agent, token) {
+                Some(true) => format!("  {} remembers {}", agent, token),
+                Some(false) => format!("  {} does not remember {}", agent, token),
+                None => format!("  there is no agent {}", agent),
+            }),
+            Condition::Consensus { token, group, .. } => out.push(match scope.consensus(group, token) {
+                Some(c) => format!("  {} of {} in {} agree on {} ({:.2})", c.agreeing, c.members, group, token, c.share()),
+                None => format!("  there is no group {}", group),
+            }),
+            Condition::Activation { agent, token, right, .. } => match scope.activation(agent, token) {
+                Some(activation) => {
+                    out.push(format!("  {} activation {} = {}", agent, token, activation));
+                    explain_expr(right, scope, 1, &mut out);
+                }
+                None => out.push(format!("  there is no agent {}", agent)),
+            },
+            Condition::And(a, b) | Condition::Or(a, b) => {
+                for part in [a, b] {
+                    out.extend(part.explain(scope).into_iter().map(|line| format!("  {}", line)));
+                }
+            }
+            Condition::Not(a) => out.extend(a.explain(scope).into_iter().map(|line| format!("  {}", line))),
+        }
+        out
+    }
+}
+
+/// One line per compound or named part of `expr`, from the whole expression
+/// down; literals go without saying.
+fn explain_expr(expr: &FieldExpr<String>, scope: &impl Scope, depth: usize, out: &mut Vec<String>) {
+    let indent = "  ".repeat(depth);
+    match expr.scalar(&|name: &String| scope.value(name)) {
+        _ if matches!(expr, FieldExpr::Scalar(_)) => return,
+        Ok(value) => out.push(format!("{}{} = {}", indent, expr_source(expr), value)),
+        Err(name) if matches!(expr, FieldExpr::Field(_)) => out.push(format!("{}{} is unknown", indent, name)),
+        Err(name) => out.push(format!("{}{} is unknown: it reads {}", indent, expr_source(expr), name)),
+    }
+    match expr {
+        FieldExpr::Neg(inner) => explain_expr(inner, scope, depth + 1, out),
+        FieldExpr::Binary(_, a, b) => {
+            explain_expr(a, scope, depth + 1, out);
+            explain_expr(b, scope, depth + 1, out);
+        }
+        FieldExpr::Field(_) | FieldExpr::Scalar(_) => {}
+    }
+}
+
+impl<F> Condition<F> {
+    /// Evaluate against `scope`, failing with the first name it does not have.
+    pub fn eval<'a>(&'a self, scope: &impl Scope<F>) -> Result<bool, Unknown<'a, F>> {
+        match self {
+            Condition::Always => Ok(true),
+            Condition::Compare { left, cmp, right } => {
+                let value_of = |name: &F| scope.value(name);
+                let left = left.scalar(&value_of).map_err(Unknown::Value)?;
+                let right = right.scalar(&value_of).map_err(Unknown::Value)?;
+                Ok(cmp.holds(left, right))
+            }
+            Condition::Knows { agent, token } => scope.knows(agent, token).ok_or(Unknown::Agent(agent)),
+            Condition::Consensus { token, group, quorum } => {
+                let consensus = scope.consensus(group, token).ok_or(Unknown::Group(group))?;
+                Ok(consensus.members > 0 && consensus.share() >= *quorum)
+            }
+            Condition::Activation { agent, token, cmp, right } => {
+                let activation = scope.activation(agent, token).ok_or(Unknown::Agent(agent))?;
+                let right = right.scalar(&|name: &F| scope.value(name)).map_err(Unknown::Value)?;
+                Ok(cmp.holds(activation, right))
+            }
+            Condition::And(a, b) => Ok(a.eval(scope)? && b.eval(scope)?),
+            Condition::Or(a, b) => Ok(a.eval(scope)? || b.eval(scope)?),
+            Condition::Not(a) => Ok(!a.eval(scope)?),
+        }
+    }
+
+    /// How tightly the condition binds: `or` loosest, then `and`, then the rest.
+    fn precedence(&self) -> u8 {
+        match self {
+            Condition::Or(..) => 0,
+            Condition::And(..) => 1,
+            _ => 2,
+        }
+    }
+
+    /// The numeric expressions this condition compares.
+    pub fn numbers(&self) -> Vec<&FieldExpr<F>> {
+        match self {
+            Condition::Compare { left, right, .. } => vec![left, right],
+            Condition::Activation { right, .. } => vec![right],
+            Condition::And(a, b) | Condition::Or(a, b) => a.numbers().into_iter().chain(b.numbers()).collect(),
+            Condition::Not(a) => a.numbers(),
+            Condition::Always | Condition::Knows { .. } | Condition::Consensus { .. } => Vec::new(),
+        }
+    }
+
+    /// Replace number names, e.g. with slot indices. Fails with the first unresolved name.
+    pub fn resolve<G>(self, lookup: &mut impl FnMut(&F) -> Option<G>) -> Result<Condition<G>, F> {
+        Ok(match self {
+            Condition::Always => Condition::Always,
+            Condition::Compare { left, cmp, right } => {
+                Condition::Compare { left: left.resolve(lookup)?, cmp, right: right.resolve(lookup)? }
+            }
+            Condition::Knows { agent, token } => Condition::Knows { agent, token },
+            Condition::Consensus { token, group, quorum } => Condition::Consensus { token, group, quorum },
+            Condition::Activation { agent, token, cmp, right } => {
+                Condition::Activation { agent, token, cmp, right: right.resolve(lookup)? }
+            }
+            Condition::And(a, b) => Condition::And(Box::new(a.resolve(lookup)?), Box::new(b.resolve(lookup)?)),
+            Condition::Or(a, b) => Condition::Or(Box::new(a.resolve(lookup)?), Box::new(b.resolve(lookup)?)),
+            Condition::Not(a) => Condition::Not(Box::new(a.resolve(lookup)?)),
+        })
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Condition::Always => f.write_str("always"),
+            Condition::Compare { left, cmp, right } => write!(f, "{} {} {}", left, cmp.symbol(), right),
+            Condition::Activation { agent, token, cmp, right } => {
+                write!(f, "{} activation {} {} {}", agent, token, cmp.symbol(), right)
+            }
+            Condition::Knows { agent, token } => write!(f, "{} knows {}", agent, token),
+            Condition::Consensus { token, group, quorum } => {
+                write!(f, "consensus on \"{}\" among group {} quorum {}", token, group, quorum)
+            }
+            Condition::And(..) | Condition::Or(..) | Condition::Not(..) => {
+                f.write_str(&self.source_with(&|single| single.to_string()))
+            }
+        }
+    }
+}
@@ -1,5 +1,89 @@
+//! Projection defaults, read from `sptl.toml` next to a script.
+
+use crate::ontology::{self, Ontology};
+use crate::recursion::RecursionLevel;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Name of the per-experiment configuration file.
+pub const CONFIG_FILE: &str = "sptl.toml";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
+    /// Alpha used by `project` blocks that omit `alpha:`.
     pub alpha: f64,
+    /// Noise used by `project` blocks that omit `noise:`.
     pub noise: f64,
-    pub steps: usize,
-}
\ No newline at end of file
+    /// Steps used by `project` blocks that omit `steps:`; `None` (`steps = "auto"`)
+    /// projects until the trace distance stops improving.
+    #[serde(deserialize_with = "steps_setting")]
+    pub steps: Option<usize>,
+    /// Smallest per-step improvement of the trace distance that keeps `steps: auto` going.
+    pub tolerance: f64,
+    /// Rate used by `decay` statements that omit it.
+    pub decay: f64,
+    /// Ontology file replacing the built-in recursion levels, relative to this file.
+    pub ontology: Option<String>,
+    /// The ontology `ontology` names, read by `load`.
+    #[serde(skip)]
+    pub levels: Option<Arc<Ontology>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config { alpha: 0.3, noise: 0.0, steps: None, tolerance: 1e-4, decay: 0.05, ontology: None, levels: None }
+    }
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut config: Config = toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if let Some(file) = &config.ontology {
+            let file = path.parent().unwrap_or(Path::new("")).join(file);
+            config.levels = Some(Arc::new(Ontology::load(&file)?));
+        }
+        Ok(config)
+    }
+
+    /// The recursion level called `name` in the configured ontology, or among the built-in levels.
+    pub fn level(&self, name: &str) -> Option<RecursionLevel> {
+        ontology::level_named(self.levels.as_deref(), name)
+    }
+
+    /// Configuration for a script: `sptl.toml` in its directory if present, else defaults.
+    pub fn for_script(script: &Path) -> Self {
+        let path = script.parent().unwrap_or(Path::new("")).join(CONFIG_FILE);
+        if !path.exists() {
+            return Config::default();
+        }
+        Config::load(&path).unwrap_or_else(|e| {
+            eprintln!("⚠️ {}; using defaults", e);
+            Config::default()
+        })
+    }
+}
+
+/// Parse a step count that is either a number or `"auto"`.
+pub fn parse_steps(value: &str) -> Option<Option<usize>> {
+    match value {
+        "auto" => Some(None),
+        n => n.parse().ok().map(Some),
+    }
+}
+
+fn steps_setting<'de, D: Deserializer<'de>>(d: D) -> Result<Option<usize>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Steps {
+        Count(usize),
+        Word(String),
+    }
+    match Steps::deserialize(d)? {
+        Steps::Count(n) => Ok(Some(n)),
+        Steps::Word(w) => parse_steps(&w)
+            .ok_or_else(|| serde::de::Error::custom(format!("steps must be a count or \"auto\", found \"{}\"", w))),
+    }
+}
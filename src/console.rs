@@ -0,0 +1,217 @@
+//! Console output of a run, throttled so long simulations stay readable.
+//!
+//! The interpreters print progress with `say!` instead of `println!`. By
+//! default every line is printed. With `--throttle N` a line repeated in a
+//! row is printed once and then summarized every N repeats
+//! (`… repeated N time(s)`); with `--max-lines-per-tau M` at most M lines are
+//! printed per τ, and the number held back is reported when τ advances.
+//! Every line, printed or not, still goes to the detail log
+//! (`--console-log <path>`, or `console.log` in the run directory), so
+//! throttling only shortens what scrolls by.
+//!
+//! Warnings about the script go through `warn!`. Both can be captured for
+//! someone other than the terminal with `capture`.
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+/// Print a line through the run's console; takes `println!` arguments.
+#[macro_export]
+macro_rules! say {
+    ($($arg:tt)*) => {
+        $crate::console::line(format!($($arg)*))
+    };
+}
+
+/// Print a warning through the run's console; takes `eprintln!` arguments.
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        $crate::console::warning(format!($($arg)*))
+    };
+}
+
+/// How much of the output reaches the terminal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Throttle {
+    /// Summarize a line repeated in a row once per this many repeats; `None` prints every repeat.
+    pub repeat_every: Option<usize>,
+    /// Lines printed per τ at most; `None` for no cap.
+    pub lines_per_tau: Option<usize>,
+}
+
+impl Throttle {
+    pub fn is_off(&self) -> bool {
+        self.repeat_every.is_none() && self.lines_per_tau.is_none()
+    }
+}
+
+/// Decides which lines are printed. Lines go in through `line` and
+/// `set_tau`; what should appear on the terminal comes out.
+#[derive(Debug, Default)]
+pub struct Console {
+    throttle: Throttle,
+    tau: u64,
+    /// Lines printed at the current τ.
+    printed: usize,
+    /// Lines held back by the per-τ cap at the current τ.
+    held_back: usize,
+    /// The last line and how many times it has repeated since it was last printed or summarized.
+    last: Option<String>,
+    repeats: usize,
+}
+
+impl Console {
+    pub fn new(throttle: Throttle) -> Self {
+        Console { throttle, ..Console::default() }
+    }
+
+    /// The lines to print for `text`: itself, a summary of repeats, or nothing.
+    pub fn line(&mut self, text: String) -> Vec<String> {
+        let mut out = Vec::new();
+        if self.last.as_deref() == Some(text.as_str()) {
+            if let Some(every) = self.throttle.repeat_every {
+                self.repeats += 1;
+                if self.repeats == every {
+                    self.repeats = 0;
+                    self.emit(format!("… repeated {} time(s): {}", every, text), &mut out);
+                }
+                return out;
+            }
+        } else {
+            out.extend(self.flush_repeats());
+            self.last = Some(text.clone());
+        }
+        self.emit(text, &mut out);
+        out
+    }
+
+    /// Move to `tau`; returns the summaries of the previous τ.
+    pub fn set_tau(&mut self, tau: u64) -> Vec<String> {
+        if tau == self.tau {
+            return Vec::new();
+        }
+        let out = self.finish();
+        self.tau = tau;
+        self.printed = 0;
+        out
+    }
+
+    /// Summaries still pending: repeats of the last line and lines held back at this τ.
+    pub fn finish(&mut self) -> Vec<String> {
+        let mut out = self.flush_repeats();
+        if self.held_back > 0 {
+            out.push(format!("… {} more line(s) at τ={} not shown", self.held_back, self.tau));
+            self.held_back = 0;
+        }
+        out
+    }
+
+    /// Repeats of the last line not yet summarized.
+    fn flush_repeats(&mut self) -> Vec<String> {
+        let mut out = Vec::new();
+        if self.repeats > 0 {
+            let text = self.last.clone().unwrap_or_default();
+            let repeats = std::mem::take(&mut self.repeats);
+            self.emit(format!("… repeated {} time(s): {}", repeats, text), &mut out);
+        }
+        out
+    }
+
+    fn emit(&mut self, text: String, out: &mut Vec<String>) {
+        match self.throttle.lines_per_tau {
+            Some(cap) if self.printed >= cap => self.held_back += 1,
+            _ => {
+                self.printed += 1;
+                out.push(text);
+            }
+        }
+    }
+}
+
+struct Global {
+    console: Console,
+    detail: Option<BufWriter<File>>,
+}
+
+fn global() -> &'static Mutex<Global> {
+    static GLOBAL: OnceLock<Mutex<Global>> = OnceLock::new();
+    GLOBAL.get_or_init(|| Mutex::new(Global { console: Console::default(), detail: None }))
+}
+
+/// Throttle the process's console output from now on.
+pub fn configure(throttle: Throttle) {
+    global().lock().unwrap().console = Console::new(throttle);
+}
+
+/// Write every line, throttled or not, to `path`.
+pub fn log_to(path: &Path) -> io::Result<()> {
+    let file = File::create(path)?;
+    global().lock().unwrap().detail = Some(BufWriter::new(file));
+    Ok(())
+}
+
+thread_local! {
+    /// Lines `capture` is collecting on this thread instead of printing.
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` and return, with its result, the lines and warnings it printed
+/// on this thread; they are not printed, but lines are still logged.
+pub fn capture<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+    let outer = CAPTURED.with(|captured| captured.replace(Some(Vec::new())));
+    let value = f();
+    let lines = CAPTURED.with(|captured| captured.replace(outer)).unwrap_or_default();
+    (value, lines)
+}
+
+/// Keep `text` if this thread is capturing; otherwise hand it back.
+fn captured(text: String) -> Option<String> {
+    CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(lines) => {
+            lines.push(text);
+            None
+        }
+        None => Some(text),
+    })
+}
+
+/// Print `text` as the throttle allows and log it in full.
+pub fn line(text: String) {
+    let mut global = global().lock().unwrap();
+    if let Some(detail) = &mut global.detail {
+        let _ = writeln!(detail, "{}", text);
+    }
+    let Some(text) = captured(text) else { return };
+    for line in global.console.line(text) {
+        println!("{}", line);
+    }
+}
+
+/// Print a warning on stderr.
+pub fn warning(text: String) {
+    if let Some(text) = captured(text) {
+        eprintln!("{}", text);
+    }
+}
+
+/// The run has reached `tau`.
+pub fn set_tau(tau: u64) {
+    for line in global().lock().unwrap().console.set_tau(tau) {
+        println!("{}", line);
+    }
+}
+
+/// Print pending summaries and flush the detail log.
+pub fn finish() {
+    let mut global = global().lock().unwrap();
+    for line in global.console.finish() {
+        println!("{}", line);
+    }
+    if let Some(detail) = &mut global.detail {
+        let _ = detail.flush();
+    }
+}
@@ -0,0 +1,96 @@
+//! Typed interpreter events and the bus that delivers them to subscribers.
+//!
+//! Executors publish an `Event` whenever something observable happens; host
+//! code, telemetry writers and UIs subscribe instead of scraping stdout.
+
+use crossbeam_channel::{unbounded, Receiver};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Write;
+
+/// Largest per-step change of a field still counted as having reached its attractor.
+pub const ATTRACTOR_EPSILON: f64 = 1e-6;
+
+/// `tau` is the narrative τ, or the statement step for SPTL programs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum Event {
+    SymbolExpressed { source: String, token: String, tau: u64 },
+    /// An agent projected a symbol it expressed into the shared substrate.
+    SymbolProjected { agent: String, token: String, tau: u64 },
+    SymbolInterpreted { agent: String, token: String, tau: u64 },
+    TraceComputed { name: String, value: f64, tau: u64 },
+    AgentCreated { name: String, tau: u64 },
+    /// A projection's last step moved the field by less than `ATTRACTOR_EPSILON`.
+    AttractorReached { field: String, delta: f64, tau: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+type Subscriber = Box<dyn FnMut(&Event) + Send>;
+
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Vec<(SubscriptionId, Subscriber)>,
+    next_id: u64,
+}
+
+impl fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EventBus").field("subscribers", &self.subscribers.len()).finish()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        EventBus::default()
+    }
+
+    /// Call `f` for every event published from now on.
+    pub fn subscribe(&mut self, f: impl FnMut(&Event) + Send + 'static) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.subscribers.push((id, Box::new(f)));
+        id
+    }
+
+    /// Receive events on a channel, e.g. from another thread. The subscription
+    /// stays registered until `unsubscribe`; events sent after the receiver is
+    /// dropped are discarded.
+    pub fn subscribe_channel(&mut self) -> (SubscriptionId, Receiver<Event>) {
+        let (tx, rx) = unbounded();
+        let id = self.subscribe(move |e| {
+            let _ = tx.send(e.clone());
+        });
+        (id, rx)
+    }
+
+    /// Write every event as one JSON object per line.
+    pub fn subscribe_jsonl(&mut self, mut out: impl Write + Send + 'static) -> SubscriptionId {
+        self.subscribe(move |e| {
+            let written = serde_json::to_string(e)
+                .map_err(std::io::Error::other)
+                .and_then(|line| writeln!(out, "{}", line));
+            if let Err(err) = written {
+                eprintln!("⚠️ Event write failed: {}", err);
+            }
+        })
+    }
+
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let before = self.subscribers.len();
+        self.subscribers.retain(|(s, _)| *s != id);
+        self.subscribers.len() != before
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+
+    pub fn publish(&mut self, event: Event) {
+        for (_, subscriber) in &mut self.subscribers {
+            subscriber(&event);
+        }
+    }
+}
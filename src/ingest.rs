@@ -0,0 +1,76 @@
+//! Corpus ingestion: turn a text file into a narrative that replays it.
+//!
+//! The text is split into lower-case words; each word gets a bit pattern
+//! derived from its spelling, so the same word always maps to the same
+//! pattern, in every run and on every machine. Words are then spoken in
+//! order, `tokens_per_tick` per τ, by a population of readers taking turns:
+//! the speaker `says` the word and the next reader `interprets` it.
+
+use crate::narrative::ast::{Action, Block};
+
+/// Prefix of generated reader names: `reader0`, `reader1`, ...
+pub const READER_PREFIX: &str = "reader";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IngestOptions {
+    /// Readers in the population (`--agents N`).
+    pub agents: usize,
+    /// Words replayed per τ (`--tokens-per-tick N`).
+    pub tokens_per_tick: usize,
+    /// Length of each generated pattern.
+    pub pattern_bits: usize,
+    /// Memory capacity and coherence of each reader.
+    pub memory: u32,
+    pub coherence: f32,
+}
+
+impl Default for IngestOptions {
+    fn default() -> Self {
+        IngestOptions { agents: 4, tokens_per_tick: 1, pattern_bits: 8, memory: 128, coherence: 0.2 }
+    }
+}
+
+/// The words of `text`: runs of letters, digits and apostrophes, lower-cased.
+pub fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '\''))
+        .map(|w| w.trim_matches('\'').to_lowercase())
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// A `bits`-long pattern of 0s and 1s for `token`, from a 64-bit FNV-1a hash
+/// of its spelling (repeated for patterns longer than 64 bits).
+pub fn pattern_for(token: &str, bits: usize) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in token.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    (0..bits).map(|i| if (hash >> (i % 64)) & 1 == 1 { '1' } else { '0' }).collect()
+}
+
+/// A narrative replaying `text`: readers are created at τ=0 and word `i`
+/// is spoken at τ = 1 + i / `tokens_per_tick`.
+pub fn corpus_script(text: &str, options: &IngestOptions) -> Vec<Block> {
+    let agents = options.agents.max(1);
+    let per_tick = options.tokens_per_tick.max(1);
+    let reader = |i: usize| format!("{}{}", READER_PREFIX, i % agents);
+    let create = (0..agents)
+        .map(|i| Action::CreateAgent { name: reader(i), mem: options.memory, coh: options.coherence, within: None, doc: None })
+        .collect();
+    let mut blocks = vec![Block::AtTau(0, create)];
+    let words = words(text);
+    for (chunk, tokens) in words.chunks(per_tick).enumerate() {
+        let mut actions = Vec::with_capacity(tokens.len() * 2);
+        for (offset, token) in tokens.iter().enumerate() {
+            let turn = chunk * per_tick + offset;
+            let pattern = pattern_for(token, options.pattern_bits);
+            actions.push(Action::Say { agent: reader(turn), token: token.clone(), pattern });
+            if agents > 1 {
+                actions.push(Action::Interpret { agent: reader(turn + 1), token: token.clone() });
+            }
+        }
+        blocks.push(Block::AtTau(1 + chunk as u64, actions));
+    }
+    blocks
+}
@@ -15,7 +15,10 @@
  * along with SPTL-SPI.  If not, see <https://www.gnu.org/licenses/>.
  */
  
-//! Structured interpretations for all recursion levels (Λ₁, Λ₂, Λ₃, Λ₄) in SPTL.
+//! Structured interpretations for all recursion levels (Λ₁, Λ₂, Λ₃, Λ₄) in SPTL,
+//! and for levels defined by a loaded ontology.
+
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone)]
 pub enum Interpretation {
@@ -23,6 +26,48 @@ pub enum Interpretation {
     Atom(AtomInterpretation),         // Λ₂
     Molecule(MoleculeInterpretation), // Λ₃
     Cell(CellInterpretation),         // Λ₄
+    Level(LevelInterpretation),       // ontology-defined
+}
+
+impl Interpretation {
+    /// Descriptors introduced at this level only (constituents excluded).
+    pub fn own_features(&self) -> Vec<String> {
+        match self {
+            Interpretation::Particle(p) => vec![format!("state:{}", p.quantum_state)],
+            Interpretation::Atom(a) => vec![format!("shell:{}", a.shell_config), format!("z:{}", a.atomic_number)],
+            Interpretation::Molecule(m) => {
+                let mut f = vec![format!("formula:{}", m.formula)];
+                f.extend(m.bonds.iter().map(|b| format!("bond:{}", b)));
+                f
+            }
+            Interpretation::Cell(c) => c.emergent_properties.iter().map(|p| format!("property:{}", p)).collect(),
+            Interpretation::Level(l) => l.values.iter().map(|(k, v)| format!("{}:{}", k, v)).collect(),
+        }
+    }
+
+    /// All descriptors of this interpretation, including those of its constituents.
+    pub fn features(&self) -> Vec<String> {
+        let mut f = self.own_features();
+        match self {
+            Interpretation::Atom(a) => {
+                for p in &a.constituent_particles {
+                    f.extend(Interpretation::Particle(p.clone()).features());
+                }
+            }
+            Interpretation::Molecule(m) => {
+                for a in &m.constituent_atoms {
+                    f.extend(Interpretation::Atom(a.clone()).features());
+                }
+            }
+            Interpretation::Level(l) => {
+                for c in &l.constituents {
+                    f.extend(Interpretation::Level(c.clone()).features());
+                }
+            }
+            Interpretation::Particle(_) | Interpretation::Cell(_) => {}
+        }
+        f
+    }
 }
 
 /// Λ₁: Particle-level interpretation (e.g., quantum state)
@@ -58,4 +103,13 @@ pub struct CellInterpretation {
     pub summary: String,
     pub emergent_properties: Vec<String>,
     pub contributing_meanings: Vec<String>,
-}
\ No newline at end of file
+}
+
+/// Interpretation of an ontology-defined level: the values its template reports.
+#[derive(Debug, Clone)]
+pub struct LevelInterpretation {
+    pub id: String,
+    pub level: String,
+    pub values: BTreeMap<String, f64>,
+    pub constituents: Vec<LevelInterpretation>,
+}
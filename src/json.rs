@@ -0,0 +1,172 @@
+//! JSON front-end for both DSLs.
+//!
+//! Tools that generate experiments can emit a JSON document instead of DSL
+//! text. The document is the serde form of the same ASTs the text parsers
+//! produce, so any program round-trips between the two.
+//!
+//! # Schema
+//!
+//! The top-level object carries a `format` tag and a `version`:
+//!
+//! ```json
+//! { "format": "sptl", "version": 1, "statements": [ <Statement>... ] }
+//! { "format": "narrative", "version": 1, "blocks": [ <Block>... ] }
+//! ```
+//!
+//! Enum values use serde's external tagging: unit variants are strings, all
+//! others are single-key objects named after the variant.
+//!
+//! SPTL statements (`sptl::Statement`):
+//!
+//! ```json
+//! { "Field": { "name": "psi", "size": 16 } }
+//! { "Field": { "name": "psi", "size": 16, "doc": "visual cortex analogue" } }
+//! { "Field": { "name": "psi", "size": { "Binary": ["Mul", "n", 2.0] } } }
+//! { "DeriveField": { "name": "c", "expr": { "Binary": ["Add", "a", 0.5] } } }
+//! { "SliceField": { "name": "psi2", "source": "psi", "start": 0, "end": 8 } }
+//! { "ConcatFields": { "name": "omega", "parts": ["psi", "chi"] } }
+//! { "FieldFromCheckpoint": { "name": "psi", "path": "out/run-1/checkpoints/psi.ckpt" } }
+//! { "Interpretation": { "name": "seed", "values": [1.0, 0.0] } }
+//! { "GenerateInterpretation": { "name": "seed", "generator": { "Gaussian": { "size": 128, "mean": 0.0, "std": 1.0, "seed": 7 } } } }
+//! { "Project": { "target": "psi", "interp": "seed", "alpha": 0.3, "noise": 0.05, "steps": 20, "tolerance": 0.0001, "until": null,
+//!     "record": { "kind": "Trajectory", "every": 10 }, "log_every": 5 } }
+//! { "ProjectMany": { "target": "psi", "interps": [["seed", 0.7], ["rival", 0.3]], "alternate": false, "alpha": 0.3, "noise": 0.0,
+//!     "steps": 50 } }
+//! { "Morph": { "target": "psi", "from": "seed", "to": "rival", "alpha": 0.3, "noise": 0.0, "steps": 50 } }
+//! { "TraceDistance": { "name": "d", "field": "psi", "interp": "seed" } }
+//! { "Let": { "name": "d", "metric": "Distance", "field": "psi", "interp": "seed" } }
+//! { "Assign": { "name": "a", "value": { "Binary": ["Mul", "d", 2.0] } } }
+//! { "Const": { "name": "ETA", "value": 0.25 } }
+//! { "Meaning": { "name": "calm", "trace_cmp": "d", "threshold": 0.5 } }
+//! { "Assert": { "trace": "d", "cmp": "Less", "bound": 0.5, "tolerance": 0.001 } }
+//! { "NarrateReturn": { "tokens": ["the field settled"] } }
+//! { "LogCoherence": "psi" }
+//! { "LogMeaning": "calm" }
+//! { "ExpressSymbol": { "token": "fire", "into_field": "psi", "encoding": "OneHot", "weight": 0.5, "blend": false } }
+//! { "Modulate": { "token": "fire", "intensity": 0.5 } }
+//! { "Level": { "level": "Cell", "name": "C", "body": [ <Statement>... ] } }
+//! { "Level": { "level": { "Defined": { "rank": 1, "top": 2 } }, "name": "S1", "body": [] } }
+//! { "Steer": { "field": "psi", "interp": "seed", "target": 0.9, "settings": { "metric": "Coherence", "keep": "Greater",
+//!     "alpha_min": 0.01, "alpha_max": 0.5, "noise": 0.0, "steps": 100 } } }
+//! { "Perturb": { "field": "psi", "amplitude": 0.5 } }
+//! { "Shock": { "field": "psi", "start": 3, "end": 10, "value": 2.0 } }
+//! { "AddFields": { "left": "psi", "right": "chi", "into": "omega" } }
+//! { "Scale": { "field": "psi", "factor": 0.5 } }
+//! { "Decay": { "field": "psi", "rate": 0.05, "steps": 10 } }
+//! { "Normalize": { "field": "psi" } }
+//! { "Repeat": { "count": 10, "body": [ <Statement>... ] } }
+//! { "If": { "condition": <Condition>, "then": [ <Statement>... ], "otherwise": [] } }
+//! { "Include": "common.sptl" }
+//! { "Run": { "path": "stage2.sptl", "sharing": ["Fields"] } }
+//! { "Require": "Gpu" }
+//! { "Seed": 42 }
+//! { "Snapshot": { "field": "psi", "name": "psi_t0" } }
+//! { "Export": { "kind": "Trace", "name": "d", "path": "d.csv" } }
+//! { "Report": { "path": "summary.json" } }
+//! ```
+//!
+//! Conditions (`condition::Condition`), shared with narrative `if`, `while` and `assert`:
+//!
+//! ```json
+//! { "Compare": { "left": "d", "cmp": "Less", "right": 0.1 } }
+//! { "Knows": { "agent": "alice", "token": "fire" } }
+//! { "Consensus": { "token": "fire", "group": "learners", "quorum": 0.8 } }
+//! { "Activation": { "agent": "alice", "token": "fire", "cmp": "Greater", "right": 0.5 } }
+//! { "And": [<Condition>, <Condition>] }
+//! { "Or": [<Condition>, <Condition>] }
+//! { "Not": <Condition> }
+//! "Always"
+//! ```
+//!
+//! A `Project` with `"steps": null` runs until the trace distance stops
+//! improving by `tolerance` (`steps: auto` in SPTL). A numeric `until` stops
+//! once the trace distance falls below it, with `steps` as the limit.
+//!
+//! Expressions (`DeriveField.expr`, `Field.size`, `Interpretation.values`,
+//! `Project.steps`, and numeric options such as `alpha`, `threshold` or
+//! `amplitude`) are written as a bare number, a bare variable or field name,
+//! `{ "Neg": <expr> }`, or `{ "Binary": [<op>, <expr>, <expr>] }` with `<op>`
+//! one of `Add`, `Sub`, `Mul`, `Div`.
+//!
+//! Narrative blocks (`narrative::ast::Block`) and actions (`narrative::ast::Action`):
+//!
+//! ```json
+//! { "AtTau": [0, [ <Action>... ]] }
+//! { "Repeat": [3, [ <Action>... ]] }
+//! { "While": [<Condition>, [ <Action>... ]] }
+//! { "Parallel": [ <Action>... ] }
+//! { "MacroDef": { "name": "greet", "params": ["a"], "body": [ <Action>... ] } }
+//! { "MacroDef": { "name": "spawn", "params": ["a"], "body": [ <Action>... ],
+//!                 "contract": { "requires": [], "creates": [{ "kind": "Agent", "name": "$a" }] } } }
+//! { "Rule": { "condition": <Condition>, "actions": [ <Action>... ], "priority": 0, "refractory": 0 } }
+//!
+//! { "Conditional": [ <Condition>, [ <Action>... ], [ <Action>... ] ] }
+//! { "CreateAgent": { "name": "alice", "mem": 64, "coh": 0.2, "within": null } }
+//! { "CreateAgent": { "name": "alice", "mem": 64, "coh": 0.2, "within": null, "doc": "the first speaker" } }
+//! { "Say": { "agent": "alice", "token": "fire", "pattern": "1010" } }
+//! { "Interpret": { "agent": "bob", "token": "fire" } }
+//! { "Project": { "agent": "alice", "token": "fire", "into": "F" } }
+//! { "Why": { "Pattern": { "pattern": "1010", "field": "F" } } }
+//! { "Describe": "alice" }
+//! { "Pin": { "agent": "alice", "token": "fire", "pinned": true } }
+//! { "Source": { "line": 3, "text": "alice says: fire → 1010" } }
+//! { "DefineGroup": { "name": "learners", "members": ["alice", "bob"] } }
+//! { "Tick": 1 }
+//! { "Assert": { "Knows": { "agent": "bob", "token": "fire" } } }
+//! ```
+//!
+//! The remaining actions follow the same rules from their definitions in
+//! `narrative::ast`.
+
+use crate::narrative::ast::Block;
+use crate::sptl::{self, Statement};
+use serde::{Deserialize, Serialize};
+
+/// Version of the JSON document layout.
+pub const JSON_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "lowercase")]
+pub enum JsonProgram {
+    Sptl { version: u32, statements: Vec<Statement> },
+    Narrative { version: u32, blocks: Vec<Block> },
+}
+
+impl JsonProgram {
+    pub fn sptl(statements: Vec<Statement>) -> Self {
+        JsonProgram::Sptl { version: JSON_FORMAT_VERSION, statements }
+    }
+
+    pub fn narrative(blocks: Vec<Block>) -> Self {
+        JsonProgram::Narrative { version: JSON_FORMAT_VERSION, blocks }
+    }
+
+    /// Parse a JSON document, rejecting versions newer than this build
+    /// understands and statements the SPTL parser would reject (see
+    /// `sptl::validate`).
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let program: JsonProgram = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        let version = match &program {
+            JsonProgram::Sptl { version, .. } | JsonProgram::Narrative { version, .. } => *version,
+        };
+        if version > JSON_FORMAT_VERSION {
+            return Err(format!(
+                "document version {} is newer than supported version {}",
+                version, JSON_FORMAT_VERSION
+            ));
+        }
+        if let JsonProgram::Sptl { statements, .. } = &program {
+            statements.iter().try_for_each(sptl::validate)?;
+        }
+        Ok(program)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("ASTs always serialize")
+    }
+}
+
+/// Whether a script path names a JSON program.
+pub fn is_json(path: &str) -> bool {
+    path.ends_with(".json")
+}
@@ -0,0 +1,138 @@
+//! Lexicons: the token → pattern table of an agent or a whole population,
+//! with how stable each entry is, in a JSON format that outlives the run.
+//!
+//! ```json
+//! { "version": 1, "entries": [ { "token": "fire", "pattern": "1010", "stability": 0.8, "speakers": 2, "pinned": true } ] }
+//! ```
+//!
+//! A lexicon can be analysed outside SPTL, or imported into fresh agents so
+//! they start a scenario with a vocabulary another population converged on.
+
+use crate::agents::{Agent, MemoryTrace};
+use crate::substrate::Pattern;
+use crate::symbol::Symbol;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Version of the lexicon format written by `save`.
+pub const LEXICON_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lexicon {
+    pub version: u32,
+    /// One entry per token, sorted by token.
+    pub entries: Vec<LexiconEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LexiconEntry {
+    pub token: String,
+    /// Pattern as a string of `0`s and `1`s.
+    pub pattern: String,
+    /// Mean memory stability of the token among its speakers; 0 for a token
+    /// whose traces have decayed away.
+    pub stability: f64,
+    /// Agents that know the token with this pattern.
+    pub speakers: usize,
+    /// Whether any of those agents pins the token, exempting it from decay.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+}
+
+impl Lexicon {
+    /// The lexicon of one agent.
+    pub fn of_agent(agent: &Agent) -> Self {
+        Lexicon::of_agents([agent])
+    }
+
+    /// The shared lexicon of a population. Where agents disagree on a token's
+    /// pattern, the pattern with the highest summed stability wins, then the
+    /// one with most speakers.
+    pub fn of_agents<'a>(agents: impl IntoIterator<Item = &'a Agent>) -> Self {
+        let mut usage: BTreeMap<&str, BTreeMap<&str, (f64, usize, bool)>> = BTreeMap::new();
+        for agent in agents {
+            for (token, pattern) in &agent.symbol_table {
+                let stability = agent
+                    .memory
+                    .traces
+                    .iter()
+                    .filter(|t| &t.symbol.token == token)
+                    .map(|t| t.stability)
+                    .fold(0.0, f64::max);
+                let entry = usage.entry(token).or_default().entry(&pattern.0).or_default();
+                entry.0 += stability;
+                entry.1 += 1;
+                entry.2 |= agent.memory.is_pinned(token);
+            }
+        }
+        let entries = usage
+            .into_iter()
+            .filter_map(|(token, patterns)| {
+                let (pattern, (total, speakers, pinned)) = patterns
+                    .into_iter()
+                    .max_by(|a, b| a.1 .0.total_cmp(&b.1 .0).then(a.1 .1.cmp(&b.1 .1)))?;
+                Some(LexiconEntry {
+                    token: token.to_string(),
+                    pattern: pattern.to_string(),
+                    stability: total / speakers as f64,
+                    speakers,
+                    pinned,
+                })
+            })
+            .collect();
+        Lexicon { version: LEXICON_VERSION, entries }
+    }
+
+    /// Write the lexicon as JSON.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    /// Read a lexicon written by `save`.
+    pub fn load(path: &Path) -> io::Result<Lexicon> {
+        let text = fs::read_to_string(path)?;
+        let lexicon: Lexicon = serde_json::from_str(&text).map_err(io::Error::other)?;
+        if lexicon.version != LEXICON_VERSION {
+            return Err(io::Error::other(format!(
+                "lexicon version {} is not supported; expected {}",
+                lexicon.version, LEXICON_VERSION
+            )));
+        }
+        Ok(lexicon)
+    }
+
+    /// Teach `agent` every entry at `tau`: the token's pattern replaces the
+    /// one it knew, a pinned entry is pinned, and an entry with positive
+    /// stability becomes a memory trace of that stability, subject to the
+    /// agent's coherence threshold. Returns the tokens that were admitted to
+    /// memory.
+    pub fn seed(&self, agent: &mut Agent, tau: usize) -> Vec<String> {
+        let mut remembered = Vec::new();
+        for entry in &self.entries {
+            let pattern = Pattern::new(&entry.pattern);
+            agent.learn_symbol(&entry.token, pattern.clone());
+            if entry.pinned {
+                agent.memory.pin(&entry.token);
+            }
+            if entry.stability <= 0.0 {
+                continue;
+            }
+            agent.memory.traces.retain(|t| t.symbol.token != entry.token);
+            let trace = MemoryTrace {
+                symbol: Symbol::new(&entry.token, pattern),
+                tau_index: tau,
+                stability: entry.stability.min(1.0),
+                interpretants: Vec::new(),
+            };
+            agent.memory.admit(trace, agent.coherence_threshold);
+            if agent.memory.traces.back().is_some_and(|t| t.symbol.token == entry.token) {
+                remembered.push(entry.token.clone());
+            }
+        }
+        remembered
+    }
+}
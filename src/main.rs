@@ -1,10 +1,44 @@
 mod shell;
+mod batch;
 mod agents;
 mod substrate;
 mod symbol;
 mod symmetry;
 mod multiproc;
+mod actors;
+mod sharding;
+mod sptl;
+mod condition;
+mod runtime;
+mod narrative;
+mod report;
+mod rundir;
+mod cli;
+mod capability;
+mod sweep;
+mod package;
+mod stats;
+mod sensitivity;
+mod templates;
+mod json;
+mod events;
+mod config;
+mod ontology;
+mod ingest;
+mod lexicon;
+mod similarity;
+mod repl;
+mod shared;
+mod snapshot;
+mod perturb;
+mod rng;
+mod provenance;
+mod protocol;
+mod console;
+mod bench;
 
+use std::collections::BTreeMap;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use agents::Agent;
 
@@ -19,7 +53,493 @@ fn load_scripts() -> Vec<String> {
     vec!["slm.sptl".to_string()]
 }
 
+/// Settings shared by every script execution in one invocation.
+struct RunSettings {
+    seed: Option<u64>,
+    cache: Option<sptl::cache::AstCache>,
+    /// Parse only; don't execute.
+    check: bool,
+    /// Print the parsed program as JSON instead of executing it.
+    emit_json: bool,
+    engine: cli::Engine,
+    /// JSON-lines file receiving every event.
+    events: Option<std::path::PathBuf>,
+    /// Count RNG draws per call site and print them after each run.
+    audit_rng: bool,
+}
+
+/// Subscribe the `--events` log, if any, to `bus`.
+fn attach_event_log(bus: &mut events::EventBus, path: Option<&Path>) -> std::io::Result<()> {
+    if let Some(path) = path {
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        bus.subscribe_jsonl(std::io::BufWriter::new(file));
+    }
+    Ok(())
+}
+
+/// Print parse errors of `path` (or of files it includes) and summarize them as one error.
+fn parse_errors(path: &str, errors: &[sptl::ParseError]) -> std::io::Error {
+    for e in errors {
+        match e.file {
+            Some(_) => eprintln!("{}", e),
+            None => eprintln!("{}:{}", path, e),
+        }
+    }
+    std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}: {} parse error(s)", path, errors.len()))
+}
+
+/// Run a single SPTL script, writing artifacts into a run directory if requested.
+fn run_script(
+    path: &str,
+    params: &BTreeMap<String, String>,
+    run_dir: Option<&Path>,
+    settings: &RunSettings,
+) -> std::io::Result<Option<report::RunReport>> {
+    let source = std::fs::read_to_string(path)?;
+    let config = config::Config::for_script(Path::new(path));
+    let program = if json::is_json(path) {
+        match json::JsonProgram::from_json(&source).map_err(std::io::Error::other)? {
+            json::JsonProgram::Sptl { statements, .. } => statements,
+            json::JsonProgram::Narrative { blocks, .. } => {
+                let problems = narrative::contract::check(&blocks);
+                if !problems.is_empty() {
+                    return Err(std::io::Error::other(problems.join("; ")));
+                }
+                let mut ctx = narrative::runner::ScriptContext::with_vars(params.clone());
+                ctx.world.ontology = config.levels.clone();
+                if let Some(seed) = settings.seed {
+                    ctx.world.rng.reseed(seed);
+                }
+                attach_event_log(&mut ctx.events, settings.events.as_deref())?;
+                narrative::runner::execute_script(&blocks, &mut ctx);
+                return Ok(None);
+            }
+        }
+    } else {
+        // Cached programs keep their `include`s, so edits to included files are seen.
+        let parsed = match &settings.cache {
+            Some(cache) => cache.parse(&source, params, &config),
+            None => sptl::parse_source_with(&source, params, &config),
+        };
+        match parsed {
+            Ok(program) => program,
+            Err(errors) => return Err(parse_errors(path, &errors)),
+        }
+    };
+    let program = sptl::resolve_includes(program, Path::new(path), params, &config).map_err(|e| parse_errors(path, &e))?;
+    if settings.emit_json {
+        println!("{}", json::JsonProgram::sptl(program).to_json());
+        return Ok(None);
+    }
+    let optimized = sptl::optimize::optimize(program);
+    for d in &optimized.diagnostics {
+        eprintln!("{}: {}", path, d);
+    }
+    if !optimized.missing.is_empty() {
+        eprintln!("{}: this build lacks features the script needs; {}", path, capability::rebuild_hint(&optimized.missing));
+    }
+    if settings.check {
+        println!("{}: {} statements parsed, {} removed as no-ops", path, optimized.program.len() + optimized.removed, optimized.removed);
+    }
+    if optimized.has_errors() {
+        let errors = optimized.diagnostics.iter().filter(|d| d.severity == sptl::optimize::Severity::Error).count();
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}: {} error(s) found before running", path, errors)));
+    }
+    if settings.check {
+        return Ok(None);
+    }
+    let mut program = optimized.program;
+    // `--seed` seeds the run as if the script began with `seed <n>`; a `seed` in the script still wins.
+    if let Some(seed) = settings.seed {
+        program.insert(0, sptl::Statement::Seed(seed));
+    }
+    let mut report = report::RunReport { script: Some(path.to_string()), ..Default::default() };
+    attach_event_log(&mut report.events, settings.events.as_deref())?;
+    let Some(dir) = run_dir else {
+        batch::execute(program, &mut report, settings.engine, &config, settings.audit_rng);
+        if settings.audit_rng {
+            print_rng_draws(&report.rng_draws);
+        }
+        return Ok(Some(report));
+    };
+    let mut run = rundir::RunDir::create(dir)?;
+    run.manifest.script = Some(path.to_string());
+    run.manifest.params = params.clone();
+    run.manifest.seed = settings.seed;
+    report.stream = Some(run.stream(report::DEFAULT_FLUSH_EVERY)?);
+    batch::execute(program, &mut report, settings.engine, &config, settings.audit_rng);
+    if settings.audit_rng {
+        print_rng_draws(&report.rng_draws);
+    }
+    run.finish(&mut report)?;
+    println!("Run artifacts written to {}", dir.display());
+    Ok(Some(report))
+}
+
+/// Start the full console log: `--console-log`, or `console.log` in the run
+/// directory when output is throttled.
+fn open_console_log(opts: &cli::CliOptions) -> std::io::Result<()> {
+    let path = match (&opts.console_log, &opts.run_dir) {
+        (Some(path), _) => path.clone(),
+        (None, Some(dir)) if !opts.throttle.is_off() => {
+            std::fs::create_dir_all(dir)?;
+            dir.join(rundir::CONSOLE_FILE)
+        }
+        _ => return Ok(()),
+    };
+    console::log_to(&path)
+}
+
+/// Print the RNG draws of an audited run, one call site per line.
+fn print_rng_draws(draws: &BTreeMap<String, u64>) {
+    println!("🎲 RNG draws: {}", draws.values().sum::<u64>());
+    for (site, n) in draws {
+        println!("  {:<16} {}", site, n);
+    }
+}
+
+/// Run a script `opts.repeat` times and print statistics over the runs.
+/// With `--vary-seed`, repetition `i` uses seed `seed + i`; with a run directory,
+/// each repetition gets `rep-<i>/` and the summary goes to `summary.json`.
+fn run_repeated(
+    script: &str,
+    params: &BTreeMap<String, String>,
+    opts: &cli::CliOptions,
+    mut settings: RunSettings,
+) -> std::io::Result<()> {
+    let base_seed = settings.seed.unwrap_or(0);
+    let mut reports = Vec::with_capacity(opts.repeat);
+    for i in 0..opts.repeat {
+        if opts.vary_seed {
+            settings.seed = Some(base_seed + i as u64);
+        }
+        let dir = opts.run_dir.as_ref().map(|d| d.join(format!("rep-{}", i)));
+        if let Some(report) = run_script(script, params, dir.as_deref(), &settings)? {
+            reports.push(report);
+        }
+    }
+    let summary = stats::RepeatSummary::from_reports(&reports);
+    summary.print();
+    if settings.audit_rng {
+        // Equal counts per site are a prerequisite for equal randomness, and
+        // usually where a nondeterministic run first shows.
+        match reports.iter().position(|r| r.rng_draws != reports[0].rng_draws) {
+            None => println!("🎲 All {} runs drew the RNG the same number of times at every site", reports.len()),
+            Some(i) => println!("🎲 Run {} drew the RNG differently from run 0", i),
+        }
+    }
+    if let Some(dir) = &opts.run_dir {
+        let json = serde_json::to_string_pretty(&summary).map_err(std::io::Error::other)?;
+        std::fs::write(dir.join("summary.json"), json)?;
+    }
+    let failed = reports.iter().filter(|r| r.failed_assertions() > 0).count();
+    if failed > 0 {
+        return Err(std::io::Error::other(format!("{}: assertions failed in {} of {} runs", script, failed, reports.len())));
+    }
+    Ok(())
+}
+
+/// Sweep one parameter over a range and rank output traces by their response to it.
+/// Runs in a run directory are resumable like any sweep.
+fn run_sensitivity(
+    script: &str,
+    params: &BTreeMap<String, String>,
+    opts: &cli::CliOptions,
+    settings: &RunSettings,
+) -> Result<(), String> {
+    let param = opts.param.as_deref().ok_or("sensitivity requires --param <name>")?;
+    let values = sensitivity::parse_range(opts.range.as_deref().ok_or("sensitivity requires --range start:end:count")?)?;
+    let mut samples = Vec::new();
+    match &opts.run_dir {
+        Some(root) => {
+            let axes = [(param.to_string(), values.iter().map(|v| v.to_string()).collect())];
+            let points = sweep::expand_grid(&axes);
+            sweep::run_sweep(root, &points, |point, dir| {
+                let mut all = params.clone();
+                all.extend(point.clone());
+                run_script(script, &all, Some(dir), settings).map(|_| ())
+            })
+            .map_err(|e| e.to_string())?;
+            // Read every point back from disk so resumed points count too.
+            for (point, x) in points.iter().zip(&values) {
+                let path = sweep::point_dir(root, point).join(rundir::REPORT_FILE);
+                let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                let report = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+                samples.push((*x, report));
+            }
+        }
+        None => {
+            for x in &values {
+                let mut all = params.clone();
+                all.insert(param.to_string(), x.to_string());
+                if let Some(report) = run_script(script, &all, None, settings).map_err(|e| e.to_string())? {
+                    samples.push((*x, report));
+                }
+            }
+        }
+    }
+    sensitivity::print_table(param, &sensitivity::analyze(&samples));
+    Ok(())
+}
+
 fn main() {
+    let opts = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
+        }
+    };
+    console::configure(opts.throttle);
+    if let Err(e) = open_console_log(&opts) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+    let bindings = opts.bindings();
+    let mut seed = opts.seed;
+    let mut script = opts.script.clone();
+    match &opts.command {
+        cli::Command::Pack { dir } => {
+            let output = opts.output.clone().unwrap_or_else(|| {
+                Path::new(dir).with_extension(package::PACKAGE_EXTENSION)
+            });
+            let result = package::Package::from_dir(Path::new(dir), opts.entry.as_deref(), opts.seed)
+                .and_then(|pkg| pkg.write(&output));
+            match result {
+                Ok(()) => println!("Packed {} into {}", dir, output.display()),
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        cli::Command::Run(path) if package::is_package(path) => {
+            let dir = std::env::temp_dir().join(format!("sptlpkg-{}", std::process::id()));
+            let entry = package::Package::read(Path::new(path)).and_then(|pkg| {
+                seed = seed.or(pkg.manifest.seed);
+                pkg.extract(&dir)
+            });
+            match entry {
+                Ok(entry) => script = Some(entry.to_string_lossy().into_owned()),
+                Err(e) => {
+                    eprintln!("error: {}: {}", path, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        cli::Command::NewExperiment { dir } => {
+            let Some(template) = &opts.template else {
+                eprintln!("error: new experiment requires --template <{}>", templates::TEMPLATE_NAMES.join("|"));
+                std::process::exit(2);
+            };
+            let dir = dir.clone().unwrap_or_else(|| template.clone());
+            match templates::scaffold(template, Path::new(&dir)) {
+                Ok(files) => {
+                    for f in files {
+                        println!("Created {}", f.display());
+                    }
+                }
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        cli::Command::Run(path) => script = Some(path.clone()),
+        cli::Command::Sensitivity(path) => {
+            let cache = opts.cache_dir.clone().map(sptl::cache::AstCache::new).or_else(sptl::cache::AstCache::from_env);
+            let settings = RunSettings {
+                seed,
+                cache,
+                check: false,
+                emit_json: false,
+                engine: opts.engine,
+                events: opts.events.clone(),
+                audit_rng: opts.audit_rng,
+            };
+            if let Err(e) = run_sensitivity(path, &bindings, &opts, &settings) {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        cli::Command::Ingest(path) => {
+            let text = std::fs::read_to_string(path).unwrap_or_else(|e| {
+                eprintln!("error: {}: {}", path, e);
+                std::process::exit(1);
+            });
+            let defaults = ingest::IngestOptions::default();
+            let options = ingest::IngestOptions {
+                agents: opts.agents.unwrap_or(defaults.agents),
+                tokens_per_tick: opts.tokens_per_tick.unwrap_or(defaults.tokens_per_tick),
+                ..defaults
+            };
+            let blocks = ingest::corpus_script(&text, &options);
+            // `--emit-json` saves the replay as a narrative to edit or rerun.
+            if opts.emit_json {
+                println!("{}", json::JsonProgram::narrative(blocks).to_json());
+                return;
+            }
+            let mut ctx = narrative::runner::ScriptContext::with_vars(bindings);
+            if let Err(e) = attach_event_log(&mut ctx.events, opts.events.as_deref()) {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+            narrative::runner::execute_script(&blocks, &mut ctx);
+            return;
+        }
+        cli::Command::Fmt(path) => {
+            let source = std::fs::read_to_string(path).unwrap_or_else(|e| {
+                eprintln!("error: {}: {}", path, e);
+                std::process::exit(1);
+            });
+            // `$name` parameters stay unbound so they are written back as they are.
+            let config = config::Config::for_script(Path::new(path));
+            let program = sptl::parse_source_with(&source, &BTreeMap::new(), &config).unwrap_or_else(|errors| {
+                for e in errors {
+                    eprintln!("{}: {}", path, e);
+                }
+                std::process::exit(1);
+            });
+            let formatted = sptl::format::format_program(&program, config.levels.as_deref());
+            if !opts.check {
+                print!("{}", formatted);
+            } else if formatted != source {
+                eprintln!("{} is not formatted", path);
+                std::process::exit(1);
+            }
+            return;
+        }
+        cli::Command::Repl => {
+            // `sptl.toml` in the working directory applies, as it would to a script there.
+            let config = config::Config::for_script(Path::new("repl.sptl"));
+            if let Err(e) = repl::Repl::new(bindings, config).run(std::io::stdin().lock()) {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        cli::Command::Serve(address) => {
+            let config = config::Config::for_script(Path::new("repl.sptl"));
+            let session = shared::SharedSession::new(repl::Repl::new(bindings, config));
+            if let Err(e) = session.serve(address.as_deref().unwrap_or(shared::DEFAULT_ADDRESS)) {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        cli::Command::Default => {}
+    }
+    if let Some(path) = &opts.narrative {
+        match std::fs::read_to_string(path) {
+            Ok(source) => {
+                let blocks = narrative::parser::parse_script(&source);
+                if opts.emit_json {
+                    println!("{}", json::JsonProgram::narrative(blocks).to_json());
+                    return;
+                }
+                let problems = narrative::contract::check(&blocks);
+                if !problems.is_empty() {
+                    for problem in problems {
+                        eprintln!("{}: {}", path, problem);
+                    }
+                    std::process::exit(1);
+                }
+                let mut ctx = narrative::runner::ScriptContext::with_vars(bindings);
+                ctx.world.ontology = config::Config::for_script(Path::new(path)).levels;
+                ctx.script = Some(path.to_string());
+                // As for SPTL, a `seed` in the script still wins.
+                if let Some(seed) = opts.seed {
+                    ctx.world.rng.reseed(seed);
+                }
+                if opts.audit_rng {
+                    ctx.world.rng.audit();
+                }
+                if let Err(e) = attach_event_log(&mut ctx.events, opts.events.as_deref()) {
+                    eprintln!("error: {}", e);
+                    std::process::exit(1);
+                }
+                let protocol = opts.check_protocol.then(|| ctx.events.subscribe_channel().1);
+                narrative::runner::execute_script(&blocks, &mut ctx);
+                if opts.audit_rng {
+                    print_rng_draws(&ctx.world.rng.draws());
+                }
+                if let Some(events) = protocol {
+                    let conformance = protocol::check(events.try_iter());
+                    conformance.print();
+                    if !conformance.is_conformant() {
+                        std::process::exit(1);
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("error: {}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    let settings = RunSettings {
+        seed,
+        cache: opts.cache_dir.clone().map(sptl::cache::AstCache::new).or_else(sptl::cache::AstCache::from_env),
+        check: opts.check,
+        emit_json: opts.emit_json,
+        engine: opts.engine,
+        events: opts.events.clone(),
+        audit_rng: opts.audit_rng,
+    };
+    if let Some(script) = &script {
+        if !opts.sweep.is_empty() {
+            let root = opts.run_dir.clone().unwrap_or_else(rundir::RunDir::default_path);
+            let points = sweep::expand_grid(&opts.sweep);
+            let result = sweep::run_sweep(&root, &points, |point, dir| {
+                let mut params = bindings.clone();
+                params.extend(point.clone());
+                match run_script(script, &params, Some(dir), &settings)? {
+                    Some(report) if report.failed_assertions() > 0 => {
+                        Err(std::io::Error::other(report.failure_summary().join("; ")))
+                    }
+                    _ => Ok(()),
+                }
+            });
+            match result {
+                Ok(summary) if summary.failed > 0 => std::process::exit(1),
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        if opts.repeat > 1 {
+            if let Err(e) = run_repeated(script, &bindings, &opts, settings) {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        match run_script(script, &bindings, opts.run_dir.as_deref(), &settings) {
+            Ok(Some(report)) if report.failed_assertions() > 0 => {
+                for failure in report.failure_summary() {
+                    eprintln!("{}: {}", script, failure);
+                }
+                eprintln!("{}: {} assertion(s) failed", script, report.failed_assertions());
+                // `exit` skips destructors; dropping the report flushes its event subscribers.
+                drop(report);
+                std::process::exit(1);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     // Multiprocessing: launch N separate interpreters
     let num_procs = 2;
     let scripts = vec!["slm.sptl"];
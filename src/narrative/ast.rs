@@ -1,24 +1,176 @@
 //! AST for SPTL narrative DSL with macro support
 
-#[derive(Debug, Clone)]
+use super::contract::Contract;
+use crate::condition::Condition;
+use crate::provenance::Question;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Block {
     AtTau(u64, Vec<Action>),
     Repeat(u32, Vec<Action>),
-    While(String, Vec<Action>),
+    While(Condition, Vec<Action>),
     Parallel(Vec<Action>),
-    MacroDef { name: String, params: Vec<String>, body: Vec<Action> },
+    /// `macro name(params) ["doc"]:` with its body; `requires` and
+    /// `creates` lines in the body make up `contract`.
+    MacroDef {
+        name: String,
+        params: Vec<String>,
+        body: Vec<Action>,
+        #[serde(default, skip_serializing_if = "Contract::is_empty")]
+        contract: Contract,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        doc: Option<String>,
+    },
+    Rule(Rule),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Action {
-    Conditional(String, Vec<Action>),
-    CreateAgent { name: String, mem: u32, coh: f32 },
+    /// `if <condition>:` with its body, then the `else:` body, empty without
+    /// one; `elif` nests as a single `Conditional` in the `else` body.
+    Conditional(Condition, Vec<Action>, Vec<Action>),
+    /// `create agent alice 64 0.2 [in A1] ["doc"]`
+    CreateAgent {
+        name: String,
+        mem: u32,
+        coh: f32,
+        within: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        doc: Option<String>,
+    },
+    /// `load agent alice from "run1/agents/alice.json" [in A1]`: restore a saved
+    /// agent's symbol table and memory under a new name.
+    LoadAgent { name: String, path: String, within: Option<String> },
+    /// `save agent alice to "run1/agents/alice.json"`
+    SaveAgent { name: String, path: String },
+    /// `save lexicon [of alice bob] to "vocab.json"`: the shared token → pattern
+    /// table of the named agents, or of every agent.
+    SaveLexicon { agents: Vec<String>, path: String },
+    /// `load lexicon "vocab.json" into alice bob`: teach the agents a saved lexicon.
+    LoadLexicon { path: String, agents: Vec<String> },
+    /// `run "pipeline.sptl"`: execute an SPTL script on this world, sharing its
+    /// fields, interpretations, hierarchies and RNG.
+    RunSptl(String),
+    /// `world fork as W2`: keep a copy of the current state as world `W2`.
+    ForkWorld(String),
+    /// `world switch W2`: continue in world `W2`, keeping the current one.
+    SwitchWorld(String),
+    /// `world diff W2`: print how world `W2` differs from the current one.
+    DiffWorld(String),
+    /// `world create W3`: an empty world at the current τ, kept until switched to.
+    CreateWorld(String),
+    /// `bridge F@main -> G@W2 [strength 0.5]`
+    Bridge(Bridge),
+    /// `create molecule M from atoms A1 A2`: the level word after `from` is optional.
+    CreateLevel { level: String, name: String, parts: Vec<String> },
+    Promote(String),
+    MigrateAgent { agent: String, from: String, to: String, copy: bool },
+    /// `group learners = alice bob carol`: name agents for a `consensus` condition.
+    DefineGroup { name: String, members: Vec<String> },
+    /// `random agents 50 [named learner] [mem 64] coh 0.1..0.4 [group learners]`:
+    /// agents `learner1`, `learner2`, ... (`agent1`, ... by default) with
+    /// coherence thresholds drawn uniformly from the range, optionally added
+    /// to a group.
+    RandomAgents { count: usize, prefix: String, mem: u32, coh: (f64, f64), group: Option<String> },
+    /// `random symbols 20 bits 8 [named sym] into alice bob`: tokens `sym1`,
+    /// ... with random patterns, all expressed by every named agent or
+    /// member of a named group.
+    RandomSymbols { count: usize, bits: usize, prefix: String, into: Vec<String> },
+    /// `seed 42`: restart the world's RNG, as SPTL `seed` does.
+    Seed(u64),
     MacroCall { name: String, args: Vec<String> },
+    /// `custom my_sensor_read alice`: run the Rust closure the host registered
+    /// as `my_sensor_read` (`ScriptContext::register_action`) with the
+    /// remaining words as arguments.
+    Custom { name: String, args: Vec<String> },
     VariableAssignment { name: String, value: String },
     Say { agent: String, token: String, pattern: String },
     Interpret { agent: String, token: String },
-    Project { agent: String, token: String },
+    /// `alice projects: fire [into F]`: activate the agent's pattern for the
+    /// token in field `F`, or only announce the projection without `into`.
+    Project { agent: String, token: String, into: Option<String> },
+    /// `why pattern 1010 in F` or `why token fire in alice`: print the script
+    /// lines that caused it.
+    Why(Question),
+    /// `describe alice`: print what `alice` names (agent, field,
+    /// interpretation or macro) and its doc string.
+    Describe(String),
+    /// A `#line`-style marker the parser puts before every action it reads:
+    /// the line the next action was written on. State changes made by the
+    /// actions that follow are attributed to it (see `provenance`).
+    Source { line: usize, text: String },
     Tick(u32),
-    Assert(String),
+    /// `field F [size] 16 ["doc"]`
+    Field {
+        name: String,
+        size: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        doc: Option<String>,
+    },
+    /// `interpretation I = 1 0 1 ["doc"]`
+    Interpretation {
+        name: String,
+        values: Vec<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        doc: Option<String>,
+    },
+    /// `measure d = distance(F, I)`; `metric` is any SPTL metric name, such as `coherence`.
+    Measure { name: String, metric: String, field: String, interp: String },
+    Log(String),
+    Perturb { field: String, amplitude: f64 },
+    Shock { field: String, indices: String, value: f64 },
+    /// Forget each of an agent's memories with probability `rate`.
+    PerturbMemory { agent: String, rate: f64 },
+    /// `pin fire in alice`: exempt the agent's traces of the token from
+    /// decay, eviction and forgetting; `unpin fire in alice` undoes it.
+    Pin { agent: String, token: String, pinned: bool },
+    /// `assert bob knows fire`: report whether the condition holds.
+    Assert(Condition),
     Comment(String),
-}
\ No newline at end of file
+}
+
+/// `F@W2`: field `F` of world `W2`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldRef {
+    pub field: String,
+    pub world: String,
+}
+
+/// Couples two fields across worlds: when declared and on every `tick`, the
+/// target moves `strength` of the way toward the source. Worlds share
+/// nothing else.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bridge {
+    pub from: FieldRef,
+    pub to: FieldRef,
+    pub strength: f64,
+}
+
+/// `when <condition> [priority N] [refractory N] then: <actions>`: a
+/// production rule, evaluated once per τ (see `narrative::rules`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    pub condition: Condition,
+    pub actions: Vec<Action>,
+    /// Rules holding at the same τ fire highest priority first.
+    pub priority: i32,
+    /// τ that must pass after the rule fires before it may fire again.
+    pub refractory: u64,
+}
+
+impl Rule {
+    pub fn new(condition: Condition, actions: Vec<Action>) -> Self {
+        Rule { condition, actions, priority: 0, refractory: 0 }
+    }
+
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn refractory(mut self, refractory: u64) -> Self {
+        self.refractory = refractory;
+        self
+    }
+}
@@ -0,0 +1,175 @@
+//! Building narrative scripts in Rust instead of generating script text.
+//!
+//! `Script` collects the `Block`s `parse_script` produces, each block's
+//! actions written with `Actions`:
+//!
+//! ```ignore
+//! let script = Script::new()
+//!     .at(0, |a| a.agent("alice", 16, 0.1).field("F", 4).say("alice", "fire", "1010"))
+//!     .repeat(3, |a| a.project("alice", "fire", Some("F")).tick(1))
+//!     .build();
+//! ```
+//!
+//! Built actions carry no `Source` markers, so `why` answers name no script
+//! line for the state they change.
+
+use super::ast::{Action, Block, Rule};
+use crate::condition::Condition;
+
+/// A script under construction.
+#[derive(Debug, Clone, Default)]
+pub struct Script {
+    blocks: Vec<Block>,
+}
+
+impl Script {
+    pub fn new() -> Self {
+        Script::default()
+    }
+
+    /// Append any block, for those without a method of their own.
+    pub fn block(mut self, block: Block) -> Self {
+        self.blocks.push(block);
+        self
+    }
+
+    /// `at τ=tau:`
+    pub fn at(self, tau: u64, actions: impl FnOnce(Actions) -> Actions) -> Self {
+        self.block(Block::AtTau(tau, actions(Actions::new()).build()))
+    }
+
+    /// `repeat count times:`
+    pub fn repeat(self, count: u32, actions: impl FnOnce(Actions) -> Actions) -> Self {
+        self.block(Block::Repeat(count, actions(Actions::new()).build()))
+    }
+
+    /// `while condition:`
+    pub fn while_(self, condition: Condition, actions: impl FnOnce(Actions) -> Actions) -> Self {
+        self.block(Block::While(condition, actions(Actions::new()).build()))
+    }
+
+    /// `parallel:`
+    pub fn parallel(self, actions: impl FnOnce(Actions) -> Actions) -> Self {
+        self.block(Block::Parallel(actions(Actions::new()).build()))
+    }
+
+    /// `when condition [priority N] [refractory N] then:`
+    pub fn rule(self, rule: Rule) -> Self {
+        self.block(Block::Rule(rule))
+    }
+
+    pub fn build(self) -> Vec<Block> {
+        self.blocks
+    }
+}
+
+impl From<Script> for Vec<Block> {
+    fn from(script: Script) -> Self {
+        script.build()
+    }
+}
+
+/// The actions of one block, in order.
+#[derive(Debug, Clone, Default)]
+pub struct Actions {
+    actions: Vec<Action>,
+}
+
+impl Actions {
+    pub fn new() -> Self {
+        Actions::default()
+    }
+
+    /// Append any action, for those without a method of their own.
+    pub fn action(mut self, action: Action) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    /// `create agent name mem coh`
+    pub fn agent(self, name: &str, mem: u32, coh: f32) -> Self {
+        self.action(Action::CreateAgent { name: name.to_string(), mem, coh, within: None, doc: None })
+    }
+
+    /// `field name size`
+    pub fn field(self, name: &str, size: usize) -> Self {
+        self.action(Action::Field { name: name.to_string(), size, doc: None })
+    }
+
+    /// `interpretation name = values`
+    pub fn interpretation(self, name: &str, values: impl Into<Vec<f64>>) -> Self {
+        self.action(Action::Interpretation { name: name.to_string(), values: values.into(), doc: None })
+    }
+
+    /// `agent says: token → pattern`
+    pub fn say(self, agent: &str, token: &str, pattern: &str) -> Self {
+        self.action(Action::Say { agent: agent.to_string(), token: token.to_string(), pattern: pattern.to_string() })
+    }
+
+    /// `agent interprets: token`
+    pub fn interpret(self, agent: &str, token: &str) -> Self {
+        self.action(Action::Interpret { agent: agent.to_string(), token: token.to_string() })
+    }
+
+    /// `agent projects: token [into field]`
+    pub fn project(self, agent: &str, token: &str, into: Option<&str>) -> Self {
+        self.action(Action::Project { agent: agent.to_string(), token: token.to_string(), into: into.map(str::to_string) })
+    }
+
+    /// `measure name = metric(field, interp)`
+    pub fn measure(self, name: &str, metric: &str, field: &str, interp: &str) -> Self {
+        self.action(Action::Measure {
+            name: name.to_string(),
+            metric: metric.to_string(),
+            field: field.to_string(),
+            interp: interp.to_string(),
+        })
+    }
+
+    /// `perturb field noise amplitude`
+    pub fn perturb(self, field: &str, amplitude: f64) -> Self {
+        self.action(Action::Perturb { field: field.to_string(), amplitude })
+    }
+
+    /// `log text`
+    pub fn log(self, text: &str) -> Self {
+        self.action(Action::Log(text.to_string()))
+    }
+
+    /// `tick n`
+    pub fn tick(self, n: u32) -> Self {
+        self.action(Action::Tick(n))
+    }
+
+    /// `seed value`
+    pub fn seed(self, value: u64) -> Self {
+        self.action(Action::Seed(value))
+    }
+
+    /// `assert condition`
+    pub fn assert(self, condition: Condition) -> Self {
+        self.action(Action::Assert(condition))
+    }
+
+    /// `if condition:` with the actions `then` adds.
+    pub fn if_then(self, condition: Condition, then: impl FnOnce(Actions) -> Actions) -> Self {
+        self.if_else(condition, then, |a| a)
+    }
+
+    /// `if condition:` with the actions `then` adds, and `else:` with those
+    /// `otherwise` adds; an empty `otherwise` has no `else`.
+    pub fn if_else(
+        self,
+        condition: Condition,
+        then: impl FnOnce(Actions) -> Actions,
+        otherwise: impl FnOnce(Actions) -> Actions,
+    ) -> Self {
+        let then = then(Actions::new()).build();
+        let otherwise = otherwise(Actions::new()).build();
+        self.action(Action::Conditional(condition, then, otherwise))
+    }
+
+    pub fn build(self) -> Vec<Action> {
+        self.actions
+    }
+}
@@ -0,0 +1,291 @@
+//! Contracts of narrative macros: what a macro needs and what it makes.
+//!
+//! Clauses written at the top level of a macro body declare the contract:
+//!
+//! ```text
+//! macro introduce(a, b):
+//!   requires agent $a exists
+//!   creates agent $b
+//!   create agent $b 16 0.1
+//!   $a says: hello → 1010
+//! ```
+//!
+//! `requires <kind> <name> [exists]` and `creates <kind> <name>`, with
+//! `<kind>` one of `agent`, `field`, `interpretation` or `group`. `check`
+//! validates a script before it runs: clauses may only name the macro's
+//! parameters or fixed names, a declared effect must have something in the
+//! body that could produce it, calls must name a defined macro with the
+//! right number of arguments, and a call's requirement must be created
+//! somewhere in the script unless an action the check cannot see into
+//! (`custom`, `run`, `random agents`) might create it. At call time the
+//! runner checks requirements again before running the body, and effects
+//! after it.
+
+use super::ast::{Action, Block};
+use crate::runtime::Runtime;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ResourceKind {
+    Agent,
+    Field,
+    Interpretation,
+    Group,
+}
+
+impl ResourceKind {
+    pub fn from_name(name: &str) -> Option<ResourceKind> {
+        match name {
+            "agent" => Some(ResourceKind::Agent),
+            "field" => Some(ResourceKind::Field),
+            "interpretation" => Some(ResourceKind::Interpretation),
+            "group" => Some(ResourceKind::Group),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ResourceKind::Agent => "agent",
+            ResourceKind::Field => "field",
+            ResourceKind::Interpretation => "interpretation",
+            ResourceKind::Group => "group",
+        }
+    }
+}
+
+/// A named thing in a world, as `agent $a` or `field F`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Resource {
+    pub kind: ResourceKind,
+    pub name: String,
+}
+
+impl Resource {
+    /// Whether `world` has it.
+    pub fn exists(&self, world: &Runtime) -> bool {
+        match self.kind {
+            ResourceKind::Agent => world.agents.contains_key(&self.name),
+            ResourceKind::Field => world.fields.contains_key(&self.name),
+            ResourceKind::Interpretation => world.interps.contains_key(&self.name),
+            ResourceKind::Group => world.groups.contains_key(&self.name),
+        }
+    }
+
+    /// `$param` replaced by the argument bound to it.
+    fn bind(&self, params: &[String], args: &[String]) -> Resource {
+        let name = match self.name.strip_prefix('$').and_then(|p| params.iter().position(|q| q == p)) {
+            Some(i) => args[i].clone(),
+            None => self.name.clone(),
+        };
+        Resource { kind: self.kind, name }
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.kind.name(), self.name)
+    }
+}
+
+/// Preconditions and effects of a macro; empty when it declares none.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contract {
+    pub requires: Vec<Resource>,
+    pub creates: Vec<Resource>,
+}
+
+impl Contract {
+    pub fn is_empty(&self) -> bool {
+        self.requires.is_empty() && self.creates.is_empty()
+    }
+
+    /// Add the clause on `line` if it is one. `Some(Err)` for a line that
+    /// starts like a clause but is not a valid one.
+    pub fn parse_clause(&mut self, line: &str) -> Option<Result<(), String>> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (requires, rest) = match words.as_slice() {
+            ["requires", rest @ ..] => (true, rest),
+            ["creates", rest @ ..] => (false, rest),
+            _ => return None,
+        };
+        let usage = || format!("Expected 'requires <kind> <name> [exists]' or 'creates <kind> <name>': {}", line);
+        let (kind, name) = match (requires, rest) {
+            (true, [kind, name] | [kind, name, "exists"]) | (false, [kind, name]) => (kind, name),
+            _ => return Some(Err(usage())),
+        };
+        let Some(kind) = ResourceKind::from_name(kind) else {
+            return Some(Err(format!("Unknown kind '{}' (expected agent, field, interpretation or group): {}", kind, line)));
+        };
+        let resource = Resource { kind, name: name.to_string() };
+        if requires {
+            self.requires.push(resource);
+        } else {
+            self.creates.push(resource);
+        }
+        Some(Ok(()))
+    }
+
+    /// Requirements of a call with `args` bound to `params`.
+    pub fn required(&self, params: &[String], args: &[String]) -> Vec<Resource> {
+        self.requires.iter().map(|r| r.bind(params, args)).collect()
+    }
+
+    /// Effects of a call with `args` bound to `params`.
+    pub fn created(&self, params: &[String], args: &[String]) -> Vec<Resource> {
+        self.creates.iter().map(|r| r.bind(params, args)).collect()
+    }
+}
+
+/// A macro as defined, for `check`.
+struct Signature<'a> {
+    params: &'a [String],
+    body: &'a [Action],
+    contract: &'a Contract,
+}
+
+/// Problems with the macros of `blocks` found without running them, one
+/// message each; empty if there are none.
+pub fn check(blocks: &[Block]) -> Vec<String> {
+    let mut macros = HashMap::new();
+    for block in blocks {
+        if let Block::MacroDef { name, params, body, contract, .. } = block {
+            macros.insert(name.as_str(), Signature { params, body, contract });
+        }
+    }
+    let mut errors = Vec::new();
+    let mut definitions: Vec<(&&str, &Signature)> = macros.iter().collect();
+    definitions.sort_by_key(|(name, _)| **name);
+    for (name, signature) in definitions {
+        check_definition(name, signature, &mut errors);
+    }
+
+    let all_actions: Vec<&Action> = blocks.iter().flat_map(block_actions).flat_map(flatten).collect();
+    let (mut created, opaque) = creations(&all_actions);
+    let calls: Vec<(Option<usize>, &Action)> = with_lines(blocks.iter().flat_map(block_actions));
+    for (_, action) in &calls {
+        if let Action::MacroCall { name, args } = action {
+            if let Some(signature) = macros.get(name.as_str()).filter(|s| s.params.len() == args.len()) {
+                created.extend(signature.contract.created(signature.params, args));
+            }
+        }
+    }
+    for (line, action) in calls {
+        let Action::MacroCall { name, args } = action else { continue };
+        let at = line.map(|l| format!("line {}: ", l)).unwrap_or_default();
+        let Some(signature) = macros.get(name.as_str()) else {
+            errors.push(format!("{}{}({}): no macro named {}", at, name, args.join(", "), name));
+            continue;
+        };
+        if signature.params.len() != args.len() {
+            errors.push(format!(
+                "{}{}({}): {} expects {} argument(s), got {}",
+                at,
+                name,
+                args.join(", "),
+                name,
+                signature.params.len(),
+                args.len()
+            ));
+            continue;
+        }
+        for resource in signature.contract.required(signature.params, args) {
+            if resource.name.contains('$') || opaque.contains(&resource.kind) || created.contains(&resource) {
+                continue;
+            }
+            errors.push(format!("{}{}({}): requires {}, but nothing in the script creates it", at, name, args.join(", "), resource));
+        }
+    }
+    errors
+}
+
+/// A definition's clauses may only name its parameters, and each effect needs an action that could produce it.
+fn check_definition(name: &str, signature: &Signature, errors: &mut Vec<String>) {
+    let clauses = signature.contract.requires.iter().map(|r| ("requires", r)).chain(signature.contract.creates.iter().map(|r| ("creates", r)));
+    for (verb, resource) in clauses {
+        if let Some(param) = resource.name.strip_prefix('$') {
+            if !signature.params.iter().any(|p| p == param) {
+                errors.push(format!("macro {}: `{} {}` names ${}, which is not a parameter of {}", name, verb, resource, param, name));
+            }
+        }
+    }
+    let body: Vec<&Action> = signature.body.iter().flat_map(flatten).collect();
+    let (created, opaque) = creations(&body);
+    let calls_macros = body.iter().any(|a| matches!(a, Action::MacroCall { .. }));
+    for resource in &signature.contract.creates {
+        if !calls_macros && !opaque.contains(&resource.kind) && !created.contains(resource) {
+            errors.push(format!("macro {}: declares `creates {}`, but nothing in its body creates it", name, resource));
+        }
+    }
+}
+
+fn block_actions(block: &Block) -> &[Action] {
+    match block {
+        Block::AtTau(_, actions) | Block::Repeat(_, actions) | Block::While(_, actions) | Block::Parallel(actions) => actions,
+        Block::MacroDef { body, .. } => body,
+        Block::Rule(rule) => &rule.actions,
+    }
+}
+
+/// `action` and the actions nested in it, in order.
+fn flatten(action: &Action) -> Vec<&Action> {
+    match action {
+        Action::Conditional(_, then, otherwise) => {
+            std::iter::once(action).chain(then.iter().chain(otherwise).flat_map(flatten)).collect()
+        }
+        _ => vec![action],
+    }
+}
+
+/// Every action with the script line of the `Source` marker before it.
+fn with_lines<'a>(actions: impl Iterator<Item = &'a Action>) -> Vec<(Option<usize>, &'a Action)> {
+    let mut line = None;
+    let mut out = Vec::new();
+    for action in actions.flat_map(flatten) {
+        match action {
+            Action::Source { line: l, .. } => line = Some(*l),
+            other => out.push((line, other)),
+        }
+    }
+    out
+}
+
+/// What `actions` create by name, and the kinds they may create under
+/// names the check cannot know.
+fn creations(actions: &[&Action]) -> (BTreeSet<Resource>, BTreeSet<ResourceKind>) {
+    let mut created = BTreeSet::new();
+    let mut opaque = BTreeSet::new();
+    let mut add = |kind, name: &String| created.insert(Resource { kind, name: name.clone() });
+    for action in actions {
+        match action {
+            Action::CreateAgent { name, .. } | Action::LoadAgent { name, .. } => {
+                add(ResourceKind::Agent, name);
+            }
+            Action::Field { name, .. } => {
+                add(ResourceKind::Field, name);
+            }
+            Action::Interpretation { name, .. } => {
+                add(ResourceKind::Interpretation, name);
+            }
+            Action::DefineGroup { name, .. } => {
+                add(ResourceKind::Group, name);
+            }
+            Action::RandomAgents { group, .. } => {
+                opaque.insert(ResourceKind::Agent);
+                if let Some(group) = group {
+                    add(ResourceKind::Group, group);
+                }
+            }
+            Action::RunSptl(_) => {
+                opaque.extend([ResourceKind::Field, ResourceKind::Interpretation]);
+            }
+            Action::Custom { .. } => {
+                opaque.extend([ResourceKind::Agent, ResourceKind::Field, ResourceKind::Interpretation, ResourceKind::Group]);
+            }
+            _ => {}
+        }
+    }
+    (created, opaque)
+}
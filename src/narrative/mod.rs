@@ -1,3 +1,8 @@
 pub mod ast;
+pub mod build;
+pub mod contract;
 pub mod parser;
-pub mod runner;
\ No newline at end of file
+pub mod rules;
+pub mod runner;
+pub mod scenario;
+pub mod world;
@@ -1,30 +1,46 @@
 //! Parser for SPTL narrative DSL with macro support
 
-use super::ast::{Block, Action};
+use super::ast::{Block, Action, Bridge, FieldRef, Rule};
+use super::contract::Contract;
+use super::scenario;
+use crate::condition::Condition;
+use crate::provenance::Question;
+use crate::sptl::Metric;
 use std::collections::VecDeque;
 
 struct LineCursor<'a> {
     lines: VecDeque<(usize, &'a str)>,
+    /// 1-based script line of each entry in `lines`.
+    numbers: VecDeque<usize>,
+    /// Line number of the entry `next` returned last.
+    last: usize,
 }
 impl<'a> LineCursor<'a> {
     fn from(script: &'a str) -> Self {
         let mut lines = VecDeque::new();
-        for line in script.lines() {
+        let mut numbers = VecDeque::new();
+        for (number, line) in script.lines().enumerate() {
             let trimmed = line.trim_start();
             if trimmed.is_empty() || trimmed.starts_with('#') {
                 continue;
             }
             let indent = line.len() - trimmed.len();
             lines.push_back((indent, trimmed));
+            numbers.push_back(number + 1);
         }
-        Self { lines }
+        Self { lines, numbers, last: 0 }
     }
     fn peek(&self) -> Option<&(usize, &'a str)> {
         self.lines.front()
     }
     fn next(&mut self) -> Option<(usize, &'a str)> {
+        self.last = self.numbers.pop_front().unwrap_or(self.last);
         self.lines.pop_front()
     }
+    /// The marker attributing what follows to the line `next` returned last.
+    fn source(&self, text: &str) -> Action {
+        Action::Source { line: self.last, text: text.trim().to_string() }
+    }
 }
 
 pub fn parse_script(script: &str) -> Vec<Block> {
@@ -41,6 +57,8 @@ pub fn parse_script(script: &str) -> Vec<Block> {
             blocks.push(parse_while(&mut cursor));
         } else if line.starts_with("parallel:") {
             blocks.push(parse_parallel(&mut cursor));
+        } else if line.starts_with("when ") {
+            blocks.push(parse_rule(&mut cursor));
         } else {
             blocks.push(parse_at_tau(&mut cursor));
         }
@@ -54,19 +72,28 @@ fn parse_macro_def(cursor: &mut LineCursor) -> Block {
     let open_paren = header.find('(').unwrap();
     let close_paren = header.find(')').unwrap();
     let name = header[..open_paren].trim().to_string();
+    // macro greet(a) "doc string":
+    let (_, doc) = split_doc(header[close_paren + 1..].trim_end().trim_end_matches(':'));
     let params: Vec<String> = header[open_paren + 1..close_paren]
         .split(',')
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty())
         .collect();
     let mut body = Vec::new();
-    while let Some((indent, _)) = cursor.peek() {
-        if *indent <= base_indent {
+    let mut contract = Contract::default();
+    while let Some(&(indent, line)) = cursor.peek() {
+        if indent <= base_indent {
             break;
         }
-        body.append(&mut parse_action_block(cursor, base_indent + 2));
+        match contract.parse_clause(line) {
+            Some(clause) => {
+                cursor.next();
+                clause.unwrap_or_else(|e| panic!("{}", e));
+            }
+            None => body.append(&mut parse_action_block(cursor, base_indent + 2)),
+        }
     }
-    Block::MacroDef { name, params, body }
+    Block::MacroDef { name, params, body, contract, doc }
 }
 
 fn parse_at_tau(cursor: &mut LineCursor) -> Block {
@@ -98,7 +125,7 @@ fn parse_repeat(cursor: &mut LineCursor) -> Block {
 
 fn parse_while(cursor: &mut LineCursor) -> Block {
     let (base_indent, header) = cursor.next().unwrap();
-    let cond = header.trim_start_matches("while").trim_end_matches(':').trim().to_string();
+    let cond = parse_condition(header.trim_start_matches("while").trim_end_matches(':'));
     let mut actions = Vec::new();
     while let Some((indent, _)) = cursor.peek() {
         if *indent <= base_indent {
@@ -109,6 +136,41 @@ fn parse_while(cursor: &mut LineCursor) -> Block {
     Block::While(cond, actions)
 }
 
+/// `when <condition> [priority N] [refractory N] then: <indented actions>`,
+/// or with a single action after `then` on the same line.
+fn parse_rule(cursor: &mut LineCursor) -> Block {
+    let (base_indent, header) = cursor.next().unwrap();
+    let usage = format!("Expected 'when <condition> [priority N] [refractory N] then: ...': {}", header);
+    let (head, then) = header.trim_start_matches("when").split_once(" then").expect(&usage);
+    let mut words: Vec<&str> = head.split_whitespace().collect();
+    let (mut priority, mut refractory) = (0, 0);
+    while let [.., key, value] = words[..] {
+        match key {
+            "priority" => priority = value.parse().expect(&usage),
+            "refractory" => refractory = value.parse().expect(&usage),
+            _ => break,
+        }
+        words.truncate(words.len() - 2);
+    }
+    let condition = parse_condition(&words.join(" "));
+    let mut actions = Vec::new();
+    match then.trim().trim_start_matches(':').trim() {
+        "" => {
+            while let Some((indent, _)) = cursor.peek() {
+                if *indent <= base_indent {
+                    break;
+                }
+                actions.append(&mut parse_action_block(cursor, base_indent + 2));
+            }
+        }
+        action => {
+            actions.push(cursor.source(header));
+            actions.push(parse_action(action));
+        }
+    }
+    Block::Rule(Rule::new(condition, actions).priority(priority).refractory(refractory))
+}
+
 fn parse_parallel(cursor: &mut LineCursor) -> Block {
     let (base_indent, _) = cursor.next().unwrap();
     let mut actions = Vec::new();
@@ -121,41 +183,257 @@ fn parse_parallel(cursor: &mut LineCursor) -> Block {
     Block::Parallel(actions)
 }
 
+fn parse_condition(text: &str) -> Condition {
+    Condition::parse(text).unwrap_or_else(|| panic!("Unrecognized condition '{}'", text.trim()))
+}
+
+/// The next action, or `if` block, after the marker of the line it is on.
 fn parse_action_block(cursor: &mut LineCursor, min_indent: usize) -> Vec<Action> {
     let (indent, line) = cursor.next().unwrap();
-    if line.starts_with("if ") && line.ends_with(':') {
-        let cond = line.trim_start_matches("if").trim_end_matches(':').trim().to_string();
-        let mut subactions = Vec::new();
-        while let Some((next_indent, _)) = cursor.peek() {
-            if *next_indent <= indent {
-                break;
-            }
-            subactions.append(&mut parse_action_block(cursor, indent + 2));
+    let source = cursor.source(line);
+    match line.strip_prefix("if ").and_then(|rest| rest.strip_suffix(':')) {
+        Some(condition) => vec![source, parse_conditional(cursor, indent, condition)],
+        None => vec![source, parse_action(line)],
+    }
+}
+
+/// The body of an `if` or `elif` written at `indent`, and the `elif:` or
+/// `else:` that follows it at the same indent.
+fn parse_conditional(cursor: &mut LineCursor, indent: usize, condition: &str) -> Action {
+    let cond = parse_condition(condition);
+    let then = parse_branch(cursor, indent);
+    let otherwise = match cursor.peek() {
+        Some(&(next_indent, line)) if next_indent == indent && line.trim_end() == "else:" => {
+            cursor.next();
+            parse_branch(cursor, indent)
         }
-        vec![Action::Conditional(cond, subactions)]
-    } else {
-        vec![parse_action(line)]
+        Some(&(next_indent, line)) if next_indent == indent && line.starts_with("elif ") && line.ends_with(':') => {
+            cursor.next();
+            let source = cursor.source(line);
+            let condition = &line["elif ".len()..line.len() - 1];
+            vec![source, parse_conditional(cursor, indent, condition)]
+        }
+        _ => Vec::new(),
+    };
+    Action::Conditional(cond, then, otherwise)
+}
+
+/// The actions indented under a branch header at `indent`.
+fn parse_branch(cursor: &mut LineCursor, indent: usize) -> Vec<Action> {
+    let mut actions = Vec::new();
+    while let Some((next_indent, _)) = cursor.peek() {
+        if *next_indent <= indent {
+            break;
+        }
+        actions.append(&mut parse_action_block(cursor, indent + 2));
     }
+    actions
 }
 
 fn parse_action(line: &str) -> Action {
     if let Some(rest) = line.strip_prefix("create agent ") {
+        let (rest, doc) = split_doc(rest);
         let mut parts = rest.split_whitespace();
         let name = parts.next().unwrap().to_string();
         let mem: u32 = parts.next().unwrap().parse().unwrap();
         let coh: f32 = parts.next().unwrap().parse().unwrap();
-        Action::CreateAgent { name, mem, coh }
+        // Optional placement: create agent alice 64 0.2 in A1
+        let within = match parts.next() {
+            Some("in") => parts.next().map(|s| s.to_string()),
+            _ => None,
+        };
+        Action::CreateAgent { name, mem, coh, within, doc }
+    } else if let Some(rest) = line.strip_prefix("load agent ") {
+        // load agent alice from "run1/agents/alice.json" in A1
+        let (name, rest) = rest.split_once(" from ")
+            .unwrap_or_else(|| panic!("Expected 'load agent <name> from \"<path>\" [in <id>]': {}", line));
+        let (path, rest) = split_path(rest);
+        let within = match rest.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [] => None,
+            ["in", id] => Some(id.to_string()),
+            _ => panic!("Expected 'load agent <name> from \"<path>\" [in <id>]': {}", line),
+        };
+        Action::LoadAgent { name: name.trim().to_string(), path, within }
+    } else if let Some(rest) = line.strip_prefix("load lexicon ") {
+        // load lexicon "vocab.json" into alice bob
+        let (path, rest) = split_path(rest);
+        let agents = match rest.trim().strip_prefix("into ") {
+            Some(names) if !names.trim().is_empty() => names.split_whitespace().map(|s| s.to_string()).collect(),
+            _ => panic!("Expected 'load lexicon \"<path>\" into <agent>...': {}", line),
+        };
+        Action::LoadLexicon { path, agents }
+    } else if let Some(rest) = line.strip_prefix("run ") {
+        // run "pipeline.sptl"
+        let (path, _) = split_path(rest);
+        Action::RunSptl(path)
+    } else if let Some(rest) = line.strip_prefix("save lexicon ") {
+        // save lexicon of alice bob to "vocab.json", or every agent's without `of`
+        let rest = format!(" {}", rest);
+        let (names, path) = rest.split_once(" to ")
+            .unwrap_or_else(|| panic!("Expected 'save lexicon [of <agent>...] to \"<path>\"': {}", line));
+        let agents = match names.trim() {
+            "" => Vec::new(),
+            names => match names.strip_prefix("of ") {
+                Some(names) => names.split_whitespace().map(|s| s.to_string()).collect(),
+                None => panic!("Expected 'save lexicon [of <agent>...] to \"<path>\"': {}", line),
+            },
+        };
+        let (path, _) = split_path(path);
+        Action::SaveLexicon { agents, path }
+    } else if let Some(rest) = line.strip_prefix("save agent ") {
+        // save agent alice to "run1/agents/alice.json"
+        let (name, rest) = rest.split_once(" to ")
+            .unwrap_or_else(|| panic!("Expected 'save agent <name> to \"<path>\"': {}", line));
+        let (path, _) = split_path(rest);
+        Action::SaveAgent { name: name.trim().to_string(), path }
+} else if let Some((verb, rest)) = line.strip_prefix("move agent ").map(|r| ("move", r))
+        .or_else(|| line.strip_prefix("copy agent ").map(|r| ("copy", r)))
+    {
+        // move agent alice from M to C
+        let parts: Vec<&str> = rest.split_whitespace().collect();
+        if parts.len() != 5 || parts[1] != "from" || parts[3] != "to" {
+            panic!("Expected '{} agent <name> from <id> to <id>': {}", verb, line);
+        }
+        Action::MigrateAgent {
+            agent: parts[0].to_string(),
+            from: parts[2].to_string(),
+            to: parts[4].to_string(),
+            copy: verb == "copy",
+        }
+    } else if let Some(rest) = line.strip_prefix("world ") {
+        // world fork as W2 | world switch W2 | world diff W2 | world create W3
+        match rest.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["fork", "as", name] => Action::ForkWorld(name.to_string()),
+            ["switch", name] => Action::SwitchWorld(name.to_string()),
+            ["diff", name] => Action::DiffWorld(name.to_string()),
+            ["create", name] => Action::CreateWorld(name.to_string()),
+            _ => panic!(
+                "Expected 'world fork as <name>', 'world switch <name>', 'world diff <name>' or 'world create <name>': {}",
+                line
+            ),
+        }
+    } else if let Some(rest) = line.strip_prefix("bridge ") {
+        // bridge F@main -> G@W2 strength 0.5
+        let usage = format!("Expected 'bridge <field>@<world> -> <field>@<world> [strength <k>]': {}", line);
+        let (from, to, strength) = match rest.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [from, "->" | "→", to] => (*from, *to, 1.0),
+            [from, "->" | "→", to, "strength", k] => (*from, *to, k.parse().expect(&usage)),
+            _ => panic!("{}", usage),
+        };
+        let field_ref = |text: &str| {
+            let (field, world) = text.split_once('@').expect(&usage);
+            FieldRef { field: field.to_string(), world: world.to_string() }
+        };
+        Action::Bridge(Bridge { from: field_ref(from), to: field_ref(to), strength })
+} else if let Some(rest) = line.strip_prefix("create ") {
+        // create molecule M from atoms A1 A2; the runner drops the level word,
+        // since only the loaded ontology knows every level name.
+        let mut parts = rest.split_whitespace();
+        let level = parts.next().unwrap().to_string();
+        let name = parts.next().unwrap().to_string();
+        let parts: Vec<String> = match parts.next() {
+            Some("from") => parts.map(|s| s.to_string()).collect(),
+            _ => Vec::new(),
+        };
+        Action::CreateLevel { level, name, parts }
+    } else if let Some(rest) = line.strip_prefix("promote ") {
+        Action::Promote(rest.trim().to_string())
+    } else if let Some(rest) = line.strip_prefix("random ") {
+        parse_random(rest, line)
+    } else if let Some(rest) = line.strip_prefix("custom ") {
+        // custom my_sensor_read alice
+        let mut words = rest.split_whitespace().map(|s| s.to_string());
+        let name = words.next().unwrap_or_else(|| panic!("Expected 'custom <action> [<argument>...]': {}", line));
+        Action::Custom { name, args: words.collect() }
+    } else if let Some(rest) = line.strip_prefix("why ") {
+        let question = Question::parse(rest)
+            .unwrap_or_else(|| panic!("Expected 'why pattern <pattern> in <field>' or 'why token <token> in <agent>': {}", line));
+        Action::Why(question)
+    } else if let Some(rest) = line.strip_prefix("describe ") {
+        Action::Describe(rest.trim().to_string())
+    } else if let Some(rest) = line.strip_prefix("seed ") {
+        Action::Seed(rest.trim().parse().unwrap_or_else(|_| panic!("Expected 'seed <n>': {}", line)))
+    } else if let Some(rest) = line.strip_prefix("group ") {
+        // group learners = alice bob carol
+        let (name, members) = rest.split_once('=')
+            .unwrap_or_else(|| panic!("Expected 'group <name> = <agent>...': {}", line));
+        let members = members.split_whitespace().map(|s| s.to_string()).collect();
+        Action::DefineGroup { name: name.trim().to_string(), members }
+    } else if let Some(rest) = line.strip_prefix("let ").filter(|r| is_metric_call(r)) {
+        // let d = trace_distance(F, I) is a measurement bound to a variable.
+        parse_action(&format!("measure {}", rest))
     } else if let Some(rest) = line.strip_prefix("let ") {
         let (name, value) = rest.split_once('=').unwrap();
         Action::VariableAssignment {
             name: name.trim().to_string(),
             value: value.trim().to_string(),
         }
+    } else if let Some(rest) = line.strip_prefix("field ") {
+        // field F 16, or field F size 16 "visual cortex analogue"
+        let (rest, doc) = split_doc(rest);
+        let mut parts = rest.split_whitespace().filter(|w| *w != "size");
+        let name = parts.next().unwrap().to_string();
+        let size = parts.next().unwrap().parse().unwrap();
+        Action::Field { name, size, doc }
+    } else if let Some(rest) = line.strip_prefix("interpretation ") {
+        // interpretation I = 1.0 0.0 1.0 ["doc"]
+        let (rest, doc) = split_doc(rest);
+        let (name, values) = rest.split_once('=').unwrap();
+        let values = values
+            .split(|c: char| c.is_whitespace() || c == ',' || c == '[' || c == ']')
+            .filter(|v| !v.is_empty())
+            .map(|v| v.parse().unwrap())
+            .collect();
+        Action::Interpretation { name: name.trim().to_string(), values, doc }
+    } else if let Some(rest) = line.strip_prefix("measure ") {
+        // measure d = distance(F, I)
+        let (name, call) = rest.split_once('=').unwrap();
+        let call = call.trim();
+        let open_paren = call.find('(').unwrap();
+        let close_paren = call.rfind(')').unwrap();
+        let (field, interp) = call[open_paren + 1..close_paren].split_once(',').unwrap();
+        Action::Measure {
+            name: name.trim().to_string(),
+            metric: call[..open_paren].trim().to_string(),
+            field: field.trim().to_string(),
+            interp: interp.trim().to_string(),
+        }
+    } else if let Some(rest) = line.strip_prefix("perturb agent ") {
+        // perturb agent alice forget 0.3
+        let parts: Vec<&str> = rest.split_whitespace().collect();
+        if parts.len() != 3 || parts[1] != "forget" {
+            panic!("Expected 'perturb agent <name> forget <rate>': {}", line);
+        }
+        Action::PerturbMemory { agent: parts[0].to_string(), rate: parts[2].parse().unwrap() }
+    } else if let Some(rest) = line.strip_prefix("pin ") {
+        parse_pin(rest, true, line)
+    } else if let Some(rest) = line.strip_prefix("unpin ") {
+        parse_pin(rest, false, line)
+    } else if let Some(rest) = line.strip_prefix("perturb ") {
+        // perturb F noise 0.5
+        let parts: Vec<&str> = rest.split_whitespace().collect();
+        if parts.len() != 3 || parts[1] != "noise" {
+            panic!("Expected 'perturb <field> noise <amplitude>': {}", line);
+        }
+        Action::Perturb { field: parts[0].to_string(), amplitude: parts[2].parse().unwrap() }
+    } else if let Some(rest) = line.strip_prefix("shock ") {
+        // shock F indices [3..10] value 2.0
+        let parts: Vec<&str> = rest.split_whitespace().collect();
+        if parts.len() != 5 || parts[1] != "indices" || parts[3] != "value" {
+            panic!("Expected 'shock <field> indices [a..b] value <v>': {}", line);
+        }
+        Action::Shock {
+            field: parts[0].to_string(),
+            indices: parts[2].trim_matches(&['[', ']'][..]).to_string(),
+            value: parts[4].parse().unwrap(),
+        }
+    } else if let Some(rest) = line.strip_prefix("log ") {
+        Action::Log(rest.trim().to_string())
     } else if let Some(rest) = line.strip_prefix("tick ") {
         let n = rest.trim().parse().unwrap();
         Action::Tick(n)
     } else if let Some(rest) = line.strip_prefix("assert ") {
-        Action::Assert(rest.trim().to_string())
+        Action::Assert(parse_condition(rest))
     } else if let Some((agent, rest)) = line.split_once(" says: ") {
         let (token, pattern) = rest.split_once(" → ").unwrap();
         Action::Say {
@@ -169,6 +447,13 @@ fn parse_action(line: &str) -> Action {
             agent: agent.trim().to_string(),
             token: token.trim().to_string(),
         }
+    } else if let Some((agent, rest)) = line.split_once(" projects: ") {
+        // alice projects: fire into F
+        let (token, into) = match rest.split_once(" into ") {
+            Some((token, field)) => (token, Some(field.trim().to_string())),
+            None => (rest, None),
+        };
+        Action::Project { agent: agent.trim().to_string(), token: token.trim().to_string(), into }
     } else if let Some((agent, rest)) = line.split_once(" interprets: ") {
         Action::Interpret {
             agent: agent.trim().to_string(),
@@ -186,4 +471,99 @@ fn parse_action(line: &str) -> Action {
     } else {
         panic!("Unrecognized action: {}", line);
     }
-}
\ No newline at end of file
+}
+/// `pin fire in alice` or `unpin fire in alice`, after the verb.
+fn parse_pin(rest: &str, pinned: bool, line: &str) -> Action {
+    match rest.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [token, "in", agent] => Action::Pin { agent: agent.to_string(), token: token.to_string(), pinned },
+        _ => panic!("Expected '{}pin <token> in <agent>': {}", if pinned { "" } else { "un" }, line),
+    }
+}
+
+/// `random agents <n> [named <prefix>] [mem <m>] coh <low>..<high> [group <name>]`
+/// or `random symbols <n> bits <b> [named <prefix>] into <agent or group>...`.
+fn parse_random(rest: &str, line: &str) -> Action {
+    let usage = format!(
+        "Expected 'random agents <n> [named <prefix>] [mem <m>] coh <low>..<high> [group <name>]' \
+         or 'random symbols <n> bits <b> [named <prefix>] into <agent>...': {}",
+        line
+    );
+    let words: Vec<&str> = rest.split_whitespace().collect();
+    let [kind, count, options @ ..] = words.as_slice() else { panic!("{}", usage) };
+    let mut options = options;
+    let count: usize = count.parse().expect(&usage);
+    let (mut prefix, mut mem, mut coh, mut group, mut bits, mut into) = (None, scenario::DEFAULT_MEMORY, None, None, None, Vec::new());
+    while let [key, value, more @ ..] = options {
+        match (*kind, *key) {
+            (_, "named") => prefix = Some(value.to_string()),
+            ("agents", "mem") => mem = value.parse().expect(&usage),
+            ("agents", "coh") => coh = Some(parse_range(value).expect(&usage)),
+            ("agents", "group") => group = Some(value.to_string()),
+            ("symbols", "bits") => bits = Some(value.parse().expect(&usage)),
+            // Every word after `into` names a recipient.
+            ("symbols", "into") => {
+                into = options[1..].iter().map(|s| s.to_string()).collect();
+                options = &[];
+                continue;
+            }
+            _ => panic!("{}", usage),
+        }
+        options = more;
+    }
+    match *kind {
+        _ if !options.is_empty() => panic!("{}", usage),
+        "agents" => Action::RandomAgents {
+            count,
+            prefix: prefix.unwrap_or_else(|| scenario::AGENT_PREFIX.to_string()),
+            mem,
+            coh: coh.expect(&usage),
+            group,
+        },
+        "symbols" if !into.is_empty() => Action::RandomSymbols {
+            count,
+            bits: bits.expect(&usage),
+            prefix: prefix.unwrap_or_else(|| scenario::SYMBOL_PREFIX.to_string()),
+            into,
+        },
+        _ => panic!("{}", usage),
+    }
+}
+
+/// `0.1..0.4`, or a single value standing for a range of one.
+fn parse_range(text: &str) -> Option<(f64, f64)> {
+    match text.split_once("..") {
+        Some((low, high)) => Some((low.parse().ok()?, high.parse().ok()?)),
+        None => text.parse().ok().map(|v| (v, v)),
+    }
+}
+
+/// Whether the right-hand side of `let name = ...` calls a trace metric.
+fn is_metric_call(assignment: &str) -> bool {
+    let Some((_, value)) = assignment.split_once('=') else { return false };
+    let value = value.trim();
+    let name = value.split('(').next().unwrap_or("");
+    value.contains('(') && Metric::from_name(name.trim()).is_some() && value.ends_with(')')
+}
+
+/// A declaration without its trailing `"doc string"`, and the doc string.
+fn split_doc(text: &str) -> (&str, Option<String>) {
+    let text = text.trim_end();
+    match text.strip_suffix('"').and_then(|t| t.rsplit_once('"')) {
+        Some((declaration, doc)) => (declaration, Some(doc.to_string())),
+        None => (text, None),
+    }
+}
+
+/// Split a leading path, quoted or a single word, from the rest of the line.
+fn split_path(text: &str) -> (String, &str) {
+    let text = text.trim_start();
+    if let Some(quoted) = text.strip_prefix('"') {
+        if let Some(end) = quoted.find('"') {
+            return (quoted[..end].to_string(), &quoted[end + 1..]);
+        }
+    }
+    match text.split_once(char::is_whitespace) {
+        Some((path, rest)) => (path.to_string(), rest),
+        None => (text.to_string(), ""),
+    }
+}
@@ -0,0 +1,66 @@
+//! Production rules for narrative scripts.
+//!
+//! A `when <condition> then: ...` block, or a rule added with
+//! `ScriptContext::add_rule`, is evaluated once per τ: at the end of every
+//! `at τ=` block and after every `tick`. Conflicts are resolved by priority:
+//! rules are tried highest priority first (declaration order on ties), and
+//! each is re-checked right before it fires, since the actions of an earlier
+//! rule may have changed the state. A rule that fired at τ is not tried
+//! again before τ + its refractory period.
+
+use super::ast::Rule;
+use std::cmp::Reverse;
+
+#[derive(Debug, Default)]
+pub struct RuleBook {
+    rules: Vec<Rule>,
+    last_fired: Vec<Option<u64>>,
+    evaluated_at: Option<u64>,
+    /// Set while rule actions run, so a `tick` among them does not start another round.
+    firing: bool,
+}
+
+impl RuleBook {
+    pub fn add(&mut self, rule: Rule) {
+        self.rules.push(rule);
+        self.last_fired.push(None);
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// τ at which rule `i` last fired.
+    pub fn last_fired(&self, i: usize) -> Option<u64> {
+        self.last_fired[i]
+    }
+
+    /// Start the round for τ, ended by `end`: the indices of the rules to
+    /// try, in order. `None` if τ was already evaluated or a round is under way.
+    pub fn begin(&mut self, tau: u64) -> Option<Vec<usize>> {
+        if self.firing || self.evaluated_at == Some(tau) {
+            return None;
+        }
+        self.evaluated_at = Some(tau);
+        self.firing = true;
+        let mut order: Vec<usize> = (0..self.rules.len()).collect();
+        order.sort_by_key(|&i| Reverse(self.rules[i].priority));
+        Some(order)
+    }
+
+    /// Whether rule `i` is out of its refractory period at τ.
+    pub fn ready(&self, i: usize, tau: u64) -> bool {
+        match self.last_fired[i] {
+            Some(fired) => tau >= fired + self.rules[i].refractory,
+            None => true,
+        }
+    }
+
+    pub fn fired(&mut self, i: usize, tau: u64) {
+        self.last_fired[i] = Some(tau);
+    }
+
+    pub fn end(&mut self) {
+        self.firing = false;
+    }
+}
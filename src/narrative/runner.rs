@@ -1,50 +1,167 @@
 //! Runner for SPTL narrative DSL with macros
 
-use super::ast::{Block, Action};
-use std::collections::HashMap;
+use super::ast::{Block, Action, Bridge, Rule};
+use super::contract::Contract;
+use crate::console;
+use crate::say;
+use super::rules::RuleBook;
+use super::scenario;
+use super::world::{self, World, MAIN_WORLD};
+use crate::agents::Agent;
+pub use crate::runtime::{AgentState, EmergenceRecord};
+use crate::condition::{Condition, Scope};
+use crate::config::Config;
+use crate::events::{Event, EventBus};
+use crate::interpretation::Interpretation;
+use crate::lexicon::Lexicon;
+use crate::perturb::{parse_index_range, perturb, perturb_memory, shock};
+use crate::provenance::Origin;
+use crate::substrate::{Pattern, Substrate};
+use crate::symbol::Symbol;
+use crate::symmetry::Consensus;
+use crate::report::RunReport;
+use crate::snapshot::Snapshots;
+use crate::sptl::{self, Metric, METRIC_NAMES};
+use crate::recursion::{find_in_forest_mut, migrate_agent, CategoryObject, MigrationMode};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use rand::Rng;
+
+/// A host-provided action: gets the running world and the arguments written
+/// after its name, and may fail with a message.
+pub type CustomAction = Box<dyn FnMut(&mut World, &[String]) -> Result<(), String> + Send>;
+
+/// A macro as defined by `macro name(params):`.
+#[derive(Debug, Clone)]
+pub struct Macro {
+    pub params: Vec<String>,
+    pub body: Vec<Action>,
+    pub contract: Contract,
+    pub doc: Option<String>,
+}
 
-#[derive(Default)]
 pub struct ScriptContext {
-    pub vars: HashMap<String, String>,
-    pub macros: HashMap<String, (Vec<String>, Vec<Action>)>,
-    pub agents: HashMap<String, AgentState>,
-    pub tau: u64,
+    pub macros: HashMap<String, Macro>,
+    /// Actions registered by the host, run by `custom <name>`.
+    pub custom: HashMap<String, CustomAction>,
+    pub events: EventBus,
+    /// State of the world the script is running in.
+    pub world: World,
+    pub world_name: String,
+    /// Worlds forked off and not currently running, by name.
+    pub worlds: BTreeMap<String, World>,
+    /// Couplings between worlds, applied on every `tick`.
+    pub bridges: Vec<Bridge>,
+    pub rules: RuleBook,
+    /// Path the script was read from, named in the origins `why` reports.
+    pub script: Option<String>,
+    /// Where every `tick` publishes the running world, once `observe` is called.
+    pub snapshots: Option<Snapshots>,
 }
 
-#[derive(Default, Debug, Clone)]
-pub struct AgentState {
-    pub memory: Vec<String>,
-    pub activation: HashMap<String, f32>,
+impl Default for ScriptContext {
+    fn default() -> Self {
+        ScriptContext {
+            macros: HashMap::new(),
+            custom: HashMap::new(),
+            events: EventBus::default(),
+            world: World::default(),
+            world_name: MAIN_WORLD.to_string(),
+            worlds: BTreeMap::new(),
+            bridges: Vec::new(),
+            rules: RuleBook::default(),
+            script: None,
+            snapshots: None,
+        }
+    }
+}
+
+impl ScriptContext {
+    /// A fresh context whose variable table starts with externally bound values.
+    pub fn with_vars(vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        let mut ctx = ScriptContext::default();
+        ctx.world.vars = vars.into_iter().collect();
+        ctx
+    }
+
+    /// Make `custom <name> ...` run `action` on the current world, with
+    /// `$variables` in its arguments expanded. Registering a name again
+    /// replaces the earlier action.
+    pub fn register_action(
+        &mut self,
+        name: impl Into<String>,
+        action: impl FnMut(&mut World, &[String]) -> Result<(), String> + Send + 'static,
+    ) {
+        self.custom.insert(name.into(), Box::new(action));
+    }
+
+    /// Add a production rule, as a `when ... then:` block would.
+    pub fn add_rule(&mut self, rule: Rule) {
+        self.rules.add(rule);
+    }
+
+    /// A handle for readers on other threads: from now on every `tick`
+    /// publishes the running world to it (see `snapshot`).
+    pub fn observe(&mut self) -> Snapshots {
+        self.snapshots.get_or_insert_with(|| Snapshots::new(&self.world)).clone()
+    }
+
+    /// The running world or a parked one, by name.
+    pub fn world_named(&self, name: &str) -> Option<&World> {
+        if name == self.world_name {
+            Some(&self.world)
+        } else {
+            self.worlds.get(name)
+        }
+    }
+
+    pub fn world_named_mut(&mut self, name: &str) -> Option<&mut World> {
+        if name == self.world_name {
+            Some(&mut self.world)
+        } else {
+            self.worlds.get_mut(name)
+        }
+    }
 }
 
 pub fn execute_script(blocks: &[Block], ctx: &mut ScriptContext) {
-    // First pass: register macros
+    // First pass: register macros and rules
     for block in blocks {
-        if let Block::MacroDef { name, params, body } = block {
-            ctx.macros.insert(name.clone(), (params.clone(), body.clone()));
+        match block {
+            Block::MacroDef { name, params, body, contract, doc } => {
+                let definition =
+                    Macro { params: params.clone(), body: body.clone(), contract: contract.clone(), doc: doc.clone() };
+                ctx.macros.insert(name.clone(), definition);
+            }
+            Block::Rule(rule) => ctx.add_rule(rule.clone()),
+            _ => {}
         }
     }
-    // Second pass: execute non-macro blocks
+    // Second pass: execute the other blocks
     for block in blocks {
         match block {
-            Block::MacroDef { .. } => {},
+            Block::MacroDef { .. } | Block::Rule(_) => {},
             _ => execute_block(block, ctx),
         }
     }
+    console::finish();
 }
 
 fn execute_block(block: &Block, ctx: &mut ScriptContext) {
     match block {
         Block::AtTau(tau, actions) => {
-            ctx.tau = *tau;
-            println!("--- at τ={} ---", tau);
+            ctx.world.tau = *tau;
+            console::set_tau(*tau);
+            say!("--- at τ={} ---", tau);
             for action in actions {
                 execute_action(action, ctx);
             }
+            fire_rules(ctx);
         }
         Block::Repeat(n, actions) => {
             for i in 0..*n {
-                println!("Repeat iteration {}/{}", i + 1, n);
+                say!("Repeat iteration {}/{}", i + 1, n);
                 for action in actions {
                     execute_action(action, ctx);
                 }
@@ -53,113 +170,675 @@ fn execute_block(block: &Block, ctx: &mut ScriptContext) {
         Block::While(cond, actions) => {
             let mut count = 0;
             while eval_condition(cond, ctx) {
-                println!("While iteration {}", count + 1);
+                say!("While iteration {}", count + 1);
                 for action in actions {
                     execute_action(action, ctx);
                 }
                 count += 1;
                 if count > 1000 {
-                    println!("Breaking infinite while loop: more than 1000 iterations.");
+                    say!("Breaking infinite while loop: more than 1000 iterations.");
                     break;
                 }
             }
         }
         Block::Parallel(actions) => {
-            println!("-- Parallel block --");
+            say!("-- Parallel block --");
             for action in actions {
                 execute_action(action, ctx);
             }
         }
-        Block::MacroDef { .. } => {}
+        Block::MacroDef { .. } | Block::Rule(_) => {}
     }
 }
 
 fn execute_action(action: &Action, ctx: &mut ScriptContext) {
     match action {
-        Action::Conditional(cond, subactions) => {
-            if eval_condition(cond, ctx) {
-                println!("Condition '{}' passed.", cond);
-                for sub in subactions {
-                    execute_action(sub, ctx);
+        Action::Conditional(cond, then, otherwise) => {
+            let branch = if eval_condition(cond, ctx) {
+                say!("Condition '{}' passed.", cond);
+                then
+            } else {
+                say!("Condition '{}' failed.", cond);
+                otherwise
+            };
+            for sub in branch {
+                execute_action(sub, ctx);
+            }
+        }
+        Action::CreateAgent { name, mem, coh, within, doc } => {
+            let name = &expand_vars(name, ctx);
+            ctx.world.document(name, doc.as_deref());
+            say!("Create agent {} mem={} coh={}", name, mem, coh);
+            let agent = Agent::new(name.clone(), *mem as usize, *coh as f64);
+            ctx.world.agents.insert(name.clone(), Arc::new(AgentState { agent: Some(agent), ..AgentState::default() }));
+            ctx.events.publish(Event::AgentCreated { name: name.clone(), tau: ctx.world.tau });
+            if let Some(within) = within {
+                let within = expand_vars(within, ctx);
+                match find_in_forest_mut(&mut ctx.world.hierarchies, &within) {
+                    Some(obj) => obj.agents.push(Agent::new(name.clone(), *mem as usize, *coh as f64)),
+                    None => say!("Hierarchy object '{}' not found; agent {} not placed.", within, name),
                 }
+            }
+        }
+        Action::LoadAgent { name, path, within } => {
+            let path = expand_vars(path, ctx);
+            let mut agent = match Agent::load(Path::new(&path)) {
+                Ok(agent) => agent,
+                Err(e) => {
+                    say!("Load agent {} failed: {}: {}", name, path, e);
+                    return;
+                }
+            };
+            agent.id = name.clone();
+            say!(
+                "Load agent {} from {} ({} symbols, {} memories)",
+                name, path, agent.symbol_table.len(), agent.memory.traces.len()
+            );
+            let memory: Vec<String> = agent.memory.traces.iter().map(|t| t.symbol.token.clone()).collect();
+            for token in &memory {
+                ctx.world.provenance.remembered(name, token, ctx.world.tau);
+            }
+            if let Some(within) = within {
+                let within = expand_vars(within, ctx);
+                match find_in_forest_mut(&mut ctx.world.hierarchies, &within) {
+                    Some(obj) => obj.agents.push(agent.clone()),
+                    None => say!("Hierarchy object '{}' not found; agent {} not placed.", within, name),
+                }
+            }
+            let state = AgentState { memory, agent: Some(agent), ..AgentState::default() };
+            ctx.world.agents.insert(name.clone(), Arc::new(state));
+            ctx.events.publish(Event::AgentCreated { name: name.clone(), tau: ctx.world.tau });
+        }
+        Action::SaveAgent { name, path } => {
+            let path = expand_vars(path, ctx);
+            let Some(agent) = ctx.world.agents.get(name).and_then(|state| state.agent.as_ref()) else {
+                say!("Agent '{}' not found.", name);
+                return;
+            };
+            if let Some(dir) = Path::new(&path).parent().filter(|d| !d.as_os_str().is_empty()) {
+                if let Err(e) = std::fs::create_dir_all(dir) {
+                    say!("Save agent {} failed: {}: {}", name, path, e);
+                    return;
+                }
+            }
+            match agent.save(Path::new(&path)) {
+                Ok(()) => say!("Save agent {} to {}", name, path),
+                Err(e) => say!("Save agent {} failed: {}: {}", name, path, e),
+            }
+        }
+        Action::SaveLexicon { agents, path } => {
+            let path = expand_vars(path, ctx);
+            let mut speakers = Vec::new();
+            if agents.is_empty() {
+                speakers.extend(ctx.world.agents.values().filter_map(|state| state.agent.as_ref()));
+            }
+            for name in agents {
+                match ctx.world.agents.get(name).and_then(|state| state.agent.as_ref()) {
+                    Some(agent) => speakers.push(agent),
+                    None => {
+                        say!("Agent '{}' not found.", name);
+                        return;
+                    }
+                }
+            }
+            let lexicon = Lexicon::of_agents(speakers);
+            if let Some(dir) = Path::new(&path).parent().filter(|d| !d.as_os_str().is_empty()) {
+                if let Err(e) = std::fs::create_dir_all(dir) {
+                    say!("Save lexicon failed: {}: {}", path, e);
+                    return;
+                }
+            }
+            match lexicon.save(Path::new(&path)) {
+                Ok(()) => say!("Save lexicon of {} tokens to {}", lexicon.entries.len(), path),
+                Err(e) => say!("Save lexicon failed: {}: {}", path, e),
+            }
+        }
+        Action::LoadLexicon { path, agents } => {
+            let path = expand_vars(path, ctx);
+            let lexicon = match Lexicon::load(Path::new(&path)) {
+                Ok(lexicon) => lexicon,
+                Err(e) => {
+                    say!("Load lexicon failed: {}: {}", path, e);
+                    return;
+                }
+            };
+            let tau = ctx.world.tau as usize;
+            for name in agents {
+                let Some(state) = ctx.world.agents.get_mut(name).map(Arc::make_mut) else {
+                    say!("Agent '{}' not found.", name);
+                    continue;
+                };
+                let Some(agent) = &mut state.agent else {
+                    say!("Agent '{}' not found.", name);
+                    continue;
+                };
+                let remembered = lexicon.seed(agent, tau);
+                say!(
+                    "Load lexicon {} into {} ({} tokens, {} remembered)",
+                    path, name, lexicon.entries.len(), remembered.len()
+                );
+                for token in remembered {
+                    ctx.world.provenance.remembered(name, &token, tau as u64);
+                    if !state.memory.contains(&token) {
+                        state.memory.push(token);
+                    }
+                }
+            }
+        }
+        Action::RunSptl(path) => {
+            let path = expand_vars(path, ctx);
+            let source = match std::fs::read_to_string(&path) {
+                Ok(source) => source,
+                Err(e) => {
+                    say!("Run {} failed: {}", path, e);
+                    return;
+                }
+            };
+            let params: BTreeMap<String, String> = ctx.world.vars.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+            let mut config = Config::for_script(Path::new(&path));
+            // A script without its own ontology uses the levels of the world it runs in.
+            if config.levels.is_none() {
+                config.levels = ctx.world.ontology.clone();
+            }
+            let program = sptl::parse_source_with(&source, &params, &config)
+                .and_then(|program| sptl::resolve_includes(program, Path::new(&path), &params, &config));
+            match program {
+                Ok(program) => {
+                    say!("Run {} ({} statements) at τ={}", path, program.len(), ctx.world.tau);
+                    let mut report = RunReport { script: Some(path), ..Default::default() };
+                    sptl::execute_program_in(program, &mut ctx.world, &mut report);
+                }
+                Err(errors) => {
+                    for e in errors {
+                        say!("Run {} failed: {}", path, e);
+                    }
+                }
+            }
+        }
+        Action::ForkWorld(name) => {
+            if *name == ctx.world_name {
+                say!("World '{}' is already running.", name);
+                return;
+            }
+            let mut fork = ctx.world.clone();
+            // The fork draws its own noise, seeded from this world's RNG.
+            fork.rng.reseed(ctx.world.rng.at("fork").gen());
+            if ctx.worlds.insert(name.clone(), fork).is_some() {
+                say!("Fork world {} from {} (replacing the previous {})", name, ctx.world_name, name);
             } else {
-                println!("Condition '{}' failed.", cond);
+                say!("Fork world {} from {}", name, ctx.world_name);
+            }
+        }
+        Action::SwitchWorld(name) => {
+            let Some(next) = ctx.worlds.remove(name) else {
+                say!("World '{}' not found.", name);
+                return;
+            };
+            let previous = std::mem::replace(&mut ctx.world, next);
+            let from = std::mem::replace(&mut ctx.world_name, name.clone());
+            say!("Switch world {} → {} at τ={}", from, name, ctx.world.tau);
+            ctx.worlds.insert(from, previous);
+        }
+        Action::DiffWorld(name) => {
+            let Some(other) = ctx.worlds.get(name) else {
+                say!("World '{}' not found.", name);
+                return;
+            };
+            let lines = world::diff(&ctx.world, other);
+            say!("Diff world {} → {}: {} differences", ctx.world_name, name, lines.len());
+            for line in lines {
+                say!("  {}", line);
+            }
+        }
+        Action::CreateWorld(name) => {
+            if ctx.world_named(name).is_some() {
+                say!("World '{}' already exists.", name);
+                return;
+            }
+            say!("Create world {} at τ={}", name, ctx.world.tau);
+            ctx.worlds.insert(name.clone(), World { tau: ctx.world.tau, ..World::default() });
+        }
+        Action::Bridge(bridge) => {
+            say!("Bridge {}@{} -> {}@{} (strength {})", bridge.from.field, bridge.from.world, bridge.to.field, bridge.to.world, bridge.strength);
+            match couple(ctx, bridge) {
+                Ok(()) => ctx.bridges.push(bridge.clone()),
+                Err(e) => say!("Bridge failed: {}", e),
+            }
+        }
+        Action::MigrateAgent { agent, from, to, copy } => {
+            let agent = expand_vars(agent, ctx);
+            let from = expand_vars(from, ctx);
+            let to = expand_vars(to, ctx);
+            let mode = if *copy { MigrationMode::Copy } else { MigrationMode::Move };
+            match migrate_agent(&mut ctx.world.hierarchies, &agent, &from, &to, mode) {
+                Ok(()) => say!("{:?} agent {} from {} to {}", mode, agent, from, to),
+                Err(e) => say!("Migration failed: {}", e),
+            }
+        }
+        Action::CreateLevel { level, name, parts } => {
+            let name = expand_vars(name, ctx);
+            let Some(level) = ctx.world.level(level) else {
+                say!("Unknown recursion level '{}'.", level);
+                return;
+            };
+            let parts = match parts.split_first() {
+                Some((first, rest)) if ctx.world.level(first).is_some() => rest,
+                _ => parts.as_slice(),
+            };
+            let mut subobjects = Vec::new();
+            for part in parts {
+                let part = expand_vars(part, ctx);
+                match ctx.world.hierarchies.remove(&part) {
+                    Some(obj) => subobjects.push(obj),
+                    None => match level.below() {
+                        Some(sub_level) => {
+                            subobjects.push(CategoryObject::new(sub_level, &part).with_ontology(ctx.world.ontology.clone()))
+                        }
+                        None => {
+                            say!("{} objects cannot have parts.", level);
+                            return;
+                        }
+                    },
+                }
+            }
+            match CategoryObject::from_parts(level, &name, subobjects) {
+                Ok(obj) => {
+                    let obj = obj.with_ontology(ctx.world.ontology.clone());
+                    say!("Create {} {} with {} parts", obj.level_name(), name, obj.subobjects.len());
+                    ctx.world.hierarchies.insert(name, obj);
+                }
+                Err(e) => say!("Create {} failed: {}", name, e),
+            }
+        }
+        Action::Promote(name) => {
+            let name = expand_vars(name, ctx);
+            match ctx.world.hierarchies.get(&name) {
+                Some(obj) if obj.level.above().is_none() => {
+                    say!("Cannot promote {} above {} level.", name, obj.level_name());
+                }
+                Some(_) => {
+                    let obj = ctx.world.hierarchies.remove(&name).unwrap();
+                    let promoted = obj.promote().unwrap();
+                    say!("Promote {} → {} {}", name, promoted.level_name(), promoted.id);
+                    ctx.world.hierarchies.insert(name, promoted);
+                }
+                None => say!("Hierarchy object '{}' not found.", name),
             }
         }
-        Action::CreateAgent { name, mem, coh } => {
-            println!("Create agent {} mem={} coh={}", name, mem, coh);
-            ctx.agents.insert(name.clone(), AgentState::default());
+        Action::DefineGroup { name, members } => {
+            say!("Group {} = {}", name, members.join(" "));
+            ctx.world.groups.insert(name.clone(), members.clone());
+        }
+        Action::RandomAgents { count, prefix, mem, coh, group } => {
+            let names = scenario::next_names(prefix, *count, |name| ctx.world.agents.contains_key(name));
+            for name in &names {
+                let threshold = scenario::draw(coh.0, coh.1, ctx.world.rng.at("scenario"));
+                let agent = Agent::new(name.clone(), *mem as usize, threshold);
+                ctx.world.agents.insert(name.clone(), Arc::new(AgentState { agent: Some(agent), ..AgentState::default() }));
+                ctx.events.publish(Event::AgentCreated { name: name.clone(), tau: ctx.world.tau });
+            }
+            say!("Create {} random agents {}... mem={} coh={}..{}", names.len(), prefix, mem, coh.0, coh.1);
+            if let Some(group) = group {
+                ctx.world.groups.entry(group.clone()).or_default().extend(names);
+            }
+        }
+        Action::RandomSymbols { count, bits, prefix, into } => {
+            // A group stands for its members.
+            let recipients: Vec<String> = into
+                .iter()
+                .flat_map(|name| ctx.world.groups.get(name).cloned().unwrap_or_else(|| vec![name.clone()]))
+                .filter(|name| {
+                    let known = ctx.world.agents.get(name).is_some_and(|state| state.agent.is_some());
+                    if !known {
+                        say!("No agent {} with a symbol table; random symbols not given to it.", name);
+                    }
+                    known
+                })
+                .collect();
+            let knows = |token: &str| {
+                recipients.iter().any(|name| ctx.world.agents[name].agent.as_ref().is_some_and(|a| a.symbol_table.contains_key(token)))
+            };
+            let tokens = scenario::next_names(prefix, *count, knows);
+            let patterns: Vec<String> =
+                tokens.iter().map(|_| scenario::random_pattern(*bits, ctx.world.rng.at("scenario"))).collect();
+            let tau = ctx.world.tau;
+            for name in &recipients {
+                let state = Arc::make_mut(ctx.world.agents.get_mut(name).expect("recipient exists"));
+                for (token, pattern) in tokens.iter().zip(&patterns) {
+                    state.memory.push(token.clone());
+                    if let Some(a) = &mut state.agent {
+                        a.express_symbol(token, Pattern::new(pattern), tau as usize);
+                    }
+                    ctx.events.publish(Event::SymbolExpressed { source: name.clone(), token: token.clone(), tau });
+                    ctx.world.provenance.remembered(name, token, tau);
+                }
+            }
+            say!("{} random symbols {}... of {} bits given to {}", tokens.len(), prefix, bits, recipients.join(" "));
+        }
+        Action::Seed(seed) => {
+            say!("Seed {}", seed);
+            ctx.world.rng.reseed(*seed);
         }
         Action::VariableAssignment { name, value } => {
             let val = expand_vars(value, ctx);
-            println!("Set variable {} = {}", name, val);
-            ctx.vars.insert(name.clone(), val);
+            say!("Set variable {} = {}", name, val);
+            ctx.world.vars.insert(name.clone(), val);
         }
         Action::Say { agent, token, pattern } => {
+            let agent = &expand_vars(agent, ctx);
             let token = expand_vars(token, ctx);
             let pattern = expand_vars(pattern, ctx);
-            println!("{} says: {} → {}", agent, token, pattern);
-            ctx.agents.entry(agent.clone()).or_default().memory.push(token.clone());
+            say!("{} says: {} → {}", agent, token, pattern);
+            let state = Arc::make_mut(ctx.world.agents.entry(agent.clone()).or_default());
+            state.memory.push(token.clone());
+            if let Some(a) = &mut state.agent {
+                a.express_symbol(&token, Pattern::new(&pattern), ctx.world.tau as usize);
+            }
+            ctx.world.provenance.remembered(agent, &token, ctx.world.tau);
+            ctx.events.publish(Event::SymbolExpressed { source: agent.clone(), token, tau: ctx.world.tau });
         }
         Action::Interpret { agent, token } => {
             let token = expand_vars(token, ctx);
-            println!("{} interprets: {}", agent, token);
-            ctx.agents.entry(agent.clone()).or_default().memory.push(token.clone());
+            say!("{} interprets: {}", agent, token);
+            Arc::make_mut(ctx.world.agents.entry(agent.clone()).or_default()).memory.push(token.clone());
+            ctx.world.provenance.remembered(agent, &token, ctx.world.tau);
+            ctx.events.publish(Event::SymbolInterpreted { agent: agent.clone(), token, tau: ctx.world.tau });
         }
-        Action::Project { agent, token } => {
+        Action::Project { agent, token, into } => {
             let token = expand_vars(token, ctx);
-            println!("{} projects: {}", agent, token);
+            match into {
+                Some(field) => {
+                    let field = expand_vars(field, ctx);
+                    let known = ctx.world.agents.get(agent).and_then(|s| s.agent.as_ref()).and_then(|a| a.symbol_table.get(&token));
+                    let Some(pattern) = known.cloned() else {
+                        say!("{} has no pattern for {}; nothing projected.", agent, token);
+                        return;
+                    };
+                    let gain = ctx.world.gain(&token);
+                    let Some(substrate) = ctx.world.fields.get_mut(&field).map(Arc::make_mut) else {
+                        say!("Project failed: unknown field '{}'.", field);
+                        return;
+                    };
+                    substrate.project_with_gain(&Symbol::new(&token, pattern.clone()), gain);
+                    say!("{} projects: {} → {} into {}", agent, token, pattern.0, field);
+                    ctx.world.provenance.activated(&field, &pattern.0, ctx.world.tau);
+                }
+                None => say!("{} projects: {}", agent, token),
+            }
+            ctx.events.publish(Event::SymbolProjected { agent: agent.clone(), token, tau: ctx.world.tau });
         }
-        Action::Tick(n) => {
-            println!("Advance τ by {}", n);
-            ctx.tau += *n as u64;
+        Action::Custom { name, args } => {
+            let args: Vec<String> = args.iter().map(|a| expand_vars(a, ctx)).collect();
+            let Some(action) = ctx.custom.get_mut(name) else {
+                say!("Unknown custom action '{}'; the host has not registered it.", name);
+                return;
+            };
+            say!("Custom {} {}", name, args.join(" "));
+            if let Err(e) = action(&mut ctx.world, &args) {
+                say!("Custom action {} failed: {}", name, e);
+            }
         }
-        Action::Assert(expr) => {
-            println!("Assert: {}", expr);
+        Action::Why(question) => {
+            for line in question.answer(&ctx.world.provenance) {
+                say!("{}", line);
+            }
         }
-        Action::Comment(text) => {
-            println!("# {}", text);
+        Action::Describe(name) => {
+            let name = expand_vars(name, ctx);
+            let mut lines = ctx.world.describe(&name);
+            if let Some(m) = ctx.macros.get(&name) {
+                let doc = m.doc.as_ref().map(|d| format!("\"{}\"", d)).unwrap_or_else(|| "no description".to_string());
+                lines.push(format!("macro {}({}): {}", name, m.params.join(", "), doc));
+            }
+            if lines.is_empty() {
+                say!("Nothing named {}.", name);
+            }
+            for line in lines {
+                say!("{}", line);
+            }
         }
-        Action::MacroCall { name, args } => {
-            if let Some((params, body)) = ctx.macros.get(name) {
-                if params.len() != args.len() {
-                    println!("Macro {} expects {} arguments, got {}", name, params.len(), args.len());
-                    return;
+        Action::Source { line, text } => {
+            let origin = Origin { file: ctx.script.clone(), line: Some(*line), source: text.clone() };
+            ctx.world.provenance.enter(origin);
+        }
+        Action::Tick(n) => {
+            say!("Advance τ by {}", n);
+            ctx.world.tau += *n as u64;
+            console::set_tau(ctx.world.tau);
+            for bridge in ctx.bridges.clone() {
+                if let Err(e) = couple(ctx, &bridge) {
+                    say!("Bridge {}@{} -> {}@{} failed: {}", bridge.from.field, bridge.from.world, bridge.to.field, bridge.to.world, e);
                 }
-                let old_vars = ctx.vars.clone();
-                for (p, a) in params.iter().zip(args.iter()) {
-                    ctx.vars.insert(p.clone(), expand_vars(a, ctx));
+            }
+            log_emergence(ctx);
+            fire_rules(ctx);
+            if let Some(snapshots) = &ctx.snapshots {
+                snapshots.publish(&ctx.world);
+            }
+        }
+        Action::Field { name, size, doc } => {
+            let name = expand_vars(name, ctx);
+            ctx.world.document(&name, doc.as_deref());
+            say!("Field {} size={}", name, size);
+            ctx.world.fields.insert(name, Arc::new(Substrate::new(*size)));
+        }
+        Action::Interpretation { name, values, doc } => {
+            let name = expand_vars(name, ctx);
+            ctx.world.document(&name, doc.as_deref());
+            say!("Interpretation {} = {:?}", name, values);
+            ctx.world.interps.insert(name, Interpretation::new(values.clone()));
+        }
+        Action::Measure { name, metric, field, interp } => {
+            let field = expand_vars(field, ctx);
+            let interp = expand_vars(interp, ctx);
+            let (Some(f), Some(i)) = (ctx.world.fields.get(&field), ctx.world.interps.get(&interp)) else {
+                say!("Measure {} failed: unknown field '{}' or interpretation '{}'.", name, field, interp);
+                return;
+            };
+            let Some(value) = Metric::from_name(metric).map(|m| m.compute(f, i)) else {
+                say!("Unknown metric '{}'; expected one of {}.", metric, METRIC_NAMES.join(", "));
+                return;
+            };
+            say!("Measure {} = {}({}, {}) = {:.4}", name, metric, field, interp, value);
+            ctx.world.measurements.insert(name.clone(), value);
+            // Measurements are also variables, so later actions can use $name.
+            ctx.world.vars.insert(name.clone(), value.to_string());
+            ctx.events.publish(Event::TraceComputed { name: name.clone(), value, tau: ctx.world.tau });
+        }
+        Action::Perturb { field, amplitude } => {
+            let field = expand_vars(field, ctx);
+            match ctx.world.fields.get_mut(&field) {
+                Some(f) => {
+                    perturb(Arc::make_mut(f), *amplitude, ctx.world.rng.at("perturb"));
+                    say!("Perturb {} with noise {}", field, amplitude);
                 }
-                for act in body {
-                    execute_action(act, ctx);
+                None => say!("Field '{}' not found.", field),
+            }
+        }
+        Action::Shock { field, indices, value } => {
+            let field = expand_vars(field, ctx);
+            let Some(range) = parse_index_range(&expand_vars(indices, ctx)) else {
+                say!("Invalid shock indices '{}'.", indices);
+                return;
+            };
+            match ctx.world.fields.get_mut(&field) {
+                Some(f) => match shock(Arc::make_mut(f), range.clone(), *value) {
+                    Ok(()) => say!("Shock {}[{}..{}] = {}", field, range.start, range.end, value),
+                    Err(e) => say!("Shock failed: {}", e),
+                },
+                None => say!("Field '{}' not found.", field),
+            }
+        }
+        Action::PerturbMemory { agent, rate } => {
+            let agent = expand_vars(agent, ctx);
+            match ctx.world.agents.get_mut(&agent) {
+                Some(state) => {
+                    let state = Arc::make_mut(state);
+                    let pinned = state.agent.as_ref().map(|a| a.memory.pinned.clone()).unwrap_or_default();
+                    let lost = perturb_memory(&mut state.memory, &pinned, *rate, ctx.world.rng.at("perturb memory"));
+                    say!("Perturb {}: forgot {} memories", agent, lost);
                 }
-                ctx.vars = old_vars;
+                None => say!("Agent '{}' not found.", agent),
+            }
+        }
+        Action::Pin { agent, token, pinned } => {
+            let (agent, token) = (expand_vars(agent, ctx), expand_vars(token, ctx));
+            let memory = ctx.world.agents.get_mut(&agent).map(Arc::make_mut).and_then(|state| state.agent.as_mut());
+            let Some(memory) = memory.map(|a| &mut a.memory) else {
+                say!("Agent '{}' not found.", agent);
+                return;
+            };
+            match (*pinned, if *pinned { memory.pin(&token) } else { memory.unpin(&token) }) {
+                (true, true) => say!("Pin {} in {}", token, agent),
+                (true, false) => say!("{} is already pinned in {}", token, agent),
+                (false, true) => say!("Unpin {} in {}", token, agent),
+                (false, false) => say!("{} is not pinned in {}", token, agent),
+            }
+        }
+        Action::Log(name) => match ctx.world.measurements.get(name) {
+            Some(value) => say!("[τ={}] {} = {:.4}", ctx.world.tau, name, value),
+            None => match ctx.world.vars.get(name) {
+                Some(value) => say!("[τ={}] {} = {}", ctx.world.tau, name, value),
+                None => say!("Nothing named '{}' to log.", name),
+            },
+        },
+        Action::Assert(cond) => {
+            if eval_condition(cond, ctx) {
+                say!("Assert '{}' passed.", cond);
             } else {
-                println!("Macro '{}' not found.", name);
+                say!("Assert '{}' failed at τ={}.", cond, ctx.world.tau);
+            }
+        }
+        Action::Comment(text) => {
+            say!("# {}", text);
+        }
+        Action::MacroCall { name, args } => {
+            let Some(definition) = ctx.macros.get(name).cloned() else {
+                say!("Macro '{}' not found.", name);
+                return;
+            };
+            if definition.params.len() != args.len() {
+                say!("Macro {} expects {} arguments, got {}", name, definition.params.len(), args.len());
+                return;
+            }
+            let args: Vec<String> = args.iter().map(|a| expand_vars(a, ctx)).collect();
+            // An unmet precondition stops the call before it changes anything.
+            let missing: Vec<String> = definition
+                .contract
+                .required(&definition.params, &args)
+                .into_iter()
+                .filter(|r| !r.exists(&ctx.world))
+                .map(|r| r.to_string())
+                .collect();
+            if !missing.is_empty() {
+                eprintln!("⚠️ {}({}) not run at τ={}: requires {}, which does not exist", name, args.join(", "), ctx.world.tau, missing.join(", "));
+                return;
+            }
+            let old_vars = ctx.world.vars.clone();
+            for (p, a) in definition.params.iter().zip(&args) {
+                ctx.world.vars.insert(p.clone(), a.clone());
+            }
+            for act in &definition.body {
+                execute_action(act, ctx);
             }
+            ctx.world.vars = old_vars;
+            for resource in definition.contract.created(&definition.params, &args) {
+                if !resource.exists(&ctx.world) {
+                    eprintln!("⚠️ {}({}) declares it creates {}, but it does not exist afterwards", name, args.join(", "), resource);
+                }
+            }
+        }
+    }
+}
+
+/// Move a bridge's target field `strength` of the way toward its source,
+/// creating the target as a copy if it does not exist yet.
+fn couple(ctx: &mut ScriptContext, bridge: &Bridge) -> Result<(), String> {
+    let (from, to) = (&bridge.from, &bridge.to);
+    let source = ctx
+        .world_named(&from.world)
+        .ok_or_else(|| format!("world '{}' not found", from.world))?
+        .fields
+        .get(&from.field)
+        .cloned()
+        .ok_or_else(|| format!("field '{}' not found in world {}", from.field, from.world))?;
+    let world = ctx.world_named_mut(&to.world).ok_or_else(|| format!("world '{}' not found", to.world))?;
+    let Some(target) = world.fields.get_mut(&to.field) else {
+        world.fields.insert(to.field.clone(), Arc::new(Substrate::clone(&source)));
+        return Ok(());
+    };
+    if target.state.len() != source.state.len() {
+        return Err(format!("{} has size {} but {} has size {}", from.field, source.state.len(), to.field, target.state.len()));
+    }
+    for (t, s) in Arc::make_mut(target).state.iter_mut().zip(&source.state) {
+        *t += bridge.strength * (s - *t);
+    }
+    Ok(())
+}
+
+/// Fire the rules that hold at the current τ, in priority order.
+fn fire_rules(ctx: &mut ScriptContext) {
+    let tau = ctx.world.tau;
+    let Some(order) = ctx.rules.begin(tau) else { return };
+    for i in order {
+        let rule = &ctx.rules.rules()[i];
+        if !ctx.rules.ready(i, tau) || !eval_condition(&rule.condition, ctx) {
+            continue;
+        }
+        say!("Rule '{}' fired at τ={}", rule.condition, tau);
+        let actions = rule.actions.clone();
+        ctx.rules.fired(i, tau);
+        for action in &actions {
+            execute_action(action, ctx);
         }
     }
+    ctx.rules.end();
 }
 
-fn eval_condition(cond: &str, ctx: &ScriptContext) -> bool {
-    if cond == "always" {
-        return true;
+/// Record the emergence score of every hierarchy container at the current τ.
+fn log_emergence(ctx: &mut ScriptContext) {
+    let tau = ctx.world.tau;
+    let mut records = Vec::new();
+    for obj in ctx.world.hierarchies.values() {
+        for (id, level, score) in obj.emergence_by_node() {
+            say!("Emergence {} ({:?}) at τ={}: {:.3}", id, level, tau, score);
+            records.push(EmergenceRecord { tau, id, level, score });
+        }
     }
-    let tokens: Vec<&str> = cond.split_whitespace().collect();
-    if tokens.len() == 3 && tokens[1] == "knows" {
-        if let Some(agent) = ctx.agents.get(tokens[0]) {
-            return agent.memory.contains(&tokens[2].to_string());
+    ctx.world.emergence_log.extend(records);
+}
+
+/// Numbers are measurements, numeric variables and `tau`; agents are those of the current world.
+impl Scope for ScriptContext {
+    fn value(&self, name: &String) -> Option<f64> {
+        let name = name.strip_prefix('$').unwrap_or(name);
+        if name == "tau" || name == "τ" {
+            return Some(self.world.tau as f64);
         }
+        self.world.measurements.get(name).copied().or_else(|| self.world.vars.get(name)?.parse().ok())
+    }
+
+    fn knows(&self, agent: &str, token: &str) -> Option<bool> {
+        self.world.agents.get(agent).map(|state| state.memory.iter().any(|m| m == token))
+    }
+
+    fn consensus(&self, group: &str, token: &str) -> Option<Consensus> {
+        self.world.consensus(group, token)
     }
-    if tokens.len() == 3 && tokens[1] == "memory" && tokens[2].starts_with("contains") {
-        let agent = tokens[0];
-        let item = cond.split("contains").nth(1).unwrap().trim();
-        if let Some(agent) = ctx.agents.get(agent) {
-            return agent.memory.contains(&item.to_string());
+
+    fn activation(&self, agent: &str, token: &str) -> Option<f64> {
+        self.world.agents.get(agent).map(|state| state.activation_of(token))
+    }
+}
+
+fn eval_condition(cond: &Condition, ctx: &ScriptContext) -> bool {
+    match cond.eval(ctx) {
+        Ok(holds) => holds,
+        Err(unknown) => {
+            say!("Condition '{}' names unknown {}, default false.", cond, unknown);
+            false
         }
     }
-    println!("Condition '{}' not recognized, default false.", cond);
-    false
 }
 
 fn expand_vars(text: &str, ctx: &ScriptContext) -> String {
@@ -173,7 +852,7 @@ fn expand_vars(text: &str, ctx: &ScriptContext) -> String {
                 name.push(n);
                 chars.next();
             }
-            if let Some(val) = ctx.vars.get(&name) {
+            if let Some(val) = ctx.world.vars.get(&name) {
                 result.push_str(val);
             } else {
                 result.push('$');
@@ -0,0 +1,34 @@
+//! Randomized initial conditions for Monte-Carlo style narratives.
+//!
+//! `random agents 50 coh 0.1..0.4` and `random symbols 20 bits 8 into alice`
+//! draw from the world's RNG, so a `seed` (or `--seed`) fixes the whole
+//! population and a different seed gives another sample of it, with no
+//! external generator writing scripts.
+
+use rand::Rng;
+
+/// `count` names `<prefix>1`, `<prefix>2`, ... skipping those already `taken`.
+pub fn next_names(prefix: &str, count: usize, taken: impl Fn(&str) -> bool) -> Vec<String> {
+    (1..).map(|i| format!("{}{}", prefix, i)).filter(|name| !taken(name)).take(count).collect()
+}
+
+/// A pattern of `bits` random 0s and 1s.
+pub fn random_pattern(bits: usize, rng: &mut impl Rng) -> String {
+    (0..bits).map(|_| if rng.gen::<bool>() { '1' } else { '0' }).collect()
+}
+
+/// A uniform draw from `low..=high`; `low` itself when the range is empty.
+pub fn draw(low: f64, high: f64, rng: &mut impl Rng) -> f64 {
+    if high > low {
+        rng.gen_range(low..=high)
+    } else {
+        low
+    }
+}
+
+/// Memory of a `random agents` agent unless given with `mem`.
+pub const DEFAULT_MEMORY: u32 = 64;
+/// Name prefix of `random agents` unless given with `named`.
+pub const AGENT_PREFIX: &str = "agent";
+/// Token prefix of `random symbols` unless given with `named`.
+pub const SYMBOL_PREFIX: &str = "sym";
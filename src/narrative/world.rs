@@ -0,0 +1,69 @@
+//! Forkable worlds for narrative scripts.
+//!
+//! `world fork as W2` snapshots the running state so a second future can be
+//! explored from the same past; `world switch W2` continues in it and
+//! `world diff W2` compares the two; `world create W3` starts an empty one.
+//! Fields and agents are shared between worlds until one of them writes
+//! (`Arc::make_mut`), so a fork copies pointers, not populations. No write in
+//! one world is seen by another, and each world draws noise from its own RNG;
+//! the only coupling is an explicit `bridge F@main -> G@W2`.
+
+use crate::runtime::Runtime;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+/// Name of the world a script starts in.
+pub const MAIN_WORLD: &str = "main";
+
+/// A world is one runtime; a script keeps the ones it is not running by name.
+pub type World = Runtime;
+
+/// Differences between two worlds, one line each, `a` before `b`.
+pub fn diff(a: &World, b: &World) -> Vec<String> {
+    let mut lines = Vec::new();
+    if a.tau != b.tau {
+        lines.push(format!("τ: {} → {}", a.tau, b.tau));
+    }
+    let names: BTreeSet<&String> = a.measurements.keys().chain(b.measurements.keys()).collect();
+    for name in names {
+        match (a.measurements.get(name), b.measurements.get(name)) {
+            (Some(x), Some(y)) if x == y => {}
+            (Some(x), Some(y)) => lines.push(format!("{}: {:.4} → {:.4} (Δ {:+.4})", name, x, y, y - x)),
+            (Some(x), None) => lines.push(format!("{}: {:.4} → unmeasured", name, x)),
+            (None, Some(y)) => lines.push(format!("{}: unmeasured → {:.4}", name, y)),
+            (None, None) => {}
+        }
+    }
+    let names: BTreeSet<&String> = a.fields.keys().chain(b.fields.keys()).collect();
+    for name in names {
+        match (a.fields.get(name), b.fields.get(name)) {
+            // Still shared since the fork: nothing to compare.
+            (Some(x), Some(y)) if Arc::ptr_eq(x, y) => {}
+            (Some(x), Some(y)) if x.state.len() != y.state.len() => {
+                lines.push(format!("field {}: size {} → {}", name, x.state.len(), y.state.len()))
+            }
+            (Some(x), Some(y)) => {
+                let distance = x.state.iter().zip(&y.state).map(|(p, q)| (p - q).powi(2)).sum::<f64>().sqrt();
+                if distance > 0.0 {
+                    lines.push(format!("field {}: distance {:.4}", name, distance));
+                }
+            }
+            (Some(_), None) => lines.push(format!("field {}: only in the first world", name)),
+            (None, Some(_)) => lines.push(format!("field {}: only in the second world", name)),
+            (None, None) => {}
+        }
+    }
+    let names: BTreeSet<&String> = a.agents.keys().chain(b.agents.keys()).collect();
+    for name in names {
+        match (a.agents.get(name), b.agents.get(name)) {
+            (Some(x), Some(y)) if Arc::ptr_eq(x, y) || x.memory == y.memory => {}
+            (Some(x), Some(y)) => {
+                lines.push(format!("agent {}: {} → {} memories", name, x.memory.len(), y.memory.len()))
+            }
+            (Some(_), None) => lines.push(format!("agent {}: only in the first world", name)),
+            (None, Some(_)) => lines.push(format!("agent {}: only in the second world", name)),
+            (None, None) => {}
+        }
+    }
+    lines
+}
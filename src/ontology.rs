@@ -0,0 +1,207 @@
+//! Recursion-level ladders defined in TOML, for domains other than
+//! particles → atoms → molecules → cells.
+//!
+//! An ontology lists its levels bottom first. Each level names the measures
+//! its interpretation reports about the object itself and the rules that
+//! aggregate measures of its parts:
+//!
+//! ```toml
+//! [[level]]
+//! name = "word"
+//! report = ["energy"]
+//!
+//! [[level]]
+//! name = "sentence"
+//! report = ["energy", "agents"]
+//! aggregate = { energy = "mean", parts = "sum" }
+//!
+//! [[level]]
+//! name = "discourse"
+//! aggregate = { energy = "max", stability = "sum" }
+//! ```
+//!
+//! `sptl.toml` selects one with `ontology = "language.toml"`; it then replaces
+//! the built-in ladder for `level` blocks and narrative `create`.
+
+use crate::recursion::{CategoryObject, RecursionLevel};
+use serde::{Deserialize, Deserializer};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Ontology {
+    /// Levels from the bottom of the ladder up.
+    #[serde(rename = "level")]
+    pub levels: Vec<LevelSpec>,
+}
+
+/// One rung of the ladder and its interpretation template.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LevelSpec {
+    pub name: String,
+    /// Measures of the object itself that its interpretation reports.
+    #[serde(default)]
+    pub report: Vec<Measure>,
+    /// Measures of the parts combined into one value each, reported as e.g. `mean energy`.
+    #[serde(default, deserialize_with = "aggregate_rules")]
+    pub aggregate: Vec<(Measure, Aggregation)>,
+}
+
+/// A number every hierarchy object has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Measure {
+    /// Sum of the object's substrate activations.
+    Energy,
+    /// Summed stability of its agents' memory traces.
+    Stability,
+    /// Number of agents placed on the object.
+    Agents,
+    /// Number of direct parts.
+    Parts,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregation {
+    Sum,
+    Mean,
+    Min,
+    Max,
+}
+
+impl Measure {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "energy" => Some(Measure::Energy),
+            "stability" => Some(Measure::Stability),
+            "agents" => Some(Measure::Agents),
+            "parts" => Some(Measure::Parts),
+            _ => None,
+        }
+    }
+
+    /// This measure of `obj` alone.
+    pub fn of(self, obj: &CategoryObject) -> f64 {
+        match self {
+            Measure::Energy => obj.substrate.activations.values().sum(),
+            Measure::Stability => obj.agents.iter().flat_map(|a| &a.memory.traces).map(|t| t.stability).sum(),
+            Measure::Agents => obj.agents.len() as f64,
+            Measure::Parts => obj.subobjects.len() as f64,
+        }
+    }
+}
+
+impl fmt::Display for Measure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Measure::Energy => "energy",
+            Measure::Stability => "stability",
+            Measure::Agents => "agents",
+            Measure::Parts => "parts",
+        })
+    }
+}
+
+impl Aggregation {
+    /// Combine `values`; an object without parts aggregates to 0.
+    pub fn apply(self, values: &[f64]) -> f64 {
+        if values.is_empty() {
+            return 0.0;
+        }
+        match self {
+            Aggregation::Sum => values.iter().sum(),
+            Aggregation::Mean => values.iter().sum::<f64>() / values.len() as f64,
+            Aggregation::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            Aggregation::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+impl fmt::Display for Aggregation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Aggregation::Sum => "sum",
+            Aggregation::Mean => "mean",
+            Aggregation::Min => "min",
+            Aggregation::Max => "max",
+        })
+    }
+}
+
+impl Ontology {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ontology::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Parse and check an ontology: at least one level, at most 255, names unique.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let ontology: Ontology = toml::from_str(text).map_err(|e| e.to_string())?;
+        if ontology.levels.is_empty() {
+            return Err("an ontology needs at least one [[level]]".to_string());
+        }
+        if ontology.levels.len() > u8::MAX as usize {
+            return Err(format!("an ontology has at most {} levels", u8::MAX));
+        }
+        let mut seen = HashSet::new();
+        for level in &ontology.levels {
+            if !seen.insert(level.name.to_lowercase()) {
+                return Err(format!("level {} is defined twice", level.name));
+            }
+        }
+        Ok(ontology)
+    }
+
+    /// The level named `name`; a trailing plural `s` is accepted as for built-in levels.
+    pub fn level(&self, name: &str) -> Option<RecursionLevel> {
+        let name = name.to_lowercase();
+        let rank = self.levels.iter().position(|l| {
+            let own = l.name.to_lowercase();
+            own == name || name.strip_suffix('s') == Some(own.as_str())
+        })?;
+        Some(RecursionLevel::Defined { rank: rank as u8, top: (self.levels.len() - 1) as u8 })
+    }
+
+    /// The template of a level defined by this ontology.
+    pub fn spec(&self, level: RecursionLevel) -> Option<&LevelSpec> {
+        match level {
+            RecursionLevel::Defined { rank, .. } => self.levels.get(rank as usize),
+            _ => None,
+        }
+    }
+}
+
+impl LevelSpec {
+    /// Reported and aggregated values of `obj`, keyed `energy`, `mean energy`, ...
+    pub fn values(&self, obj: &CategoryObject) -> BTreeMap<String, f64> {
+        let mut values: BTreeMap<String, f64> = self.report.iter().map(|m| (m.to_string(), m.of(obj))).collect();
+        for (measure, aggregation) in &self.aggregate {
+            let parts: Vec<f64> = obj.subobjects.iter().map(|s| measure.of(s)).collect();
+            values.insert(format!("{} {}", aggregation, measure), aggregation.apply(&parts));
+        }
+        values
+    }
+}
+
+/// Resolve a level name against `ontology`, or the built-in ladder without one.
+pub fn level_named(ontology: Option<&Ontology>, name: &str) -> Option<RecursionLevel> {
+    match ontology {
+        Some(ontology) => ontology.level(name),
+        None => RecursionLevel::from_name(name),
+    }
+}
+
+fn aggregate_rules<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<(Measure, Aggregation)>, D::Error> {
+    BTreeMap::<String, Aggregation>::deserialize(d)?
+        .into_iter()
+        .map(|(name, aggregation)| match Measure::from_name(&name) {
+            Some(measure) => Ok((measure, aggregation)),
+            None => Err(serde::de::Error::custom(format!(
+                "unknown measure \"{}\"; expected energy, stability, agents or parts",
+                name
+            ))),
+        })
+        .collect()
+}
@@ -0,0 +1,157 @@
+//! Script packages: an experiment directory (scripts, includes, config) bundled
+//! into one `.sptlpkg` file that records the crate version and seed.
+//!
+//! The package is a single JSON document so it can be inspected and diffed
+//! without extra tooling.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+pub const PACKAGE_EXTENSION: &str = "sptlpkg";
+
+/// Entry script picked when `--entry` is not given.
+const DEFAULT_ENTRY: &str = "main.sptl";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageManifest {
+    /// Crate version that built the package.
+    pub version: String,
+    /// Seed the experiment is meant to run with.
+    pub seed: Option<u64>,
+    /// Script executed by `sptl run`, relative to the package root.
+    pub entry: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Package {
+    pub manifest: PackageManifest,
+    /// File contents keyed by path relative to the package root ('/' separated).
+    pub files: BTreeMap<String, Contents>,
+}
+
+/// A packaged file: written as a string when it is UTF-8, so the package
+/// stays readable, and as an array of bytes otherwise.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Contents {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl Contents {
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Contents::Text(text) => text.as_bytes(),
+            Contents::Bytes(bytes) => bytes,
+        }
+    }
+}
+
+impl From<Vec<u8>> for Contents {
+    fn from(bytes: Vec<u8>) -> Self {
+        match String::from_utf8(bytes) {
+            Ok(text) => Contents::Text(text),
+            Err(e) => Contents::Bytes(e.into_bytes()),
+        }
+    }
+}
+
+/// Whether `rel` stays under the directory it is joined to: relative, with
+/// no `..`, `.` or root components.
+fn is_safe_path(rel: &str) -> bool {
+    !rel.is_empty() && Path::new(rel).components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+fn collect_files(root: &Path, dir: &Path, out: &mut BTreeMap<String, Contents>) -> io::Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|e| e.path());
+    for entry in entries {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(root, &path, out)?;
+        } else {
+            let rel = path.strip_prefix(root).unwrap_or(&path);
+            let key = rel.components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join("/");
+            out.insert(key, Contents::from(fs::read(&path)?));
+        }
+    }
+    Ok(())
+}
+
+impl Package {
+    /// Bundle every file under `dir`. The entry is `entry` if given, otherwise
+    /// `main.sptl`, otherwise the only `.sptl` file present.
+    pub fn from_dir(dir: &Path, entry: Option<&str>, seed: Option<u64>) -> io::Result<Self> {
+        let mut files = BTreeMap::new();
+        collect_files(dir, dir, &mut files)?;
+        let entry = match entry {
+            Some(e) => e.to_string(),
+            None => {
+                let scripts: Vec<&String> = files.keys().filter(|k| k.ends_with(".sptl")).collect();
+                if files.contains_key(DEFAULT_ENTRY) {
+                    DEFAULT_ENTRY.to_string()
+                } else if scripts.len() == 1 {
+                    scripts[0].clone()
+                } else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "cannot pick an entry script; pass --entry <file>",
+                    ));
+                }
+            }
+        };
+        if !files.contains_key(&entry) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("entry '{}' is not in the package", entry)));
+        }
+        let manifest = PackageManifest { version: env!("CARGO_PKG_VERSION").to_string(), seed, entry };
+        Ok(Package { manifest, files })
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self).map_err(io::Error::other)?)
+    }
+
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let pkg: Package = serde_json::from_str(&fs::read_to_string(path)?).map_err(io::Error::other)?;
+        if pkg.manifest.version != env!("CARGO_PKG_VERSION") {
+            eprintln!(
+                "⚠️ Package built with sptl-spi {}, running with {}",
+                pkg.manifest.version,
+                env!("CARGO_PKG_VERSION")
+            );
+        }
+        Ok(pkg)
+    }
+
+    /// Write the package files under `dir` and return the path of the entry
+    /// script. Nothing is written if any path would leave `dir` or the entry
+    /// is not one of the files.
+    pub fn extract(&self, dir: &Path) -> io::Result<PathBuf> {
+        if let Some(rel) = self.files.keys().find(|rel| !is_safe_path(rel)) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unsafe path '{}' in package", rel)));
+        }
+        if !self.files.contains_key(&self.manifest.entry) {
+            let message = format!("entry '{}' is not in the package", self.manifest.entry);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+        for (rel, contents) in &self.files {
+            let path = dir.join(rel);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, contents.as_bytes())?;
+        }
+        Ok(dir.join(&self.manifest.entry))
+    }
+}
+
+/// Whether a path names a package rather than a plain script.
+pub fn is_package(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|e| e == PACKAGE_EXTENSION)
+}
@@ -0,0 +1,47 @@
+//! External disturbances applied to fields and agent memories.
+
+use crate::substrate::Substrate;
+use rand::Rng;
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+/// Add uniform noise in `[-amplitude, amplitude]`, drawn from `rng`, to every element.
+pub fn perturb(field: &mut Substrate, amplitude: f64, rng: &mut impl Rng) {
+    for s in field.state.iter_mut() {
+        *s += rng.gen_range(-amplitude..=amplitude);
+    }
+}
+
+/// Set every element in `indices` to `value`.
+pub fn shock(field: &mut Substrate, indices: Range<usize>, value: f64) -> Result<(), String> {
+    if indices.end > field.state.len() || indices.start > indices.end {
+        return Err(format!(
+            "shock indices {}..{} out of range for field of size {}",
+            indices.start,
+            indices.end,
+            field.state.len()
+        ));
+    }
+    field.state[indices].fill(value);
+    Ok(())
+}
+
+/// Forget each memory item not in `pinned` independently with probability
+/// `rate`, drawn from `rng`; returns how many were lost.
+pub fn perturb_memory(memory: &mut Vec<String>, pinned: &BTreeSet<String>, rate: f64, rng: &mut impl Rng) -> usize {
+    let before = memory.len();
+    memory.retain(|token| pinned.contains(token) || !rng.gen_bool(rate.clamp(0.0, 1.0)));
+    before - memory.len()
+}
+
+/// Parse `3..10`, `3..=10` or a single index `5` (brackets already stripped).
+pub fn parse_index_range(text: &str) -> Option<Range<usize>> {
+    if let Some((start, end)) = text.split_once("..=") {
+        return Some(start.trim().parse().ok()?..end.trim().parse::<usize>().ok()? + 1);
+    }
+    if let Some((start, end)) = text.split_once("..") {
+        return Some(start.trim().parse().ok()?..end.trim().parse().ok()?);
+    }
+    let i: usize = text.trim().parse().ok()?;
+    Some(i..i + 1)
+}
@@ -2,13 +2,15 @@ use crate::substrate::Substrate;
 use crate::interpretation::Interpretation;
 use rand::Rng;
 
+/// Move `substrate` a fraction `alpha` toward `interpretation`, with uniform
+/// noise in `[-noise, noise]` drawn from `rng`.
 pub fn project(
     substrate: &mut Substrate,
     interpretation: &Interpretation,
     alpha: f64,
     noise: f64,
+    rng: &mut impl Rng,
 ) {
-    let mut rng = rand::thread_rng();
     for (s, i) in substrate.state.iter_mut().zip(&interpretation.data) {
         let n = rng.gen_range(-noise..=noise);
         *s = (1.0 - alpha) * *s + alpha * (*i + n);
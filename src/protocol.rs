@@ -0,0 +1,164 @@
+//! Conformance checking of the say → project → interpret loop.
+//!
+//! Every symbol an agent expresses should be projected by that agent and then
+//! interpreted by some agent. The checker replays the events of a run and
+//! reports, per agent, each expression that never completed the loop and each
+//! phase that happened without the phase before it.
+
+use crate::events::Event;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// Expressed at `tau` but never projected by the expressing agent.
+    NeverProjected { token: String, tau: u64 },
+    /// Projected at `tau` but never interpreted by any agent.
+    NeverInterpreted { token: String, tau: u64 },
+    /// Projected at `tau` without being expressed by the projecting agent first.
+    ProjectedUnexpressed { token: String, tau: u64 },
+    /// Interpreted at `tau` while the matching expression had not been projected yet.
+    InterpretedBeforeProjected { token: String, tau: u64 },
+    /// Interpreted at `tau` although no agent expressed the symbol.
+    InterpretedUnexpressed { token: String, tau: u64 },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::NeverProjected { token, tau } => write!(f, "{} expressed at τ={} was never projected", token, tau),
+            Violation::NeverInterpreted { token, tau } => {
+                write!(f, "{} projected at τ={} was never interpreted", token, tau)
+            }
+            Violation::ProjectedUnexpressed { token, tau } => {
+                write!(f, "{} projected at τ={} without being expressed", token, tau)
+            }
+            Violation::InterpretedBeforeProjected { token, tau } => {
+                write!(f, "{} interpreted at τ={} before it was projected", token, tau)
+            }
+            Violation::InterpretedUnexpressed { token, tau } => {
+                write!(f, "{} interpreted at τ={} but never expressed", token, tau)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Expressed,
+    Projected,
+}
+
+/// One expression still waiting for its later phases.
+#[derive(Debug, Clone)]
+struct Pending {
+    agent: String,
+    token: String,
+    phase: Phase,
+    tau: u64,
+}
+
+/// Violations of one run, keyed by the agent responsible.
+#[derive(Debug, Clone, Default)]
+pub struct ConformanceReport {
+    /// Expressions that completed the whole loop.
+    pub completed: usize,
+    pub violations: BTreeMap<String, Vec<Violation>>,
+}
+
+impl ConformanceReport {
+    pub fn is_conformant(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    pub fn print(&self) {
+        if self.is_conformant() {
+            println!("Protocol: {} expressions completed say → project → interpret.", self.completed);
+            return;
+        }
+        let count: usize = self.violations.values().map(|v| v.len()).sum();
+        println!("Protocol: {} completed, {} violations.", self.completed, count);
+        for (agent, violations) in &self.violations {
+            println!("  {}:", agent);
+            for v in violations {
+                println!("    {}", v);
+            }
+        }
+    }
+}
+
+/// Replays events in publication order. Each phase matches the oldest
+/// outstanding expression of the same token.
+#[derive(Debug, Default)]
+pub struct ProtocolChecker {
+    pending: Vec<Pending>,
+    report: ConformanceReport,
+}
+
+impl ProtocolChecker {
+    pub fn new() -> Self {
+        ProtocolChecker::default()
+    }
+
+    pub fn observe(&mut self, event: &Event) {
+        match event {
+            Event::SymbolExpressed { source, token, tau } => self.pending.push(Pending {
+                agent: source.clone(),
+                token: token.clone(),
+                phase: Phase::Expressed,
+                tau: *tau,
+            }),
+            Event::SymbolProjected { agent, token, tau } => {
+                let found = self
+                    .pending
+                    .iter_mut()
+                    .find(|p| p.phase == Phase::Expressed && &p.agent == agent && &p.token == token);
+                match found {
+                    Some(p) => {
+                        p.phase = Phase::Projected;
+                        p.tau = *tau;
+                    }
+                    None => self.violation(agent, Violation::ProjectedUnexpressed { token: token.clone(), tau: *tau }),
+                }
+            }
+            Event::SymbolInterpreted { agent, token, tau } => {
+                let of_token = |phase| self.pending.iter().position(|p| p.phase == phase && &p.token == token);
+                if let Some(i) = of_token(Phase::Projected) {
+                    self.pending.remove(i);
+                    self.report.completed += 1;
+                } else if let Some(i) = of_token(Phase::Expressed) {
+                    let p = self.pending.remove(i);
+                    self.violation(&p.agent, Violation::InterpretedBeforeProjected { token: p.token, tau: *tau });
+                } else {
+                    self.violation(agent, Violation::InterpretedUnexpressed { token: token.clone(), tau: *tau });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Report every expression still outstanding as a violation.
+    pub fn finish(mut self) -> ConformanceReport {
+        for p in std::mem::take(&mut self.pending) {
+            let v = match p.phase {
+                Phase::Expressed => Violation::NeverProjected { token: p.token, tau: p.tau },
+                Phase::Projected => Violation::NeverInterpreted { token: p.token, tau: p.tau },
+            };
+            self.violation(&p.agent, v);
+        }
+        self.report
+    }
+
+    fn violation(&mut self, agent: &str, v: Violation) {
+        self.report.violations.entry(agent.to_string()).or_default().push(v);
+    }
+}
+
+/// Check a complete event sequence.
+pub fn check(events: impl IntoIterator<Item = Event>) -> ConformanceReport {
+    let mut checker = ProtocolChecker::new();
+    for event in events {
+        checker.observe(&event);
+    }
+    checker.finish()
+}
@@ -0,0 +1,132 @@
+//! Which script line caused a piece of state.
+//!
+//! While a script runs, the interpreter keeps the origin of the statement or
+//! action it is executing current (`Provenance::enter`). Whatever writes
+//! state stamps the current origin on it: a pattern's activation in a field
+//! (`activated`) or a token entering an agent's memory (`remembered`).
+//! `why pattern 1010 in F` and `why token fire in alice` list those causes
+//! in the order they happened, from script text to simulation state.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Where a statement or action was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin {
+    /// Script path, when the script was read from a file.
+    pub file: Option<String>,
+    /// 1-based line, when known.
+    pub line: Option<usize>,
+    /// The statement or action as written.
+    pub source: String,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}:{}: ", file, line)?,
+            (Some(file), None) => write!(f, "{}: ", file)?,
+            (None, Some(line)) => write!(f, "line {}: ", line)?,
+            (None, None) => {}
+        }
+        f.write_str(&self.source)
+    }
+}
+
+/// One state change: what caused it and when.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cause {
+    /// `None` for changes made outside any script, such as from a REPL command.
+    pub origin: Option<Origin>,
+    pub tau: u64,
+}
+
+impl fmt::Display for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.origin {
+            Some(origin) => write!(f, "τ={} {}", self.tau, origin),
+            None => write!(f, "τ={} (no script)", self.tau),
+        }
+    }
+}
+
+/// The causes of every activation and memory trace, keyed by (field, pattern)
+/// and (agent, token).
+#[derive(Debug, Clone, Default)]
+pub struct Provenance {
+    current: Option<Origin>,
+    activations: BTreeMap<(String, String), Vec<Cause>>,
+    traces: BTreeMap<(String, String), Vec<Cause>>,
+}
+
+impl Provenance {
+    /// Attribute the following changes to `origin`.
+    pub fn enter(&mut self, origin: Origin) {
+        self.current = Some(origin);
+    }
+
+    pub fn current(&self) -> Option<&Origin> {
+        self.current.as_ref()
+    }
+
+    /// `pattern` was activated in `field`.
+    pub fn activated(&mut self, field: &str, pattern: &str, tau: u64) {
+        let cause = Cause { origin: self.current.clone(), tau };
+        self.activations.entry((field.to_string(), pattern.to_string())).or_default().push(cause);
+    }
+
+    /// `token` entered or was reinforced in the memory of `agent`.
+    pub fn remembered(&mut self, agent: &str, token: &str, tau: u64) {
+        let cause = Cause { origin: self.current.clone(), tau };
+        self.traces.entry((agent.to_string(), token.to_string())).or_default().push(cause);
+    }
+
+    /// Why `pattern` is active in `field`, oldest cause first.
+    pub fn why_pattern(&self, field: &str, pattern: &str) -> &[Cause] {
+        self.activations.get(&(field.to_string(), pattern.to_string())).map_or(&[], Vec::as_slice)
+    }
+
+    /// Why `agent` remembers `token`, oldest cause first.
+    pub fn why_token(&self, agent: &str, token: &str) -> &[Cause] {
+        self.traces.get(&(agent.to_string(), token.to_string())).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// A `why` question, as in `why pattern 1010 in F` or `why token fire in alice`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Question {
+    Pattern { pattern: String, field: String },
+    Token { token: String, agent: String },
+}
+
+impl Question {
+    /// Parse the words after `why`.
+    pub fn parse(text: &str) -> Option<Question> {
+        match text.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["pattern", pattern, "in", field] => {
+                Some(Question::Pattern { pattern: pattern.to_string(), field: field.to_string() })
+            }
+            ["token", token, "in", agent] => Some(Question::Token { token: token.to_string(), agent: agent.to_string() }),
+            _ => None,
+        }
+    }
+
+    /// The answer: one line per cause, or a line saying there is none.
+    pub fn answer(&self, provenance: &Provenance) -> Vec<String> {
+        let (causes, subject) = match self {
+            Question::Pattern { pattern, field } => {
+                (provenance.why_pattern(field, pattern), format!("pattern {} in {}", pattern, field))
+            }
+            Question::Token { token, agent } => {
+                (provenance.why_token(agent, token), format!("token {} in {}", token, agent))
+            }
+        };
+        if causes.is_empty() {
+            return vec![format!("Nothing recorded for {}.", subject)];
+        }
+        let mut lines = vec![format!("{} was caused by:", subject)];
+        lines.extend(causes.iter().map(|cause| format!("  {}", cause)));
+        lines
+    }
+}
@@ -20,26 +20,156 @@
 use crate::agents::Agent;
 use crate::substrate::Substrate;
 use crate::interpretation::*;
-use std::collections::HashMap;
+use crate::ontology::Ontology;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
 /// Enum for the recursion/categorical level.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum RecursionLevel {
     Void,       // Λ₀
     Particle,   // Λ₁
     Atom,       // Λ₂
     Molecule,   // Λ₃
     Cell,       // Λ₄
+    /// Rung `rank` (from 0 at the bottom) of a loaded ontology whose top rung is `top`.
+    Defined { rank: u8, top: u8 },
 }
 
-#[derive(Debug)]
+impl RecursionLevel {
+    /// Parse a level name as written in scripts ("atom", "molecules", ...).
+    pub fn from_name(name: &str) -> Option<Self> {
+        use RecursionLevel::*;
+        match name.to_lowercase().trim_end_matches('s') {
+            "void" => Some(Void),
+            "particle" => Some(Particle),
+            "atom" => Some(Atom),
+            "molecule" => Some(Molecule),
+            "cell" => Some(Cell),
+            _ => None,
+        }
+    }
+
+    /// The level directly above this one, if any.
+    pub fn above(self) -> Option<Self> {
+        use RecursionLevel::*;
+        match self {
+            Void => Some(Particle),
+            Particle => Some(Atom),
+            Atom => Some(Molecule),
+            Molecule => Some(Cell),
+            Cell => None,
+            Defined { rank, top } => (rank < top).then_some(Defined { rank: rank + 1, top }),
+        }
+    }
+
+    /// The level directly below this one, if any.
+    pub fn below(self) -> Option<Self> {
+        use RecursionLevel::*;
+        match self {
+            Void => None,
+            Particle => Some(Void),
+            Atom => Some(Particle),
+            Molecule => Some(Atom),
+            Cell => Some(Molecule),
+            Defined { rank, top } => rank.checked_sub(1).map(|rank| Defined { rank, top }),
+        }
+    }
+
+    /// Height on the ladder, Λ₀ = 0.
+    pub fn rank(self) -> u8 {
+        use RecursionLevel::*;
+        match self {
+            Void => 0,
+            Particle => 1,
+            Atom => 2,
+            Molecule => 3,
+            Cell => 4,
+            Defined { rank, .. } => rank,
+        }
+    }
+}
+
+impl fmt::Display for RecursionLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecursionLevel::Defined { rank, .. } => write!(f, "Λ{}", rank),
+            built_in => write!(f, "{:?}", built_in),
+        }
+    }
+}
+
+/// Decay rate applied to substrates and agent memory on each hierarchy tick.
+pub const TICK_DECAY: f64 = 0.05;
+
+/// Below this many descendants, a subtree is ticked sequentially.
+pub const DEFAULT_TICK_GRAIN: usize = 64;
+
+/// Scheduling strategy for ticking a hierarchy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickMode {
+    /// Recurse the tree, parallelizing only subtrees with at least `grain` descendants.
+    Recursive { grain: usize },
+    /// Collect every node into one worklist and tick it with a single parallel iterator.
+    Flattened,
+}
+
+impl Default for TickMode {
+    fn default() -> Self {
+        TickMode::Recursive { grain: DEFAULT_TICK_GRAIN }
+    }
+}
+
+/// Read-only analysis over a hierarchy; see [`CategoryObject::visit`].
+pub trait Visitor {
+    /// Called before an object's children. Return `false` to skip its subtree.
+    fn enter(&mut self, _obj: &CategoryObject, _depth: usize) -> bool {
+        true
+    }
+    /// Called after an object's children have been visited.
+    fn leave(&mut self, _obj: &CategoryObject, _depth: usize) {}
+}
+
+/// Mutating pass over a hierarchy; see [`CategoryObject::visit_mut`].
+pub trait VisitorMut {
+    /// Called before an object's children. Return `false` to skip its subtree.
+    fn enter(&mut self, _obj: &mut CategoryObject, _depth: usize) -> bool {
+        true
+    }
+    /// Called after an object's children have been visited.
+    fn leave(&mut self, _obj: &mut CategoryObject, _depth: usize) {}
+}
+
+#[derive(Debug, Clone)]
 pub struct CategoryObject {
     pub level: RecursionLevel,
     pub id: String,
     pub substrate: Substrate,
     pub subobjects: Vec<Box<CategoryObject>>,
     pub agents: Vec<Agent>,
+    /// Events recorded on this object (e.g. agents arriving or departing).
+    pub events: Vec<HierarchyEvent>,
+    /// Ontology defining `level`, for levels that are not built in.
+    pub ontology: Option<Arc<Ontology>>,
+}
+
+/// Something that happened to a category object.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HierarchyEvent {
+    /// An agent left (or was copied out of) this object towards `to`.
+    AgentDeparted { agent: String, to: String, copied: bool },
+    /// An agent arrived (or a copy arrived) from `from`.
+    AgentArrived { agent: String, from: String, copied: bool },
+}
+
+/// Whether a migration moves the agent or leaves the original in place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationMode {
+    Move,
+    Copy,
 }
 
 impl CategoryObject {
@@ -50,33 +180,171 @@ impl CategoryObject {
             substrate: Substrate::default(),
             subobjects: Vec::new(),
             agents: Vec::new(),
+            events: Vec::new(),
+            ontology: None,
+        }
+    }
+
+    /// Attach the ontology that defines this object's level.
+    pub fn with_ontology(mut self, ontology: Option<Arc<Ontology>>) -> Self {
+        self.ontology = ontology;
+        self
+    }
+
+    /// Name of this object's level: its ontology's name for it, else the built-in name.
+    pub fn level_name(&self) -> String {
+        match self.ontology.as_deref().and_then(|o| o.spec(self.level)) {
+            Some(spec) => spec.name.clone(),
+            None => self.level.to_string(),
         }
     }
 
     /// "Promote" this object to the next recursion level, wrapping as a subobject
     pub fn promote(self) -> Option<CategoryObject> {
-        use RecursionLevel::*;
-        let next_level = match self.level {
-            Void => Particle,
-            Particle => Atom,
-            Atom => Molecule,
-            Molecule => Cell,
-            Cell => return None,
-        };
+        let next_level = self.level.above()?;
         Some(CategoryObject {
             level: next_level,
-            id: format!("{}-{}", next_level as u8, self.id),
+            id: format!("{}-{}", next_level.rank(), self.id),
             substrate: Substrate::default(),
+            ontology: self.ontology.clone(),
             subobjects: vec![Box::new(self)],
             agents: Vec::new(),
+            events: Vec::new(),
         })
     }
 
+    /// Build an object from existing parts, which must sit exactly one level below.
+    /// The object shares the ontology of its first part.
+    pub fn from_parts(level: RecursionLevel, id: &str, parts: Vec<CategoryObject>) -> Result<Self, String> {
+        let ontology = parts.first().and_then(|p| p.ontology.clone());
+        let mut obj = CategoryObject::new(level, id).with_ontology(ontology);
+        for part in parts {
+            obj.add_subobject(part)?;
+        }
+        Ok(obj)
+    }
+
+    /// Attach a subobject, rejecting parts that are not directly below this level.
+    pub fn add_subobject(&mut self, sub: CategoryObject) -> Result<(), String> {
+        if Some(sub.level) != self.level.below() {
+            return Err(format!(
+                "cannot place {} ({}) inside {} ({})",
+                sub.id, sub.level_name(), self.id, self.level_name()
+            ));
+        }
+        self.subobjects.push(Box::new(sub));
+        Ok(())
+    }
+
+    /// Find this object or a descendant by id.
+    pub fn find(&self, id: &str) -> Option<&CategoryObject> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.subobjects.iter().find_map(|sub| sub.find(id))
+    }
+
+    /// Find this object or a descendant by id, mutably.
+    pub fn find_mut(&mut self, id: &str) -> Option<&mut CategoryObject> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.subobjects.iter_mut().find_map(|sub| sub.find_mut(id))
+    }
+
     /// Recursively tick all subobjects and agents in parallel.
     pub fn tick_recursive(&mut self) {
-        self.subobjects.par_iter_mut().for_each(|sub| sub.tick_recursive());
-        self.agents.par_iter_mut().for_each(|agent| agent.decay_memory(0.05));
-        self.substrate.decay(0.05);
+        self.tick(TickMode::default());
+    }
+
+    /// Tick the whole hierarchy using the given scheduling strategy.
+    pub fn tick(&mut self, mode: TickMode) {
+        match mode {
+            TickMode::Recursive { grain } => self.tick_grained(grain),
+            TickMode::Flattened => self.tick_flattened(),
+        }
+    }
+
+    /// Number of objects below this one.
+    pub fn descendant_count(&self) -> usize {
+        self.fold(0, |n, _, depth| if depth > 0 { n + 1 } else { n })
+    }
+
+    /// Tick only this node's own agents and substrate.
+    fn tick_local(substrate: &mut Substrate, agents: &mut [Agent]) {
+        agents.iter_mut().for_each(|agent| agent.decay_memory(TICK_DECAY));
+        substrate.decay(TICK_DECAY);
+    }
+
+    /// Descendant counts of this object and every object below it, in pre-order:
+    /// a subtree's entries are its own count followed by that many more.
+    fn subtree_sizes(&self) -> Vec<usize> {
+        let depths = self.fold(Vec::new(), |mut depths, _, depth| {
+            depths.push(depth);
+            depths
+        });
+        let mut sizes = vec![0; depths.len()];
+        let mut open: Vec<usize> = Vec::new();
+        for (i, &depth) in depths.iter().enumerate() {
+            while let Some(&start) = open.last().filter(|start| depths[**start] >= depth) {
+                open.pop();
+                sizes[start] = i - start - 1;
+            }
+            open.push(i);
+        }
+        for start in open {
+            sizes[start] = depths.len() - start - 1;
+        }
+        sizes
+    }
+
+    /// Recursive tick that spawns rayon tasks only for subtrees with at least `grain` descendants.
+    fn tick_grained(&mut self, grain: usize) {
+        let sizes = self.subtree_sizes();
+        self.tick_grained_sized(grain, &sizes);
+    }
+
+    /// `tick_grained` given the subtree's `subtree_sizes`, so each is counted once per tick.
+    fn tick_grained_sized(&mut self, grain: usize, sizes: &[usize]) {
+        if sizes[0] < grain {
+            self.tick_sequential();
+            return;
+        }
+        let mut rest = &sizes[1..];
+        let parts: Vec<&[usize]> = self
+            .subobjects
+            .iter()
+            .map(|_| {
+                let (part, after) = rest.split_at(rest[0] + 1);
+                rest = after;
+                part
+            })
+            .collect();
+        self.subobjects.par_iter_mut().zip(parts).for_each(|(sub, sizes)| sub.tick_grained_sized(grain, sizes));
+        self.agents.par_iter_mut().for_each(|agent| agent.decay_memory(TICK_DECAY));
+        self.substrate.decay(TICK_DECAY);
+    }
+
+    /// Depth-first tick on the current thread, using an explicit stack so deep trees don't overflow.
+    fn tick_sequential(&mut self) {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            let CategoryObject { substrate, agents, subobjects, .. } = node;
+            Self::tick_local(substrate, agents);
+            stack.extend(subobjects.iter_mut().map(|sub| &mut **sub));
+        }
+    }
+
+    /// Flatten the hierarchy into a worklist of nodes and tick them all in one parallel pass.
+    fn tick_flattened(&mut self) {
+        let mut work: Vec<(&mut Substrate, &mut Vec<Agent>)> = Vec::new();
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            let CategoryObject { substrate, agents, subobjects, .. } = node;
+            work.push((substrate, agents));
+            stack.extend(subobjects.iter_mut().map(|sub| &mut **sub));
+        }
+        work.into_par_iter().for_each(|(substrate, agents)| Self::tick_local(substrate, agents));
     }
 
     /// Recursively propagate a mutation (cross-level feedback) down to all subobjects and agents.
@@ -96,6 +364,84 @@ impl CategoryObject {
         sub_sum + agent_sum
     }
 
+    /// Emergence score in [0, 1]: the share of the whole's interpretation that is not
+    /// explained by the interpretations of its parts. Objects without parts score 0.
+    pub fn emergence_score(&self) -> f64 {
+        if self.subobjects.is_empty() {
+            return 0.0;
+        }
+        let own: HashSet<String> = match self.interpret() {
+            Some(i) => i.own_features().into_iter().collect(),
+            None => return 0.0,
+        };
+        let parts: HashSet<String> = self.subobjects.iter()
+            .filter_map(|s| s.interpret())
+            .flat_map(|i| i.features())
+            .collect();
+        let total = own.union(&parts).count();
+        if total == 0 {
+            return 0.0;
+        }
+        own.difference(&parts).count() as f64 / total as f64
+    }
+
+    /// Emergence scores for this object and every container below it, keyed by id.
+    pub fn emergence_by_node(&self) -> Vec<(String, RecursionLevel, f64)> {
+        self.fold(Vec::new(), |mut out, obj, _| {
+            if !obj.subobjects.is_empty() {
+                out.push((obj.id.clone(), obj.level, obj.emergence_score()));
+            }
+            out
+        })
+    }
+
+    // --- TRAVERSAL ---
+
+    /// Walk the hierarchy depth-first, calling the visitor on entry and exit of every object.
+    pub fn visit<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        self.visit_at(visitor, 0);
+    }
+
+    fn visit_at<V: Visitor + ?Sized>(&self, visitor: &mut V, depth: usize) {
+        if visitor.enter(self, depth) {
+            for sub in &self.subobjects {
+                sub.visit_at(visitor, depth + 1);
+            }
+        }
+        visitor.leave(self, depth);
+    }
+
+    /// Walk the hierarchy depth-first with mutable access to every object.
+    pub fn visit_mut<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+        self.visit_mut_at(visitor, 0);
+    }
+
+    fn visit_mut_at<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V, depth: usize) {
+        if visitor.enter(self, depth) {
+            for sub in &mut self.subobjects {
+                sub.visit_mut_at(visitor, depth + 1);
+            }
+        }
+        visitor.leave(self, depth);
+    }
+
+    /// Pre-order fold over every object in the hierarchy, with its depth below `self`.
+    pub fn fold<T>(&self, init: T, mut f: impl FnMut(T, &CategoryObject, usize) -> T) -> T {
+        let mut acc = Some(init);
+        let mut stack = vec![(self, 0)];
+        while let Some((obj, depth)) = stack.pop() {
+            acc = Some(f(acc.take().unwrap(), obj, depth));
+            stack.extend(obj.subobjects.iter().rev().map(|sub| (&**sub, depth + 1)));
+        }
+        acc.unwrap()
+    }
+
+    /// Bottom-up fold: `f` receives each object together with the results of its children.
+    pub fn fold_up<T>(&self, f: &impl Fn(&CategoryObject, Vec<T>) -> T) -> T {
+        let children = self.subobjects.iter().map(|sub| sub.fold_up(f)).collect();
+        f(self, children)
+    }
+
     /// --- INTERPRETATION METHODS FOR ALL LEVELS ---
 
     /// Unified interpretation entrypoint.
@@ -106,9 +452,21 @@ impl CategoryObject {
             RecursionLevel::Molecule => Some(Interpretation::Molecule(self.interpret_molecule())),
             RecursionLevel::Cell => Some(Interpretation::Cell(self.interpret_cell())),
             RecursionLevel::Void => None,
+            RecursionLevel::Defined { .. } => self.interpret_defined().map(Interpretation::Level),
         }
     }
 
+    /// Interpretation of an ontology-defined level, following its template.
+    pub fn interpret_defined(&self) -> Option<LevelInterpretation> {
+        let spec = self.ontology.as_deref()?.spec(self.level)?;
+        Some(LevelInterpretation {
+            id: self.id.clone(),
+            level: spec.name.clone(),
+            values: spec.values(self),
+            constituents: self.subobjects.iter().filter_map(|s| s.interpret_defined()).collect(),
+        })
+    }
+
     /// Λ₁: Particle-level interpretation.
     pub fn interpret_particle(&self) -> ParticleInterpretation {
         ParticleInterpretation {
@@ -166,4 +524,37 @@ impl CategoryObject {
             contributing_meanings,
         }
     }
-}
\ No newline at end of file
+}
+
+/// Find an object by id anywhere in a forest of hierarchies.
+pub fn find_in_forest_mut<'a>(roots: &'a mut HashMap<String, CategoryObject>, id: &str) -> Option<&'a mut CategoryObject> {
+    roots.values_mut().find_map(|root| root.find_mut(id))
+}
+
+/// Move or copy an agent, memory included, between two objects at any level of a forest.
+/// Records `AgentDeparted` on the source and `AgentArrived` on the target.
+pub fn migrate_agent(
+    roots: &mut HashMap<String, CategoryObject>,
+    agent_id: &str,
+    from: &str,
+    to: &str,
+    mode: MigrationMode,
+) -> Result<(), String> {
+    if from == to {
+        return Err(format!("source and target are both '{}'", from));
+    }
+    if !roots.values().any(|root| root.find(to).is_some()) {
+        return Err(format!("target object '{}' not found", to));
+    }
+    let source = find_in_forest_mut(roots, from).ok_or_else(|| format!("source object '{}' not found", from))?;
+    let idx = source.agents.iter().position(|a| a.id == agent_id)
+        .ok_or_else(|| format!("agent '{}' is not in '{}'", agent_id, from))?;
+    let copied = mode == MigrationMode::Copy;
+    let agent = if copied { source.agents[idx].clone() } else { source.agents.remove(idx) };
+    source.events.push(HierarchyEvent::AgentDeparted { agent: agent_id.to_string(), to: to.to_string(), copied });
+
+    let target = find_in_forest_mut(roots, to).expect("target checked above");
+    target.agents.push(agent);
+    target.events.push(HierarchyEvent::AgentArrived { agent: agent_id.to_string(), from: from.to_string(), copied });
+    Ok(())
+}
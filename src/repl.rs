@@ -0,0 +1,394 @@
+//! `sptl repl`: type SPTL statements and see their effect immediately.
+//!
+//! Every entry runs on one `sptl::ExecutionEnv`, so fields, interpretations and
+//! variables persist from line to line. A line that opens more braces than it
+//! closes is continued on the following lines until its block is complete.
+//! Lines starting with `:` are commands rather than statements (see `HELP`).
+//! A `proc` is only known within the entry that defines it.
+//!
+//! Between entries the session is paused, so it can be tuned before going
+//! on: `:set` changes projection and decay defaults, variables and agent
+//! thresholds, and `:run` continues with a script file. Every change is
+//! journaled, and `:save` writes a script that replays it.
+
+use crate::bench::Bench;
+use crate::condition::{Condition, Relation, Scope};
+use crate::config::Config;
+use crate::provenance::Question;
+use crate::runtime::Runtime;
+use crate::say;
+use crate::sptl::expr::{Expr, FieldExpr};
+use crate::sptl::{self, lexer, ExecutionEnv, ParseError, Statement};
+use crate::symmetry::Consensus;
+use crate::visualize::print_vector;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+const HELP: &str = "\
+:fields          list fields and their sizes
+:interps         list interpretations and their sizes
+:vars            list numeric variables
+:show <name>     print a field, interpretation or variable
+:explain meaning <name>
+                 show the values a meaning was judged on, as they are now
+:explain condition <condition>
+                 evaluate a condition, such as `d < a * 2`, `alice knows fire` or
+                 `consensus on fire among group learners quorum 0.8`,
+                 showing every value it reads
+:why pattern <pattern> in <field>
+:why token <token> in <agent>
+                 list the script lines that caused an activation or memory
+:set <name> <value>
+                 set `alpha`, `noise`, `steps` or `tolerance` for later `project`s
+                 that omit them, `decay` for later `decay`s that omit the rate,
+                 `<agent>.threshold` or `<agent>.min_similarity`,
+                 or any other name as a variable
+:bench project <field> <interp> [steps=10000]
+:bench tick [population=1000] [n=100]
+                 time projection steps or agent ticks on copies of the
+                 session's state and report steps per second
+:describe <name> show what a name is and its doc string
+:run <path>      run a script file in this session
+:save <path>     write every statement that ran as a script; not after
+                 tuning an agent, which no statement can replay
+:reset           start over with an empty session
+:quit            leave (as does end of input)";
+
+/// What the caller should do after an entry.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Reply {
+    /// Ready for the next statement.
+    Ready,
+    /// The statement continues on the next line.
+    More,
+    Quit,
+}
+
+pub struct Repl {
+    pub env: ExecutionEnv,
+    /// Values bound to `$name` in every entry.
+    params: BTreeMap<String, String>,
+    config: Config,
+    /// Lines of a statement still waiting for its closing braces.
+    pending: String,
+    /// Source of every entry that ran, for `:save`.
+    history: Vec<String>,
+    /// Whether `:set` changed a default that later entries are parsed with.
+    tuned_defaults: bool,
+    /// The agent setting `:set` last changed, which no statement can replay.
+    tuned_agent: Option<String>,
+    /// Trace and threshold of every meaning defined so far, for `:explain`.
+    meanings: HashMap<String, (String, Expr)>,
+}
+
+impl Repl {
+    pub fn new(params: BTreeMap<String, String>, config: Config) -> Self {
+        Repl {
+            env: new_env(&config),
+            params,
+            config,
+            pending: String::new(),
+            history: Vec::new(),
+            tuned_defaults: false,
+            tuned_agent: None,
+            meanings: HashMap::new(),
+        }
+    }
+
+    pub fn prompt(&self) -> &'static str {
+        if self.pending.is_empty() {
+            "sptl> "
+        } else {
+            "  ... "
+        }
+    }
+
+    /// Read entries from `input` until it ends or `:quit`, prompting on stdout.
+    pub fn run(&mut self, input: impl BufRead) -> io::Result<()> {
+        println!("SPTL {} — statements run as you type them; :help lists commands.", env!("CARGO_PKG_VERSION"));
+        let mut lines = input.lines();
+        loop {
+            print!("{}", self.prompt());
+            io::stdout().flush()?;
+            let Some(line) = lines.next() else {
+                println!();
+                return Ok(());
+            };
+            if self.eval(&line?) == Reply::Quit {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Handle one line of input.
+    pub fn eval(&mut self, line: &str) -> Reply {
+        if self.pending.is_empty() {
+            if let Some(command) = line.trim().strip_prefix(':') {
+                return self.command(command);
+            }
+        }
+        self.pending.push_str(line);
+        self.pending.push('\n');
+        if brace_depth(&self.pending) > 0 {
+            return Reply::More;
+        }
+        let source = std::mem::take(&mut self.pending);
+        let program = sptl::parse_source_with(&source, &self.params, &self.config)
+            .and_then(|program| sptl::resolve_includes(program, Path::new("repl.sptl"), &self.params, &self.config));
+        self.execute(program, &source);
+        Reply::Ready
+    }
+
+    fn execute(&mut self, program: Result<Vec<Statement>, Vec<ParseError>>, source: &str) {
+        match program {
+            Ok(program) => {
+                self.note_meanings(&program);
+                // With a tuned default an entry's source no longer says what
+                // ran; its formatted form writes every option out.
+                let entry = if self.tuned_defaults {
+                    sptl::format::format_program(&program, self.config.levels.as_deref())
+                } else {
+                    source.to_string()
+                };
+                self.env.exec(program);
+                self.history.push(entry.trim_end().to_string());
+            }
+            Err(errors) => {
+                for e in errors {
+                    say!("error: {}", e);
+                }
+            }
+        }
+    }
+
+    fn command(&mut self, command: &str) -> Reply {
+        let mut words = command.split_whitespace();
+        match (words.next().unwrap_or(""), words.next()) {
+            ("quit" | "q", None) => return Reply::Quit,
+            ("help", None) => say!("{}", HELP),
+            ("fields", None) => {
+                for (name, state) in self.env.rt.field_states() {
+                    say!("{} ({})", name, state.len());
+                }
+            }
+            ("interps", None) => {
+                let interps: BTreeMap<_, _> = self.env.rt.interps.iter().collect();
+                for (name, interp) in interps {
+                    say!("{} ({})", name, interp.data.len());
+                }
+            }
+            ("vars", None) => {
+                let vars: BTreeMap<_, _> = self.env.vars.iter().collect();
+                for (name, value) in vars {
+                    say!("{} = {}", name, value);
+                }
+            }
+            ("show", Some(name)) => self.show(name),
+            ("describe", Some(name)) => {
+                let lines = self.env.rt.describe(name);
+                if lines.is_empty() {
+                    say!("Nothing named {}.", name);
+                }
+                for line in lines {
+                    say!("{}", line);
+                }
+            }
+            ("explain", Some("meaning")) => match words.next() {
+                Some(name) => self.explain_meaning(name),
+                None => say!("Usage: :explain meaning <name>"),
+            },
+            ("explain", Some("condition")) => {
+                let text = words.collect::<Vec<_>>().join(" ");
+                match Condition::parse(text.trim_matches('"')) {
+                    Some(condition) => self.explain(&condition),
+                    None => say!("Usage: :explain condition <condition>"),
+                }
+            }
+            ("why", Some(kind)) => {
+                let text = format!("{} {}", kind, words.collect::<Vec<_>>().join(" "));
+                match Question::parse(&text) {
+                    Some(question) => {
+                        for line in question.answer(&self.env.rt.provenance) {
+                            say!("{}", line);
+                        }
+                    }
+                    None => say!("Usage: :why pattern <pattern> in <field> or :why token <token> in <agent>"),
+                }
+            }
+            ("bench", Some(kind)) => {
+                let words: Vec<&str> = std::iter::once(kind).chain(words).collect();
+                let measured = Bench::parse(&words, self.config.alpha, self.config.noise)
+                    .and_then(|bench| bench.run(&self.env.rt));
+                match measured {
+                    Ok(measurement) => say!("{}", measurement),
+                    Err(e) => say!("{}", e),
+                }
+            }
+            ("set", Some(name)) => match words.next().map(str::parse::<f64>) {
+                Some(Ok(value)) => self.set(name, value),
+                _ => say!("Usage: :set <name> <number>"),
+            },
+            ("run", Some(path)) => match std::fs::read_to_string(path) {
+                Ok(source) => {
+                    let program = sptl::parse_source_with(&source, &self.params, &self.config)
+                        .and_then(|program| sptl::resolve_includes(program, Path::new(path), &self.params, &self.config));
+                    self.execute(program, &source);
+                }
+                Err(e) => say!("error: {}: {}", path, e),
+            },
+            ("save", Some(path)) => {
+                if let Some(setting) = &self.tuned_agent {
+                    say!("error: a script cannot replay :set {}; nothing saved", setting);
+                    return Reply::Ready;
+                }
+                let mut script = self.history.join("\n");
+                script.push('\n');
+                match std::fs::write(path, script) {
+                    Ok(()) => say!("Saved {} entries to {}", self.history.len(), path),
+                    Err(e) => say!("error: {}: {}", path, e),
+                }
+            }
+            ("reset", None) => {
+                self.env = new_env(&self.config);
+                self.history.clear();
+                self.tuned_agent = None;
+                self.meanings.clear();
+            }
+            _ => say!("Unknown command :{}; :help lists commands.", command.trim()),
+        }
+        Reply::Ready
+    }
+
+    fn show(&self, name: &str) {
+        let rt = &self.env.rt;
+        if let Some(field) = rt.fields.get(name) {
+            print_vector(name, &field.state);
+        } else if let Some(interp) = rt.interps.get(name) {
+            print_vector(name, &interp.data);
+        } else if let Some(value) = self.env.vars.get(name) {
+            say!("{} = {}", name, value);
+        } else {
+            say!("Nothing named {}.", name);
+        }
+    }
+
+    /// Change one setting of the paused session, journal it and keep it
+    /// for `:save`.
+    fn set(&mut self, name: &str, value: f64) {
+        match name {
+            "alpha" => self.config.alpha = value,
+            "noise" => self.config.noise = value,
+            "tolerance" => self.config.tolerance = value,
+            "decay" => self.config.decay = value,
+            "steps" if value >= 1.0 && value.fract() == 0.0 => self.config.steps = Some(value as usize),
+            "steps" => {
+                say!("steps must be a whole number of at least 1");
+                return;
+            }
+            _ => match name.split_once('.') {
+                Some((agent, setting)) => {
+                    let Some(state) = self.env.rt.agents.get_mut(agent) else {
+                        say!("No agent named {}.", agent);
+                        return;
+                    };
+                    let Some(agent) = Arc::make_mut(state).agent.as_mut() else {
+                        say!("Agent {} has no symbol table to tune.", agent);
+                        return;
+                    };
+                    match setting {
+                        "threshold" => agent.coherence_threshold = value,
+                        "min_similarity" => agent.reinforcement.min_similarity = value,
+                        _ => {
+                            say!("Agents have no setting {}; use threshold or min_similarity.", setting);
+                            return;
+                        }
+                    }
+                    self.tuned_agent = Some(name.to_string());
+                }
+                None => {
+                    self.env.vars.insert(name.to_string(), value);
+                    self.history.push(format!("let {} = {}", name, value));
+                }
+            },
+        }
+        if matches!(name, "alpha" | "noise" | "tolerance" | "decay" | "steps") {
+            self.tuned_defaults = true;
+        }
+        say!("{} = {}", name, value);
+        self.env.report.log(format!("[{}] set {} = {}", self.env.step, name, value));
+    }
+
+    fn note_meanings(&mut self, program: &[Statement]) {
+        for statement in program {
+            match statement {
+                Statement::Meaning { name, trace_cmp, threshold } => {
+                    self.meanings.insert(name.clone(), (trace_cmp.clone(), threshold.clone()));
+                }
+                Statement::Repeat { body, .. } | Statement::Level { body, .. } => self.note_meanings(body),
+                Statement::If { then, otherwise, .. } => {
+                    self.note_meanings(then);
+                    self.note_meanings(otherwise);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// A meaning holds while its trace is below its threshold, so it is
+    /// explained as that comparison.
+    fn explain_meaning(&self, name: &str) {
+        let Some((trace, threshold)) = self.meanings.get(name) else {
+            say!("No meaning named {}.", name);
+            return;
+        };
+        match self.env.report.meanings.get(name) {
+            Some(holds) => say!("meaning {} was last judged {}", name, if *holds { "to hold" } else { "not to hold" }),
+            None => say!("meaning {} has not been judged yet", name),
+        }
+        let condition = Condition::Compare { left: FieldExpr::Field(trace.clone()), cmp: Relation::Less, right: threshold.clone() };
+        self.explain(&condition);
+    }
+
+    fn explain(&self, condition: &Condition) {
+        for line in condition.explain(&SessionScope(&self.env)) {
+            say!("{}", line);
+        }
+    }
+}
+
+/// Numbers are the session's variables, then its measurements; agents are
+/// those of its runtime.
+struct SessionScope<'a>(&'a ExecutionEnv);
+
+impl Scope for SessionScope<'_> {
+    fn value(&self, name: &String) -> Option<f64> {
+        self.0.vars.get(name).or_else(|| self.0.rt.measurements.get(name)).copied()
+    }
+
+    fn knows(&self, agent: &str, token: &str) -> Option<bool> {
+        self.0.rt.agents.get(agent).map(|state| state.memory.iter().any(|m| m == token))
+    }
+
+    fn consensus(&self, group: &str, token: &str) -> Option<Consensus> {
+        self.0.rt.consensus(group, token)
+    }
+}
+
+fn new_env(config: &Config) -> ExecutionEnv {
+    ExecutionEnv::with_runtime(Runtime { ontology: config.levels.clone(), ..Default::default() })
+}
+
+/// Braces opened and not yet closed in `source`; braces in strings and
+/// comments do not count.
+fn brace_depth(source: &str) -> i64 {
+    lexer::lex(source)
+        .iter()
+        .map(|t| match t.text.as_str() {
+            "{" if t.kind == lexer::TokenKind::Punct => 1,
+            "}" if t.kind == lexer::TokenKind::Punct => -1,
+            _ => 0,
+        })
+        .sum()
+}
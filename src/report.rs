@@ -0,0 +1,243 @@
+//! Run report: everything an SPTL program measured, plus its journal and telemetry.
+
+use crate::events::EventBus;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Entries buffered by a `ReportStream` before it flushes to disk.
+pub const DEFAULT_FLUSH_EVERY: usize = 1024;
+
+/// Maximum points kept per series when rendering plots.
+pub const PLOT_POINTS: usize = 256;
+
+/// Incremental journal/telemetry output. Entries are written as they happen and
+/// flushed every `capacity` entries, so memory use stays bounded on long runs.
+#[derive(Debug)]
+pub struct ReportStream {
+    journal: BufWriter<File>,
+    telemetry: BufWriter<File>,
+    pending: usize,
+    capacity: usize,
+}
+
+impl ReportStream {
+    /// Create (truncating) the journal and telemetry files.
+    pub fn create(journal: &Path, telemetry: &Path, capacity: usize) -> io::Result<Self> {
+        let mut telemetry = BufWriter::new(File::create(telemetry)?);
+        telemetry.write_all(b"step,name,value\n")?;
+        Ok(ReportStream {
+            journal: BufWriter::new(File::create(journal)?),
+            telemetry,
+            pending: 0,
+            capacity: capacity.max(1),
+        })
+    }
+
+    fn entry_written(&mut self) -> io::Result<()> {
+        self.pending += 1;
+        if self.pending >= self.capacity {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.pending = 0;
+        self.journal.flush()?;
+        self.telemetry.flush()
+    }
+}
+
+/// Keeps at most `2 * max` evenly spaced samples of an unbounded series by
+/// doubling its sampling stride whenever the buffer fills.
+struct Decimator {
+    max: usize,
+    stride: usize,
+    seen: usize,
+    values: Vec<f64>,
+}
+
+impl Decimator {
+    fn new(max: usize) -> Self {
+        Decimator { max: max.max(1), stride: 1, seen: 0, values: Vec::new() }
+    }
+
+    fn push(&mut self, v: f64) {
+        if self.seen.is_multiple_of(self.stride) {
+            self.values.push(v);
+            if self.values.len() >= 2 * self.max {
+                self.values = self.values.iter().step_by(2).copied().collect();
+                self.stride *= 2;
+            }
+        }
+        self.seen += 1;
+    }
+}
+
+/// Read a telemetry CSV back as decimated per-name series, without loading it whole.
+pub fn read_series(path: &Path, max_points: usize) -> io::Result<BTreeMap<String, Vec<f64>>> {
+    let mut series: BTreeMap<String, Decimator> = BTreeMap::new();
+    for line in BufReader::new(File::open(path)?).lines().skip(1) {
+        let line = line?;
+        let mut cols = line.splitn(3, ',');
+        let (Some(_), Some(name), Some(value)) = (cols.next(), cols.next(), cols.next()) else { continue };
+        if let Ok(v) = value.parse() {
+            series.entry(name.to_string()).or_insert_with(|| Decimator::new(max_points)).push(v);
+        }
+    }
+    Ok(series.into_iter().map(|(k, d)| (k, d.values)).collect())
+}
+
+/// One telemetry sample: a named value observed at a statement step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryRow {
+    pub step: usize,
+    pub name: String,
+    pub value: f64,
+}
+
+/// Outcome of one `assert trace` statement.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Assertion {
+    pub step: usize,
+    pub trace: String,
+    /// `<` or `>`.
+    pub comparison: String,
+    pub expected: f64,
+    pub tolerance: f64,
+    /// Value of the trace when checked; `None` if it was never recorded.
+    pub actual: Option<f64>,
+    pub passed: bool,
+}
+
+impl fmt::Display for Assertion {
+    /// `step 3: assert trace d < 0.2 within 0.001: d = 0.350000`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "step {}: assert trace {} {} {}", self.step, self.trace, self.comparison, self.expected)?;
+        if self.tolerance != 0.0 {
+            write!(f, " within {}", self.tolerance)?;
+        }
+        match self.actual {
+            Some(value) => write!(f, ": {} = {:.6}", self.trace, value),
+            None => write!(f, ": {} was never recorded", self.trace),
+        }
+    }
+}
+
+/// Outcome of executing an SPTL program.
+/// With a `stream` attached, journal and telemetry go straight to disk instead of memory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RunReport {
+    /// Script the program was loaded from, if any.
+    pub script: Option<String>,
+    /// Final value of every named trace.
+    pub traces: BTreeMap<String, f64>,
+    /// Truth value of every evaluated meaning.
+    pub meanings: BTreeMap<String, bool>,
+    /// Every `assert trace` checked, in execution order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assertions: Vec<Assertion>,
+    /// Problems that kept the program from running at all, such as parse errors.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+    /// Noise RNG draws by call site, when the run was audited (`--audit-rng`).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rng_draws: BTreeMap<String, u64>,
+    /// Doc strings of the run's fields, interpretations and agents, by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub docs: BTreeMap<String, String>,
+    /// Final state of every field (written as checkpoints, not into report.json).
+    #[serde(skip)]
+    pub fields: BTreeMap<String, Vec<f64>>,
+    /// One line per executed statement.
+    #[serde(skip)]
+    pub journal: Vec<String>,
+    /// Every measured value, in execution order.
+    #[serde(skip)]
+    pub telemetry: Vec<TelemetryRow>,
+    #[serde(skip)]
+    pub stream: Option<ReportStream>,
+    /// Subscribers notified of events as the program runs.
+    #[serde(skip)]
+    pub events: EventBus,
+}
+
+impl RunReport {
+    /// Number of `assert trace` checks that failed.
+    pub fn failed_assertions(&self) -> usize {
+        self.assertions.iter().filter(|a| !a.passed).count()
+    }
+
+    /// One line per failed `assert trace`, in execution order.
+    pub fn failure_summary(&self) -> Vec<String> {
+        self.assertions.iter().filter(|a| !a.passed).map(|a| a.to_string()).collect()
+    }
+
+    /// Record a measured value under `name`, both as its latest trace value and as telemetry.
+    pub fn record(&mut self, step: usize, name: &str, value: f64) {
+        self.traces.insert(name.to_string(), value);
+        match &mut self.stream {
+            Some(stream) => {
+                let written = writeln!(stream.telemetry, "{},{},{}", step, name, value)
+                    .and_then(|_| stream.entry_written());
+                if let Err(e) = written {
+                    eprintln!("⚠️ Telemetry write failed: {}", e);
+                }
+            }
+            None => self.telemetry.push(TelemetryRow { step, name: name.to_string(), value }),
+        }
+    }
+
+    /// Append a line to the journal.
+    pub fn log(&mut self, line: impl Into<String>) {
+        match &mut self.stream {
+            Some(stream) => {
+                let written = writeln!(stream.journal, "{}", line.into()).and_then(|_| stream.entry_written());
+                if let Err(e) = written {
+                    eprintln!("⚠️ Journal write failed: {}", e);
+                }
+            }
+            None => self.journal.push(line.into()),
+        }
+    }
+
+    /// Whether journal and telemetry are being streamed to disk.
+    pub fn is_streaming(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Write the report summary as pretty JSON.
+    pub fn write_json(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    /// Write telemetry as `step,name,value` CSV.
+    pub fn write_telemetry_csv(&self, path: &Path) -> io::Result<()> {
+        let mut out = String::from("step,name,value\n");
+        for row in &self.telemetry {
+            out.push_str(&format!("{},{},{}\n", row.step, row.name, row.value));
+        }
+        fs::write(path, out)
+    }
+
+    /// Write the journal, one entry per line.
+    pub fn write_journal(&self, path: &Path) -> io::Result<()> {
+        let mut out = self.journal.join("\n");
+        out.push('\n');
+        fs::write(path, out)
+    }
+
+    /// In-memory telemetry grouped into one decimated series per name, in step order.
+    pub fn series(&self, max_points: usize) -> BTreeMap<String, Vec<f64>> {
+        let mut series: BTreeMap<String, Decimator> = BTreeMap::new();
+        for row in &self.telemetry {
+            series.entry(row.name.clone()).or_insert_with(|| Decimator::new(max_points)).push(row.value);
+        }
+        series.into_iter().map(|(k, d)| (k, d.values)).collect()
+    }
+}
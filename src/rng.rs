@@ -0,0 +1,90 @@
+//! The noise RNG of a run, optionally counting its draws per call site.
+//!
+//! Hunting nondeterminism starts with knowing whether two runs consumed
+//! randomness the same way. With auditing on (`--audit-rng`), every word the
+//! RNG produces is counted under the site that asked for it (`project`,
+//! `perturb`, ...) and the counts end up in `RunReport::rng_draws`. Callers
+//! name their site with `at` right before handing the RNG on.
+
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use std::collections::BTreeMap;
+
+/// Site charged for draws made without naming one.
+const UNNAMED_SITE: &str = "other";
+
+#[derive(Clone)]
+pub struct NoiseRng {
+    inner: StdRng,
+    site: &'static str,
+    /// Draws per site; `None` while not auditing.
+    draws: Option<BTreeMap<&'static str, u64>>,
+}
+
+impl NoiseRng {
+    pub fn from_entropy() -> Self {
+        NoiseRng::wrap(StdRng::from_entropy())
+    }
+
+    pub fn seed_from_u64(seed: u64) -> Self {
+        NoiseRng::wrap(StdRng::seed_from_u64(seed))
+    }
+
+    fn wrap(inner: StdRng) -> Self {
+        NoiseRng { inner, site: UNNAMED_SITE, draws: None }
+    }
+
+    /// Restart the sequence from `seed`, as `seed <n>` does; an audit keeps counting.
+    pub fn reseed(&mut self, seed: u64) {
+        self.inner = StdRng::seed_from_u64(seed);
+    }
+
+    /// Start counting draws per site.
+    pub fn audit(&mut self) {
+        self.draws.get_or_insert_with(BTreeMap::new);
+    }
+
+    pub fn is_audited(&self) -> bool {
+        self.draws.is_some()
+    }
+
+    /// Charge the following draws to `site`.
+    pub fn at(&mut self, site: &'static str) -> &mut Self {
+        self.site = site;
+        self
+    }
+
+    /// Draws so far by site; empty unless auditing.
+    pub fn draws(&self) -> BTreeMap<String, u64> {
+        let draws = self.draws.iter().flatten();
+        draws.map(|(site, n)| (site.to_string(), *n)).collect()
+    }
+
+    fn count(&mut self) {
+        if let Some(draws) = &mut self.draws {
+            *draws.entry(self.site).or_default() += 1;
+        }
+    }
+}
+
+impl RngCore for NoiseRng {
+    fn next_u32(&mut self) -> u32 {
+        self.count();
+        self.inner.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.count();
+        self.inner.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.count();
+        self.inner.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.count();
+        self.inner.try_fill_bytes(dest)
+    }
+}
@@ -0,0 +1,204 @@
+//! Run directory convention: every artifact of one run lives under a single directory.
+//!
+//! ```text
+//! out/run-<timestamp>/
+//!   manifest.json      what ran, with which parameters, and which artifacts exist
+//!   report.json        final trace values
+//!   telemetry.csv      every measured value
+//!   journal.log        one line per executed statement
+//!   console.log        everything printed, unthrottled (only when output is throttled)
+//!   checkpoints/       final field states (<field>.ckpt)
+//!   plots/             rendered trace plots (<trace>.txt)
+//!   agents/            saved agents (<agent>.json), loadable into later runs
+//! ```
+
+use crate::agents::Agent;
+use crate::report::{read_series, ReportStream, RunReport, PLOT_POINTS};
+use crate::visualize::render_plot;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const REPORT_FILE: &str = "report.json";
+pub const TELEMETRY_FILE: &str = "telemetry.csv";
+pub const JOURNAL_FILE: &str = "journal.log";
+pub const CONSOLE_FILE: &str = "console.log";
+pub const CHECKPOINT_DIR: &str = "checkpoints";
+pub const PLOT_DIR: &str = "plots";
+pub const AGENT_DIR: &str = "agents";
+
+/// Bookkeeping for one run, stored as `manifest.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Crate version that produced the run.
+    pub version: String,
+    /// Unix time the run directory was created.
+    pub created: u64,
+    pub script: Option<String>,
+    pub seed: Option<u64>,
+    /// Parameter values the run was launched with.
+    pub params: BTreeMap<String, String>,
+    /// Artifact paths relative to the run directory.
+    pub artifacts: Vec<String>,
+    /// Set once the final report has been written.
+    pub completed: bool,
+}
+
+/// Serialized state of a single field.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub field: String,
+    pub state: Vec<f64>,
+}
+
+impl Checkpoint {
+    /// Read the checkpoint at `path`, or, if `path` is a run directory, the
+    /// one it holds for `field`.
+    pub fn load(path: &Path, field: &str) -> io::Result<Checkpoint> {
+        let path = match path.is_dir() {
+            true => path.join(CHECKPOINT_DIR).join(format!("{}.ckpt", field)),
+            false => path.to_path_buf(),
+        };
+        let text = fs::read_to_string(&path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+    }
+}
+
+pub struct RunDir {
+    pub root: PathBuf,
+    pub manifest: Manifest,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+impl RunDir {
+    /// Default location for a new run: `out/run-<unix timestamp>`.
+    pub fn default_path() -> PathBuf {
+        PathBuf::from(format!("out/run-{}", unix_now()))
+    }
+
+    /// Create the directory layout and an initial manifest.
+    pub fn create(root: impl Into<PathBuf>) -> io::Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(root.join(CHECKPOINT_DIR))?;
+        fs::create_dir_all(root.join(PLOT_DIR))?;
+        let run = RunDir {
+            root,
+            manifest: Manifest {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                created: unix_now(),
+                ..Manifest::default()
+            },
+        };
+        run.write_manifest()?;
+        Ok(run)
+    }
+
+    /// Open an existing run directory by reading its manifest.
+    pub fn open(root: impl Into<PathBuf>) -> io::Result<Self> {
+        let root = root.into();
+        let text = fs::read_to_string(root.join(MANIFEST_FILE))?;
+        let manifest = serde_json::from_str(&text).map_err(io::Error::other)?;
+        Ok(RunDir { root, manifest })
+    }
+
+    pub fn path(&self, rel: &str) -> PathBuf {
+        self.root.join(rel)
+    }
+
+    pub fn checkpoint_path(&self, field: &str) -> PathBuf {
+        self.root.join(CHECKPOINT_DIR).join(format!("{}.ckpt", field))
+    }
+
+    pub fn plot_path(&self, name: &str) -> PathBuf {
+        self.root.join(PLOT_DIR).join(format!("{}.txt", name))
+    }
+
+    pub fn agent_path(&self, name: &str) -> PathBuf {
+        self.root.join(AGENT_DIR).join(format!("{}.json", name))
+    }
+
+    /// Note an artifact in the manifest (path relative to the run directory).
+    pub fn record_artifact(&mut self, path: &Path) {
+        let rel = path.strip_prefix(&self.root).unwrap_or(path).to_string_lossy().into_owned();
+        if !self.manifest.artifacts.contains(&rel) {
+            self.manifest.artifacts.push(rel);
+        }
+    }
+
+    pub fn write_manifest(&self) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.manifest).map_err(io::Error::other)?;
+        fs::write(self.root.join(MANIFEST_FILE), json)
+    }
+
+    /// Write a single field checkpoint.
+    pub fn write_checkpoint(&mut self, field: &str, state: &[f64]) -> io::Result<()> {
+        let path = self.checkpoint_path(field);
+        let ckpt = Checkpoint { field: field.to_string(), state: state.to_vec() };
+        fs::write(&path, serde_json::to_string(&ckpt).map_err(io::Error::other)?)?;
+        self.record_artifact(&path);
+        Ok(())
+    }
+
+    /// Save an agent for loading into later runs.
+    pub fn write_agent(&mut self, agent: &Agent) -> io::Result<()> {
+        fs::create_dir_all(self.root.join(AGENT_DIR))?;
+        let path = self.agent_path(&agent.id);
+        agent.save(&path)?;
+        self.record_artifact(&path);
+        self.write_manifest()
+    }
+
+    /// Stream journal and telemetry into this directory as the run progresses.
+    pub fn stream(&mut self, capacity: usize) -> io::Result<ReportStream> {
+        let journal = self.path(JOURNAL_FILE);
+        let telemetry = self.path(TELEMETRY_FILE);
+        let stream = ReportStream::create(&journal, &telemetry, capacity)?;
+        self.record_artifact(&journal);
+        self.record_artifact(&telemetry);
+        self.write_manifest()?;
+        Ok(stream)
+    }
+
+    /// Write every artifact of a finished run and mark the manifest completed.
+    /// A streamed report's journal and telemetry are already on disk and are only flushed.
+    pub fn finish(&mut self, report: &mut RunReport) -> io::Result<()> {
+        let report_path = self.path(REPORT_FILE);
+        report.write_json(&report_path)?;
+        self.record_artifact(&report_path);
+
+        let telemetry_path = self.path(TELEMETRY_FILE);
+        let series = match report.stream.take() {
+            Some(mut stream) => {
+                stream.flush()?;
+                drop(stream);
+                read_series(&telemetry_path, PLOT_POINTS)?
+            }
+            None => {
+                report.write_telemetry_csv(&telemetry_path)?;
+                report.write_journal(&self.path(JOURNAL_FILE))?;
+                report.series(PLOT_POINTS)
+            }
+        };
+        self.record_artifact(&telemetry_path);
+        self.record_artifact(&self.path(JOURNAL_FILE));
+
+        for (field, state) in &report.fields {
+            self.write_checkpoint(field, state)?;
+        }
+        for (name, values) in series {
+            let path = self.plot_path(&name);
+            fs::write(&path, render_plot(&name, &values))?;
+            self.record_artifact(&path);
+        }
+
+        self.manifest.completed = true;
+        self.write_manifest()
+    }
+}
@@ -0,0 +1,176 @@
+//! State shared by the SPTL and narrative interpreters.
+//!
+//! A `Runtime` holds everything a run can change: fields, interpretations,
+//! agents, hierarchies, the τ clock, measured telemetry and the noise RNG.
+//! Both executors operate on one (`sptl::execute_program_in`,
+//! `narrative::runner::ScriptContext::world`), so a narrative can `run` an
+//! SPTL script and see the fields it projected, and the other way round.
+//! Fields and agents sit behind `Arc` so a forked world copies pointers, not
+//! populations; writers go through `Arc::make_mut`.
+
+use crate::agents::Agent;
+use crate::interpretation::Interpretation;
+use crate::ontology::Ontology;
+use crate::provenance::Provenance;
+use crate::recursion::{CategoryObject, RecursionLevel};
+use crate::rng::NoiseRng;
+use crate::sptl::encode::{bitstring, SymbolRegistry};
+use crate::substrate::{Pattern, Substrate};
+use crate::symmetry::{self, Consensus, CONSENSUS_WINDOW};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct Runtime {
+    pub tau: u64,
+    /// Narrative variables (`let x = ...`), as written.
+    pub vars: HashMap<String, String>,
+    pub agents: HashMap<String, Arc<AgentState>>,
+    /// Named groups of agents (`group learners = alice bob`), members in order.
+    pub groups: BTreeMap<String, Vec<String>>,
+    pub hierarchies: HashMap<String, CategoryObject>,
+    pub fields: HashMap<String, Arc<Substrate>>,
+    pub interps: HashMap<String, Interpretation>,
+    /// Latest value of every trace, `let` metric and `measure`, by name.
+    pub measurements: BTreeMap<String, f64>,
+    pub emergence_log: Vec<EmergenceRecord>,
+    /// Source of projection and perturbation noise; reseeded by `seed`.
+    pub rng: NoiseRng,
+    /// Recursion levels in use, if not the built-in ones.
+    pub ontology: Option<Arc<Ontology>>,
+    /// The script line behind every activation and memory trace, for `why`.
+    pub provenance: Provenance,
+    /// Tokens `expresssymbol` has one-hot encoded, in order.
+    pub symbols: SymbolRegistry,
+    /// Gain of every `modulate`d token; expressing or projecting the token scales by it.
+    pub gains: BTreeMap<String, f64>,
+    /// Doc strings of fields, interpretations and agents, by name, as in
+    /// `field F 128 "visual cortex analogue"`.
+    pub docs: BTreeMap<String, String>,
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        Runtime {
+            tau: 0,
+            vars: HashMap::new(),
+            agents: HashMap::new(),
+            groups: BTreeMap::new(),
+            hierarchies: HashMap::new(),
+            fields: HashMap::new(),
+            interps: HashMap::new(),
+            measurements: BTreeMap::new(),
+            emergence_log: Vec::new(),
+            rng: NoiseRng::from_entropy(),
+            ontology: None,
+            provenance: Provenance::default(),
+            symbols: SymbolRegistry::default(),
+            gains: BTreeMap::new(),
+            docs: BTreeMap::new(),
+        }
+    }
+}
+
+impl Runtime {
+    /// The recursion level called `name` in this runtime's ontology.
+    pub fn level(&self, name: &str) -> Option<RecursionLevel> {
+        crate::ontology::level_named(self.ontology.as_deref(), name)
+    }
+
+    /// What `name` is and its doc string, for `describe`: one line per
+    /// field, interpretation or agent of that name; empty if there is none.
+    pub fn describe(&self, name: &str) -> Vec<String> {
+        let mut kinds = Vec::new();
+        if let Some(field) = self.fields.get(name) {
+            kinds.push(format!("field {} ({} elements)", name, field.state.len()));
+        }
+        if let Some(interp) = self.interps.get(name) {
+            kinds.push(format!("interpretation {} ({} values)", name, interp.data.len()));
+        }
+        if let Some(state) = self.agents.get(name) {
+            kinds.push(format!("agent {} ({} memories)", name, state.memory.len()));
+        }
+        let doc = match self.docs.get(name) {
+            Some(doc) => format!("\"{}\"", doc),
+            None => "no description".to_string(),
+        };
+        kinds.into_iter().map(|kind| format!("{}: {}", kind, doc)).collect()
+    }
+
+    /// Record the doc string of `name`, if it has one.
+    pub fn document(&mut self, name: &str, doc: Option<&str>) {
+        if let Some(doc) = doc {
+            self.docs.insert(name.to_string(), doc.to_string());
+        }
+    }
+
+    /// Final state of every field, as a report stores it.
+    pub fn field_states(&self) -> BTreeMap<String, Vec<f64>> {
+        self.fields.iter().map(|(name, f)| (name.clone(), f.state.clone())).collect()
+    }
+
+    /// What expressing or projecting `token` is scaled by: 1 until it is modulated.
+    pub fn gain(&self, token: &str) -> f64 {
+        self.gains.get(token).copied().unwrap_or(1.0)
+    }
+
+    /// `modulate`: scale what `token` has already activated by `intensity`
+    /// and make it the token's gain from now on. The token's activations
+    /// are those of its bitstring and of every pattern an agent has for it.
+    pub fn modulate(&mut self, token: &str, intensity: f64) {
+        let mut patterns = vec![Pattern(bitstring(token))];
+        let known = self.agents.values().filter_map(|state| state.agent.as_ref()?.symbol_table.get(token));
+        patterns.extend(known.cloned());
+        for field in self.fields.values_mut() {
+            // Only copy fields a forked world shares if they change.
+            if !patterns.iter().any(|p| field.activations.contains_key(p)) {
+                continue;
+            }
+            let field = Arc::make_mut(field);
+            for pattern in &patterns {
+                if let Some(activation) = field.activations.get_mut(pattern) {
+                    *activation *= intensity;
+                }
+            }
+        }
+        self.gains.insert(token.to_string(), intensity);
+    }
+
+    /// How far group `group` agrees on `token`; `None` if there is no such group.
+    pub fn consensus(&self, group: &str, token: &str) -> Option<Consensus> {
+        let members = self.groups.get(group)?;
+        let agents: Vec<Option<&Agent>> =
+            members.iter().map(|m| self.agents.get(m).and_then(|state| state.agent.as_ref())).collect();
+        Some(symmetry::consensus(&agents, token, CONSENSUS_WINDOW))
+    }
+}
+
+/// Emergence score of one hierarchy node, recorded at τ.
+#[derive(Debug, Clone)]
+pub struct EmergenceRecord {
+    pub tau: u64,
+    pub id: String,
+    pub level: RecursionLevel,
+    pub score: f64,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct AgentState {
+    pub memory: Vec<String>,
+    pub activation: HashMap<String, f32>,
+    /// The agent's symbol table and memory traces, which `save agent` writes.
+    pub agent: Option<Agent>,
+}
+
+impl AgentState {
+    /// How strongly the agent holds `token`: its recorded activation if it
+    /// has one, else the stability of its strongest memory trace of the
+    /// token, else 0.
+    pub fn activation_of(&self, token: &str) -> f64 {
+        if let Some(activation) = self.activation.get(token) {
+            return *activation as f64;
+        }
+        let traces = self.agent.iter().flat_map(|agent| &agent.memory.traces);
+        traces.filter(|t| t.symbol.token == token).map(|t| t.stability).fold(0.0, f64::max)
+    }
+}
@@ -0,0 +1,90 @@
+//! Sensitivity analysis: how strongly each output trace responds to one script parameter.
+
+use crate::report::RunReport;
+use std::collections::BTreeMap;
+
+/// Parse `start:end:count` into `count` evenly spaced values, both ends included.
+pub fn parse_range(spec: &str) -> Result<Vec<f64>, String> {
+    let parts: Vec<&str> = spec.split(':').collect();
+    let err = || format!("invalid range '{}', expected start:end:count", spec);
+    if parts.len() != 3 {
+        return Err(err());
+    }
+    let start: f64 = parts[0].parse().map_err(|_| err())?;
+    let end: f64 = parts[1].parse().map_err(|_| err())?;
+    let count: usize = parts[2].parse().map_err(|_| err())?;
+    Ok(match count {
+        0 => Vec::new(),
+        1 => vec![start],
+        n => (0..n).map(|i| start + (end - start) * i as f64 / (n - 1) as f64).collect(),
+    })
+}
+
+/// Least-squares line `y = slope * x + intercept`.
+#[derive(Debug, Clone, Copy)]
+pub struct LinearFit {
+    pub slope: f64,
+    pub intercept: f64,
+    /// Coefficient of determination; 1 means the line explains all variation.
+    pub r2: f64,
+}
+
+pub fn fit_linear(xs: &[f64], ys: &[f64]) -> Option<LinearFit> {
+    let n = xs.len().min(ys.len());
+    if n < 2 {
+        return None;
+    }
+    let mx = xs[..n].iter().sum::<f64>() / n as f64;
+    let my = ys[..n].iter().sum::<f64>() / n as f64;
+    let sxx: f64 = xs[..n].iter().map(|x| (x - mx).powi(2)).sum();
+    let sxy: f64 = xs[..n].iter().zip(&ys[..n]).map(|(x, y)| (x - mx) * (y - my)).sum();
+    let syy: f64 = ys[..n].iter().map(|y| (y - my).powi(2)).sum();
+    if sxx == 0.0 {
+        return None;
+    }
+    let slope = sxy / sxx;
+    let r2 = if syy == 0.0 { 1.0 } else { (sxy * sxy) / (sxx * syy) };
+    Some(LinearFit { slope, intercept: my - slope * mx, r2 })
+}
+
+/// Response of one trace to the swept parameter.
+#[derive(Debug, Clone)]
+pub struct TraceSensitivity {
+    pub trace: String,
+    pub fit: LinearFit,
+    /// Elasticity at the mean: % change in the trace per % change in the parameter.
+    pub elasticity: f64,
+}
+
+/// Fit every trace against the parameter values and rank by |elasticity|.
+pub fn analyze(samples: &[(f64, RunReport)]) -> Vec<TraceSensitivity> {
+    let mut series: BTreeMap<&str, (Vec<f64>, Vec<f64>)> = BTreeMap::new();
+    for (x, report) in samples {
+        for (name, y) in &report.traces {
+            let entry = series.entry(name.as_str()).or_default();
+            entry.0.push(*x);
+            entry.1.push(*y);
+        }
+    }
+    let mut out: Vec<TraceSensitivity> = series.into_iter()
+        .filter_map(|(name, (xs, ys))| {
+            let fit = fit_linear(&xs, &ys)?;
+            let mx = xs.iter().sum::<f64>() / xs.len() as f64;
+            let my = ys.iter().sum::<f64>() / ys.len() as f64;
+            let elasticity = if my == 0.0 { 0.0 } else { fit.slope * mx / my };
+            Some(TraceSensitivity { trace: name.to_string(), fit, elasticity })
+        })
+        .collect();
+    out.sort_by(|a, b| b.elasticity.abs().total_cmp(&a.elasticity.abs()));
+    out
+}
+
+pub fn print_table(param: &str, results: &[TraceSensitivity]) {
+    println!("🔬 Sensitivity to {} (most sensitive first)", param);
+    for r in results {
+        println!(
+            "  {:<16} slope={:>10.4} elasticity={:>8.3} r²={:.3}",
+            r.trace, r.fit.slope, r.elasticity, r.fit.r2
+        );
+    }
+}
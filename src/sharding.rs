@@ -0,0 +1,132 @@
+//! Sharded population mode.
+//!
+//! Agents are split into shards that tick independently, each collecting the
+//! projections made in it. The shared substrate decays once per τ, and every
+//! `sync_interval` τ the shards add their projections to it and exchange the
+//! symbols they expressed since the last sync. This trades strict simultaneity (agents in
+//! other shards hear a symbol up to `sync_interval` τ late) for throughput.
+
+use crate::agents::Agent;
+use crate::substrate::{Pattern, Substrate};
+use crate::symbol::Symbol;
+use rayon::prelude::*;
+
+/// Memory decay applied to every agent on each τ.
+const TICK_DECAY: f64 = 0.05;
+
+struct Shard {
+    agents: Vec<Agent>,
+    /// Projections made here since the last sync, decayed as the shared
+    /// substrate is, so merging them adds what they would have left.
+    local: Substrate,
+    /// Symbols expressed here since the last sync, with the τ they were expressed at.
+    outbox: Vec<(Symbol, usize)>,
+}
+
+impl Shard {
+    fn tick(&mut self) {
+        for agent in &mut self.agents {
+            agent.decay_memory(TICK_DECAY);
+        }
+        // Not `Substrate::decay`: a small projection may still lift a shared
+        // activation above its threshold, so none is dropped before the merge.
+        for value in self.local.activations.values_mut() {
+            *value *= 1.0 - TICK_DECAY;
+        }
+    }
+}
+
+pub struct ShardedPopulation {
+    shards: Vec<Shard>,
+    /// Shared substrate as of the last sync, decayed every τ since.
+    pub substrate: Substrate,
+    /// Number of τ shards run independently between syncs.
+    pub sync_interval: usize,
+    pub tau: usize,
+    /// Number of syncs performed so far.
+    pub syncs: usize,
+}
+
+impl ShardedPopulation {
+    /// Distribute `agents` round-robin over `shards` shards.
+    pub fn new(agents: Vec<Agent>, shards: usize, sync_interval: usize) -> Self {
+        let mut buckets: Vec<Vec<Agent>> = (0..shards.max(1)).map(|_| Vec::new()).collect();
+        let n = buckets.len();
+        for (i, agent) in agents.into_iter().enumerate() {
+            buckets[i % n].push(agent);
+        }
+        ShardedPopulation {
+            shards: buckets.into_iter()
+                .map(|agents| Shard { agents, local: Substrate::default(), outbox: Vec::new() })
+                .collect(),
+            substrate: Substrate::default(),
+            sync_interval: sync_interval.max(1),
+            tau: 0,
+            syncs: 0,
+        }
+    }
+
+    pub fn agents(&self) -> impl Iterator<Item = &Agent> {
+        self.shards.iter().flat_map(|s| s.agents.iter())
+    }
+
+    /// Have an agent express a symbol into its shard; other shards hear it at the next sync.
+    pub fn express(&mut self, agent_id: &str, token: &str, pattern: Pattern) -> Result<(), String> {
+        let tau = self.tau;
+        for shard in &mut self.shards {
+            if let Some(agent) = shard.agents.iter_mut().find(|a| a.id == agent_id) {
+                let symbol = agent.express_symbol(token, pattern, tau);
+                shard.local.project(&symbol);
+                for other in shard.agents.iter_mut().filter(|a| a.id != agent_id) {
+                    other.interpret_symbol(&symbol, tau);
+                }
+                shard.outbox.push((symbol, tau));
+                return Ok(());
+            }
+        }
+        Err(format!("unknown agent '{}'", agent_id))
+    }
+
+    /// Advance one τ, syncing when the interval elapses.
+    pub fn step(&mut self) {
+        self.shards.par_iter_mut().for_each(|shard| shard.tick());
+        self.substrate.decay(TICK_DECAY);
+        self.tau += 1;
+        if self.tau.is_multiple_of(self.sync_interval) {
+            self.sync();
+        }
+    }
+
+    pub fn run(&mut self, taus: usize) {
+        for _ in 0..taus {
+            self.step();
+        }
+    }
+
+    /// Add every shard's projections to the shared substrate and exchange queued symbols.
+    pub fn sync(&mut self) {
+        let mut merged = std::mem::take(&mut self.substrate);
+        for shard in &mut self.shards {
+            for (pattern, value) in std::mem::take(&mut shard.local).activations {
+                *merged.activations.entry(pattern).or_insert(0.0) += value;
+            }
+        }
+        merged.activations.retain(|_, v| *v > 0.01);
+
+        let outboxes: Vec<Vec<(Symbol, usize)>> = self.shards.iter_mut().map(|s| std::mem::take(&mut s.outbox)).collect();
+        self.shards.par_iter_mut().enumerate().for_each(|(i, shard)| {
+            for (j, outbox) in outboxes.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                for (symbol, tau) in outbox {
+                    for agent in &mut shard.agents {
+                        agent.interpret_symbol(symbol, *tau);
+                    }
+                }
+            }
+        });
+        self.substrate = merged;
+        self.syncs += 1;
+    }
+}
@@ -0,0 +1,337 @@
+//! One REPL session shared by several clients.
+//!
+//! `sptl serve [address]` listens for TCP connections speaking a line
+//! protocol: a client sends its name as the first line, then REPL entries.
+//! The first client to attach controls the session and runs entries; later
+//! clients observe. Every entry the controller runs is broadcast to all
+//! clients with what it printed and what it changed, so a team can watch a
+//! long simulation:
+//!
+//! ```text
+//! alice> project psi <- I { alpha: 0.1, steps: 500 }
+//!   field psi changed
+//!   trace d = 0.0132
+//! ```
+//!
+//! Any client may also send `:say <text>` to talk to the others, `:who` to
+//! list who is attached, and `:fields`, `:traces` or `:vars` to query the
+//! session without changing it. Queries answer from the session as the last
+//! entry left it, so they, like talk, go on while an entry runs. The controller hands over with `:pass
+//! <name>`; when it leaves, the longest attached observer takes over.
+
+use crate::console;
+use crate::repl::{Repl, Reply};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+/// Address `sptl serve` listens on without one given.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ClientId(u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Runs entries; there is at most one.
+    Controller,
+    /// Sees every change and may query, but not change, the session.
+    Observer,
+}
+
+struct Client {
+    name: String,
+    role: Role,
+    /// Lines sent to the client.
+    out: Sender<String>,
+}
+
+/// What a client's line came to.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Submitted {
+    /// The hub handled it.
+    Handled(Reply),
+    /// An entry from the controller, named here, to run on the session.
+    Entry(String),
+}
+
+/// What the session looked like after an entry, to answer queries and
+/// report what the next entry changed.
+struct Snapshot {
+    fields: BTreeMap<String, Vec<f64>>,
+    traces: BTreeMap<String, f64>,
+    vars: BTreeMap<String, f64>,
+}
+
+impl Snapshot {
+    fn of(repl: &Repl) -> Self {
+        Snapshot {
+            fields: repl.env.rt.field_states(),
+            traces: repl.env.report.traces.clone(),
+            vars: repl.env.vars.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+        }
+    }
+
+    /// One line per field, trace or variable that differs in `after`.
+    fn changes(&self, after: &Snapshot) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (name, state) in &after.fields {
+            match self.fields.get(name) {
+                None => lines.push(format!("  field {} created ({} elements)", name, state.len())),
+                Some(before) if before != state => lines.push(format!("  field {} changed", name)),
+                Some(_) => {}
+            }
+        }
+        for name in self.fields.keys().filter(|name| !after.fields.contains_key(*name)) {
+            lines.push(format!("  field {} removed", name));
+        }
+        for (name, value) in &after.traces {
+            if self.traces.get(name) != Some(value) {
+                lines.push(format!("  trace {} = {:.4}", name, value));
+            }
+        }
+        for (name, value) in &after.vars {
+            if self.vars.get(name) != Some(value) {
+                lines.push(format!("  {} = {}", name, value));
+            }
+        }
+        lines
+    }
+}
+
+/// Everyone attached to a session, and what they last saw of it.
+pub struct Hub {
+    /// In the order they attached.
+    clients: BTreeMap<ClientId, Client>,
+    next_id: u64,
+    session: Snapshot,
+}
+
+impl Hub {
+    pub fn new(repl: &Repl) -> Self {
+        Hub { clients: BTreeMap::new(), next_id: 0, session: Snapshot::of(repl) }
+    }
+
+    /// Add a client; it controls the session if nobody else does. Lines for
+    /// it arrive on the returned receiver.
+    pub fn attach(&mut self, name: &str) -> (ClientId, Role, Receiver<String>) {
+        let id = ClientId(self.next_id);
+        self.next_id += 1;
+        let role = match self.controller() {
+            Some(_) => Role::Observer,
+            None => Role::Controller,
+        };
+        let (out, rx) = unbounded();
+        self.clients.insert(id, Client { name: name.to_string(), role, out });
+        let as_role = match role {
+            Role::Controller => "controller",
+            Role::Observer => "observer",
+        };
+        self.broadcast(format!("* {} joined as {}", name, as_role));
+        (id, role, rx)
+    }
+
+    /// Remove a client, passing control on if it had it.
+    pub fn detach(&mut self, id: ClientId) {
+        let Some(client) = self.clients.remove(&id) else { return };
+        self.broadcast(format!("* {} left", client.name));
+        if client.role == Role::Controller {
+            if let Some(next) = self.clients.keys().next().copied() {
+                self.hand_over(next);
+            }
+        }
+    }
+
+    pub fn role(&self, id: ClientId) -> Option<Role> {
+        self.clients.get(&id).map(|c| c.role)
+    }
+
+    pub fn controller(&self) -> Option<ClientId> {
+        self.clients.iter().find(|(_, c)| c.role == Role::Controller).map(|(id, _)| *id)
+    }
+
+    /// Handle one line from client `id`, unless it is an entry for the
+    /// session. Entries from observers, and commands unknown here from
+    /// anyone but the controller, are refused with a message to that client
+    /// alone.
+    pub fn submit(&mut self, id: ClientId, line: &str) -> Submitted {
+        let Some(client) = self.clients.get(&id) else { return Submitted::Handled(Reply::Quit) };
+        let (name, role) = (client.name.clone(), client.role);
+        let command = line.trim().strip_prefix(':').map(|c| c.trim());
+        match command.map(|c| c.split_once(' ').unwrap_or((c, ""))) {
+            Some(("quit" | "q", _)) => {
+                self.detach(id);
+                return Submitted::Handled(Reply::Quit);
+            }
+            Some(("say", text)) => self.broadcast(format!("{}: {}", name, text.trim())),
+            Some(("who", _)) => {
+                let who: Vec<String> = self
+                    .clients
+                    .values()
+                    .map(|c| match c.role {
+                        Role::Controller => format!("{} (controller)", c.name),
+                        Role::Observer => c.name.clone(),
+                    })
+                    .collect();
+                self.tell(id, who.join(", "));
+            }
+            Some((what @ ("fields" | "traces" | "vars"), _)) => {
+                for line in self.query(what) {
+                    self.tell(id, line);
+                }
+            }
+            Some(("pass", to)) if role == Role::Controller => {
+                match self.clients.iter().find(|(_, c)| c.name == to.trim()).map(|(other, _)| *other) {
+                    Some(next) if next != id => {
+                        self.clients.get_mut(&id).unwrap().role = Role::Observer;
+                        self.hand_over(next);
+                    }
+                    _ => self.tell(id, format!("Nobody else named {} is attached.", to.trim())),
+                }
+            }
+            _ if role == Role::Observer => {
+                self.tell(id, "You are observing; only the controller can change the session.".to_string());
+            }
+            _ => return Submitted::Entry(name),
+        }
+        Submitted::Handled(Reply::Ready)
+    }
+
+    /// Tell everyone that `name` ran `line`, what it printed and, once the
+    /// entry is complete, what it changed.
+    fn ran(&mut self, id: ClientId, name: &str, line: &str, reply: Reply, output: Vec<String>, after: Snapshot) -> Reply {
+        if reply == Reply::Quit {
+            self.detach(id);
+            return Reply::Quit;
+        }
+        self.broadcast(format!("{}> {}", name, line));
+        for printed in output {
+            self.broadcast(format!("  {}", printed));
+        }
+        if reply == Reply::Ready {
+            for change in self.session.changes(&after) {
+                self.broadcast(change);
+            }
+            self.session = after;
+        }
+        reply
+    }
+
+    fn query(&self, what: &str) -> Vec<String> {
+        let session = &self.session;
+        match what {
+            "fields" => session.fields.iter().map(|(name, state)| format!("{} ({})", name, state.len())).collect(),
+            "traces" => session.traces.iter().map(|(name, value)| format!("{} = {}", name, value)).collect(),
+            _ => session.vars.iter().map(|(name, value)| format!("{} = {}", name, value)).collect(),
+        }
+    }
+
+    fn hand_over(&mut self, id: ClientId) {
+        let Some(client) = self.clients.get_mut(&id) else { return };
+        client.role = Role::Controller;
+        let name = client.name.clone();
+        self.broadcast(format!("* {} now controls the session", name));
+    }
+
+    fn tell(&self, id: ClientId, line: String) {
+        if let Some(client) = self.clients.get(&id) {
+            let _ = client.out.send(line);
+        }
+    }
+
+    fn broadcast(&self, line: String) {
+        for client in self.clients.values() {
+            let _ = client.out.send(line.clone());
+        }
+    }
+}
+
+/// A session and its hub, shared between the threads serving its clients.
+/// Each has its own lock, so the hub stays responsive while an entry runs.
+#[derive(Clone)]
+pub struct SharedSession {
+    hub: Arc<Mutex<Hub>>,
+    repl: Arc<Mutex<Repl>>,
+}
+
+impl SharedSession {
+    pub fn new(repl: Repl) -> Self {
+        SharedSession { hub: Arc::new(Mutex::new(Hub::new(&repl))), repl: Arc::new(Mutex::new(repl)) }
+    }
+
+    pub fn hub(&self) -> MutexGuard<'_, Hub> {
+        self.hub.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn repl(&self) -> MutexGuard<'_, Repl> {
+        self.repl.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Handle one line from client `id`. An entry runs holding only the
+    /// session, and everything it printed is broadcast with it.
+    pub fn submit(&self, id: ClientId, line: &str) -> Reply {
+        let name = match self.hub().submit(id, line) {
+            Submitted::Handled(reply) => return reply,
+            Submitted::Entry(name) => name,
+        };
+        let (reply, output, after) = {
+            let mut repl = self.repl();
+            let (reply, output) = console::capture(|| repl.eval(line));
+            (reply, output, Snapshot::of(&repl))
+        };
+        self.hub().ran(id, &name, line, reply, output, after)
+    }
+
+    /// Accept clients on `address` until the listener fails.
+    pub fn serve(&self, address: impl ToSocketAddrs) -> io::Result<()> {
+        let listener = TcpListener::bind(address)?;
+        println!("Sharing the session on {}", listener.local_addr()?);
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let session = self.clone();
+            thread::spawn(move || {
+                if let Err(e) = session.handle(stream) {
+                    eprintln!("⚠️ Client connection failed: {}", e);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Serve one client: its first line is its name, later lines entries.
+    fn handle(&self, stream: TcpStream) -> io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        let mut lines = BufReader::new(stream).lines();
+        let Some(name) = lines.next().transpose()? else { return Ok(()) };
+        let (id, role, rx) = self.hub().attach(name.trim());
+        if let Err(e) = writeln!(writer, "Attached as {}.", if role == Role::Controller { "controller" } else { "observer" }) {
+            self.hub().detach(id);
+            return Err(e);
+        }
+        let forward = thread::spawn(move || {
+            for line in rx {
+                if writeln!(writer, "{}", line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut quit = false;
+        // A connection that fails to read ends like one that closes, so its
+        // client is detached either way.
+        for line in lines.map_while(Result::ok) {
+            if self.submit(id, &line) == Reply::Quit {
+                quit = true;
+                break;
+            }
+        }
+        if !quit {
+            self.hub().detach(id);
+        }
+        // Detaching dropped the sender, so forwarding ends.
+        let _ = forward.join();
+        Ok(())
+    }
+}
@@ -15,10 +15,12 @@
  * along with SPTL-SPI.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::recursion::{CategoryObject, RecursionLevel};
+use crate::agents::Agent;
+use crate::recursion::{find_in_forest_mut, migrate_agent, CategoryObject, MigrationMode, RecursionLevel};
 use crate::interpretation::Interpretation;
 
 use std::collections::HashMap;
+use std::path::Path;
 
 pub struct Shell {
     pub categories: HashMap<String, CategoryObject>,
@@ -39,14 +41,189 @@ impl Shell {
         if let Some(obj) = self.categories.get(id) {
             match obj.interpret() {
                 Some(interpretation) => {
-                    println!("Interpretation at level {:?} for {}:\n{:#?}", obj.level, id, interpretation);
+                    println!("Interpretation at level {} for {}:\n{:#?}", obj.level_name(), id, interpretation);
                 }
                 None => {
-                    println!("No interpretation available for {} at level {:?}", id, obj.level);
+                    println!("No interpretation available for {} at level {}", id, obj.level_name());
                 }
             }
         } else {
             println!("Category object '{}' not found.", id);
         }
     }
-}
\ No newline at end of file
+
+    /// Move or copy an agent between category objects at any level.
+    /// Usage: migrate <agent> <from-id> <to-id> [--copy]
+    pub fn handle_migrate(&mut self, args: &[String]) {
+        let mode = match args.get(3).map(|s| s.as_str()) {
+            None => MigrationMode::Move,
+            Some("--copy") => MigrationMode::Copy,
+            Some(_) => {
+                println!("Usage: migrate <agent> <from-id> <to-id> [--copy]");
+                return;
+            }
+        };
+        if args.len() < 3 {
+            println!("Usage: migrate <agent> <from-id> <to-id> [--copy]");
+            return;
+        }
+        match migrate_agent(&mut self.categories, &args[0], &args[1], &args[2], mode) {
+            Ok(()) => println!("{:?} agent {} from {} to {}", mode, args[0], args[1], args[2]),
+            Err(e) => println!("Migration failed: {}", e),
+        }
+    }
+
+    /// Load an agent saved by a previous run into a category object, renamed to `<name>`.
+    /// Usage: load agent <name> from <path> in <id>
+    pub fn handle_load_agent(&mut self, args: &[String]) {
+        let usage = "Usage: load agent <name> from <path> in <id>";
+        let [name, from, path, into, id] = args else {
+            println!("{}", usage);
+            return;
+        };
+        if from != "from" || into != "in" {
+            println!("{}", usage);
+            return;
+        }
+        let path = path.trim_matches('"');
+        let mut agent = match Agent::load(Path::new(path)) {
+            Ok(agent) => agent,
+            Err(e) => {
+                println!("Load agent {} failed: {}: {}", name, path, e);
+                return;
+            }
+        };
+        let Some(obj) = find_in_forest_mut(&mut self.categories, id) else {
+            println!("Category object '{}' not found.", id);
+            return;
+        };
+        agent.id = name.clone();
+        println!(
+            "Loaded agent {} into {} ({} symbols, {} memories)",
+            name, id, agent.symbol_table.len(), agent.memory.traces.len()
+        );
+        obj.agents.push(agent);
+    }
+
+    /// Pin an agent's symbol so its traces are exempt from decay and eviction, or unpin it.
+    /// Usage: pin <agent> <token> in <id>, or unpin <agent> <token> in <id>
+    pub fn handle_pin(&mut self, args: &[String], pinned: bool) {
+        let verb = if pinned { "pin" } else { "unpin" };
+        let [name, token, into, id] = args else {
+            println!("Usage: {} <agent> <token> in <id>", verb);
+            return;
+        };
+        if into != "in" {
+            println!("Usage: {} <agent> <token> in <id>", verb);
+            return;
+        }
+        let Some(obj) = find_in_forest_mut(&mut self.categories, id) else {
+            println!("Category object '{}' not found.", id);
+            return;
+        };
+        let Some(agent) = obj.agents.iter_mut().find(|a| &a.id == name) else {
+            println!("Agent '{}' not found in {}.", name, id);
+            return;
+        };
+        let changed = if pinned { agent.memory.pin(token) } else { agent.memory.unpin(token) };
+        match (pinned, changed) {
+            (true, true) => println!("Pinned {} in {}", token, name),
+            (true, false) => println!("{} is already pinned in {}", token, name),
+            (false, true) => println!("Unpinned {} in {}", token, name),
+            (false, false) => println!("{} is not pinned in {}", token, name),
+        }
+    }
+
+    /// Print the recursion hierarchy under an object as a tree.
+    /// Usage: tree <id> [--depth N] [--level <level>] [--match <text>]
+    pub fn handle_tree(&self, args: &[String]) {
+        let usage = "Usage: tree <id> [--depth N] [--level <level>] [--match <text>]";
+        let Some(id) = args.first() else {
+            println!("{}", usage);
+            return;
+        };
+        let mut filter = TreeFilter::default();
+        let mut rest = args[1..].iter();
+        while let Some(opt) = rest.next() {
+            let value = rest.next();
+            match (opt.as_str(), value) {
+                ("--depth", Some(v)) => match v.parse() {
+                    Ok(d) => filter.max_depth = Some(d),
+                    Err(_) => {
+                        println!("Invalid depth '{}'.", v);
+                        return;
+                    }
+                },
+                ("--level", Some(v)) => match RecursionLevel::from_name(v) {
+                    Some(level) => filter.level = Some(level),
+                    None => {
+                        println!("Unknown level '{}'.", v);
+                        return;
+                    }
+                },
+                ("--match", Some(v)) => filter.text = Some(v.clone()),
+                _ => {
+                    println!("{}", usage);
+                    return;
+                }
+            }
+        }
+        let Some(obj) = self.categories.get(id) else {
+            println!("Category object '{}' not found.", id);
+            return;
+        };
+        let mut out = String::new();
+        render_tree_node(obj, &filter, "", None, 0, &mut out);
+        print!("{}", out);
+    }
+}
+
+/// Options restricting which nodes `tree` prints.
+#[derive(Default)]
+struct TreeFilter {
+    max_depth: Option<usize>,
+    level: Option<RecursionLevel>,
+    text: Option<String>,
+}
+
+impl TreeFilter {
+    fn matches(&self, obj: &CategoryObject) -> bool {
+        self.level.is_none_or(|l| obj.level == l)
+            && self.text.as_ref().is_none_or(|t| obj.id.contains(t.as_str()))
+    }
+
+    /// A node is shown if it, or anything below it, matches.
+    fn shows(&self, obj: &CategoryObject) -> bool {
+        self.matches(obj) || obj.subobjects.iter().any(|sub| self.shows(sub))
+    }
+}
+
+/// Render one node and its visible children with box-drawing connectors.
+/// `last` is `None` for the root, otherwise whether this node is its parent's last visible child.
+fn render_tree_node(obj: &CategoryObject, filter: &TreeFilter, prefix: &str, last: Option<bool>, depth: usize, out: &mut String) {
+    let connector = match last {
+        None => "",
+        Some(true) => "└── ",
+        Some(false) => "├── ",
+    };
+    let energy: f64 = obj.substrate.activations.values().sum();
+    out.push_str(&format!(
+        "{}{}{} [{}] energy={:.2} agents={}\n",
+        prefix, connector, obj.id, obj.level_name(), energy, obj.agents.len()
+    ));
+    if filter.max_depth.is_some_and(|d| depth >= d) {
+        return;
+    }
+    let child_prefix = match last {
+        None => prefix.to_string(),
+        Some(true) => format!("{}    ", prefix),
+        Some(false) => format!("{}│   ", prefix),
+    };
+    let visible: Vec<&CategoryObject> = obj.subobjects.iter()
+        .map(|sub| &**sub)
+        .filter(|sub| filter.shows(sub))
+        .collect();
+    for (i, sub) in visible.iter().enumerate() {
+        render_tree_node(sub, filter, &child_prefix, Some(i + 1 == visible.len()), depth + 1, out);
+    }
+}
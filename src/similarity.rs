@@ -0,0 +1,104 @@
+//! Similarity index over symbol patterns, so fuzzy lookups in tables of tens
+//! of thousands of symbols do not compare against every entry.
+//!
+//! Patterns are split into `BANDS` bands (locality-sensitive hashing for
+//! Hamming distance); two patterns of equal length that differ in fewer than
+//! `BANDS` positions share at least one band exactly, and so a bucket. Only
+//! the symbols sharing a bucket with the query are scored with
+//! `pattern_similarity`; when that gives fewer than `k` candidates the whole
+//! table is scanned. Lookups are therefore exact for symbols within
+//! `BANDS - 1` differences of the query, and approximate beyond.
+
+use crate::agents::pattern_similarity;
+use crate::substrate::Pattern;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+/// Bands each pattern is split into.
+pub const BANDS: usize = 4;
+
+/// Bucket key: pattern length, band number and the band's characters.
+type BandKey = (usize, usize, String);
+
+#[derive(Debug, Clone, Default)]
+pub struct SymbolIndex {
+    /// Indexed pattern of each token, to find its buckets again on removal.
+    patterns: HashMap<String, Pattern>,
+    buckets: HashMap<BandKey, Vec<String>>,
+}
+
+fn bands(pattern: &Pattern) -> Vec<BandKey> {
+    let chars: Vec<char> = pattern.0.chars().collect();
+    let width = chars.len().div_ceil(BANDS).max(1);
+    if chars.is_empty() {
+        return vec![(0, 0, String::new())];
+    }
+    chars.chunks(width).enumerate().map(|(band, chunk)| (chars.len(), band, chunk.iter().collect())).collect()
+}
+
+impl SymbolIndex {
+    /// Index every entry of a symbol table.
+    pub fn from_table(table: &HashMap<String, Pattern>) -> Self {
+        let mut index = SymbolIndex::default();
+        for (token, pattern) in table {
+            index.insert(token, pattern);
+        }
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Index `token` under `pattern`, replacing its previous pattern.
+    pub fn insert(&mut self, token: &str, pattern: &Pattern) {
+        self.remove(token);
+        for key in bands(pattern) {
+            self.buckets.entry(key).or_default().push(token.to_string());
+        }
+        self.patterns.insert(token.to_string(), pattern.clone());
+    }
+
+    pub fn remove(&mut self, token: &str) {
+        let Some(old) = self.patterns.remove(token) else {
+            return;
+        };
+        for key in bands(&old) {
+            if let Some(bucket) = self.buckets.get_mut(&key) {
+                bucket.retain(|t| t != token);
+                if bucket.is_empty() {
+                    self.buckets.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Up to `k` tokens of `table` whose patterns are most similar to
+    /// `pattern`, with their similarity, most similar first (ties by token).
+    /// Similarities are computed against `table`, so an index that has fallen
+    /// behind it can only make the lookup slower, not wrong.
+    pub fn nearest(&self, table: &HashMap<String, Pattern>, pattern: &Pattern, k: usize) -> Vec<(String, f64)> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let candidates: HashSet<&str> = bands(pattern)
+            .iter()
+            .filter_map(|key| self.buckets.get(key))
+            .flatten()
+            .map(String::as_str)
+            .filter(|token| table.contains_key(*token))
+            .collect();
+        let mut scored: Vec<(String, f64)> = if candidates.len() >= k {
+            candidates.into_iter().map(|token| (token.to_string(), pattern_similarity(&table[token], pattern))).collect()
+        } else {
+            table.iter().map(|(token, known)| (token.clone(), pattern_similarity(known, pattern))).collect()
+        };
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal).then_with(|| a.0.cmp(&b.0)));
+        scored.truncate(k);
+        scored
+    }
+}
@@ -0,0 +1,71 @@
+//! Consistent views of a running world for readers on other threads.
+//!
+//! Observers such as a TUI, shell clients or telemetry writers must not read
+//! a world while a tick is halfway through changing it, and must not hold up
+//! the tick loop either. After every `tick` the narrative runner publishes a
+//! `Snapshot` to its `Snapshots`: the fields and agents as they stand at that
+//! τ. Publishing copies `Arc` pointers, not substrates, and later writes go
+//! through `Arc::make_mut`, so a snapshot keeps seeing the state it was taken
+//! from. Readers take the latest snapshot with `Snapshots::latest`; the lock
+//! is held only to swap or clone one pointer.
+
+use crate::runtime::{AgentState, Runtime};
+use crate::substrate::Substrate;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+/// A world as it stood at the end of one tick.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    /// Publications before this one; 0 for the view taken before any tick.
+    pub epoch: u64,
+    pub tau: u64,
+    pub fields: HashMap<String, Arc<Substrate>>,
+    pub agents: HashMap<String, Arc<AgentState>>,
+    pub measurements: BTreeMap<String, f64>,
+}
+
+impl Snapshot {
+    pub fn of(world: &Runtime, epoch: u64) -> Self {
+        Snapshot {
+            epoch,
+            tau: world.tau,
+            fields: world.fields.clone(),
+            agents: world.agents.clone(),
+            measurements: world.measurements.clone(),
+        }
+    }
+
+    /// Values of field `name` at this snapshot's τ.
+    pub fn field(&self, name: &str) -> Option<&[f64]> {
+        self.fields.get(name).map(|f| f.state.as_slice())
+    }
+}
+
+/// The latest snapshot of a world, shared by its writer and any number of
+/// readers. Clones share one slot.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshots {
+    latest: Arc<RwLock<Arc<Snapshot>>>,
+}
+
+impl Snapshots {
+    /// A slot holding the view of `world` as it is now, as epoch 0.
+    pub fn new(world: &Runtime) -> Self {
+        Snapshots { latest: Arc::new(RwLock::new(Arc::new(Snapshot::of(world, 0)))) }
+    }
+
+    /// Replace the latest snapshot with `world` as it is now; returns its epoch.
+    pub fn publish(&self, world: &Runtime) -> u64 {
+        let epoch = self.latest().epoch + 1;
+        let snapshot = Arc::new(Snapshot::of(world, epoch));
+        *self.latest.write().unwrap_or_else(|e| e.into_inner()) = snapshot;
+        epoch
+    }
+
+    /// The most recently published snapshot. It stays valid, and unchanged,
+    /// however many are published after it.
+    pub fn latest(&self) -> Arc<Snapshot> {
+        Arc::clone(&self.latest.read().unwrap_or_else(|e| e.into_inner()))
+    }
+}
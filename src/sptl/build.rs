@@ -0,0 +1,256 @@
+//! Building SPTL programs in Rust instead of generating source text.
+//!
+//! `Program` collects the same `Statement`s `parse_source` produces, with
+//! omitted options filled in the way the parser fills them:
+//!
+//! ```ignore
+//! let program = Program::new()
+//!     .field("psi", 128)
+//!     .interpretation("I", vec![1.0; 128])
+//!     .project("psi", "I", |p| p.alpha(0.3).steps(100))
+//!     .trace("d", "psi", "I")
+//!     .build();
+//! ```
+//!
+//! Option values take anything that converts to an `Expr`, so a number or
+//! `var("a")` for a variable bound by `let`.
+
+use super::encode::Encoding;
+use super::expr::{Expr, FieldExpr};
+use super::{Comparison, Metric, RecordKind, Recording, Statement};
+use crate::condition::Condition;
+use crate::config::Config;
+
+/// The variable `name`, as an option value.
+pub fn var(name: &str) -> Expr {
+    FieldExpr::Field(name.to_string())
+}
+
+/// A program under construction.
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    statements: Vec<Statement>,
+    config: Config,
+}
+
+impl Program {
+    pub fn new() -> Self {
+        Program::default()
+    }
+
+    /// Fill omitted `project` options from `config`, as `parse_source_with` does.
+    pub fn with_config(config: &Config) -> Self {
+        Program { statements: Vec::new(), config: config.clone() }
+    }
+
+    /// Append any statement, for those without a method of their own.
+    pub fn statement(mut self, statement: Statement) -> Self {
+        self.statements.push(statement);
+        self
+    }
+
+    /// `field name size`
+    pub fn field(self, name: &str, size: usize) -> Self {
+        self.statement(Statement::Field { name: name.to_string(), size: Expr::from(size as f64), doc: None })
+    }
+
+    /// `field name size "doc"`
+    pub fn documented_field(self, name: &str, size: usize, doc: &str) -> Self {
+        self.statement(Statement::Field { name: name.to_string(), size: Expr::from(size as f64), doc: Some(doc.to_string()) })
+    }
+
+    /// `interpretation name = [values]`
+    pub fn interpretation(self, name: &str, values: impl Into<Vec<f64>>) -> Self {
+        let values = values.into().into_iter().map(Expr::from).collect();
+        self.statement(Statement::Interpretation { name: name.to_string(), values, doc: None })
+    }
+
+    /// `project target <- interp { ... }`, with the options `options` sets.
+    pub fn project(self, target: &str, interp: &str, options: impl FnOnce(Project) -> Project) -> Self {
+        let project = options(Project::new(&self.config));
+        let statement = project.statement(target, interp, &self.config);
+        self.statement(statement)
+    }
+
+    /// `trace name = trace_distance(field, interp)`
+    pub fn trace(self, name: &str, field: &str, interp: &str) -> Self {
+        self.statement(Statement::TraceDistance { name: name.to_string(), field: field.to_string(), interp: interp.to_string() })
+    }
+
+    /// `let name = metric(field, interp)`
+    pub fn measure(self, name: &str, metric: Metric, field: &str, interp: &str) -> Self {
+        self.statement(Statement::Let { name: name.to_string(), metric, field: field.to_string(), interp: interp.to_string() })
+    }
+
+    /// `let name = value`
+    pub fn assign(self, name: &str, value: impl Into<Expr>) -> Self {
+        self.statement(Statement::Assign { name: name.to_string(), value: value.into() })
+    }
+
+    /// `seed value`
+    pub fn seed(self, value: u64) -> Self {
+        self.statement(Statement::Seed(value))
+    }
+
+    /// `perturb field noise amplitude`
+    pub fn perturb(self, field: &str, amplitude: impl Into<Expr>) -> Self {
+        self.statement(Statement::Perturb { field: field.to_string(), amplitude: amplitude.into() })
+    }
+
+    /// `scale field factor`
+    pub fn scale(self, field: &str, factor: impl Into<Expr>) -> Self {
+        self.statement(Statement::Scale { field: field.to_string(), factor: factor.into() })
+    }
+
+    /// `normalize field`
+    pub fn normalize(self, field: &str) -> Self {
+        self.statement(Statement::Normalize { field: field.to_string() })
+    }
+
+    /// `expresssymbol "token" into field` with the default encoding and weight.
+    pub fn express(self, token: &str, field: &str) -> Self {
+        self.statement(Statement::ExpressSymbol {
+            token: token.to_string(),
+            into_field: field.to_string(),
+            encoding: Encoding::Bits,
+            weight: Expr::from(1.0),
+            blend: false,
+        })
+    }
+
+    /// `assert trace name < bound` or `>`, loosened by `tolerance`.
+    pub fn assert_trace(self, trace: &str, cmp: Comparison, bound: impl Into<Expr>, tolerance: f64) -> Self {
+        self.statement(Statement::Assert { trace: trace.to_string(), cmp, bound: bound.into(), tolerance })
+    }
+
+    /// `repeat count { body }`
+    pub fn repeat(self, count: usize, body: impl FnOnce(Program) -> Program) -> Self {
+        let body = body(Program::with_config(&self.config)).build();
+        self.statement(Statement::Repeat { count, body })
+    }
+
+    /// `if condition { then } else { otherwise }`; an empty `otherwise` has no `else`.
+    pub fn if_else(
+        self,
+        condition: Condition,
+        then: impl FnOnce(Program) -> Program,
+        otherwise: impl FnOnce(Program) -> Program,
+    ) -> Self {
+        let then = then(Program::with_config(&self.config)).build();
+        let otherwise = otherwise(Program::with_config(&self.config)).build();
+        self.statement(Statement::If { condition, then, otherwise })
+    }
+
+    /// `if condition { then }`
+    pub fn if_then(self, condition: Condition, then: impl FnOnce(Program) -> Program) -> Self {
+        self.if_else(condition, then, |p| p)
+    }
+
+    pub fn statements(&self) -> &[Statement] {
+        &self.statements
+    }
+
+    pub fn build(self) -> Vec<Statement> {
+        self.statements
+    }
+}
+
+impl From<Program> for Vec<Statement> {
+    fn from(program: Program) -> Self {
+        program.build()
+    }
+}
+
+/// The options block of a `project` statement.
+#[derive(Debug, Clone)]
+pub struct Project {
+    alpha: Expr,
+    noise: Expr,
+    /// `None` until set; `Some(None)` for `steps: auto`.
+    steps: Option<Option<usize>>,
+    tolerance: Expr,
+    until: Option<Expr>,
+    record: Option<Recording>,
+    log_every: Option<usize>,
+}
+
+impl Project {
+    fn new(config: &Config) -> Self {
+        Project {
+            alpha: Expr::from(config.alpha),
+            noise: Expr::from(config.noise),
+            steps: None,
+            tolerance: Expr::from(config.tolerance),
+            until: None,
+            record: None,
+            log_every: None,
+        }
+    }
+
+    /// `alpha:`; the configured alpha if not set.
+    pub fn alpha(mut self, alpha: impl Into<Expr>) -> Self {
+        self.alpha = alpha.into();
+        self
+    }
+
+    pub fn noise(mut self, noise: impl Into<Expr>) -> Self {
+        self.noise = noise.into();
+        self
+    }
+
+    /// `steps: N`, or the step limit with `until`.
+    pub fn steps(mut self, steps: usize) -> Self {
+        self.steps = Some(Some(steps));
+        self
+    }
+
+    /// `steps: auto`: project until the distance improves by less than the tolerance.
+    pub fn auto_steps(mut self) -> Self {
+        self.steps = Some(None);
+        self
+    }
+
+    pub fn tolerance(mut self, tolerance: impl Into<Expr>) -> Self {
+        self.tolerance = tolerance.into();
+        self
+    }
+
+    /// `until: dist < threshold`
+    pub fn until(mut self, threshold: impl Into<Expr>) -> Self {
+        self.until = Some(threshold.into());
+        self
+    }
+
+    /// `record trajectory every N steps` or `record snapshots every N steps`.
+    pub fn record(mut self, kind: RecordKind, every: usize) -> Self {
+        self.record = Some(Recording { kind, every });
+        self
+    }
+
+    /// `log_every: N`; `N` must be at least 1.
+    pub fn log_every(mut self, every: usize) -> Self {
+        self.log_every = Some(every.max(1));
+        self
+    }
+
+    fn statement(self, target: &str, interp: &str, config: &Config) -> Statement {
+        // As in the parser: with `until` an omitted `steps` leaves the limit
+        // to `AUTO_MAX_STEPS`, without it the configured default applies.
+        let steps = match self.until {
+            Some(_) => self.steps.flatten(),
+            None => self.steps.unwrap_or(config.steps),
+        };
+        let steps = steps.map(|n| Expr::from(n as f64));
+        Statement::Project {
+            target: target.to_string(),
+            interp: interp.to_string(),
+            alpha: self.alpha,
+            noise: self.noise,
+            steps,
+            tolerance: self.tolerance,
+            until: self.until,
+            record: self.record,
+            log_every: self.log_every,
+        }
+    }
+}
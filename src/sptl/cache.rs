@@ -0,0 +1,83 @@
+//! Content-hash cache of parsed SPTL programs.
+//!
+//! Each entry is a bincode file named after a hash of the source text and the
+//! parameters bound into it. Entries record the grammar version they were
+//! parsed with and are ignored once `GRAMMAR_VERSION` changes.
+
+use super::{parse_source_with, ParseError, Statement};
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Bump whenever `Statement` or the parser's output for existing syntax changes.
+pub const GRAMMAR_VERSION: u32 = 40;
+
+/// Environment variable enabling the cache in the given directory.
+pub const CACHE_DIR_ENV: &str = "SPTL_CACHE_DIR";
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    grammar_version: u32,
+    program: Vec<Statement>,
+}
+
+/// 64-bit FNV-1a; stable across builds, unlike `DefaultHasher`.
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+pub struct AstCache {
+    dir: PathBuf,
+}
+
+impl AstCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        AstCache { dir: dir.into() }
+    }
+
+    /// Cache configured through `SPTL_CACHE_DIR`, if set.
+    pub fn from_env() -> Option<Self> {
+        std::env::var_os(CACHE_DIR_ENV).map(AstCache::new)
+    }
+
+    fn entry_path(&self, source: &str, params: &BTreeMap<String, String>, config: &Config) -> PathBuf {
+        // Omitted `project` options are filled from the config, so it is part of the key.
+        let mut key = format!("v{}\0{:?}\0{}", GRAMMAR_VERSION, config, source);
+        for (k, v) in params {
+            key.push_str(&format!("\0{}={}", k, v));
+        }
+        self.dir.join(format!("{:016x}.ast", content_hash(key.as_bytes())))
+    }
+
+    /// Parse `source`, reusing a cached AST when one exists for the same content and grammar.
+    /// Scripts with syntax errors are not cached.
+    pub fn parse(
+        &self,
+        source: &str,
+        params: &BTreeMap<String, String>,
+        config: &Config,
+    ) -> Result<Vec<Statement>, Vec<ParseError>> {
+        let path = self.entry_path(source, params, config);
+        if let Ok(bytes) = fs::read(&path) {
+            if let Ok(entry) = bincode::deserialize::<CacheEntry>(&bytes) {
+                if entry.grammar_version == GRAMMAR_VERSION {
+                    return Ok(entry.program);
+                }
+            }
+        }
+        let program = parse_source_with(source, params, config)?;
+        let entry = CacheEntry { grammar_version: GRAMMAR_VERSION, program };
+        // A cache that cannot be written only costs a re-parse next time.
+        if let Ok(bytes) = bincode::serialize(&entry) {
+            let _ = fs::create_dir_all(&self.dir).and_then(|_| fs::write(&path, bytes));
+        }
+        Ok(entry.program)
+    }
+}
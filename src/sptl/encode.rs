@@ -0,0 +1,90 @@
+//! How `expresssymbol` turns a token into field values.
+//!
+//! `bits` (the default) expands the token's bitstring across the field: a
+//! token written in 0s and 1s is its own bitstring, any other token stands
+//! for the 64 bits of its hash. The bits repeat until the field is full, a
+//! 1 becoming 1.0 and a 0 becoming 0.0. `onehot` gives every token its own
+//! element instead: the runtime's symbol registry numbers tokens in the
+//! order they are first expressed, and a token sets the element at its
+//! number (wrapping around the field's size).
+
+use serde::{Deserialize, Serialize};
+
+/// Encoding names as written in scripts.
+pub const ENCODING_NAMES: [&str; 2] = ["bits", "onehot"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Encoding {
+    /// The token's bitstring, repeated across the field.
+    #[default]
+    Bits,
+    /// 1 at the token's registry number, 0 elsewhere.
+    OneHot,
+}
+
+impl Encoding {
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        match name.to_lowercase().as_str() {
+            "bits" => Some(Encoding::Bits),
+            "onehot" | "one_hot" => Some(Encoding::OneHot),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Bits => "bits",
+            Encoding::OneHot => "onehot",
+        }
+    }
+}
+
+/// Tokens numbered in the order they were first expressed.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolRegistry {
+    tokens: Vec<String>,
+}
+
+impl SymbolRegistry {
+    /// The number of `token`, registering it if it is new.
+    pub fn index(&mut self, token: &str) -> usize {
+        match self.tokens.iter().position(|t| t == token) {
+            Some(i) => i,
+            None => {
+                self.tokens.push(token.to_string());
+                self.tokens.len() - 1
+            }
+        }
+    }
+
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+}
+
+/// The bitstring `token` stands for under the `bits` encoding.
+pub fn bitstring(token: &str) -> String {
+    if !token.is_empty() && token.chars().all(|c| c == '0' || c == '1') {
+        return token.to_string();
+    }
+    format!("{:064b}", fnv1a(token))
+}
+
+/// The values `token` adds to a field of `size` elements.
+pub fn encode(token: &str, encoding: Encoding, size: usize, registry: &mut SymbolRegistry) -> Vec<f64> {
+    match encoding {
+        Encoding::Bits => {
+            let bits: Vec<f64> = bitstring(token).chars().map(|c| if c == '1' { 1.0 } else { 0.0 }).collect();
+            bits.iter().copied().cycle().take(size).collect()
+        }
+        Encoding::OneHot => {
+            let hot = registry.index(token) % size.max(1);
+            (0..size).map(|i| if i == hot { 1.0 } else { 0.0 }).collect()
+        }
+    }
+}
+
+/// 64-bit FNV-1a: stable across runs and builds, unlike `std`'s hashers.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+}
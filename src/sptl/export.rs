@@ -0,0 +1,266 @@
+//! `export trace t1 to "results.csv"` and `export field psi to "psi.json"`:
+//! write results where pandas or R can read them. The format follows the
+//! file extension.
+//!
+//! A trace is written as every value recorded under its name, in step
+//! order (`step,value` CSV, or a JSON array of `{ "step", "value" }`); a
+//! field as its current state (`index,value` CSV, or a JSON array of numbers).
+//!
+//! `report` prints a summary of the run so far: statistics of every field
+//! and the latest value of every trace, and with `report to "summary.json"`
+//! also writes it (a JSON object, or `kind,name,...` CSV rows).
+//!
+//! Plots (`.png`, `.svg`), SQLite databases (`.sqlite`, `.db`) and WebSocket
+//! URLs (`ws://`) are destinations of optional subsystems, see `capability_of`.
+
+use crate::capability::Capability;
+use crate::report::RunReport;
+use crate::{say, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportKind {
+    Trace,
+    Field,
+}
+
+impl ExportKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "trace" => Some(ExportKind::Trace),
+            "field" => Some(ExportKind::Field),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ExportKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ExportKind::Trace => "trace",
+            ExportKind::Field => "field",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    /// The format for `path`'s extension; `None` if it is neither `.csv` nor `.json`.
+    pub fn from_path(path: &str) -> Option<Self> {
+        match Path::new(path).extension()?.to_str()?.to_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// The optional subsystem that writes to `path`, for destinations CSV and
+/// JSON export cannot.
+pub fn capability_of(path: &str) -> Option<Capability> {
+    if path.starts_with("ws://") || path.starts_with("wss://") {
+        return Some(Capability::WebSocket);
+    }
+    match Path::new(path).extension()?.to_str()?.to_lowercase().as_str() {
+        "png" | "svg" => Some(Capability::Plots),
+        "sqlite" | "db" => Some(Capability::Sqlite),
+        _ => None,
+    }
+}
+
+#[derive(Serialize)]
+struct TracePoint {
+    step: usize,
+    value: f64,
+}
+
+/// Run an `export` statement at `step`; `field` is the state of field `name`, if there is one.
+pub fn export(report: &RunReport, step: usize, kind: ExportKind, name: &str, path: &str, field: Option<&[f64]>) {
+    let written = match kind {
+        ExportKind::Trace => write_trace(report, step, name, path),
+        ExportKind::Field => match field {
+            Some(state) => write_field(state, path),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, format!("unknown field {}", name))),
+        },
+    };
+    match written {
+        Ok(count) => println!("💾 Exported {} {} to {} ({} values)", kind, name, path, count),
+        Err(e) => warn!("⚠️ Export {} {} to {} failed: {}", kind, name, path, e),
+    }
+}
+
+/// Write every recorded value of trace `name`; returns how many. While
+/// streaming, telemetry is already on disk and only the latest value is
+/// written, at `step`.
+pub fn write_trace(report: &RunReport, step: usize, name: &str, path: &str) -> io::Result<usize> {
+    let mut points: Vec<TracePoint> = report
+        .telemetry
+        .iter()
+        .filter(|row| row.name == name)
+        .map(|row| TracePoint { step: row.step, value: row.value })
+        .collect();
+    if points.is_empty() {
+        let Some(&value) = report.traces.get(name) else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("unknown trace {}", name)));
+        };
+        points.push(TracePoint { step, value });
+    }
+    let text = match format_of(path)? {
+        ExportFormat::Csv => {
+            let mut out = String::from("step,value\n");
+            for p in &points {
+                out.push_str(&format!("{},{}\n", p.step, p.value));
+            }
+            out
+        }
+        ExportFormat::Json => serde_json::to_string_pretty(&points).map_err(io::Error::other)?,
+    };
+    write(path, text)?;
+    Ok(points.len())
+}
+
+/// Write a field's state; returns its size.
+pub fn write_field(state: &[f64], path: &str) -> io::Result<usize> {
+    let text = match format_of(path)? {
+        ExportFormat::Csv => {
+            let mut out = String::from("index,value\n");
+            for (i, v) in state.iter().enumerate() {
+                out.push_str(&format!("{},{}\n", i, v));
+            }
+            out
+        }
+        ExportFormat::Json => serde_json::to_string_pretty(state).map_err(io::Error::other)?,
+    };
+    write(path, text)?;
+    Ok(state.len())
+}
+
+fn format_of(path: &str) -> io::Result<ExportFormat> {
+    ExportFormat::from_path(path).ok_or_else(|| match capability_of(path) {
+        Some(c) => io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} needs {} (feature `{}`), which this build does not include", path, c.description(), c),
+        ),
+        None => io::Error::new(io::ErrorKind::InvalidInput, "expected a .csv or .json file"),
+    })
+}
+
+/// Statistics of one field's state, over its elements.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldStats {
+    pub name: String,
+    pub size: usize,
+    pub mean: f64,
+    pub variance: f64,
+    pub min: f64,
+    /// Largest activation.
+    pub max: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+}
+
+impl FieldStats {
+    pub fn of(name: &str, state: &[f64]) -> Self {
+        let size = state.len();
+        let mean = state.iter().sum::<f64>() / size.max(1) as f64;
+        let variance = state.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / size.max(1) as f64;
+        let min = state.iter().copied().reduce(f64::min).unwrap_or(0.0);
+        let max = state.iter().copied().reduce(f64::max).unwrap_or(0.0);
+        FieldStats { name: name.to_string(), size, mean, variance, min, max, doc: None }
+    }
+}
+
+/// What `report` prints: every field and the latest value of every trace.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunSummary {
+    pub step: usize,
+    /// In name order.
+    pub fields: Vec<FieldStats>,
+    pub traces: BTreeMap<String, f64>,
+}
+
+impl RunSummary {
+    /// Statistics of `fields`, each with its doc string from `docs`, and `traces`.
+    pub fn new<'a>(
+        step: usize,
+        fields: impl IntoIterator<Item = (&'a str, &'a [f64])>,
+        traces: &BTreeMap<String, f64>,
+        docs: &BTreeMap<String, String>,
+    ) -> Self {
+        let mut fields: Vec<FieldStats> = fields
+            .into_iter()
+            .map(|(name, state)| FieldStats { doc: docs.get(name).cloned(), ..FieldStats::of(name, state) })
+            .collect();
+        fields.sort_by(|a, b| a.name.cmp(&b.name));
+        RunSummary { step, fields, traces: traces.clone() }
+    }
+
+    /// The summary as printed, one line per field and trace after a heading.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("📋 Report at step {}", self.step)];
+        for f in &self.fields {
+            let mut line = format!(
+                "  field {} ({}): mean {:.4}, variance {:.4}, min {:.4}, max {:.4}",
+                f.name, f.size, f.mean, f.variance, f.min, f.max
+            );
+            if let Some(doc) = &f.doc {
+                line.push_str(&format!(" — {}", doc));
+            }
+            lines.push(line);
+        }
+        for (name, value) in &self.traces {
+            lines.push(format!("  trace {} = {:.6}", name, value));
+        }
+        lines
+    }
+
+    /// Write the summary to `path`; returns how many fields and traces it holds.
+    pub fn write(&self, path: &str) -> io::Result<usize> {
+        let text = match format_of(path)? {
+            ExportFormat::Csv => {
+                let mut out = String::from("kind,name,size,mean,variance,min,max,value\n");
+                for f in &self.fields {
+                    out.push_str(&format!("field,{},{},{},{},{},{},\n", f.name, f.size, f.mean, f.variance, f.min, f.max));
+                }
+                for (name, value) in &self.traces {
+                    out.push_str(&format!("trace,{},,,,,,{}\n", name, value));
+                }
+                out
+            }
+            ExportFormat::Json => serde_json::to_string_pretty(self).map_err(io::Error::other)?,
+        };
+        write(path, text)?;
+        Ok(self.fields.len() + self.traces.len())
+    }
+}
+
+/// Run a `report` statement: print `summary`, and write it to `path` if given.
+pub fn report(summary: &RunSummary, path: Option<&str>) {
+    for line in summary.lines() {
+        say!("{}", line);
+    }
+    if let Some(path) = path {
+        match summary.write(path) {
+            Ok(count) => say!("💾 Wrote report to {} ({} entries)", path, count),
+            Err(e) => warn!("⚠️ Report to {} failed: {}", path, e),
+        }
+    }
+}
+
+/// Write `text` to `path`, creating its directory.
+fn write(path: &str, text: String) -> io::Result<()> {
+    if let Some(dir) = Path::new(path).parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, text)
+}
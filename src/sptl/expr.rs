@@ -0,0 +1,371 @@
+//! Arithmetic expressions: elementwise field expressions such as
+//! `field C = A + 0.5 * B`, and the numbers written in statement options
+//! such as `alpha: a*2`.
+//!
+//! Operands are field names, variable names or numbers; scalars broadcast
+//! over fields, and fields combined with each other must have the same size.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// `F` names a field or variable: a `String` as parsed, or a slot once compiled.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldExpr<F = String> {
+    Field(F),
+    Scalar(f64),
+    Neg(Box<FieldExpr<F>>),
+    Binary(BinOp, Box<FieldExpr<F>>, Box<FieldExpr<F>>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl BinOp {
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "+" => Some(BinOp::Add),
+            "-" => Some(BinOp::Sub),
+            "*" => Some(BinOp::Mul),
+            "/" => Some(BinOp::Div),
+            _ => None,
+        }
+    }
+
+    /// Binding strength; `*` and `/` bind tighter than `+` and `-`.
+    pub fn precedence(self) -> u8 {
+        match self {
+            BinOp::Add | BinOp::Sub => 1,
+            BinOp::Mul | BinOp::Div => 2,
+        }
+    }
+
+    fn apply(self, a: f64, b: f64) -> f64 {
+        match self {
+            BinOp::Add => a + b,
+            BinOp::Sub => a - b,
+            BinOp::Mul => a * b,
+            BinOp::Div => a / b,
+        }
+    }
+}
+
+impl fmt::Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+        };
+        f.write_str(s)
+    }
+}
+
+/// A numeric statement option: a literal, a variable, or arithmetic over them.
+pub type Expr = FieldExpr<String>;
+
+impl Expr {
+    /// The value of a bare number literal.
+    pub fn as_literal(&self) -> Option<f64> {
+        match self {
+            FieldExpr::Scalar(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+impl<F> From<f64> for FieldExpr<F> {
+    fn from(v: f64) -> Self {
+        FieldExpr::Scalar(v)
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldExpr::Field(name) => f.write_str(name),
+            FieldExpr::Scalar(v) => write!(f, "{}", v),
+            FieldExpr::Neg(e) => write!(f, "-({})", e),
+            FieldExpr::Binary(op, a, b) => write!(f, "({} {} {})", a, op, b),
+        }
+    }
+}
+
+/// Serde form of `Expr`. In human-readable formats a number or a name stands
+/// for itself (`"alpha": 0.3`, `"alpha": "a"`); compound expressions and every
+/// node in binary formats use the tagged variants.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "FieldExpr")]
+enum Tagged {
+    Field(String),
+    Scalar(f64),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Serialize)]
+#[serde(rename = "FieldExpr")]
+enum TaggedRef<'a> {
+    Field(&'a String),
+    Scalar(f64),
+    Neg(&'a Expr),
+    Binary(BinOp, &'a Expr, &'a Expr),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Readable {
+    Scalar(f64),
+    Field(String),
+    Tagged(Tagged),
+}
+
+impl From<Tagged> for Expr {
+    fn from(t: Tagged) -> Self {
+        match t {
+            Tagged::Field(f) => FieldExpr::Field(f),
+            Tagged::Scalar(v) => FieldExpr::Scalar(v),
+            Tagged::Neg(e) => FieldExpr::Neg(e),
+            Tagged::Binary(op, a, b) => FieldExpr::Binary(op, a, b),
+        }
+    }
+}
+
+impl Serialize for Expr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            FieldExpr::Scalar(v) if serializer.is_human_readable() => serializer.serialize_f64(*v),
+            FieldExpr::Field(f) if serializer.is_human_readable() => serializer.serialize_str(f),
+            FieldExpr::Field(f) => TaggedRef::Field(f).serialize(serializer),
+            FieldExpr::Scalar(v) => TaggedRef::Scalar(*v).serialize(serializer),
+            FieldExpr::Neg(e) => TaggedRef::Neg(e).serialize(serializer),
+            FieldExpr::Binary(op, a, b) => TaggedRef::Binary(*op, a, b).serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Expr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if !deserializer.is_human_readable() {
+            return Tagged::deserialize(deserializer).map(Expr::from);
+        }
+        Ok(match Readable::deserialize(deserializer)? {
+            Readable::Scalar(v) => FieldExpr::Scalar(v),
+            Readable::Field(f) => FieldExpr::Field(f),
+            Readable::Tagged(t) => t.into(),
+        })
+    }
+}
+
+/// Result of evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Scalar(f64),
+    Vector(Vec<f64>),
+}
+
+impl<F> FieldExpr<F> {
+    /// Every field operand, left to right.
+    pub fn fields(&self) -> Vec<&F> {
+        match self {
+            FieldExpr::Field(f) => vec![f],
+            FieldExpr::Scalar(_) => Vec::new(),
+            FieldExpr::Neg(e) => e.fields(),
+            FieldExpr::Binary(_, a, b) => {
+                let mut out = a.fields();
+                out.extend(b.fields());
+                out
+            }
+        }
+    }
+
+    /// Replace field names, e.g. with slot indices. Fails with the first unresolved name.
+    pub fn resolve<G>(self, lookup: &mut impl FnMut(&F) -> Option<G>) -> Result<FieldExpr<G>, F> {
+        Ok(match self {
+            FieldExpr::Field(f) => match lookup(&f) {
+                Some(g) => FieldExpr::Field(g),
+                None => return Err(f),
+            },
+            FieldExpr::Scalar(v) => FieldExpr::Scalar(v),
+            FieldExpr::Neg(e) => FieldExpr::Neg(Box::new(e.resolve(lookup)?)),
+            FieldExpr::Binary(op, a, b) => {
+                FieldExpr::Binary(op, Box::new(a.resolve(lookup)?), Box::new(b.resolve(lookup)?))
+            }
+        })
+    }
+
+    /// Size of the result given each operand's size (`Some(None)` for scalar
+    /// variables), checking that combined fields agree. `Ok(None)` means the
+    /// expression is a scalar.
+    pub fn size(&self, size_of: &impl Fn(&F) -> Option<Option<usize>>) -> Result<Option<usize>, String>
+    where
+        F: fmt::Display,
+    {
+        match self {
+            FieldExpr::Field(f) => size_of(f).ok_or_else(|| format!("unknown field or variable {}", f)),
+            FieldExpr::Scalar(_) => Ok(None),
+            FieldExpr::Neg(e) => e.size(size_of),
+            FieldExpr::Binary(op, a, b) => match (a.size(size_of)?, b.size(size_of)?) {
+                (Some(x), Some(y)) if x != y => Err(format!("dimension mismatch in {}: {} vs {}", op, x, y)),
+                (x, y) => Ok(x.or(y)),
+            },
+        }
+    }
+
+    /// Evaluate with every operand a number. Fails with the first operand
+    /// `value_of` has no value for.
+    pub fn scalar<'a>(&'a self, value_of: &impl Fn(&F) -> Option<f64>) -> Result<f64, &'a F> {
+        match self {
+            FieldExpr::Field(f) => value_of(f).ok_or(f),
+            FieldExpr::Scalar(v) => Ok(*v),
+            FieldExpr::Neg(e) => Ok(-e.scalar(value_of)?),
+            FieldExpr::Binary(op, a, b) => Ok(op.apply(a.scalar(value_of)?, b.scalar(value_of)?)),
+        }
+    }
+
+    pub fn eval(&self, value_of: &impl Fn(&F) -> Option<Value>) -> Result<Value, String>
+    where
+        F: fmt::Display,
+    {
+        match self {
+            FieldExpr::Field(f) => value_of(f).ok_or_else(|| format!("unknown field or variable {}", f)),
+            FieldExpr::Scalar(v) => Ok(Value::Scalar(*v)),
+            FieldExpr::Neg(e) => Ok(match e.eval(value_of)? {
+                Value::Scalar(v) => Value::Scalar(-v),
+                Value::Vector(v) => Value::Vector(v.into_iter().map(|x| -x).collect()),
+            }),
+            FieldExpr::Binary(op, a, b) => Ok(match (a.eval(value_of)?, b.eval(value_of)?) {
+                (Value::Scalar(x), Value::Scalar(y)) => Value::Scalar(op.apply(x, y)),
+                (Value::Vector(v), Value::Scalar(y)) => Value::Vector(v.into_iter().map(|x| op.apply(x, y)).collect()),
+                (Value::Scalar(x), Value::Vector(v)) => Value::Vector(v.into_iter().map(|y| op.apply(x, y)).collect()),
+                (Value::Vector(v), Value::Vector(w)) => {
+                    if v.len() != w.len() {
+                        return Err(format!("dimension mismatch in {}: {} vs {}", op, v.len(), w.len()));
+                    }
+                    Value::Vector(v.into_iter().zip(w).map(|(x, y)| op.apply(x, y)).collect())
+                }
+            }),
+        }
+    }
+}
+
+/// Parse an expression starting at `tokens[*cursor]`. Operators and
+/// parentheses need no surrounding spaces (`a*2`, `(a+b)/2`); the expression
+/// ends at the first operand not followed by an operator, which must also be
+/// the end of a token.
+pub fn parse_expr<T: AsRef<str>>(tokens: &[T], cursor: &mut usize) -> Option<FieldExpr> {
+    let mut lexer = Lexer { tokens, cursor: *cursor, pending: Vec::new(), fresh: false };
+    let expr = parse_binary(&mut lexer, 1)?;
+    if !lexer.pending.is_empty() {
+        if !lexer.fresh {
+            return None;
+        }
+        // The next token was only looked at; leave it to the caller.
+        lexer.cursor -= 1;
+    }
+    *cursor = lexer.cursor;
+    Some(expr)
+}
+
+/// Parse a whole string, such as an option value, as one expression.
+pub fn parse_str(text: &str) -> Option<FieldExpr> {
+    let tokens: Vec<String> = text.split_whitespace().map(|t| t.to_string()).collect();
+    let mut cursor = 0;
+    let expr = parse_expr(&tokens, &mut cursor)?;
+    (cursor == tokens.len()).then_some(expr)
+}
+
+/// Splits tokens into operands, operators and parentheses one token at a time.
+struct Lexer<'a, T> {
+    tokens: &'a [T],
+    /// Next token to split.
+    cursor: usize,
+    /// Unread pieces of the last split token, in reverse order.
+    pending: Vec<String>,
+    /// Whether no piece of the last split token has been read yet.
+    fresh: bool,
+}
+
+impl<T: AsRef<str>> Lexer<'_, T> {
+    fn peek(&mut self) -> Option<&str> {
+        if self.pending.is_empty() {
+            let token = self.tokens.get(self.cursor)?;
+            self.cursor += 1;
+            self.pending = split_token(token.as_ref());
+            self.pending.reverse();
+            self.fresh = true;
+        }
+        self.pending.last().map(|s| s.as_str())
+    }
+
+    fn next(&mut self) -> Option<String> {
+        self.peek()?;
+        self.fresh = false;
+        self.pending.pop()
+    }
+}
+
+/// `a*(2+b)` → `a`, `*`, `(`, `2`, `+`, `b`, `)`. A sign after a number's
+/// exponent (`1e-4`) stays part of the number.
+fn split_token(token: &str) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    for c in token.chars() {
+        let exponent_sign = matches!(c, '+' | '-')
+            && (current.ends_with('e') || current.ends_with('E'))
+            && current.starts_with(|d: char| d.is_ascii_digit() || d == '.');
+        if "+-*/()".contains(c) && !exponent_sign {
+            if !current.is_empty() {
+                pieces.push(std::mem::take(&mut current));
+            }
+            pieces.push(c.to_string());
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        pieces.push(current);
+    }
+    pieces
+}
+
+fn parse_binary<T: AsRef<str>>(lexer: &mut Lexer<T>, min_prec: u8) -> Option<FieldExpr> {
+    let mut lhs = parse_operand(lexer)?;
+    while let Some(op) = lexer.peek().and_then(BinOp::from_token) {
+        if op.precedence() < min_prec {
+            break;
+        }
+        lexer.next();
+        let rhs = parse_binary(lexer, op.precedence() + 1)?;
+        lhs = FieldExpr::Binary(op, Box::new(lhs), Box::new(rhs));
+    }
+    Some(lhs)
+}
+
+fn parse_operand<T: AsRef<str>>(lexer: &mut Lexer<T>) -> Option<FieldExpr> {
+    let token = lexer.next()?;
+    match token.as_str() {
+        "(" => {
+            let inner = parse_binary(lexer, 1)?;
+            if lexer.next()? != ")" {
+                return None;
+            }
+            Some(inner)
+        }
+        // A negated number is a literal, so `value -0.1` reads as one.
+        "-" => match parse_operand(lexer)? {
+            FieldExpr::Scalar(v) => Some(FieldExpr::Scalar(-v)),
+            operand => Some(FieldExpr::Neg(Box::new(operand))),
+        },
+        t => match t.parse::<f64>() {
+            Ok(v) => Some(FieldExpr::Scalar(v)),
+            Err(_) if BinOp::from_token(t).is_none() && t != ")" => Some(FieldExpr::Field(t.to_string())),
+            Err(_) => None,
+        },
+    }
+}
@@ -0,0 +1,351 @@
+//! `sptl fmt`: print a parsed program back as canonical SPTL source.
+//!
+//! One statement per line, blocks indented by four spaces, options written
+//! `{ key: value, ... }` in a fixed order and expressions with only the
+//! parentheses they need. Parsing the output gives the same program, so a
+//! script's formatted form also shows how the parser understood it.
+//!
+//! Formatting works on the parsed program: comments are dropped, procs
+//! appear expanded at their call sites and `include`s stay as written.
+
+use super::encode::Encoding;
+use super::expr::{BinOp, Expr, FieldExpr};
+use super::generate::Generator;
+use super::{Comparison, Metric, RecordKind, Statement, DEFAULT_STEER_STEPS};
+use crate::condition::Condition;
+use crate::ontology::Ontology;
+use crate::recursion::RecursionLevel;
+use std::fmt::Write;
+
+const INDENT: &str = "    ";
+
+/// Canonical source of `program`. Levels defined by `ontology` are written
+/// by name.
+pub fn format_program(program: &[Statement], ontology: Option<&Ontology>) -> String {
+    let mut out = String::new();
+    for statement in program {
+        write_statement(&mut out, statement, 0, ontology);
+    }
+    out
+}
+
+fn write_statement(out: &mut String, statement: &Statement, depth: usize, ontology: Option<&Ontology>) {
+    out.push_str(&INDENT.repeat(depth));
+    // Writing to a `String` cannot fail.
+    let _ = write_source(out, statement, depth, ontology);
+    out.push('\n');
+}
+
+fn write_source(out: &mut String, statement: &Statement, depth: usize, ontology: Option<&Ontology>) -> std::fmt::Result {
+    match statement {
+        Statement::Field { name, size, doc } => {
+            write!(out, "field {} {}", name, expr_source(size))?;
+            write_doc(out, doc)
+        }
+        Statement::DeriveField { name, expr } => write!(out, "field {} = {}", name, expr_source(expr)),
+        Statement::SliceField { name, source, start, end } => write!(out, "field {} = {}[{}..{}]", name, source, start, end),
+        Statement::ConcatFields { name, parts } => write!(out, "field {} = concat({})", name, parts.join(", ")),
+        Statement::FieldFromCheckpoint { name, path } => write!(out, "field {} from checkpoint {}", name, quote(path)),
+        Statement::Interpretation { name, values, doc } => {
+            let values: Vec<String> = values.iter().map(expr_source).collect();
+            write!(out, "interpretation {} = [{}]", name, values.join(", "))?;
+            write_doc(out, doc)
+        }
+        Statement::GenerateInterpretation { name, generator } => {
+            let mut options = vec![format!("size: {}", generator.size())];
+            match generator {
+                Generator::Random { low, high, seed, .. } => {
+                    options.push(format!("low: {}", expr_source(low)));
+                    options.push(format!("high: {}", expr_source(high)));
+                    options.extend(seed.map(|s| format!("seed: {}", s)));
+                }
+                Generator::Gaussian { mean, std, seed, .. } => {
+                    options.push(format!("mean: {}", expr_source(mean)));
+                    options.push(format!("std: {}", expr_source(std)));
+                    options.extend(seed.map(|s| format!("seed: {}", s)));
+                }
+                Generator::OneHot { index, .. } => options.push(format!("index: {}", index)),
+                Generator::Sine { periods, amplitude, phase, .. } => {
+                    options.push(format!("periods: {}", expr_source(periods)));
+                    options.push(format!("amplitude: {}", expr_source(amplitude)));
+                    options.push(format!("phase: {}", expr_source(phase)));
+                }
+            }
+            write!(out, "interpretation {} = {}({})", name, generator.name(), options.join(", "))
+        }
+        Statement::Project { target, interp, alpha, noise, steps, tolerance, until, record, log_every } => {
+            let mut options = vec![format!("alpha: {}", expr_source(alpha)), format!("noise: {}", expr_source(noise))];
+            match (until, steps) {
+                (Some(until), steps) => {
+                    options.push(format!("until: dist < {}", expr_source(until)));
+                    options.extend(steps.as_ref().map(|n| format!("max_steps: {}", expr_source(n))));
+                }
+                (None, Some(n)) => options.push(format!("steps: {}", expr_source(n))),
+                (None, None) => {
+                    options.push("steps: auto".to_string());
+                    options.push(format!("tolerance: {}", expr_source(tolerance)));
+                }
+            }
+            options.extend(log_every.map(|n| format!("log_every: {}", n)));
+            write!(out, "project {} <- {} {{ {} }}", target, interp, options.join(", "))?;
+            match record {
+                Some(recording) => {
+                    let kind = match recording.kind {
+                        RecordKind::Trajectory => "trajectory",
+                        RecordKind::Snapshots => "snapshots",
+                    };
+                    write!(out, " record {} every {} steps", kind, recording.every)
+                }
+                None => Ok(()),
+            }
+        }
+        Statement::ProjectMany { target, interps, alternate, alpha, noise, steps } => {
+            let weighted = interps.iter().any(|(_, w)| *w != 1.0);
+            let interps: Vec<String> = interps
+                .iter()
+                .map(|(name, w)| if weighted { format!("{}: {}", name, w) } else { name.clone() })
+                .collect();
+            let mut options = Vec::new();
+            if *alternate {
+                options.push("alternate".to_string());
+            }
+            options.push(format!("alpha: {}", expr_source(alpha)));
+            options.push(format!("noise: {}", expr_source(noise)));
+            options.push(format!("steps: {}", steps));
+            write!(out, "project {} <- [{}] {{ {} }}", target, interps.join(", "), options.join(", "))
+        }
+        Statement::Morph { target, from, to, alpha, noise, steps } => write!(
+            out,
+            "morph {} from {} to {} {{ alpha: {}, noise: {}, steps: {} }}",
+            target,
+            from,
+            to,
+            expr_source(alpha),
+            expr_source(noise),
+            steps
+        ),
+        Statement::TraceDistance { name, field, interp } => {
+            write!(out, "trace {} = trace_distance({}, {})", name, field, interp)
+        }
+        Statement::Let { name, metric, field, interp } => {
+            write!(out, "let {} = {}({}, {})", name, metric_name(*metric), field, interp)
+        }
+        Statement::Assign { name, value } => write!(out, "let {} = {}", name, expr_source(value)),
+        Statement::Seed(seed) => write!(out, "seed {}", seed),
+        Statement::Const { name, value } => write!(out, "const {} = {}", name, value),
+        Statement::Meaning { name, trace_cmp, threshold } => {
+            write!(out, "meaning {} = below({}, {})", name, trace_cmp, expr_source(threshold))
+        }
+        Statement::Assert { trace, cmp, bound, tolerance } => {
+            write!(out, "assert trace {} {} {}", trace, comparison(*cmp), expr_source(bound))?;
+            if *tolerance != 0.0 {
+                write!(out, " within {}", tolerance)?;
+            }
+            Ok(())
+        }
+        Statement::NarrateReturn { tokens } => {
+            let strings: Vec<String> = tokens.iter().map(|t| quote(t)).collect();
+            write!(out, "narratereturn {}", strings.join(" "))
+        }
+        Statement::LogCoherence(field) => write!(out, "logcoherence {}", field),
+        Statement::LogMeaning(name) => write!(out, "logmeaning {}", name),
+        Statement::ExpressSymbol { token, into_field, encoding, weight, blend } => {
+            write!(out, "expresssymbol {} into {}", quote(token), into_field)?;
+            let mut options = Vec::new();
+            if *encoding != Encoding::Bits {
+                options.push(format!("encoding: {}", encoding.name()));
+            }
+            if weight.as_literal() != Some(1.0) {
+                options.push(format!("weight: {}", expr_source(weight)));
+            }
+            if *blend {
+                options.push("blend".to_string());
+            }
+            if options.is_empty() {
+                return Ok(());
+            }
+            write!(out, " {{ {} }}", options.join(", "))
+        }
+        Statement::Modulate { token, intensity } => {
+            write!(out, "modulate {} intensity {}", quote(token), expr_source(intensity))
+        }
+        Statement::Level { level, name, body } => {
+            write!(out, "level {} {} ", level_source(*level, ontology), name)?;
+            write_block(out, body, depth, ontology)
+        }
+        Statement::Steer { field, interp, target, settings } => {
+            write!(
+                out,
+                "steer {} toward {} keep {} {} {} using alpha in [{}, {}]",
+                field,
+                interp,
+                metric_name(settings.metric),
+                comparison(settings.keep),
+                expr_source(target),
+                settings.alpha_min,
+                settings.alpha_max
+            )?;
+            if settings.steps != DEFAULT_STEER_STEPS {
+                write!(out, " for {} steps", settings.steps)?;
+            }
+            Ok(())
+        }
+        Statement::Perturb { field, amplitude } => write!(out, "perturb {} noise {}", field, expr_source(amplitude)),
+        Statement::Shock { field, start, end, value } => {
+            write!(out, "shock {} indices [{}..{}] value {}", field, start, end, expr_source(value))
+        }
+        Statement::AddFields { left, right, into } => write!(out, "add {} {} into {}", left, right, into),
+        Statement::Scale { field, factor } => write!(out, "scale {} {}", field, expr_source(factor)),
+        Statement::Decay { field, rate, steps } => {
+            write!(out, "decay {} {}", field, expr_source(rate))?;
+            if *steps != 1 {
+                write!(out, " steps {}", steps)?;
+            }
+            Ok(())
+        }
+        Statement::Normalize { field } => write!(out, "normalize {}", field),
+        Statement::Repeat { count, body } => {
+            write!(out, "repeat {} ", count)?;
+            write_block(out, body, depth, ontology)
+        }
+        Statement::If { condition, then, otherwise } => write_if(out, condition, then, otherwise, depth, ontology),
+        Statement::Snapshot { field, name } => write!(out, "snapshot {} as {}", field, name),
+        Statement::Export { kind, name, path } => write!(out, "export {} {} to {}", kind, name, quote(path)),
+        Statement::Report { path: None } => write!(out, "report"),
+        Statement::Report { path: Some(path) } => write!(out, "report to {}", quote(path)),
+        Statement::Include(path) => write!(out, "include {}", quote(path)),
+        Statement::Run { path, sharing, .. } => {
+            write!(out, "run {}", quote(path))?;
+            if !sharing.is_empty() {
+                let sharing: Vec<&str> = sharing.iter().map(|s| s.name()).collect();
+                write!(out, " sharing {}", sharing.join(", "))?;
+            }
+            Ok(())
+        }
+        Statement::Require(capability) => write!(out, "require {}", capability),
+    }
+}
+
+/// `if ... { } else if ... { } else { }`, an `else` holding only an `if` written as `else if`.
+fn write_if(
+    out: &mut String,
+    condition: &Condition,
+    then: &[Statement],
+    otherwise: &[Statement],
+    depth: usize,
+    ontology: Option<&Ontology>,
+) -> std::fmt::Result {
+    write!(out, "if {} ", condition_source(condition))?;
+    write_block(out, then, depth, ontology)?;
+    match otherwise {
+        [] => Ok(()),
+        [Statement::If { condition, then, otherwise }] => {
+            out.push_str(" else ");
+            write_if(out, condition, then, otherwise, depth, ontology)
+        }
+        body => {
+            out.push_str(" else ");
+            write_block(out, body, depth, ontology)
+        }
+    }
+}
+
+/// `{`, the body one level deeper, and `}` at `depth`, without a final newline.
+fn write_block(out: &mut String, body: &[Statement], depth: usize, ontology: Option<&Ontology>) -> std::fmt::Result {
+    out.push_str("{\n");
+    for statement in body {
+        write_statement(out, statement, depth + 1, ontology);
+    }
+    out.push_str(&INDENT.repeat(depth));
+    out.push('}');
+    Ok(())
+}
+
+fn metric_name(metric: Metric) -> &'static str {
+    match metric {
+        Metric::Distance => "trace_distance",
+        Metric::Coherence => "coherence",
+        Metric::Manhattan => "manhattan",
+        Metric::Rmse => "rmse",
+    }
+}
+
+fn comparison(cmp: Comparison) -> &'static str {
+    match cmp {
+        Comparison::Greater => ">",
+        Comparison::Less => "<",
+    }
+}
+
+fn level_source(level: RecursionLevel, ontology: Option<&Ontology>) -> String {
+    match ontology.and_then(|o| o.spec(level)) {
+        Some(spec) => spec.name.clone(),
+        None => level.to_string().to_lowercase(),
+    }
+}
+
+/// A condition as written in source, its expressions as `expr_source` writes them.
+pub fn condition_source(condition: &Condition) -> String {
+    condition.source_with(&|single| match single {
+        Condition::Compare { left, cmp, right } => format!("{} {} {}", expr_source(left), cmp.symbol(), expr_source(right)),
+        Condition::Activation { agent, token, cmp, right } => {
+            format!("{} activation {} {} {}", agent, token, cmp.symbol(), expr_source(right))
+        }
+        other => other.to_string(),
+    })
+}
+
+/// An expression with parentheses only where precedence needs them; operators
+/// of equal precedence group to the left.
+pub fn expr_source(expr: &Expr) -> String {
+    match expr {
+        FieldExpr::Field(name) => name.clone(),
+        FieldExpr::Scalar(v) => v.to_string(),
+        FieldExpr::Neg(inner) => match **inner {
+            FieldExpr::Binary(..) => format!("-({})", expr_source(inner)),
+            _ => format!("-{}", expr_source(inner)),
+        },
+        FieldExpr::Binary(op, left, right) => {
+            let left = operand_source(left, *op, false);
+            let right = operand_source(right, *op, true);
+            format!("{} {} {}", left, op, right)
+        }
+    }
+}
+
+fn operand_source(operand: &Expr, parent: BinOp, right: bool) -> String {
+    let text = expr_source(operand);
+    match operand {
+        FieldExpr::Binary(op, ..)
+            if op.precedence() < parent.precedence() || (right && op.precedence() == parent.precedence()) =>
+        {
+            format!("({})", text)
+        }
+        _ => text,
+    }
+}
+
+/// A string literal the lexer reads back as `text`.
+/// ` "doc"` after a declaration that has one.
+fn write_doc(out: &mut String, doc: &Option<String>) -> std::fmt::Result {
+    match doc {
+        Some(doc) => write!(out, " {}", quote(doc)),
+        None => Ok(()),
+    }
+}
+
+fn quote(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,130 @@
+//! Interpretations computed from a few numbers instead of written out:
+//! `interpretation seed = gaussian(size: 128, mean: 0, std: 1, seed: 7)`.
+//!
+//! Values are generated when the statement runs, so options may use
+//! variables. Random generators with a `seed` always give the same values;
+//! without one they are seeded from the run's noise RNG (`seed <n>`).
+
+use super::expr::FieldExpr;
+use crate::rng::NoiseRng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::TAU;
+
+/// Generator names as written in scripts.
+pub const GENERATOR_NAMES: [&str; 4] = ["random", "gaussian", "one_hot", "sine"];
+
+/// A generated interpretation of `size` values, with options over variables named by `F`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(serialize = "FieldExpr<F>: Serialize", deserialize = "FieldExpr<F>: Deserialize<'de>"))]
+pub enum Generator<F = String> {
+    /// `random(size: N, low: 0, high: 1, seed: S)`: uniform between `low` and `high`.
+    Random { size: usize, low: FieldExpr<F>, high: FieldExpr<F>, seed: Option<u64> },
+    /// `gaussian(size: N, mean: 0, std: 1, seed: S)`: normally distributed.
+    Gaussian { size: usize, mean: FieldExpr<F>, std: FieldExpr<F>, seed: Option<u64> },
+    /// `one_hot(size: N, index: K)`: 1 at `index`, 0 elsewhere.
+    OneHot { size: usize, index: usize },
+    /// `sine(size: N, periods: 1, amplitude: 1, phase: 0)`: `periods` full
+    /// waves across the values, starting at `phase` radians.
+    Sine { size: usize, periods: FieldExpr<F>, amplitude: FieldExpr<F>, phase: FieldExpr<F> },
+}
+
+impl<F> Generator<F> {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Generator::Random { .. } => "random",
+            Generator::Gaussian { .. } => "gaussian",
+            Generator::OneHot { .. } => "one_hot",
+            Generator::Sine { .. } => "sine",
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            Generator::Random { size, .. }
+            | Generator::Gaussian { size, .. }
+            | Generator::OneHot { size, .. }
+            | Generator::Sine { size, .. } => *size,
+        }
+    }
+
+    /// Whether the values can be generated at all: a size of at least 1,
+    /// and for `one_hot` an index within it.
+    pub fn check(&self) -> Result<(), String> {
+        match self {
+            _ if self.size() == 0 => Err(format!("{}: option `size` must be at least 1", self.name())),
+            Generator::OneHot { size, index } if index >= size => {
+                Err(format!("one_hot: index {} is out of range for size {}", index, size))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Numeric options, in the order they are written.
+    pub fn numbers(&self) -> Vec<&FieldExpr<F>> {
+        match self {
+            Generator::Random { low, high, .. } => vec![low, high],
+            Generator::Gaussian { mean, std, .. } => vec![mean, std],
+            Generator::OneHot { .. } => Vec::new(),
+            Generator::Sine { periods, amplitude, phase, .. } => vec![periods, amplitude, phase],
+        }
+    }
+
+    /// Replace variable names, e.g. with slot indices. Fails with the first unresolved name.
+    pub fn resolve<G>(self, lookup: &mut impl FnMut(&F) -> Option<G>) -> Result<Generator<G>, F> {
+        Ok(match self {
+            Generator::Random { size, low, high, seed } => {
+                Generator::Random { size, low: low.resolve(lookup)?, high: high.resolve(lookup)?, seed }
+            }
+            Generator::Gaussian { size, mean, std, seed } => {
+                Generator::Gaussian { size, mean: mean.resolve(lookup)?, std: std.resolve(lookup)?, seed }
+            }
+            Generator::OneHot { size, index } => Generator::OneHot { size, index },
+            Generator::Sine { size, periods, amplitude, phase } => Generator::Sine {
+                size,
+                periods: periods.resolve(lookup)?,
+                amplitude: amplitude.resolve(lookup)?,
+                phase: phase.resolve(lookup)?,
+            },
+        })
+    }
+
+    /// The values, with options evaluated against the current variables;
+    /// fails with the first variable that has no value.
+    pub fn generate(&self, value_of: &impl Fn(&F) -> Option<f64>, rng: &mut NoiseRng) -> Result<Vec<f64>, &F> {
+        Ok(match self {
+            Generator::Random { size, low, high, seed } => {
+                let (low, high) = (low.scalar(value_of)?, high.scalar(value_of)?);
+                let (low, high) = (low.min(high), low.max(high));
+                let mut rng = own_rng(*seed, rng);
+                (0..*size).map(|_| rng.gen_range(low..=high)).collect()
+            }
+            Generator::Gaussian { size, mean, std, seed } => {
+                let (mean, std) = (mean.scalar(value_of)?, std.scalar(value_of)?);
+                let mut rng = own_rng(*seed, rng);
+                (0..*size).map(|_| mean + std * standard_normal(&mut rng)).collect()
+            }
+            Generator::OneHot { size, index } => (0..*size).map(|i| if i == *index { 1.0 } else { 0.0 }).collect(),
+            Generator::Sine { size, periods, amplitude, phase } => {
+                let (periods, amplitude, phase) =
+                    (periods.scalar(value_of)?, amplitude.scalar(value_of)?, phase.scalar(value_of)?);
+                let n = *size as f64;
+                (0..*size).map(|i| amplitude * (TAU * periods * i as f64 / n + phase).sin()).collect()
+            }
+        })
+    }
+}
+
+/// The generator's own RNG: seeded by `seed`, or else by one draw from the run's.
+fn own_rng(seed: Option<u64>, rng: &mut NoiseRng) -> StdRng {
+    StdRng::seed_from_u64(seed.unwrap_or_else(|| rng.at("generate").gen()))
+}
+
+/// One sample of N(0, 1) by the Box–Muller transform.
+fn standard_normal(rng: &mut StdRng) -> f64 {
+    // `gen` is in [0, 1); the logarithm needs (0, 1].
+    let u = 1.0 - rng.gen::<f64>();
+    let v: f64 = rng.gen();
+    (-2.0 * u.ln()).sqrt() * (TAU * v).cos()
+}
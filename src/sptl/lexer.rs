@@ -0,0 +1,207 @@
+//! Character-level lexer for SPTL source.
+//!
+//! Splits source into identifiers, numbers, strings and punctuation, so
+//! `alpha:0.3`, `[1,2,3]` and `trace_distance(F,I)` need no spaces. Comments
+//! (`# ...`, `// ...` and `/* ... */`, which may span lines) are skipped; `#`
+//! only starts a comment at the beginning of a token, and nothing does inside
+//! a string.
+
+use super::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// `psi`, `trace_distance`, `$alpha`, `seed.sptl`
+    Ident,
+    /// `20`, `0.3`, `-1`, `1e-4`
+    Number,
+    /// `"the field converged"`, quotes and escapes kept; see `unquote`.
+    Str,
+    /// `{`, `:`, `<-`, `..`, `*`, ...
+    Punct,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    pub span: Span,
+}
+
+impl Token {
+    /// Whether `next` follows this token with no space in between, as the
+    /// pieces of `lib/seed.sptl` or `3..10` do.
+    pub fn touches(&self, next: &Token) -> bool {
+        next.span.line == self.span.line && next.span.column == self.span.column + self.text.chars().count()
+    }
+}
+
+impl AsRef<str> for Token {
+    fn as_ref(&self) -> &str {
+        &self.text
+    }
+}
+
+/// Punctuation of two characters, matched before single ones.
+const PAIRS: &[&str] = &["<-", "->", "..", "<=", ">=", "==", "!="];
+
+/// Lex a whole source text.
+pub fn lex(source: &str) -> Vec<Token> {
+    lex_source(source).0
+}
+
+/// Lex a whole source text, with the position of the `/*` of a block
+/// comment still open at its end: everything after it is blank.
+pub fn lex_source(source: &str) -> (Vec<Token>, Option<Span>) {
+    let mut tokens = Vec::new();
+    let mut open_comment = None;
+    for (i, line) in source.lines().enumerate() {
+        let chars: Vec<char> = strip_comments(line, i + 1, &mut open_comment).chars().collect();
+        lex_line(&chars, i + 1, &mut tokens);
+    }
+    (tokens, open_comment)
+}
+
+fn lex_line(chars: &[char], line: usize, tokens: &mut Vec<Token>) {
+    let mut at = 0;
+    while at < chars.len() {
+        let c = chars[at];
+        if c.is_whitespace() {
+            at += 1;
+            continue;
+        }
+        let start = at;
+        let next = chars.get(at + 1).copied();
+        // A minus sign belongs to a number unless it follows an operand, as in `a-1`.
+        let after_operand = start > 0 && (chars[start - 1].is_alphanumeric() || matches!(chars[start - 1], '_' | ')' | ']'));
+        let kind = if c == '"' {
+            at += 1;
+            while at < chars.len() && chars[at] != '"' {
+                at += if chars[at] == '\\' { 2 } else { 1 };
+            }
+            at = (at + 1).min(chars.len());
+            TokenKind::Str
+        } else if c.is_ascii_digit() || (c == '-' && !after_operand && next.is_some_and(|n| n.is_ascii_digit())) {
+            at = number_end(chars, at + 1);
+            TokenKind::Number
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            at += 1;
+            while at < chars.len()
+                && (chars[at].is_alphanumeric()
+                    || chars[at] == '_'
+                    || (chars[at] == '.' && chars.get(at + 1).is_some_and(|n| n.is_alphanumeric())))
+            {
+                at += 1;
+            }
+            TokenKind::Ident
+        } else {
+            let pair: String = chars[at..(at + 2).min(chars.len())].iter().collect();
+            at += if PAIRS.contains(&pair.as_str()) { 2 } else { 1 };
+            TokenKind::Punct
+        };
+        let text = chars[start..at].iter().collect();
+        tokens.push(Token { kind, text, span: Span { line, column: start + 1 } });
+    }
+}
+
+/// End of a number whose first character is before `at`: digits, a
+/// fraction (a `.` directly followed by a digit, so `3..10` is a range) and
+/// an exponent.
+fn number_end(chars: &[char], mut at: usize) -> usize {
+    let digit = |i: usize| chars.get(i).is_some_and(|c| c.is_ascii_digit());
+    while digit(at) {
+        at += 1;
+    }
+    if chars.get(at) == Some(&'.') && digit(at + 1) {
+        at += 1;
+        while digit(at) {
+            at += 1;
+        }
+    }
+    if matches!(chars.get(at), Some('e' | 'E')) {
+        let sign = usize::from(matches!(chars.get(at + 1), Some('+' | '-')));
+        if digit(at + 1 + sign) {
+            at += 1 + sign;
+            while digit(at) {
+                at += 1;
+            }
+        }
+    }
+    at
+}
+
+/// The text of a string token: `"a \"b\""` becomes `a "b"`. Escapes are `\"`,
+/// `\\`, `\n` and `\t`. `None` if the token is not a complete string literal.
+pub fn unquote(token: &str) -> Option<String> {
+    let inner = token.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                other => out.push(other),
+            },
+            '"' => return None,
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+/// Blank out the comment parts of line number `line`, keeping columns intact.
+/// `open_comment` carries where an unterminated `/* ... */` opened over to
+/// the next line.
+fn strip_comments(line_text: &str, line: usize, open_comment: &mut Option<Span>) -> String {
+    let chars: Vec<char> = line_text.chars().collect();
+    let mut out = String::with_capacity(line_text.len());
+    let mut in_string = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let pair = (chars[i], chars.get(i + 1).copied());
+        if in_string {
+            out.push(chars[i]);
+            match pair {
+                ('\\', Some(next)) => {
+                    out.push(next);
+                    i += 1;
+                }
+                ('"', _) => in_string = false,
+                _ => {}
+            }
+            i += 1;
+            continue;
+        }
+        if open_comment.is_some() {
+            if pair == ('*', Some('/')) {
+                *open_comment = None;
+                out.push_str("  ");
+                i += 2;
+            } else {
+                out.push(' ');
+                i += 1;
+            }
+            continue;
+        }
+        let token_start = i == 0 || chars[i - 1].is_whitespace();
+        match pair {
+            ('/', Some('*')) => {
+                *open_comment = Some(Span { line, column: i + 1 });
+                out.push_str("  ");
+                i += 2;
+            }
+            ('/', Some('/')) => break,
+            ('#', _) if token_start => break,
+            ('"', _) => {
+                in_string = true;
+                out.push('"');
+                i += 1;
+            }
+            (c, _) => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
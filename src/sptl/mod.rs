@@ -1,153 +1,1003 @@
-use std::collections::HashMap;
+pub mod build;
+pub mod cache;
+pub mod encode;
+pub mod export;
+pub mod expr;
+pub mod format;
+pub mod generate;
+pub mod lexer;
+pub mod optimize;
+pub mod vm;
+
+pub use crate::condition::Condition;
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::capability::Capability;
+use crate::config::Config;
+use encode::{bitstring, encode, Encoding, SymbolRegistry, ENCODING_NAMES};
+use export::ExportKind;
+use expr::{Expr, FieldExpr, Value};
+use generate::{Generator, GENERATOR_NAMES};
+use lexer::{Token, TokenKind};
+use crate::perturb::{parse_index_range, perturb, shock};
+use crate::rundir::Checkpoint;
+use crate::rng::NoiseRng;
+use crate::runtime::Runtime;
+use crate::console;
+use crate::{say, warn};
 use crate::substrate::Substrate;
 use crate::interpretation::Interpretation;
 use crate::projection::project;
-use crate::trace::{trace_distance, coherence};
+use crate::ontology::Ontology;
+use crate::recursion::{CategoryObject, RecursionLevel};
+use crate::events::{Event, ATTRACTOR_EPSILON};
+use crate::report::{Assertion, RunReport};
+use crate::trace::{coherence, manhattan, rmse, trace_distance};
 use crate::visualize::print_vector;
 
-#[derive(Debug)]
+/// Real-valued options (`alpha`, `noise`, amplitudes, thresholds, ...), field
+/// sizes, interpretation values and `project` step counts are `Expr`s over
+/// `let` variables, evaluated when the statement runs. Other sizes, step
+/// counts and indices stay literal so they can be checked before running.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Statement {
-    Field { name: String, size: usize },
-    Interpretation { name: String, values: Vec<f64> },
+    /// `field psi 128 "visual cortex analogue"`; the doc string is optional,
+    /// as is the word `size` before the size.
+    Field {
+        name: String,
+        size: Expr,
+        #[serde(default)]
+        doc: Option<String>,
+    },
+    /// `field C = A + 0.5 * B`
+    DeriveField { name: String, expr: FieldExpr },
+    /// `field psi2 = psi[0..8]`: a copy of elements `start..end` of `source`.
+    SliceField { name: String, source: String, start: usize, end: usize },
+    /// `field omega = concat(psi, chi)`: the elements of `parts`, one field after another.
+    ConcatFields { name: String, parts: Vec<String> },
+    /// `field psi from checkpoint "run1/checkpoints/psi.ckpt"`: restore one
+    /// field as an earlier run left it. A run directory stands for its
+    /// checkpoint of the same name.
+    FieldFromCheckpoint { name: String, path: String },
+    /// `interpretation seed = [1, 0, 1, 0] "the pattern to recover"`; the doc
+    /// string is optional. Bracketed values may be expressions, as in `[v, 1]`.
+    Interpretation {
+        name: String,
+        values: Vec<Expr>,
+        #[serde(default)]
+        doc: Option<String>,
+    },
+    /// `interpretation seed = gaussian(size: 128, std: 0.5)`: values computed
+    /// when the statement runs, see `generate`.
+    GenerateInterpretation { name: String, generator: Generator },
     Project {
         target: String,
         interp: String,
-        alpha: f64,
-        noise: f64,
-        steps: usize,
+        alpha: Expr,
+        noise: Expr,
+        /// `None` projects until the trace distance improves by less than `tolerance`.
+        /// With `until`, this is the step limit.
+        steps: Option<Expr>,
+        tolerance: Expr,
+        /// Stop once the trace distance falls below this value.
+        until: Option<Expr>,
+        /// `record trajectory every N steps` after the options block.
+        record: Option<Recording>,
+        /// `log_every: N`: print and journal the trace distance and coherence every N steps.
+        log_every: Option<usize>,
     },
+    /// `project psi <- [seed, rival] { alternate, alpha: 0.3, steps: 50 }`:
+    /// competing interpretations in one projection. With `alternate` each step
+    /// projects the next interpretation in turn; otherwise every step projects
+    /// their mix, weighted as in `[seed: 0.7, rival: 0.3]` (equally if not).
+    /// The distance to each is recorded as `<target>.<interp>.distance`.
+    ProjectMany { target: String, interps: Vec<(String, f64)>, alternate: bool, alpha: Expr, noise: Expr, steps: usize },
+    /// `morph psi from A to B { alpha: 0.3, steps: 50 }`: project a target
+    /// that drifts linearly from `A` to `B`, reaching `B` on the last step.
+    /// The final distance to `B` is recorded as `<target>.distance`.
+    Morph { target: String, from: String, to: String, alpha: Expr, noise: Expr, steps: usize },
+    /// `trace d = trace_distance(F, I)`; the result is also bound as variable `d`.
     TraceDistance { name: String, field: String, interp: String },
-    Meaning { name: String, trace_cmp: String, threshold: f64 },
+    /// `let d = trace_distance(F, I)`: bind a metric to a variable usable in
+    /// field expressions; also recorded as the trace `d`. A `trace` of any
+    /// other metric than the distance, such as `trace c = coherence(F, I)`,
+    /// parses to this too.
+    Let { name: String, metric: Metric, field: String, interp: String },
+    /// `let a = 0.3` or `let b = a * 2`: bind a number to a variable.
+    Assign { name: String, value: Expr },
+    /// `seed 42`: draw the noise of every later projection and perturbation
+    /// from an RNG seeded with this value, making the run reproducible.
+    Seed(u64),
+    /// `const ETA 0.25`: a variable that may not be reassigned.
+    Const { name: String, value: f64 },
+    /// `meaning calm = below(d, 0.5)`: holds while trace `d` is below the threshold.
+    /// The outcome is recorded in the report and bound as variable `calm`
+    /// (1 if it holds, 0 if not).
+    Meaning { name: String, trace_cmp: String, threshold: Expr },
+    /// `assert trace d < 0.5 within 1e-3`: check the latest value of a trace
+    /// against a bound, loosened by `tolerance`. The outcome is recorded in the
+    /// report; a failed assertion makes `sptl` exit with status 1.
+    Assert { trace: String, cmp: Comparison, bound: Expr, tolerance: f64 },
     NarrateReturn { tokens: Vec<String> },
     LogCoherence(String),
     LogMeaning(String),
-    ExpressSymbol { token: String, into_field: String },
-    Modulate { token: String, intensity: f64 },
+    /// `expresssymbol "fire" into psi { encoding: onehot, weight: 0.5, blend }`:
+    /// encode the token (see `encode`) and add it to the field, scaled by
+    /// `weight`; with `blend`, move the field the fraction `weight` toward it instead.
+    ExpressSymbol { token: String, into_field: String, encoding: Encoding, weight: Expr, blend: bool },
+    /// `modulate "fire" intensity 0.5`: scale what the token has activated,
+    /// and how strongly it is expressed or projected from now on.
+    Modulate { token: String, intensity: Expr },
+    Level { level: RecursionLevel, name: String, body: Vec<Statement> },
+    /// `steer F toward I keep coherence > 0.9 using alpha in [0.01, 0.5] [for 100 steps]`
+    Steer { field: String, interp: String, target: Expr, settings: SteerSettings },
+    /// `perturb F noise 0.5`
+    Perturb { field: String, amplitude: Expr },
+    /// `shock F indices [3..10] value 2.0`; `end` is exclusive.
+    Shock { field: String, start: usize, end: usize, value: Expr },
+    /// `add psi chi into omega`: element-wise sum, creating or replacing `into`.
+    AddFields { left: String, right: String, into: String },
+    /// `scale psi 0.5`
+    Scale { field: String, factor: Expr },
+    /// `decay psi 0.05 steps 10`: relax toward zero, removing the fraction
+    /// `rate` of every element on each step; one step without `steps`, and
+    /// the configured rate without one.
+    Decay { field: String, rate: Expr, steps: usize },
+    /// `normalize psi`: scale to unit Euclidean norm.
+    Normalize { field: String },
+    /// `repeat 10 { ... }`: run the body `count` times.
+    Repeat { count: usize, body: Vec<Statement> },
+    /// `if d < 0.1 { ... } else { ... }`; see `Condition` for what may be tested.
+    If { condition: Condition, then: Vec<Statement>, otherwise: Vec<Statement> },
+    /// `snapshot psi as psi_t0`: keep a copy of the field's current state as
+    /// the interpretation `psi_t0`, so later statements can compare against
+    /// it (`trace drift = distance(psi, psi_t0)`) or project back toward it.
+    Snapshot { field: String, name: String },
+    /// `export trace d to "d.csv"` or `export field psi to "psi.json"`.
+    Export { kind: ExportKind, name: String, path: String },
+    /// `report` or `report to "summary.json"`: print the mean, variance,
+    /// minimum and maximum of every field and the latest value of every
+    /// trace, and write them to `path` if given.
+    Report { path: Option<String> },
+    /// `include "common.sptl"` (or `import`): replaced by the statements of
+    /// that file when the script is loaded, see `resolve_includes`.
+    Include(String),
+    /// `run "other.sptl" sharing fields`: execute another script with
+    /// variables of its own, on this script's fields and interpretations
+    /// for the kinds it shares; see `run_script`. `body` is the other
+    /// script, loaded by `resolve_includes`.
+    Run {
+        path: String,
+        sharing: Vec<Shared>,
+        #[serde(default)]
+        body: Vec<Statement>,
+    },
+    /// `require gpu`: the script needs an optional subsystem, see `capability`.
+    Require(Capability),
+}
+
+impl Statement {
+    /// The numeric options of this statement that may read variables.
+    pub fn numbers(&self) -> Vec<&Expr> {
+        match self {
+            Statement::Field { size, .. } => vec![size],
+            Statement::Interpretation { values, .. } => values.iter().collect(),
+            Statement::Project { alpha, noise, steps, tolerance, until, .. } => {
+                let mut out = vec![alpha, noise];
+                out.extend(steps);
+                out.push(tolerance);
+                out.extend(until);
+                out
+            }
+            Statement::ProjectMany { alpha, noise, .. } | Statement::Morph { alpha, noise, .. } => vec![alpha, noise],
+            Statement::GenerateInterpretation { generator, .. } => generator.numbers(),
+            Statement::Assign { value, .. } | Statement::Shock { value, .. } => vec![value],
+            Statement::Meaning { threshold, .. } => vec![threshold],
+            Statement::Assert { bound, .. } => vec![bound],
+            Statement::Modulate { intensity, .. } => vec![intensity],
+            Statement::ExpressSymbol { weight, .. } => vec![weight],
+            Statement::Steer { target, .. } => vec![target],
+            Statement::If { condition, .. } => condition.numbers(),
+            Statement::Perturb { amplitude, .. } => vec![amplitude],
+            Statement::Scale { factor, .. } => vec![factor],
+            Statement::Decay { rate, .. } => vec![rate],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Check what a statement must satisfy to run, however it was written: the
+/// parser applies this to every statement it reads, and `json` to
+/// statements read from JSON. Nested bodies are checked too.
+pub fn validate(statement: &Statement) -> Result<(), String> {
+    match statement {
+        Statement::SliceField { start, end, .. } if start >= end => {
+            Err(format!("field: index range {}..{} is empty", start, end))
+        }
+        Statement::Field { size, .. } => literal_count(size, "field: size"),
+        Statement::GenerateInterpretation { generator, .. } => generator.check(),
+        Statement::Project { steps, record, log_every, .. } => {
+            if let Some(steps) = steps {
+                literal_count(steps, "project: option `steps`")?;
+            }
+            match (record, log_every) {
+                (_, Some(0)) => Err("project: option `log_every` expects at least 1".to_string()),
+                (Some(Recording { every: 0, .. }), _) => Err("recording interval must be at least 1".to_string()),
+                _ => Ok(()),
+            }
+        }
+        Statement::ProjectMany { interps, alternate, .. } => {
+            let weights: Vec<f64> = interps.iter().map(|(_, w)| *w).collect();
+            if interps.len() < 2 {
+                Err("project: a list of interpretations needs at least two".to_string())
+            } else if *alternate && weights.iter().any(|w| *w != 1.0) {
+                Err("project: weights apply to mixed projections, not `alternate`".to_string())
+            } else if !weights.iter().all(|w| w.is_finite() && *w >= 0.0) || weights.iter().sum::<f64>() <= 0.0 {
+                Err("project: weights must be non-negative and not all zero".to_string())
+            } else {
+                Ok(())
+            }
+        }
+        Statement::Steer { settings, .. }
+            if !(0.0..=1.0).contains(&settings.alpha_min) || !(settings.alpha_min..=1.0).contains(&settings.alpha_max) =>
+        {
+            Err("alpha bounds must satisfy 0 <= min <= max <= 1".to_string())
+        }
+        Statement::Level { body, .. } | Statement::Repeat { body, .. } | Statement::Run { body, .. } => {
+            body.iter().try_for_each(validate)
+        }
+        Statement::If { then, otherwise, .. } => then.iter().chain(otherwise).try_for_each(validate),
+        _ => Ok(()),
+    }
+}
+
+/// A size or step count written as a number must be a whole one; one
+/// computed from variables is checked when it is evaluated.
+fn literal_count(expr: &Expr, what: &str) -> Result<(), String> {
+    match expr.as_literal() {
+        Some(value) => whole_number(value, what).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+/// `value` as a size or step count: a whole number, not negative.
+fn whole_number(value: f64, what: &str) -> Result<usize, String> {
+    if value >= 0.0 && value.fract() == 0.0 && value <= usize::MAX as f64 {
+        Ok(value as usize)
+    } else {
+        Err(format!("{} must be a whole number, found {}", what, value))
+    }
+}
+
+/// Field–interpretation metric computed by `let`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Metric {
+    /// Euclidean distance (`trace_distance` or `distance`).
+    Distance,
+    /// Cosine similarity (`coherence`).
+    Coherence,
+    /// Sum of absolute differences (`manhattan`).
+    Manhattan,
+    /// Root mean squared difference (`rmse`).
+    Rmse,
+}
+
+/// Metric names accepted by `trace`, `let` and `steer`.
+pub const METRIC_NAMES: [&str; 5] = ["trace_distance", "distance", "coherence", "manhattan", "rmse"];
+
+impl Metric {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "trace_distance" | "distance" => Some(Metric::Distance),
+            "coherence" => Some(Metric::Coherence),
+            "manhattan" => Some(Metric::Manhattan),
+            "rmse" => Some(Metric::Rmse),
+            _ => None,
+        }
+    }
+
+    /// Short name, as used in telemetry such as `<field>.distance`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::Distance => "distance",
+            Metric::Coherence => "coherence",
+            Metric::Manhattan => "manhattan",
+            Metric::Rmse => "rmse",
+        }
+    }
+
+    /// Whether larger values mean the field is closer to the interpretation.
+    pub fn is_similarity(self) -> bool {
+        self == Metric::Coherence
+    }
+
+    pub fn compute(self, field: &Substrate, interp: &Interpretation) -> f64 {
+        match self {
+            Metric::Distance => trace_distance(field, interp),
+            Metric::Coherence => coherence(&field.state, &interp.data),
+            Metric::Manhattan => manhattan(&field.state, &interp.data),
+            Metric::Rmse => rmse(&field.state, &interp.data),
+        }
+    }
+}
+
+/// Parse error for a call to something that is not a metric.
+fn unknown_metric(name: &str) -> String {
+    format!("unknown metric `{}`; expected one of {}", name, METRIC_NAMES.join(", "))
+}
+
+/// Position of a token in the source, 1-based.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A syntax error at a token. Line and column are 0 when the error concerns a
+/// whole file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// Script the error is in, when it is not the one being loaded but a file it includes.
+    pub file: Option<String>,
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        if self.token.is_empty() {
+            write!(f, "{}:{}: {}", self.line, self.column, self.message)
+        } else {
+            write!(f, "{}:{}: {} (at `{}`)", self.line, self.column, self.message, self.token)
+        }
+    }
+}
+
+/// First tokens of every statement; parsing resumes at one of these after an error.
+const KEYWORDS: &[&str] = &[
+    "field", "interpretation", "project", "steer", "let", "trace", "meaning", "narratereturn", "perturb", "shock",
+    "logcoherence", "logmeaning", "expresssymbol", "modulate", "level", "repeat", "if", "include", "import",
+    "const", "add", "scale", "normalize", "seed", "export", "snapshot", "proc", "assert", "decay", "require",
+    "morph", "report", "run",
+];
+
+/// Calls a single parse may expand before a proc is assumed to call itself forever.
+pub const MAX_PROC_EXPANSIONS: usize = 1000;
+
+/// What a `project` statement samples while it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordKind {
+    /// Trace distance to the interpretation, as `<field>.trajectory`.
+    Trajectory,
+    /// Every element of the field, as `<field>[i]`.
+    Snapshots,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recording {
+    pub kind: RecordKind,
+    /// Sample after every `every`-th step.
+    pub every: usize,
+}
+
+/// What a `run` script shares with the script running it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Shared {
+    Fields,
+    Interpretations,
+}
+
+impl Shared {
+    pub fn name(self) -> &'static str {
+        match self {
+            Shared::Fields => "fields",
+            Shared::Interpretations => "interpretations",
+        }
+    }
+}
+
+/// Direction of a bound on a metric.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Comparison {
+    Greater,
+    Less,
+}
+
+impl Comparison {
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token {
+            ">" => Some(Comparison::Greater),
+            "<" => Some(Comparison::Less),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Greater => ">",
+            Comparison::Less => "<",
+        }
+    }
+
+    pub fn holds(self, value: f64, bound: f64) -> bool {
+        match self {
+            Comparison::Greater => value > bound,
+            Comparison::Less => value < bound,
+        }
+    }
+}
+
+/// Steps a `steer` runs for when `for N steps` is omitted.
+pub const DEFAULT_STEER_STEPS: usize = 100;
+
+/// Alpha change per unit of metric error in `steer`.
+pub const STEER_GAIN: f64 = 0.5;
+
+/// Closed-loop projection: alpha is adjusted every step to hold `metric keep target`,
+/// the target being given with the statement.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SteerSettings {
+    pub metric: Metric,
+    pub keep: Comparison,
+    pub alpha_min: f64,
+    pub alpha_max: f64,
+    pub noise: f64,
+    pub steps: usize,
+}
+
+/// Substitute `$name` tokens with parameter values bound from outside the
+/// script. A value is lexed like source, at the position of its `$name`.
+pub fn bind_params(tokens: Vec<Token>, params: &BTreeMap<String, String>) -> Vec<Token> {
+    let mut out = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        match token.text.strip_prefix('$').and_then(|name| params.get(name)) {
+            Some(value) => out.extend(lexer::lex(value).into_iter().map(|t| Token { span: token.span, ..t })),
+            None => out.push(token),
+        }
+    }
+    out
+}
+
+/// Tokenize, bind parameters, and parse a script source with default projection settings.
+pub fn parse_source(source: &str, params: &BTreeMap<String, String>) -> Result<Vec<Statement>, Vec<ParseError>> {
+    parse_source_with(source, params, &Config::default())
 }
 
-pub struct Tokenizer<'a> {
-    input: &'a str,
+/// Like `parse_source`, filling omitted `project` options from `config`.
+pub fn parse_source_with(
+    source: &str,
+    params: &BTreeMap<String, String>,
+    config: &Config,
+) -> Result<Vec<Statement>, Vec<ParseError>> {
+    let (tokens, open_comment) = lexer::lex_source(source);
+    let parsed = Parser::with_config(bind_params(tokens, params), config.clone()).parse();
+    let Some(span) = open_comment else {
+        return parsed;
+    };
+    // The comment blanked out the rest of the source, so its error comes last.
+    let mut errors = parsed.err().unwrap_or_default();
+    errors.push(ParseError {
+        file: None,
+        line: span.line,
+        column: span.column,
+        token: "/*".to_string(),
+        message: "unterminated block comment".to_string(),
+    });
+    Err(errors)
 }
 
-impl<'a> Tokenizer<'a> {
-    pub fn new(input: &'a str) -> Self {
-        Tokenizer { input }
+/// Splice the statements of every `include`d file into `program`, which was
+/// loaded from `script`. Included files are parsed with the same parameters
+/// and config, paths are relative to the including file, and a file that
+/// includes itself, directly or through others, is an error.
+pub fn resolve_includes(
+    program: Vec<Statement>,
+    script: &Path,
+    params: &BTreeMap<String, String>,
+    config: &Config,
+) -> Result<Vec<Statement>, Vec<ParseError>> {
+    let mut stack = vec![script.canonicalize().unwrap_or_else(|_| script.to_path_buf())];
+    splice_includes(program, &mut stack, params, config)
+}
+
+/// `stack` holds the files being included, outermost first.
+fn splice_includes(
+    program: Vec<Statement>,
+    stack: &mut Vec<PathBuf>,
+    params: &BTreeMap<String, String>,
+    config: &Config,
+) -> Result<Vec<Statement>, Vec<ParseError>> {
+    let mut out = Vec::with_capacity(program.len());
+    let mut errors = Vec::new();
+    for stmt in program {
+        match stmt {
+            Statement::Include(path) => match include_file("include", &path, stack, params, config) {
+                Ok(body) => out.extend(body),
+                Err(e) => errors.extend(e),
+            },
+            Statement::Run { path, sharing, .. } => match include_file("run", &path, stack, params, config) {
+                Ok(body) => out.push(Statement::Run { path, sharing, body }),
+                Err(e) => errors.extend(e),
+            },
+            Statement::Repeat { count, body } => match splice_includes(body, stack, params, config) {
+                Ok(body) => out.push(Statement::Repeat { count, body }),
+                Err(e) => errors.extend(e),
+            },
+            Statement::If { condition, then, otherwise } => {
+                match (splice_includes(then, stack, params, config), splice_includes(otherwise, stack, params, config)) {
+                    (Ok(then), Ok(otherwise)) => out.push(Statement::If { condition, then, otherwise }),
+                    (then, otherwise) => {
+                        errors.extend(then.err().into_iter().flatten());
+                        errors.extend(otherwise.err().into_iter().flatten());
+                    }
+                }
+            }
+            other => out.push(other),
+        }
     }
+    if errors.is_empty() {
+        Ok(out)
+    } else {
+        Err(errors)
+    }
+}
 
-    pub fn tokenize(&mut self) -> Vec<String> {
-        self.input
-            .split_whitespace()
-            .map(|s| s.trim_matches(&['"', ',', '[', ']'][..]).to_string())
-            .collect()
+/// The statements of the file at `rel`, read for `verb` (`include` or `run`).
+fn include_file(
+    verb: &str,
+    rel: &str,
+    stack: &mut Vec<PathBuf>,
+    params: &BTreeMap<String, String>,
+    config: &Config,
+) -> Result<Vec<Statement>, Vec<ParseError>> {
+    let including = stack.last().expect("the loaded script is always on the stack");
+    let error = |message: String| {
+        vec![ParseError {
+            file: Some(including.display().to_string()),
+            line: 0,
+            column: 0,
+            token: rel.to_string(),
+            message,
+        }]
+    };
+    let path = including.parent().unwrap_or(Path::new("")).join(rel);
+    let source = fs::read_to_string(&path).map_err(|e| error(format!("cannot {} {}: {}", verb, path.display(), e)))?;
+    let path = path.canonicalize().unwrap_or(path);
+    if let Some(first) = stack.iter().position(|p| *p == path) {
+        let chain: Vec<String> = stack[first..].iter().chain([&path]).map(|p| p.display().to_string()).collect();
+        return Err(error(format!("{} cycle: {}", verb, chain.join(" → "))));
     }
-}pub struct Parser {
-    tokens: Vec<String>,
+    let program = parse_source_with(&source, params, config).map_err(|errors| {
+        let file = path.display().to_string();
+        errors.into_iter().map(|e| ParseError { file: Some(file.clone()), ..e }).collect::<Vec<_>>()
+    })?;
+    stack.push(path);
+    let result = splice_includes(program, stack, params, config);
+    stack.pop();
+    result
+}
+
+pub struct Parser {
+    tokens: Vec<Token>,
     cursor: usize,
+    config: Config,
+    /// First failure of the statement being parsed: token index and message.
+    error: Option<(usize, String)>,
+    /// `proc` definitions seen so far, by name.
+    procs: HashMap<String, Proc>,
+    /// Proc calls expanded so far.
+    expansions: usize,
+}
+
+/// `proc warmup(field, n) { ... }`: a body of tokens, expanded where it is called.
+struct Proc {
+    params: Vec<String>,
+    body: Vec<Token>,
 }
 
 impl Parser {
-    pub fn new(tokens: Vec<String>) -> Self {
-        Parser { tokens, cursor: 0 }
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Parser::with_config(tokens, Config::default())
+    }
+
+    pub fn with_config(tokens: Vec<Token>, config: Config) -> Self {
+        Parser { tokens, cursor: 0, config, error: None, procs: HashMap::new(), expansions: 0 }
     }
 
-    pub fn parse(&mut self) -> Vec<Statement> {
+    /// Parse every statement, collecting all syntax errors rather than stopping at the first.
+    pub fn parse(&mut self) -> Result<Vec<Statement>, Vec<ParseError>> {
         let mut statements = Vec::new();
+        let mut errors = Vec::new();
         while self.cursor < self.tokens.len() {
-            if let Some(stmt) = self.parse_statement() {
+            let start = self.cursor;
+            let parsed = match self.expand_procs() {
+                Some(()) if self.cursor == self.tokens.len() => break,
+                Some(()) => self.parse_statement(),
+                None => None,
+            };
+            if let Some(stmt) = parsed {
                 statements.push(stmt);
-            } else {
-                break;
+                continue;
             }
+            let (at, message) = self.error.take().unwrap_or_else(|| (start, "invalid statement".to_string()));
+            errors.push(self.error_at(at, message));
+            // Skip to the next statement keyword after the failure.
+            self.cursor = at.max(start + 1);
+            while self.peek().is_some_and(|t| !KEYWORDS.contains(&t.to_lowercase().as_str())) {
+                self.cursor += 1;
+            }
+        }
+        if errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn error_at(&self, at: usize, message: String) -> ParseError {
+        let span = self.tokens.get(at).or(self.tokens.last()).map(|t| t.span).unwrap_or_default();
+        let token = self.tokens.get(at).map(|t| t.text.clone()).unwrap_or_default();
+        ParseError { file: None, line: span.line, column: span.column, token, message }
+    }
+
+    /// Record a failure at token `at` (keeping an earlier one) and fail.
+    fn fail<T>(&mut self, at: usize, message: impl Into<String>) -> Option<T> {
+        if self.error.is_none() {
+            self.error = Some((at, message.into()));
+        }
+        None
+    }
+
+    /// Unwrap an option-block result, reporting its error at the statement start.
+    fn check<T>(&mut self, start: usize, result: Result<T, String>) -> Option<T> {
+        match result {
+            Ok(v) => Some(v),
+            Err(e) => self.fail(start, e),
         }
-        statements
     }
 
+    /// Parse one statement and `validate` it; its body, if any, already was.
     fn parse_statement(&mut self) -> Option<Statement> {
+        let start = self.cursor;
+        let statement = self.parse_statement_syntax()?;
+        let checked = match &statement {
+            Statement::Level { .. } | Statement::Repeat { .. } | Statement::Run { .. } | Statement::If { .. } => Ok(()),
+            other => validate(other),
+        };
+        self.check(start, checked)?;
+        Some(statement)
+    }
+
+    fn parse_statement_syntax(&mut self) -> Option<Statement> {
+        let start = self.cursor;
         let t = self.next()?.to_lowercase();
         match t.as_str() {
             "field" => {
                 let name = self.next()?;
-                let size = self.next()?.parse().ok()?;
-                Some(Statement::Field { name, size })
+                if self.peek() == Some("=") {
+                    self.next();
+                    let bracket = self.tokens.get(self.cursor + 1).map(|t| t.text.clone());
+                    if bracket.as_deref() == Some("[") {
+                        let source = self.next()?;
+                        self.next();
+                        let at = self.cursor;
+                        let indices = self.word()?;
+                        self.expect("]")?;
+                        return match parse_index_range(&indices) {
+                            Some(range) => Some(Statement::SliceField { name, source, start: range.start, end: range.end }),
+                            None => self.fail(at, "expected an index range like `0..8`"),
+                        };
+                    }
+                    if bracket.as_deref() == Some("(") && self.peek().is_some_and(|t| t.eq_ignore_ascii_case("concat")) {
+                        self.cursor += 2;
+                        let mut parts = vec![self.next()?];
+                        while self.peek() == Some(",") {
+                            self.next();
+                            parts.push(self.next()?);
+                        }
+                        self.expect(")")?;
+                        return Some(Statement::ConcatFields { name, parts });
+                    }
+                    let Some(expr) = expr::parse_expr(&self.tokens, &mut self.cursor) else {
+                        return self.fail(self.cursor.saturating_sub(1), "invalid field expression");
+                    };
+                    return Some(Statement::DeriveField { name, expr });
+                }
+                if self.peek() == Some("from") {
+                    self.next();
+                    self.expect("checkpoint")?;
+                    let path = self.string("a checkpoint path")?;
+                    return Some(Statement::FieldFromCheckpoint { name, path });
+                }
+                // `field psi size 16`; a `size` with nothing after it on its line is the error.
+                if self.peek() == Some("size") {
+                    let line = self.tokens[self.cursor].span.line;
+                    if !self.tokens.get(self.cursor + 1).is_some_and(|t| t.span.line == line) {
+                        return self.fail(self.cursor, "expected a field size");
+                    }
+                    self.next();
+                }
+                let size = self.expr("a field size")?;
+                let doc = self.doc()?;
+                Some(Statement::Field { name, size, doc })
             }
             "interpretation" => {
                 let name = self.next()?;
                 self.expect("=")?;
-                self.expect("[")?;
+                if self.tokens.get(self.cursor + 1).is_some_and(|t| t.text == "(") {
+                    let generator = self.parse_generator(start)?;
+                    return Some(Statement::GenerateInterpretation { name, generator });
+                }
+                // `[1, 0, 1]`, `[1 0 1]` or a bare `1 0 1`.
+                let bracketed = self.peek() == Some("[");
+                if bracketed {
+                    self.next();
+                }
                 let mut values = Vec::new();
-                while let Some(tok) = self.peek() {
-                    if tok == "]" {
-                        self.next();
-                        break;
+                if bracketed {
+                    while self.peek().is_some_and(|t| t != "]") {
+                        values.push(self.list_value()?);
+                        if self.peek() == Some(",") {
+                            self.next();
+                        }
                     }
-                    if let Ok(num) = tok.parse::<f64>() {
-                        values.push(num);
-                        self.next();
-                    } else {
-                        break;
+                    self.expect("]")?;
+                } else {
+                    while self.peek_kind() == Some(TokenKind::Number) {
+                        values.push(Expr::from(self.number::<f64>("a value")?));
                     }
                 }
-                Some(Statement::Interpretation { name, values })
+                let doc = self.doc()?;
+                Some(Statement::Interpretation { name, values, doc })
             }
             "project" => {
                 let target = self.next()?;
                 self.expect("<-")?;
+                if self.peek() == Some("[") {
+                    return self.parse_project_many(start, target);
+                }
                 let interp = self.next()?;
-                self.expect("{")?;
-                let alpha = self.expect_value("alpha:")?;
-                let noise = self.expect_value("noise:")?;
-                let steps = self.expect_value("steps:")? as usize;
-                self.expect("}")?;
+                let mut opts = self.parse_options("project")?;
+                let alpha = self.check(start, opts.expr("alpha", Some(self.config.alpha)))?;
+                let noise = self.check(start, opts.expr("noise", Some(self.config.noise)))?;
+                let steps = match opts.take("steps") {
+                    Some(v) if v == "auto" => Some(None),
+                    Some(v) => match expr::parse_str(&v) {
+                        Some(steps) => Some(Some(steps)),
+                        None => {
+                            return self.fail(start, format!("project: option `steps` expects a count or `auto`, found `{}`", v))
+                        }
+                    },
+                    None => None,
+                };
+                let tolerance = self.check(start, opts.expr("tolerance", Some(self.config.tolerance)))?;
+                let log_every = self.check(start, opts.count("log_every"))?;
+                let until = match opts.take("until") {
+                    Some(v) => match parse_until(&v) {
+                        Some(until) => Some(until),
+                        None => {
+                            return self.fail(start, format!("project: option `until` expects `dist < <expression>`, found `{}`", v))
+                        }
+                    },
+                    None => None,
+                };
+                let max_steps = match opts.take("max_steps") {
+                    Some(_) if until.is_none() => return self.fail(start, "project: option `max_steps` requires `until`"),
+                    Some(v) => match expr::parse_str(&v) {
+                        Some(n) => Some(n),
+                        None => return self.fail(start, format!("project: option `max_steps` expects a count, found `{}`", v)),
+                    },
+                    None => None,
+                };
+                // A convergence target runs up to `max_steps` (or `AUTO_MAX_STEPS`);
+                // otherwise an omitted `steps` falls back to the configured default.
+                let steps = match until {
+                    Some(_) => max_steps.or(steps.flatten()),
+                    None => steps.unwrap_or(self.config.steps.map(|n| Expr::from(n as f64))),
+                };
+                self.check(start, opts.finish())?;
+                let record = if self.peek() == Some("record") {
+                    self.next();
+                    Some(self.parse_recording()?)
+                } else {
+                    None
+                };
                 Some(Statement::Project {
                     target,
                     interp,
                     alpha,
                     noise,
                     steps,
+                    tolerance,
+                    until,
+                    record,
+                    log_every,
                 })
             }
+            "steer" => {
+                let field = self.next()?;
+                self.expect("toward")?;
+                let interp = self.next()?;
+                self.expect("keep")?;
+                let metric_name = self.next()?;
+                let Some(metric) = Metric::from_name(&metric_name) else {
+                    return self.fail(self.cursor - 1, unknown_metric(&metric_name));
+                };
+                let op = self.next()?;
+                let Some(keep) = Comparison::from_token(&op) else {
+                    return self.fail(self.cursor - 1, "expected `>` or `<`");
+                };
+                let target = self.expr("a target value")?;
+                self.expect("using")?;
+                self.expect("alpha")?;
+                self.expect("in")?;
+                let bounds = "alpha bounds like `[0.01, 0.5]`";
+                self.expect("[")?;
+                let alpha_min: f64 = self.number(bounds)?;
+                if self.peek() == Some(",") {
+                    self.next();
+                }
+                let alpha_max: f64 = self.number(bounds)?;
+                self.expect("]")?;
+                let mut steps = DEFAULT_STEER_STEPS;
+                if self.peek() == Some("for") {
+                    self.next();
+                    steps = self.number("a step count")?;
+                    self.expect("steps")?;
+                }
+                let settings = SteerSettings { metric, keep, alpha_min, alpha_max, noise: self.config.noise, steps };
+                Some(Statement::Steer { field, interp, target, settings })
+            }
+            "let" => {
+                let name = self.next()?;
+                self.expect("=")?;
+                if !self.at_metric_call() {
+                    let value = self.expr("a number or a metric call")?;
+                    return Some(Statement::Assign { name, value });
+                }
+                let call_at = self.cursor;
+                let (func, args) = self.parse_call()?;
+                let Some(metric) = Metric::from_name(&func) else {
+                    return self.fail(call_at, unknown_metric(&func));
+                };
+                let Ok([field, interp]) = <[String; 2]>::try_from(args) else {
+                    return self.fail(call_at, format!("{} takes a field and an interpretation", func));
+                };
+                Some(Statement::Let { name, metric, field, interp })
+            }
             "trace" => {
                 let name = self.next()?;
                 self.expect("=")?;
-                let func = self.next()?;
-                self.expect("(")?;
-                let field = self.next()?;
-                self.expect(",")?;
-                let interp = self.next()?;
-                self.expect(")")?;
-                Some(Statement::TraceDistance {
-                    name,
-                    field,
-                    interp,
-                })
+                let call_at = self.cursor;
+                let (func, args) = self.parse_call()?;
+                let Some(metric) = Metric::from_name(&func) else {
+                    return self.fail(call_at, unknown_metric(&func));
+                };
+                let Ok([field, interp]) = <[String; 2]>::try_from(args) else {
+                    return self.fail(call_at, "expected `func(field, interpretation)`");
+                };
+                // `trace c = coherence(F, I)` is recorded and bound like `let c = coherence(F, I)`.
+                match metric {
+                    Metric::Distance => Some(Statement::TraceDistance {
+                        name,
+                        field,
+                        interp,
+                    }),
+                    metric => Some(Statement::Let { name, metric, field, interp }),
+                }
             }
             "meaning" => {
                 let name = self.next()?;
                 self.expect("=")?;
-                let func = self.next()?;
-                self.expect("(")?;
-                let trace_cmp = self.next()?;
-                self.expect(",")?;
-                let threshold = self.next()?.parse().ok()?;
-                self.expect(")")?;
+                let call_at = self.cursor;
+                let (_func, args) = self.parse_call()?;
+                // The threshold may itself have been split on spaces: `below(d, a * 2)`.
+                let parsed = match args.as_slice() {
+                    [trace, threshold @ ..] => expr::parse_str(&threshold.join(" ")).map(|t| (trace.clone(), t)),
+                    _ => None,
+                };
+                let Some((trace_cmp, threshold)) = parsed else {
+                    return self.fail(call_at, "expected `func(trace, threshold)`");
+                };
                 Some(Statement::Meaning {
                     name,
                     trace_cmp,
                     threshold,
                 })
             }
-            "narratereturn" => {
-                let mut tokens = Vec::new();
-                while let Some(tok) = self.peek() {
-                    if tok.starts_with('"') {
-                        tokens.push(tok.trim_matches('"').to_string());
-                        self.next();
-                    } else {
-                        break;
+            "assert" => {
+                self.expect("trace")?;
+                let trace = self.next()?;
+                let at = self.cursor;
+                let token = self.next()?;
+                let Some(cmp) = Comparison::from_token(&token) else {
+                    return self.fail(at, "expected `<` or `>`");
+                };
+                let bound = self.expr("a bound")?;
+                let tolerance = match self.peek() {
+                    Some(t) if t.eq_ignore_ascii_case("within") => {
+                        self.cursor += 1;
+                        self.number("a tolerance")?
                     }
+                    _ => 0.0,
+                };
+                Some(Statement::Assert { trace, cmp, bound, tolerance })
+            }
+            "narratereturn" => {
+                let mut tokens = vec![self.string("a quoted string")?];
+                while self.peek_kind() == Some(TokenKind::Str) {
+                    tokens.push(self.string("a quoted string")?);
                 }
                 Some(Statement::NarrateReturn { tokens })
             }
+            "perturb" => {
+                let field = self.next()?;
+                self.expect("noise")?;
+                let amplitude = self.expr("a noise amplitude")?;
+                Some(Statement::Perturb { field, amplitude })
+            }
+            "shock" => {
+                let field = self.next()?;
+                self.expect("indices")?;
+                let at = self.cursor;
+                let bracketed = self.peek() == Some("[");
+                if bracketed {
+                    self.next();
+                }
+                let indices = self.word()?;
+                if bracketed {
+                    self.expect("]")?;
+                }
+                let Some(range) = parse_index_range(&indices) else {
+                    return self.fail(at, "expected indices like `3..10`");
+                };
+                self.expect("value")?;
+                let value = self.expr("a shock value")?;
+                Some(Statement::Shock { field, start: range.start, end: range.end, value })
+            }
+            "add" => {
+                let left = self.next()?;
+                let right = self.next()?;
+                self.expect("into")?;
+                let into = self.next()?;
+                Some(Statement::AddFields { left, right, into })
+            }
+            "scale" => {
+                let field = self.next()?;
+                let factor = self.expr("a scale factor")?;
+                Some(Statement::Scale { field, factor })
+            }
+            "decay" => {
+                let field = self.next()?;
+                // `decay psi` and `decay psi steps 10` take the configured rate.
+                let line = self.tokens[self.cursor - 1].span.line;
+                let rate_given = self.tokens.get(self.cursor).is_some_and(|t| {
+                    t.span.line == line && !t.text.eq_ignore_ascii_case("steps") && t.text != "}"
+                });
+                let rate = if rate_given { self.expr("a decay rate")? } else { Expr::from(self.config.decay) };
+                let steps = match self.peek() {
+                    Some(t) if t.eq_ignore_ascii_case("steps") => {
+                        self.cursor += 1;
+                        self.number("a step count")?
+                    }
+                    _ => 1,
+                };
+                Some(Statement::Decay { field, rate, steps })
+            }
+            "normalize" => {
+                let field = self.next()?;
+                Some(Statement::Normalize { field })
+            }
             "logcoherence" => {
                 let field = self.next()?;
                 Some(Statement::LogCoherence(field))
@@ -157,36 +1007,315 @@ impl Parser {
                 Some(Statement::LogMeaning(name))
             }
             "expresssymbol" => {
-                let token = self.next()?;
+                let token = self.string("a symbol")?;
                 let _ = self.next()?; // into_field
                 let field = self.next()?;
-                Some(Statement::ExpressSymbol {
-                    token,
-                    into_field: field,
-                })
+                if self.peek() != Some("{") {
+                    let weight = Expr::from(1.0);
+                    return Some(Statement::ExpressSymbol { token, into_field: field, encoding: Encoding::Bits, weight, blend: false });
+                }
+                let mut opts = self.parse_options("expresssymbol")?;
+                let encoding = match opts.take("encoding") {
+                    Some(name) => match Encoding::from_name(&name) {
+                        Some(encoding) => encoding,
+                        None => {
+                            let known = ENCODING_NAMES.join(", ");
+                            return self.fail(start, format!("expresssymbol: unknown encoding `{}` (expected {})", name, known));
+                        }
+                    },
+                    None => Encoding::Bits,
+                };
+                let weight = self.check(start, opts.expr("weight", Some(1.0)))?;
+                let blend = opts.flag("blend");
+                self.check(start, opts.finish())?;
+                Some(Statement::ExpressSymbol { token, into_field: field, encoding, weight, blend })
             }
             "modulate" => {
-                let token = self.next()?;
+                let token = self.string("a symbol")?;
                 let _ = self.next()?; // intensity
-                let val = self.next()?.parse().ok()?;
+                let val = self.expr("an intensity")?;
                 Some(Statement::Modulate { token, intensity: val })
             }
-            _ => None,
+            "level" => {
+                let level_name = self.next()?;
+                let Some(level) = self.config.level(&level_name) else {
+                    return self.fail(self.cursor - 1, "unknown recursion level");
+                };
+                let name = self.next()?;
+                let body = self.parse_block()?;
+                Some(Statement::Level { level, name, body })
+            }
+            "repeat" => {
+                let count = self.number("a repeat count")?;
+                let body = self.parse_block()?;
+                Some(Statement::Repeat { count, body })
+            }
+            "if" => {
+                let condition = self.parse_condition()?;
+                let then = self.parse_block()?;
+                let otherwise = match self.peek() {
+                    Some("else") => {
+                        self.next();
+                        if self.peek() == Some("if") {
+                            // `else if` chains nest as a single statement.
+                            vec![self.parse_statement()?]
+                        } else {
+                            self.parse_block()?
+                        }
+                    }
+                    _ => Vec::new(),
+                };
+                Some(Statement::If { condition, then, otherwise })
+            }
+            "include" | "import" => Some(Statement::Include(self.string("a file path")?)),
+            "run" => {
+                let path = self.string("a file path")?;
+                let mut sharing = Vec::new();
+                if self.peek() == Some("sharing") {
+                    self.next();
+                    loop {
+                        let at = self.cursor;
+                        match self.word()?.as_str() {
+                            "fields" => sharing.push(Shared::Fields),
+                            "interpretations" => sharing.push(Shared::Interpretations),
+                            _ => return self.fail(at, "expected fields or interpretations"),
+                        }
+                        if self.peek() != Some(",") {
+                            break;
+                        }
+                        self.next();
+                    }
+                }
+                Some(Statement::Run { path, sharing, body: Vec::new() })
+            }
+            "seed" => Some(Statement::Seed(self.number("a seed")?)),
+            "morph" => {
+                let target = self.next()?;
+                self.expect("from")?;
+                let from = self.next()?;
+                self.expect("to")?;
+                let to = self.next()?;
+                let mut opts = self.parse_options("morph")?;
+                let alpha = self.check(start, opts.expr("alpha", None))?;
+                let noise = self.check(start, opts.expr("noise", Some(self.config.noise)))?;
+                let steps = self.fixed_steps(start, &mut opts, "morph")?;
+                self.check(start, opts.finish())?;
+                Some(Statement::Morph { target, from, to, alpha, noise, steps })
+            }
+            "require" => {
+                let at = self.cursor;
+                let name = self.next()?;
+                match Capability::from_name(&name) {
+                    Some(capability) => Some(Statement::Require(capability)),
+                    None => {
+                        let known: Vec<&str> = Capability::ALL.iter().map(|c| c.feature()).collect();
+                        self.fail(at, format!("unknown capability `{}` (expected {})", name, known.join(", ")))
+                    }
+                }
+            }
+            "snapshot" => {
+                let field = self.next()?;
+                self.expect("as")?;
+                let name = self.next()?;
+                Some(Statement::Snapshot { field, name })
+            }
+            "export" => {
+                let at = self.cursor;
+                let Some(kind) = ExportKind::from_name(&self.next()?) else {
+                    return self.fail(at, "expected `trace` or `field`");
+                };
+                let name = self.next()?;
+                self.expect("to")?;
+                let at = self.cursor;
+                let path = self.string("a file path")?;
+                // Destinations of optional subsystems parse; the checker
+                // reports them if this build lacks the subsystem.
+                if export::ExportFormat::from_path(&path).is_none() && export::capability_of(&path).is_none() {
+                    return self.fail(at, "expected a .csv, .json, .png, .svg, .sqlite or .db file, or a ws:// URL");
+                }
+                Some(Statement::Export { kind, name, path })
+            }
+            "report" => {
+                if self.peek() != Some("to") {
+                    return Some(Statement::Report { path: None });
+                }
+                self.next();
+                let at = self.cursor;
+                let path = self.string("a file path")?;
+                if export::ExportFormat::from_path(&path).is_none() {
+                    return self.fail(at, "expected a .csv or .json file");
+                }
+                Some(Statement::Report { path: Some(path) })
+            }
+            "const" => {
+                let name = self.next()?;
+                if self.peek() == Some("=") {
+                    self.next();
+                }
+                let value = self.number("a constant value")?;
+                Some(Statement::Const { name, value })
+            }
+            _ => self.fail(start, "unknown statement"),
+        }
+    }
+
+    /// Parse a `{ ... }` block of nested statements.
+    fn parse_block(&mut self) -> Option<Vec<Statement>> {
+        self.expect("{")?;
+        let mut body = Vec::new();
+        loop {
+            self.expand_procs()?;
+            if self.peek() == Some("}") {
+                break;
+            }
+            body.push(self.parse_statement()?);
+        }
+        self.next();
+        Some(body)
+    }
+
+    /// Take in `proc` definitions and expand proc calls until the next token
+    /// starts an ordinary statement.
+    fn expand_procs(&mut self) -> Option<()> {
+        loop {
+            match self.peek() {
+                Some(t) if t.eq_ignore_ascii_case("proc") => {
+                    self.next();
+                    self.define_proc()?;
+                }
+                Some(t) if self.procs.contains_key(t) && self.tokens.get(self.cursor + 1).is_some_and(|t| t.text == "(") => {
+                    self.expand_call()?;
+                }
+                _ => return Some(()),
+            }
+        }
+    }
+
+    /// `proc name(a, b) { ... }`, after the keyword. The body is kept as tokens.
+    fn define_proc(&mut self) -> Option<()> {
+        let at = self.cursor;
+        let (name, args) = self.call_tokens()?;
+        if KEYWORDS.contains(&name.to_lowercase().as_str()) {
+            return self.fail(at, format!("`{}` cannot name a proc", name));
+        }
+        let mut params = Vec::with_capacity(args.len());
+        for arg in args {
+            match arg.as_slice() {
+                [param] if param.kind == TokenKind::Ident => params.push(param.text.clone()),
+                _ => return self.fail(at, format!("parameters of {} must be plain names", name)),
+            }
+        }
+        self.expect("{")?;
+        let open = self.cursor;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next()?.as_str() {
+                "{" => depth += 1,
+                "}" => depth -= 1,
+                _ => {}
+            }
         }
+        let body = self.tokens[open..self.cursor - 1].to_vec();
+        self.procs.insert(name, Proc { params, body });
+        Some(())
+    }
+
+    /// Replace the call `name(arg, ...)` at the cursor with the proc's body,
+    /// its parameters substituted by the arguments. Errors inside the body
+    /// are reported at the call.
+    fn expand_call(&mut self) -> Option<()> {
+        let at = self.cursor;
+        let span = self.tokens[at].span;
+        let (name, args) = self.call_tokens()?;
+        let expected = self.procs[&name].params.len();
+        if args.len() != expected {
+            return self.fail(at, format!("{} expects {} argument(s), found {}", name, expected, args.len()));
+        }
+        self.expansions += 1;
+        if self.expansions > MAX_PROC_EXPANSIONS {
+            return self.fail(at, format!("more than {} proc calls expanded; does {} call itself?", MAX_PROC_EXPANSIONS, name));
+        }
+        let proc = &self.procs[&name];
+        let mut body = Vec::with_capacity(proc.body.len());
+        for token in &proc.body {
+            match proc.params.iter().position(|p| token.kind == TokenKind::Ident && *p == token.text) {
+                Some(i) => body.extend(args[i].iter().cloned()),
+                None => body.push(token.clone()),
+            }
+        }
+        let body = body.into_iter().map(|t| Token { span, ..t });
+        self.tokens.splice(at..self.cursor, body);
+        self.cursor = at;
+        Some(())
+    }
+
+    /// Read `name(a, b)`: the name and the tokens of each argument.
+    fn call_tokens(&mut self) -> Option<(String, Vec<Vec<Token>>)> {
+        let at = self.cursor;
+        if self.peek_kind() != Some(TokenKind::Ident) {
+            return self.fail(at, "expected `name(...)`");
+        }
+        let name = self.next()?;
+        self.expect("(")?;
+        let mut args = Vec::new();
+        let mut arg = Vec::new();
+        let mut depth = 0;
+        loop {
+            let token = match self.tokens.get(self.cursor) {
+                Some(t) if t.text != "{" => t.clone(),
+                _ => return self.fail(at, "expected `)` closing the argument list"),
+            };
+            self.cursor += 1;
+            match token.text.as_str() {
+                ")" if depth == 0 => break,
+                "," if depth == 0 => args.push(std::mem::take(&mut arg)),
+                "(" => {
+                    depth += 1;
+                    arg.push(token);
+                }
+                ")" => {
+                    depth -= 1;
+                    arg.push(token);
+                }
+                _ => arg.push(token),
+            }
+        }
+        if !arg.is_empty() || !args.is_empty() {
+            args.push(arg);
+        }
+        Some((name, args))
     }
 
     fn next(&mut self) -> Option<String> {
         if self.cursor < self.tokens.len() {
-            let t = self.tokens[self.cursor].clone();
+            let t = self.tokens[self.cursor].text.clone();
             self.cursor += 1;
             Some(t)
         } else {
-            None
+            self.fail(self.tokens.len(), "unexpected end of input")
         }
     }
 
     fn peek(&self) -> Option<&str> {
-        self.tokens.get(self.cursor).map(|s| s.as_str())
+        self.tokens.get(self.cursor).map(|t| t.text.as_str())
+    }
+
+    fn peek_kind(&self) -> Option<TokenKind> {
+        self.tokens.get(self.cursor).map(|t| t.kind)
+    }
+
+    /// The next token and every token touching it, as one word: a bare path
+    /// such as `lib/seed.sptl` or a range such as `3..10`.
+    fn word(&mut self) -> Option<String> {
+        let mut word = self.next()?;
+        while self.cursor < self.tokens.len()
+            && self.tokens[self.cursor - 1].touches(&self.tokens[self.cursor])
+            && !matches!(self.tokens[self.cursor].text.as_str(), "(" | ")" | "[" | "]" | "{" | "}" | ",")
+        {
+            word.push_str(&self.tokens[self.cursor].text);
+            self.cursor += 1;
+        }
+        Some(word)
     }
 
     fn expect(&mut self, expected: &str) -> Option<()> {
@@ -194,90 +1323,1295 @@ impl Parser {
         if token.to_lowercase() == expected.to_lowercase() {
             Some(())
         } else {
-            None
+            self.fail(self.cursor - 1, format!("expected `{}`", expected))
         }
     }
 
-    fn expect_value(&mut self, label: &str) -> Option<f64> {
-        let l = self.next()?;
-        if !l.starts_with(label) {
-            return None;
+    /// A string literal, unquoted. A bare word is taken as written, so
+    /// `include common.sptl` and `include "common.sptl"` are the same.
+    fn string(&mut self, what: &str) -> Option<String> {
+        if self.peek_kind() != Some(TokenKind::Str) {
+            return self.word();
+        }
+        let token = self.next()?;
+        match lexer::unquote(&token) {
+            Some(text) => Some(text),
+            None => self.fail(self.cursor - 1, format!("expected {}: unterminated string", what)),
         }
-        let val = self.next()?.parse().ok()?;
-        Some(val)
     }
-}
-pub fn execute_program(program: Vec<Statement>) {
-    let mut fields: HashMap<String, Substrate> = HashMap::new();
-    let mut interps: HashMap<String, Interpretation> = HashMap::new();
 
-    for stmt in program {
-        match stmt {
-            Statement::Field { name, size } => {
-                fields.insert(name, Substrate::new(size));
-            }
-            Statement::Interpretation { name, values } => {
-                interps.insert(name, Interpretation::new(values));
-            }
-            Statement::Project {
-                target,
-                interp,
-                alpha,
-                noise,
-                steps,
-            } => {
-                if let (Some(field), Some(interp_val)) =
-                    (fields.get_mut(&target), interps.get(&interp))
-                {
-                    for _ in 0..steps {
-                        project(field, interp_val, alpha, noise);
-                    }
-                } else {
-                    eprintln!("⚠️ Unknown field or interpretation in Project");
-                }
-            }
-            Statement::TraceDistance {
-                name,
-                field,
-                interp,
-            } => {
-                if let (Some(f), Some(i)) = (fields.get(&field), interps.get(&interp)) {
-                    let result = trace_distance(f, i);
-                    println!("Trace {} = {:.4}", name, result);
-                } else {
-                    eprintln!("⚠️ Unknown field or interpretation in TraceDistance");
+    /// The doc string ending a declaration, if there is one.
+    fn doc(&mut self) -> Option<Option<String>> {
+        if self.peek_kind() != Some(TokenKind::Str) {
+            return Some(None);
+        }
+        self.string("a doc string").map(Some)
+    }
+
+    /// Parse the next token as a number, describing it as `what` on failure.
+    fn number<T: std::str::FromStr>(&mut self, what: &str) -> Option<T> {
+        let token = self.next()?;
+        match token.parse() {
+            Ok(n) => Some(n),
+            Err(_) => self.fail(self.cursor - 1, format!("expected {}", what)),
+        }
+    }
+
+    /// One value of a bracketed list. A negative number written after a
+    /// value starts the next one, so `[1 -1]` is two values and `[1 - 1]` one.
+    fn list_value(&mut self) -> Option<Expr> {
+        let start = self.cursor;
+        let ends_operand = |t: &Token| matches!(t.kind, TokenKind::Number | TokenKind::Ident) || t.text == ")";
+        let end = (start + 1..self.tokens.len())
+            .find(|&i| {
+                let token = &self.tokens[i];
+                matches!(token.text.as_str(), "," | "]")
+                    || (token.kind == TokenKind::Number && token.text.starts_with('-') && ends_operand(&self.tokens[i - 1]))
+            })
+            .unwrap_or(self.tokens.len());
+        match expr::parse_expr(&self.tokens[..end], &mut self.cursor) {
+            Some(e) => Some(e),
+            None => self.fail(start, "expected a value"),
+        }
+    }
+
+    /// Parse an arithmetic expression, describing it as `what` on failure.
+    fn expr(&mut self, what: &str) -> Option<Expr> {
+        let start = self.cursor;
+        match expr::parse_expr(&self.tokens, &mut self.cursor) {
+            Some(e) => Some(e),
+            None => self.fail(start, format!("expected {}", what)),
+        }
+    }
+
+    /// Parse `left < right` (or another comparison) up to the `{` opening a block.
+    fn parse_condition(&mut self) -> Option<Condition> {
+        let start = self.cursor;
+        let mut text = String::new();
+        while self.peek().is_some_and(|t| t != "{") {
+            text.push_str(&self.next()?);
+            text.push(' ');
+        }
+        match Condition::parse(&text) {
+            Some(condition) => Some(condition),
+            None => self.fail(start, "expected a condition like `d < 0.1`"),
+        }
+    }
+
+    /// Whether the next tokens are a metric call such as `trace_distance(F, I)`.
+    fn at_metric_call(&self) -> bool {
+        let Some(token) = self.peek() else { return false };
+        let name = token.split('(').next().unwrap_or(token);
+        let opens = token.contains('(') || self.tokens.get(self.cursor + 1).is_some_and(|t| t.text.starts_with('('));
+        opens && Metric::from_name(name).is_some()
+    }
+
+    /// Parse `trajectory every N steps` (or `snapshots ...`) after `record`.
+    fn parse_recording(&mut self) -> Option<Recording> {
+        let kind = match self.next()?.to_lowercase().as_str() {
+            "trajectory" => RecordKind::Trajectory,
+            "snapshots" => RecordKind::Snapshots,
+            _ => return self.fail(self.cursor - 1, "expected `trajectory` or `snapshots`"),
+        };
+        self.expect("every")?;
+        let every: usize = self.number("a step interval")?;
+        if matches!(self.peek(), Some("steps") | Some("step")) {
+            self.next();
+        }
+        Some(Recording { kind, every })
+    }
+
+    /// Parse `name(a, b, ...)`.
+    fn parse_call(&mut self) -> Option<(String, Vec<String>)> {
+        let start = self.cursor;
+        let mut text = String::new();
+        while !text.contains(')') {
+            text.push_str(&self.next()?);
+            text.push(' ');
+        }
+        let Some((name, rest)) = text.split_once('(') else {
+            return self.fail(start, "expected `name(arguments)`");
+        };
+        let args = rest.split(')').next().unwrap_or_default();
+        let args = args
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|a| !a.is_empty())
+            .map(|a| a.to_string())
+            .collect();
+        Some((name.trim().to_string(), args))
+    }
+
+    /// The rest of `project F <- [I, J: 0.5, ...] { alternate, alpha: ..., steps: N }`.
+    fn parse_project_many(&mut self, start: usize, target: String) -> Option<Statement> {
+        self.expect("[")?;
+        let mut interps = Vec::new();
+        while self.peek() != Some("]") {
+            let name = self.next()?;
+            let weight = match self.peek() {
+                Some(":") => {
+                    self.next();
+                    self.number("a weight")?
                 }
+                _ => 1.0,
+            };
+            interps.push((name, weight));
+            if self.peek() == Some(",") {
+                self.next();
             }
-            Statement::Meaning {
-                name,
-                trace_cmp,
-                threshold,
-            } => {
-                println!("💡 Meaning {} ← {} < {}", name, trace_cmp, threshold);
+        }
+        self.next();
+        let mut opts = self.parse_options("project")?;
+        let alternate = opts.flag("alternate");
+        let alpha = self.check(start, opts.expr("alpha", Some(self.config.alpha)))?;
+        let noise = self.check(start, opts.expr("noise", Some(self.config.noise)))?;
+        let steps = self.fixed_steps(start, &mut opts, "project: a list of interpretations")?;
+        self.check(start, opts.finish())?;
+        Some(Statement::ProjectMany { target, interps, alternate, alpha, noise, steps })
+    }
+
+    /// The `steps` option, or the configured default, for statements that
+    /// cannot decide their step count while running; `what` names the
+    /// statement if neither is given.
+    fn fixed_steps(&mut self, start: usize, opts: &mut Options, what: &str) -> Option<usize> {
+        match (self.check(start, opts.count("steps"))?, self.config.steps) {
+            (Some(n), _) | (None, Some(n)) => Some(n),
+            (None, None) => self.fail(start, format!("{} needs `steps: N`", what)),
+        }
+    }
+
+    /// The generator call after `interpretation I =`, such as `sine(size: 64)`.
+    fn parse_generator(&mut self, start: usize) -> Option<Generator> {
+        let at = self.cursor;
+        let written = self.next()?;
+        let Some(&kind) = GENERATOR_NAMES.iter().find(|g| **g == written.to_lowercase()) else {
+            let known = GENERATOR_NAMES.join(", ");
+            return self.fail(at, format!("interpretation: unknown generator `{}` (expected {})", written, known));
+        };
+        let mut opts = self.parse_options_in(kind, "(", ")")?;
+        let Some(size) = self.check(start, opts.count("size"))? else {
+            return self.fail(start, format!("{}: missing option `size`", kind));
+        };
+        let generator = match kind {
+            "random" => Generator::Random {
+                size,
+                low: self.check(start, opts.expr("low", Some(0.0)))?,
+                high: self.check(start, opts.expr("high", Some(1.0)))?,
+                seed: self.check(start, opts.count("seed"))?,
+            },
+            "gaussian" => Generator::Gaussian {
+                size,
+                mean: self.check(start, opts.expr("mean", Some(0.0)))?,
+                std: self.check(start, opts.expr("std", Some(1.0)))?,
+                seed: self.check(start, opts.count("seed"))?,
+            },
+            "one_hot" => match self.check(start, opts.count("index"))? {
+                Some(index) => Generator::OneHot { size, index },
+                None => return self.fail(start, "one_hot: missing option `index`"),
+            },
+            _ => Generator::Sine {
+                size,
+                periods: self.check(start, opts.expr("periods", Some(1.0)))?,
+                amplitude: self.check(start, opts.expr("amplitude", Some(1.0)))?,
+                phase: self.check(start, opts.expr("phase", Some(0.0)))?,
+            },
+        };
+        self.check(start, opts.finish())?;
+        Some(generator)
+    }
+
+    /// Parse a `{ key: value ... }` options block. Keys may appear in any order
+    /// and be written `key: value`, `key:value` or `key : value`; a key written
+    /// alone, as in `{ alternate, steps: 5 }`, is a flag.
+    fn parse_options(&mut self, statement: &'static str) -> Option<Options> {
+        self.parse_options_in(statement, "{", "}")
+    }
+
+    /// Parse options between `open` and `close`, as `parse_options` does.
+    fn parse_options_in(&mut self, statement: &'static str, open: &str, close: &str) -> Option<Options> {
+        self.expect(open)?;
+        let mut pairs = BTreeMap::new();
+        let mut flags = BTreeSet::new();
+        loop {
+            let key = match self.next()? {
+                separator if separator == "," => continue,
+                end if end == close => break,
+                key => key,
+            };
+            let key_at = self.cursor - 1;
+            if self.peek().is_some_and(|t| t == "," || t == close) {
+                if !flags.insert(key.to_lowercase()) {
+                    return self.fail(key_at, format!("{}: option `{}` given twice", statement, key));
+                }
+                continue;
             }
-            Statement::NarrateReturn { tokens } => {
-                println!("🗣 {}", tokens.join(" "));
+            if self.peek() != Some(":") {
+                return self.fail(key_at, format!("{}: expected `key: value`", statement));
             }
-            Statement::LogCoherence(name) => {
-                if let Some(f) = fields.get(&name) {
-                    print_vector(&format!("Ψ[{}]", name), &f.state);
-                } else {
-                    eprintln!("⚠️ Unknown field in LogCoherence");
+            self.cursor += 1;
+            // Values such as `dist < 0.01` span several tokens; they end at a
+            // comma, the closing brace or the next `key:`, outside parentheses.
+            let mut value = String::new();
+            let mut depth = 0usize;
+            while let Some(t) = self.peek() {
+                let next_key = self.tokens.get(self.cursor + 1).is_some_and(|t| t.text == ":");
+                if depth == 0 && (t == close || t == "," || next_key) {
+                    break;
+                }
+                match t {
+                    "(" => depth += 1,
+                    ")" => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                let token = &self.tokens[self.cursor];
+                if !value.is_empty() && !self.tokens[self.cursor - 1].touches(token) {
+                    value.push(' ');
                 }
+                value.push_str(&token.text);
+                self.cursor += 1;
+            }
+            if value.is_empty() {
+                return self.fail(key_at, format!("{}: option `{}` has no value", statement, key));
+            }
+            if pairs.insert(key.to_lowercase(), value).is_some() {
+                return self.fail(key_at, format!("{}: option `{}` given twice", statement, key));
+            }
+        }
+        Some(Options { statement, pairs, flags })
+    }
+}
+
+/// Parse a convergence condition such as `dist < 0.01`, `distance < 0.05`
+/// or `trace_distance < tol / 2`.
+fn parse_until(value: &str) -> Option<Expr> {
+    let compact: String = value.split_whitespace().collect();
+    let threshold = compact
+        .strip_prefix("trace_distance")
+        .or_else(|| compact.strip_prefix("distance"))
+        .or_else(|| compact.strip_prefix("dist"))?
+        .strip_prefix('<')?;
+    expr::parse_str(threshold)
+}
+
+/// Options of one statement, consumed key by key so leftovers can be reported.
+struct Options {
+    statement: &'static str,
+    pairs: BTreeMap<String, String>,
+    /// Keys written without a value, such as `alternate`.
+    flags: BTreeSet<String>,
+}
+
+impl Options {
+    fn take(&mut self, key: &str) -> Option<String> {
+        self.pairs.remove(key)
+    }
+
+    fn flag(&mut self, key: &str) -> bool {
+        self.flags.remove(key)
+    }
+
+    /// Take a whole-number option such as a size or a seed.
+    fn count<T: std::str::FromStr>(&mut self, key: &str) -> Result<Option<T>, String> {
+        match self.pairs.remove(key) {
+            Some(v) => v
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("{}: option `{}` expects a whole number, found `{}`", self.statement, key, v)),
+            None => Ok(None),
+        }
+    }
+
+    /// Take a numeric option, written as a number or an expression over
+    /// variables; `default` of `None` makes it required.
+    fn expr(&mut self, key: &str, default: Option<f64>) -> Result<Expr, String> {
+        match self.pairs.remove(key) {
+            Some(v) => expr::parse_str(&v)
+                .ok_or_else(|| format!("{}: option `{}` expects a number, found `{}`", self.statement, key, v)),
+            None => default.map(Expr::from).ok_or_else(|| format!("{}: missing option `{}`", self.statement, key)),
+        }
+    }
+
+    /// Fail if any option was not consumed.
+    fn finish(self) -> Result<(), String> {
+        if self.pairs.is_empty() && self.flags.is_empty() {
+            return Ok(());
+        }
+        let unknown: Vec<&str> = self.pairs.keys().chain(&self.flags).map(|k| k.as_str()).collect();
+        Err(format!("{}: unknown option(s) {}", self.statement, unknown.join(", ")))
+    }
+}
+
+/// Build a hierarchy object from a `level` block; only nested `level` blocks may appear inside.
+/// Levels of a loaded ontology are interpreted through `ontology`.
+fn build_level(level: RecursionLevel, name: &str, body: Vec<Statement>, ontology: &Option<Arc<Ontology>>) -> Result<CategoryObject, String> {
+    let mut obj = CategoryObject::new(level, name).with_ontology(ontology.clone());
+    for stmt in body {
+        match stmt {
+            Statement::Level { level, name, body } => obj.add_subobject(build_level(level, &name, body, ontology)?)?,
+            other => return Err(format!("only `level` blocks are allowed inside {}, found {:?}", name, other)),
+        }
+    }
+    Ok(obj)
+}
+
+/// Turn an evaluated field expression into a new field.
+fn derived_field(name: &str, result: Result<Value, String>) -> Result<Substrate, String> {
+    match result {
+        Ok(Value::Vector(state)) => {
+            let mut field = Substrate::new(state.len());
+            field.state = state;
+            say!("🧮 Derived field {} ({} elements)", name, field.state.len());
+            Ok(field)
+        }
+        Ok(Value::Scalar(_)) => Err(format!("field {}: expression must involve at least one field", name)),
+        Err(e) => Err(format!("field {}: {}", name, e)),
+    }
+}
+
+/// Hard limit on `steps: auto` and `until:` projections that keep going.
+pub const AUTO_MAX_STEPS: usize = 10_000;
+
+/// Coupling and stopping parameters of one `project` statement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProjectSettings {
+    pub alpha: f64,
+    pub noise: f64,
+    pub steps: Option<usize>,
+    pub tolerance: f64,
+    pub until: Option<f64>,
+    pub record: Option<Recording>,
+    pub log_every: Option<usize>,
+}
+
+/// Why a statement's expressions could not be evaluated.
+#[derive(Debug)]
+pub enum EvalError<'a, F> {
+    /// A variable with no value.
+    Unknown(&'a F),
+    /// A size or step count that is not a whole number.
+    NotWhole(String),
+}
+
+impl<'a, F> From<&'a F> for EvalError<'a, F> {
+    fn from(name: &'a F) -> Self {
+        EvalError::Unknown(name)
+    }
+}
+
+/// Evaluate a size or step count, described as `what` if it is not a whole number.
+fn eval_count<'a, F>(
+    expr: &'a FieldExpr<F>,
+    what: &str,
+    value_of: &impl Fn(&F) -> Option<f64>,
+) -> Result<usize, EvalError<'a, F>> {
+    whole_number(expr.scalar(value_of)?, what).map_err(EvalError::NotWhole)
+}
+
+/// The options of a `project` statement as written, over variables named by `F`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectParams<F = String> {
+    pub alpha: FieldExpr<F>,
+    pub noise: FieldExpr<F>,
+    pub steps: Option<FieldExpr<F>>,
+    pub tolerance: FieldExpr<F>,
+    pub until: Option<FieldExpr<F>>,
+    pub record: Option<Recording>,
+    pub log_every: Option<usize>,
+}
+
+impl<F> ProjectParams<F> {
+    /// Evaluate the options against the current variables, failing with the
+    /// first variable that has no value or a step count that is not whole.
+    pub fn evaluate(&self, value_of: &impl Fn(&F) -> Option<f64>) -> Result<ProjectSettings, EvalError<'_, F>> {
+        Ok(ProjectSettings {
+            alpha: self.alpha.scalar(value_of)?,
+            noise: self.noise.scalar(value_of)?,
+            steps: self.steps.as_ref().map(|s| eval_count(s, "option `steps`", value_of)).transpose()?,
+            tolerance: self.tolerance.scalar(value_of)?,
+            until: self.until.as_ref().map(|u| u.scalar(value_of)).transpose()?,
+            record: self.record,
+            log_every: self.log_every,
+        })
+    }
+
+    /// Replace variable names, e.g. with slot indices. Fails with the first unresolved name.
+    pub fn resolve<G>(self, lookup: &mut impl FnMut(&F) -> Option<G>) -> Result<ProjectParams<G>, F> {
+        Ok(ProjectParams {
+            alpha: self.alpha.resolve(lookup)?,
+            noise: self.noise.resolve(lookup)?,
+            steps: self.steps.map(|s| s.resolve(lookup)).transpose()?,
+            tolerance: self.tolerance.resolve(lookup)?,
+            until: self.until.map(|u| u.resolve(lookup)).transpose()?,
+            record: self.record,
+            log_every: self.log_every,
+        })
+    }
+}
+
+impl ProjectSettings {
+    /// Exactly `steps` steps, with nothing recorded or logged.
+    pub fn fixed(alpha: f64, noise: f64, steps: usize) -> Self {
+        ProjectSettings { alpha, noise, steps: Some(steps), tolerance: 0.0, until: None, record: None, log_every: None }
+    }
+
+    /// Whether the number of steps is decided while running.
+    fn is_adaptive(&self) -> bool {
+        self.steps.is_none() || self.until.is_some()
+    }
+}
+
+/// What one projection statement did.
+#[derive(Debug, Clone, Copy)]
+pub struct ProjectionOutcome {
+    pub steps: usize,
+    /// Largest change of any element in the last step; infinite if no step ran.
+    pub delta: f64,
+    /// Trace distance after the last step.
+    pub distance: f64,
+    /// Whether an `until` target was met.
+    pub converged: bool,
+}
+
+/// Project `interp` into `field`. Runs `steps` times, or with `until` until the
+/// trace distance falls below it (`steps` then being the limit), or with neither
+/// until the distance improves by less than `tolerance`.
+/// `observe` is called with the step count after every step.
+fn project_steps(
+    field: &mut Substrate,
+    interp: &Interpretation,
+    s: &ProjectSettings,
+    rng: &mut NoiseRng,
+    mut observe: impl FnMut(usize, &Substrate),
+) -> ProjectionOutcome {
+    let mut delta = f64::INFINITY;
+    let mut distance = trace_distance(field, interp);
+    let mut taken = 0;
+    let mut converged = false;
+    while taken < s.steps.unwrap_or(AUTO_MAX_STEPS) {
+        if s.until.is_some_and(|target| distance < target) {
+            converged = true;
+            break;
+        }
+        let before = field.state.clone();
+        project(field, interp, s.alpha, s.noise, rng);
+        taken += 1;
+        observe(taken, field);
+        delta = before.iter().zip(&field.state).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+        if s.is_adaptive() {
+            let next = trace_distance(field, interp);
+            let improved = distance - next;
+            distance = next;
+            if s.until.is_none() && improved < s.tolerance {
+                break;
             }
-            Statement::LogMeaning(name) => {
-                println!("🧠 Meaning declared: {}", name);
+        }
+    }
+    if s.until.is_some_and(|target| distance < target) {
+        converged = true;
+    }
+    if !s.is_adaptive() {
+        distance = trace_distance(field, interp);
+    }
+    ProjectionOutcome { steps: taken, delta, distance, converged }
+}
+
+/// Run a projection statement; adaptive ones record `<field>.steps` and
+/// `<field>.distance`, and those with `until` also `<field>.converged` (1 if
+/// the target was reached within the step limit, 0 if not). With
+/// `log_every`, progress is printed and journaled.
+fn apply_projection(
+    report: &mut RunReport,
+    step: usize,
+    target: &str,
+    field: &mut Substrate,
+    interp: &Interpretation,
+    settings: &ProjectSettings,
+    rng: &mut NoiseRng,
+) {
+    let outcome = project_steps(field, interp, settings, rng, |n, field| {
+        if settings.log_every.is_some_and(|every| n % every == 0) {
+            let (distance, coherence) = (trace_distance(field, interp), coherence(&field.state, &interp.data));
+            let line = format!("{} step {}: dist = {:.4}, coherence = {:.4}", target, n, distance, coherence);
+            say!("📈 {}", line);
+            report.log(format!("[{}] {}", step, line));
+        }
+        let Some(rec) = settings.record.filter(|r| n % r.every == 0) else { return };
+        match rec.kind {
+            RecordKind::Trajectory => {
+                report.record(step, &format!("{}.trajectory", target), trace_distance(field, interp))
             }
-            Statement::ExpressSymbol {
-                token,
-                into_field,
-            } => {
-                println!("➕ Expressed {} into {}", token, into_field);
+            RecordKind::Snapshots => {
+                for (i, v) in field.state.iter().enumerate() {
+                    report.record(step, &format!("{}[{}]", target, i), *v);
+                }
             }
-            Statement::Modulate { token, intensity } => {
-                println!("🎛 Modulated {} @ {:.2}", token, intensity);
+        }
+    });
+    if settings.is_adaptive() {
+        match settings.until {
+            Some(target_dist) if !outcome.converged => say!(
+                "⏱ Projection into {} stopped after {} steps without reaching dist < {} (dist = {:.4})",
+                target, outcome.steps, target_dist, outcome.distance
+            ),
+            _ => say!(
+                "⏱ Projection into {} stopped after {} steps (dist = {:.4})",
+                target, outcome.steps, outcome.distance
+            ),
+        }
+        report.record(step, &format!("{}.steps", target), outcome.steps as f64);
+        report.record(step, &format!("{}.distance", target), outcome.distance);
+        if settings.until.is_some() {
+            report.record(step, &format!("{}.converged", target), if outcome.converged { 1.0 } else { 0.0 });
+        }
+    }
+    publish_attractor(report, target, outcome.delta, step);
+}
+
+/// The field a multi-step statement projects into, with the name its
+/// messages and telemetry use.
+struct ProjectionTarget<'a> {
+    name: &'a str,
+    field: &'a mut Substrate,
+}
+
+/// Run a `ProjectMany` statement: `settings.steps` steps, alternating
+/// between the interpretations or projecting their weighted mix.
+fn project_many(
+    report: &mut RunReport,
+    step: usize,
+    ProjectionTarget { name: target, field }: ProjectionTarget,
+    interps: &[(&str, &Interpretation, f64)],
+    alternate: bool,
+    settings: &ProjectSettings,
+    rng: &mut NoiseRng,
+) {
+    let size = field.state.len();
+    if let Some((name, interp, _)) = interps.iter().find(|(_, interp, _)| interp.data.len() != size) {
+        warn!("⚠️ Interpretation {} has {} values but field {} has size {}", name, interp.data.len(), target, size);
+        return;
+    }
+    let steps = settings.steps.unwrap_or(0);
+    if alternate {
+        for k in 0..steps {
+            project(field, interps[k % interps.len()].1, settings.alpha, settings.noise, rng);
+        }
+    } else {
+        let total: f64 = interps.iter().map(|(_, _, w)| w).sum();
+        let mut mix = vec![0.0; size];
+        for (_, interp, weight) in interps {
+            for (m, v) in mix.iter_mut().zip(&interp.data) {
+                *m += weight / total * v;
             }
         }
+        let mix = Interpretation::new(mix);
+        for _ in 0..steps {
+            project(field, &mix, settings.alpha, settings.noise, rng);
+        }
+    }
+    let how = if alternate { "alternating" } else { "mixed" };
+    say!("🔀 Projected {} interpretations into {} for {} steps ({})", interps.len(), target, steps, how);
+    for (name, interp, _) in interps {
+        report.record(step, &format!("{}.{}.distance", target, name), trace_distance(field, interp));
+    }
+}
+
+/// Run a `Morph` statement: step `k` of `n` projects the blend of `from`
+/// and `to` that is `(k + 1) / n` of the way to `to`.
+fn morph(
+    report: &mut RunReport,
+    step: usize,
+    ProjectionTarget { name: target, field }: ProjectionTarget,
+    from: &Interpretation,
+    to: &Interpretation,
+    settings: &ProjectSettings,
+    rng: &mut NoiseRng,
+) {
+    let size = field.state.len();
+    if from.data.len() != size || to.data.len() != size {
+        warn!(
+            "⚠️ Morph into {} (size {}) needs interpretations of that size, not {} and {}",
+            target,
+            size,
+            from.data.len(),
+            to.data.len()
+        );
+        return;
+    }
+    let steps = settings.steps.unwrap_or(0);
+    for k in 0..steps {
+        let t = (k + 1) as f64 / steps as f64;
+        let blend = from.data.iter().zip(&to.data).map(|(a, b)| a + t * (b - a)).collect();
+        project(field, &Interpretation::new(blend), settings.alpha, settings.noise, rng);
+    }
+    let distance = trace_distance(field, to);
+    say!("🌗 Morphed the target of {} over {} steps (dist = {:.4})", target, steps, distance);
+    report.record(step, &format!("{}.distance", target), distance);
+}
+
+/// Run a `steer` controller: before each projection step, move alpha toward
+/// whatever side of the bound the metric is on, then record alpha and the
+/// metric as `<field>.alpha` and `<field>.<metric>` telemetry.
+fn steer(
+    report: &mut RunReport,
+    step: usize,
+    ProjectionTarget { name, field }: ProjectionTarget,
+    interp: &Interpretation,
+    target: f64,
+    s: &SteerSettings,
+    rng: &mut NoiseRng,
+) {
+    let metric_name = s.metric.name();
+    let mut alpha = s.alpha_min;
+    let mut held = 0;
+    let mut value = s.metric.compute(field, interp);
+    for _ in 0..s.steps {
+        // How far the metric is on the wrong side of the bound (negative when inside it).
+        let shortfall = match s.keep {
+            Comparison::Greater => target - value,
+            Comparison::Less => value - target,
+        };
+        // More pull raises coherence and lowers distances; a positive error asks for more pull.
+        let error = match (s.metric.is_similarity(), s.keep) {
+            (true, Comparison::Greater) | (false, Comparison::Less) => shortfall,
+            (true, Comparison::Less) | (false, Comparison::Greater) => -shortfall,
+        };
+        alpha = (alpha + STEER_GAIN * error).clamp(s.alpha_min, s.alpha_max);
+        project(field, interp, alpha, s.noise, rng);
+        value = s.metric.compute(field, interp);
+        if s.keep.holds(value, target) {
+            held += 1;
+        }
+        report.record(step, &format!("{}.alpha", name), alpha);
+        report.record(step, &format!("{}.{}", name, metric_name), value);
+    }
+    let held = if s.steps == 0 { 0.0 } else { held as f64 / s.steps as f64 };
+    report.record(step, &format!("{}.held", name), held);
+    say!(
+        "🎯 Steered {}: {} = {:.4}, alpha = {:.3}, bound held {:.0}% of steps",
+        name,
+        metric_name,
+        value,
+        alpha,
+        held * 100.0
+    );
+}
+
+/// Record a `let` result as a trace, like `trace` does.
+fn bind_metric(report: &mut RunReport, step: usize, name: &str, value: f64) {
+    say!("📏 {} = {:.4}", name, value);
+    report.record(step, name, value);
+    report.events.publish(Event::TraceComputed { name: name.to_string(), value, tau: step as u64 });
+}
+
+/// Values of the `let` variables bound so far.
+fn lookup(vars: &HashMap<String, f64>) -> impl Fn(&String) -> Option<f64> + '_ {
+    |name| vars.get(name).copied()
+}
+
+/// Execute `body`, the script of a `run` statement, on `rt` with variables
+/// of its own. It works on the caller's fields and interpretations for the
+/// kinds in `sharing`; of the others it starts with none, and what it makes
+/// is dropped when it ends. Its statements are journaled under `step`.
+fn run_script(path: &str, sharing: &[Shared], body: Vec<Statement>, step: usize, rt: &mut Runtime, report: &mut RunReport) {
+    let shared: Vec<&str> = sharing.iter().map(|s| s.name()).collect();
+    if shared.is_empty() {
+        say!("▶️ Running {}", path);
+    } else {
+        say!("▶️ Running {} sharing {}", path, shared.join(", "));
+    }
+    let fields = (!sharing.contains(&Shared::Fields)).then(|| std::mem::take(&mut rt.fields));
+    let interps = (!sharing.contains(&Shared::Interpretations)).then(|| std::mem::take(&mut rt.interps));
+    let mut env = Env { rt, vars: HashMap::new() };
+    for stmt in body {
+        report.log(format!("[{}] {}: {:?}", step, path, stmt));
+        execute_statement(stmt, step, &mut env, report);
+    }
+    if let Some(fields) = fields {
+        env.rt.fields = fields;
+    }
+    if let Some(interps) = interps {
+        env.rt.interps = interps;
+    }
+}
+
+/// Run a `require` statement. The checker rejects scripts that need what
+/// this build lacks, so this only warns programs run unchecked.
+fn require(capability: Capability) {
+    if !capability.available() {
+        warn!("⚠️ This build does not include {} (feature `{}`)", capability.description(), capability);
+    }
+}
+
+/// Report a numeric option that names a variable without a value.
+fn unknown_variable(name: impl fmt::Display, statement: &str) {
+    warn!("⚠️ Unknown variable {} in {}", name, statement);
+}
+
+/// Judge a meaning against the current trace value, record the outcome and
+/// return the value the meaning binds as a variable.
+fn evaluate_meaning(report: &mut RunReport, name: &str, trace_cmp: &str, value: f64, threshold: f64) -> f64 {
+    let holds = value < threshold;
+    let verdict = if holds { "✅ holds" } else { "❌ does not hold" };
+    say!("💡 Meaning {} {}: {} = {:.4}, threshold {}", name, verdict, trace_cmp, value, threshold);
+    report.meanings.insert(name.to_string(), holds);
+    if holds { 1.0 } else { 0.0 }
+}
+
+/// Check the latest value of `trace` against `bound`, loosened by `tolerance`,
+/// and record the outcome. An unknown trace fails the assertion.
+fn check_assertion(report: &mut RunReport, step: usize, trace: &str, cmp: Comparison, bound: f64, tolerance: f64) {
+    let actual = report.traces.get(trace).copied();
+    let limit = match cmp {
+        Comparison::Less => bound + tolerance,
+        Comparison::Greater => bound - tolerance,
+    };
+    let passed = actual.is_some_and(|value| cmp.holds(value, limit));
+    let expected = format!("{} {} {} within {}", trace, cmp.symbol(), bound, tolerance);
+    match actual {
+        Some(value) if passed => say!("✅ Assert {} passed: {} = {:.6}", expected, trace, value),
+        Some(value) => say!("❌ Assert {} failed: {} = {:.6}", expected, trace, value),
+        None => say!("❌ Assert {} failed: trace {} was never recorded", expected, trace),
+    }
+    report.assertions.push(Assertion {
+        step,
+        trace: trace.to_string(),
+        comparison: cmp.symbol().to_string(),
+        expected: bound,
+        tolerance,
+        actual,
+        passed,
+    });
+}
+
+/// Field `name` as the checkpoint at `path` holds it.
+fn restore_field(name: &str, path: &str) -> Option<Substrate> {
+    match Checkpoint::load(Path::new(path), name) {
+        Ok(checkpoint) => {
+            say!("📂 Restored {} from {} ({} values)", name, path, checkpoint.state.len());
+            let mut field = Substrate::new(checkpoint.state.len());
+            field.state = checkpoint.state;
+            Some(field)
+        }
+        Err(e) => {
+            warn!("⚠️ Cannot restore field {}: {}", name, e);
+            None
+        }
+    }
+}
+
+/// Write `token` into `field` as `expresssymbol` does.
+fn express(field: &mut Substrate, token: &str, encoding: Encoding, weight: f64, blend: bool, symbols: &mut SymbolRegistry) {
+    let values = encode(token, encoding, field.state.len(), symbols);
+    for (s, v) in field.state.iter_mut().zip(values) {
+        *s = if blend { (1.0 - weight) * *s + weight * v } else { *s + weight * v };
+    }
+}
+
+/// The pattern `why pattern` finds an expressed token under: its bitstring,
+/// or the token itself when one-hot encoded.
+fn expressed_pattern(token: &str, encoding: Encoding) -> String {
+    match encoding {
+        Encoding::Bits => bitstring(token),
+        Encoding::OneHot => token.to_string(),
+    }
+}
+
+fn decay_field(field: &mut Substrate, name: &str, rate: f64, steps: usize) {
+    if !(0.0..=1.0).contains(&rate) {
+        warn!("⚠️ Decay rate {} of {} is outside [0, 1]", rate, name);
+        return;
+    }
+    field.decay_state(rate, steps);
+    say!("🍂 Decayed {} by {} for {} step(s)", name, rate, steps);
+}
+
+fn log_meaning(report: &RunReport, name: &str) {
+    match report.meanings.get(name) {
+        Some(holds) => say!("🧠 Meaning {} = {}", name, holds),
+        None => warn!("⚠️ Meaning {} has not been evaluated", name),
+    }
+}
+
+fn publish_attractor(report: &mut RunReport, field: &str, delta: f64, step: usize) {
+    if delta < ATTRACTOR_EPSILON {
+        report.events.publish(Event::AttractorReached { field: field.to_string(), delta, tau: step as u64 });
+    }
+}
+
+/// Execute a program on an empty runtime.
+pub fn execute_program(program: Vec<Statement>) -> RunReport {
+    let mut env = ExecutionEnv::new();
+    env.exec(program);
+    env.into_report()
+}
+
+/// Interpreter state shared by a program and the blocks nested in it.
+struct Env<'r> {
+    rt: &'r mut Runtime,
+    /// Numeric variables bound by `let`, `trace`, `meaning` and `const`.
+    vars: HashMap<String, f64>,
+}
+
+/// Interpreter state kept between statements executed one after another:
+/// the runtime with its fields, interpretations and RNG, numeric variables,
+/// the report so far, and the step the next statement is journaled under.
+/// A host program can run a script, read or change the resulting fields,
+/// and carry on with more statements; `sptl repl` runs every entry on one.
+#[derive(Default)]
+pub struct ExecutionEnv {
+    pub rt: Runtime,
+    pub vars: HashMap<String, f64>,
+    pub report: RunReport,
+    pub step: usize,
+}
+
+impl ExecutionEnv {
+    pub fn new() -> Self {
+        ExecutionEnv::default()
+    }
+
+    /// An environment on a runtime that may already hold fields, agents or hierarchies.
+    pub fn with_runtime(rt: Runtime) -> Self {
+        ExecutionEnv { rt, ..ExecutionEnv::default() }
+    }
+
+    /// Execute one top-level statement as a continuation of everything
+    /// executed before. Unlike `exec`, it leaves `report.fields` as it was.
+    pub fn exec_statement(&mut self, stmt: Statement) {
+        self.report.log(format!("[{}] {:?}", self.step, stmt));
+        let mut env = Env { rt: &mut self.rt, vars: std::mem::take(&mut self.vars) };
+        execute_statement(stmt, self.step, &mut env, &mut self.report);
+        self.vars = env.vars;
+        self.step += 1;
+    }
+
+    /// Execute `program` as a continuation of everything executed before,
+    /// then record the fields and RNG draws in the report.
+    pub fn exec(&mut self, program: Vec<Statement>) {
+        for stmt in program {
+            self.exec_statement(stmt);
+        }
+        self.report.fields = self.rt.field_states();
+        self.report.rng_draws = self.rt.rng.draws();
+        self.report.docs = self.rt.docs.clone();
+    }
+
+    /// Parse `source` with default settings and execute it.
+    pub fn exec_source(&mut self, source: &str) -> Result<(), Vec<ParseError>> {
+        let program = parse_source(source, &BTreeMap::new())?;
+        self.exec(program);
+        Ok(())
+    }
+
+    /// Current values of field `name`.
+    pub fn field(&self, name: &str) -> Option<&[f64]> {
+        self.rt.fields.get(name).map(|f| f.state.as_slice())
+    }
+
+    /// Field `name`, to change in place; a copy if another runtime shares it.
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut Substrate> {
+        self.rt.fields.get_mut(name).map(Arc::make_mut)
+    }
+
+    /// Create or replace field `name`.
+    pub fn set_field(&mut self, name: &str, field: Substrate) {
+        self.rt.fields.insert(name.to_string(), Arc::new(field));
+    }
+
+    pub fn interp(&self, name: &str) -> Option<&Interpretation> {
+        self.rt.interps.get(name)
+    }
+
+    /// Create or replace interpretation `name`.
+    pub fn set_interp(&mut self, name: &str, values: Vec<f64>) {
+        self.rt.interps.insert(name.to_string(), Interpretation::new(values));
+    }
+
+    /// Latest value of trace `name`.
+    pub fn trace(&self, name: &str) -> Option<f64> {
+        self.report.traces.get(name).copied()
+    }
+
+    pub fn var(&self, name: &str) -> Option<f64> {
+        self.vars.get(name).copied()
+    }
+
+    /// Bind `name` for later statements, as `let name = value` would.
+    pub fn set_var(&mut self, name: &str, value: f64) {
+        self.vars.insert(name.to_string(), value);
+    }
+
+    /// The noise RNG, to reseed or draw from between statements.
+    pub fn rng(&mut self) -> &mut NoiseRng {
+        &mut self.rt.rng
+    }
+
+    /// End the run: flush console summaries and return the report with the final fields.
+    pub fn into_report(mut self) -> RunReport {
+        console::finish();
+        self.report.fields = self.rt.field_states();
+        self.report.rng_draws = self.rt.rng.draws();
+        self.report.docs = self.rt.docs.clone();
+        self.report
+    }
+}
+
+/// Execute a program, recording results into an existing (possibly streaming) report.
+pub fn execute_program_into(program: Vec<Statement>, report: &mut RunReport) {
+    execute_program_in(program, &mut Runtime::default(), report);
+}
+
+/// Execute a program on a runtime that may already hold fields, agents or
+/// hierarchies, e.g. the world of a narrative script. `report.fields` gets
+/// every field of the runtime afterwards.
+pub fn execute_program_in(program: Vec<Statement>, rt: &mut Runtime, report: &mut RunReport) {
+    let mut env = Env { rt, vars: HashMap::new() };
+    for (step, stmt) in program.into_iter().enumerate() {
+        report.log(format!("[{}] {:?}", step, stmt));
+        execute_statement(stmt, step, &mut env, report);
     }
+    console::finish();
+    report.fields = env.rt.field_states();
+    report.rng_draws = env.rt.rng.draws();
+    report.docs = env.rt.docs.clone();
 }
 
+/// Execute one statement. Statements nested in a block report the step of
+/// the top-level statement containing them.
+fn execute_statement(stmt: Statement, step: usize, env: &mut Env, report: &mut RunReport) {
+    match stmt {
+        Statement::Field { name, size, doc } => match eval_count(&size, "size", &lookup(&env.vars)) {
+            Ok(size) => {
+                env.rt.document(&name, doc.as_deref());
+                env.rt.fields.insert(name, Arc::new(Substrate::new(size)));
+            }
+            Err(EvalError::Unknown(var)) => unknown_variable(var, "Field"),
+            Err(EvalError::NotWhole(e)) => warn!("⚠️ field {}: {}", name, e),
+        },
+        Statement::DeriveField { name, expr } => {
+            let result = expr.eval(&|f: &String| {
+                env.rt.fields
+                    .get(f)
+                    .map(|s| Value::Vector(s.state.clone()))
+                    .or_else(|| env.vars.get(f).map(|v| Value::Scalar(*v)))
+            });
+            match derived_field(&name, result) {
+                Ok(field) => {
+                    env.rt.fields.insert(name, Arc::new(field));
+                }
+                Err(e) => warn!("⚠️ {}", e),
+            }
+        }
+        Statement::SliceField { name, source, start, end } => match env.rt.fields.get(&source) {
+            Some(field) => match field.slice(start..end) {
+                Ok(slice) => {
+                    say!("✂️ Sliced {}[{}..{}] into {}", source, start, end, name);
+                    env.rt.fields.insert(name, Arc::new(slice));
+                }
+                Err(e) => warn!("⚠️ field {}: {}", name, e),
+            },
+            None => warn!("⚠️ Unknown field in Slice"),
+        },
+        Statement::ConcatFields { name, parts } => {
+            let fields: Option<Vec<&Substrate>> = parts.iter().map(|p| env.rt.fields.get(p).map(|f| &**f)).collect();
+            match fields {
+                Some(fields) => {
+                    let joined = Substrate::concat(&fields);
+                    say!("🔗 Concatenated {} into {} ({} elements)", parts.join(", "), name, joined.state.len());
+                    env.rt.fields.insert(name, Arc::new(joined));
+                }
+                None => warn!("⚠️ Unknown field in Concat"),
+            }
+        }
+        Statement::FieldFromCheckpoint { name, path } => {
+            if let Some(field) = restore_field(&name, &path) {
+                env.rt.fields.insert(name, Arc::new(field));
+            }
+        }
+        Statement::Interpretation { name, values, doc } => {
+            let value_of = lookup(&env.vars);
+            match values.iter().map(|v| v.scalar(&value_of)).collect::<Result<Vec<_>, _>>() {
+                Ok(values) => {
+                    env.rt.document(&name, doc.as_deref());
+                    env.rt.interps.insert(name, Interpretation::new(values));
+                }
+                Err(unknown) => unknown_variable(unknown, "Interpretation"),
+            }
+        }
+        Statement::GenerateInterpretation { name, generator } => {
+            match generator.generate(&lookup(&env.vars), &mut env.rt.rng) {
+                Ok(values) => {
+                    env.rt.interps.insert(name, Interpretation::new(values));
+                }
+                Err(unknown) => unknown_variable(unknown, "Interpretation"),
+            }
+        }
+        Statement::Project {
+            target,
+            interp,
+            alpha,
+            noise,
+            steps,
+            tolerance,
+            until,
+            record,
+            log_every,
+        } => {
+            if let (Some(field), Some(interp_val)) =
+                (env.rt.fields.get_mut(&target).map(Arc::make_mut), env.rt.interps.get(&interp))
+            {
+                let params = ProjectParams { alpha, noise, steps, tolerance, until, record, log_every };
+                match params.evaluate(&lookup(&env.vars)) {
+                    Ok(settings) => apply_projection(report, step, &target, field, interp_val, &settings, env.rt.rng.at("project")),
+                    Err(EvalError::Unknown(name)) => unknown_variable(name, "Project"),
+                    Err(EvalError::NotWhole(e)) => warn!("⚠️ project: {}", e),
+                }
+            } else {
+                warn!("⚠️ Unknown field or interpretation in Project");
+            }
+        }
+        Statement::ProjectMany { target, interps, alternate, alpha, noise, steps } => {
+            let resolved: Option<Vec<_>> = interps
+                .iter()
+                .map(|(name, weight)| env.rt.interps.get(name).map(|i| (name.as_str(), i.clone(), *weight)))
+                .collect();
+            let (Some(resolved), Some(field)) = (resolved, env.rt.fields.get_mut(&target).map(Arc::make_mut)) else {
+                warn!("⚠️ Unknown field or interpretation in Project");
+                return;
+            };
+            let settings = (alpha.scalar(&lookup(&env.vars)), noise.scalar(&lookup(&env.vars)));
+            match settings {
+                (Ok(alpha), Ok(noise)) => {
+                    let settings = ProjectSettings::fixed(alpha, noise, steps);
+                    let interps: Vec<_> = resolved.iter().map(|(name, i, w)| (*name, i, *w)).collect();
+                    let target = ProjectionTarget { name: &target, field };
+                    project_many(report, step, target, &interps, alternate, &settings, env.rt.rng.at("project"));
+                }
+                (Err(name), _) | (_, Err(name)) => unknown_variable(name, "Project"),
+            }
+        }
+        Statement::Morph { target, from, to, alpha, noise, steps } => {
+            let interps = (env.rt.interps.get(&from).cloned(), env.rt.interps.get(&to).cloned());
+            let ((Some(from), Some(to)), Some(field)) = (interps, env.rt.fields.get_mut(&target).map(Arc::make_mut)) else {
+                warn!("⚠️ Unknown field or interpretation in Morph");
+                return;
+            };
+            match (alpha.scalar(&lookup(&env.vars)), noise.scalar(&lookup(&env.vars))) {
+                (Ok(alpha), Ok(noise)) => {
+                    let settings = ProjectSettings::fixed(alpha, noise, steps);
+                    let target = ProjectionTarget { name: &target, field };
+                    morph(report, step, target, &from, &to, &settings, env.rt.rng.at("project"));
+                }
+                (Err(name), _) | (_, Err(name)) => unknown_variable(name, "Morph"),
+            }
+        }
+        Statement::TraceDistance {
+            name,
+            field,
+            interp,
+        } => {
+            if let (Some(f), Some(i)) = (env.rt.fields.get(&field), env.rt.interps.get(&interp)) {
+                let result = trace_distance(f, i);
+                say!("Trace {} = {:.4}", name, result);
+                report.record(step, &name, result);
+                env.vars.insert(name.clone(), result);
+                env.rt.measurements.insert(name.clone(), result);
+                report.events.publish(Event::TraceComputed { name, value: result, tau: step as u64 });
+            } else {
+                warn!("⚠️ Unknown field or interpretation in TraceDistance");
+            }
+        }
+        Statement::Let { name, metric, field, interp } => {
+            if let (Some(f), Some(i)) = (env.rt.fields.get(&field), env.rt.interps.get(&interp)) {
+                let value = metric.compute(f, i);
+                bind_metric(report, step, &name, value);
+                env.rt.measurements.insert(name.clone(), value);
+                env.vars.insert(name, value);
+            } else {
+                warn!("⚠️ Unknown field or interpretation in Let");
+            }
+        }
+        Statement::Assign { name, value } => {
+            let result = value.scalar(&lookup(&env.vars));
+            match result {
+                Ok(value) => {
+                    env.vars.insert(name, value);
+                }
+                Err(unknown) => unknown_variable(unknown, "Let"),
+            }
+        }
+        Statement::Const { name, value } => {
+            env.vars.insert(name, value);
+        }
+        Statement::Meaning {
+            name,
+            trace_cmp,
+            threshold,
+        } => {
+            let result = match env.vars.get(&trace_cmp) {
+                Some(&value) => threshold
+                    .scalar(&lookup(&env.vars))
+                    .map(|threshold| Some(evaluate_meaning(report, &name, &trace_cmp, value, threshold))),
+                None => Ok(None),
+            };
+            match result {
+                Ok(Some(holds)) => {
+                    env.vars.insert(name, holds);
+                }
+                Ok(None) => warn!("⚠️ Unknown trace {} in Meaning", trace_cmp),
+                Err(unknown) => unknown_variable(unknown, "Meaning"),
+            }
+        }
+        Statement::Assert { trace, cmp, bound, tolerance } => match bound.scalar(&lookup(&env.vars)) {
+            Ok(bound) => check_assertion(report, step, &trace, cmp, bound, tolerance),
+            Err(unknown) => unknown_variable(unknown, "Assert"),
+        },
+        Statement::NarrateReturn { tokens } => {
+            say!("🗣 {}", tokens.join(" "));
+        }
+        Statement::Steer { field, interp, target, settings } => {
+            if let (Some(f), Some(i)) = (env.rt.fields.get_mut(&field).map(Arc::make_mut), env.rt.interps.get(&interp)) {
+                match target.scalar(&lookup(&env.vars)) {
+                    Ok(target) => {
+                        let steered = ProjectionTarget { name: &field, field: f };
+                        steer(report, step, steered, i, target, &settings, env.rt.rng.at("steer"))
+                    }
+                    Err(unknown) => unknown_variable(unknown, "Steer"),
+                }
+            } else {
+                warn!("⚠️ Unknown field or interpretation in Steer");
+            }
+        }
+        Statement::Perturb { field, amplitude } => match env.rt.fields.get_mut(&field).map(Arc::make_mut) {
+            Some(f) => match amplitude.scalar(&lookup(&env.vars)) {
+                Ok(amplitude) => {
+                    perturb(f, amplitude, env.rt.rng.at("perturb"));
+                    say!("🌪 Perturbed {} with noise {}", field, amplitude);
+                }
+                Err(unknown) => unknown_variable(unknown, "Perturb"),
+            },
+            None => warn!("⚠️ Unknown field in Perturb"),
+        },
+        Statement::Shock { field, start, end, value } => match env.rt.fields.get_mut(&field).map(Arc::make_mut) {
+            Some(f) => match value.scalar(&lookup(&env.vars)) {
+                Ok(value) => match shock(f, start..end, value) {
+                    Ok(()) => say!("⚡ Shocked {}[{}..{}] = {}", field, start, end, value),
+                    Err(e) => warn!("⚠️ {}", e),
+                },
+                Err(unknown) => unknown_variable(unknown, "Shock"),
+            },
+            None => warn!("⚠️ Unknown field in Shock"),
+        },
+        Statement::AddFields { left, right, into } => match (env.rt.fields.get(&left), env.rt.fields.get(&right)) {
+            (Some(a), Some(b)) => match a.add(b) {
+                Ok(sum) => {
+                    say!("➕ Added {} + {} into {}", left, right, into);
+                    env.rt.fields.insert(into, Arc::new(sum));
+                }
+                Err(e) => warn!("⚠️ {}", e),
+            },
+            _ => warn!("⚠️ Unknown field in Add"),
+        },
+        Statement::Scale { field, factor } => match env.rt.fields.get_mut(&field).map(Arc::make_mut) {
+            Some(f) => match factor.scalar(&lookup(&env.vars)) {
+                Ok(factor) => {
+                    f.scale(factor);
+                    say!("✖️ Scaled {} by {}", field, factor);
+                }
+                Err(unknown) => unknown_variable(unknown, "Scale"),
+            },
+            None => warn!("⚠️ Unknown field in Scale"),
+        },
+        Statement::Decay { field, rate, steps } => match env.rt.fields.get_mut(&field).map(Arc::make_mut) {
+            Some(f) => match rate.scalar(&lookup(&env.vars)) {
+                Ok(rate) => decay_field(f, &field, rate, steps),
+                Err(unknown) => unknown_variable(unknown, "Decay"),
+            },
+            None => warn!("⚠️ Unknown field in Decay"),
+        },
+        Statement::Normalize { field } => match env.rt.fields.get_mut(&field).map(Arc::make_mut) {
+            Some(f) => {
+                let norm = f.normalize();
+                say!("📐 Normalized {} (norm was {:.4})", field, norm);
+            }
+            None => warn!("⚠️ Unknown field in Normalize"),
+        },
+        Statement::LogCoherence(name) => {
+            if let Some(f) = env.rt.fields.get(&name) {
+                print_vector(&format!("Ψ[{}]", name), &f.state);
+            } else {
+                warn!("⚠️ Unknown field in LogCoherence");
+            }
+        }
+        Statement::LogMeaning(name) => log_meaning(report, &name),
+        Statement::ExpressSymbol { token, into_field, encoding, weight, blend } => {
+            let gain = env.rt.gain(&token);
+            let Some(field) = env.rt.fields.get_mut(&into_field).map(Arc::make_mut) else {
+                warn!("⚠️ Unknown field in ExpressSymbol");
+                return;
+            };
+            match weight.scalar(&lookup(&env.vars)) {
+                Ok(weight) => {
+                    express(field, &token, encoding, weight * gain, blend, &mut env.rt.symbols);
+                    say!("➕ Expressed {} into {}", token, into_field);
+                    env.rt.provenance.activated(&into_field, &expressed_pattern(&token, encoding), env.rt.tau);
+                    report.events.publish(Event::SymbolExpressed { source: into_field, token, tau: step as u64 });
+                }
+                Err(unknown) => unknown_variable(unknown, "ExpressSymbol"),
+            }
+        }
+        Statement::Modulate { token, intensity } => match intensity.scalar(&lookup(&env.vars)) {
+            Ok(intensity) => {
+                env.rt.modulate(&token, intensity);
+                say!("🎛 Modulated {} @ {:.2}", token, intensity);
+            }
+            Err(unknown) => unknown_variable(unknown, "Modulate"),
+        },
+        Statement::Level { level, name, body } => match build_level(level, &name, body, &env.rt.ontology) {
+            Ok(obj) => {
+                say!("🧬 Level {} {} with {} parts", obj.level_name(), name, obj.subobjects.len());
+                env.rt.hierarchies.insert(name, obj);
+            }
+            Err(e) => warn!("⚠️ {}", e),
+        },
+        Statement::Include(path) => warn!("⚠️ include {} was not resolved before execution", path),
+        Statement::Run { path, sharing, body } => run_script(&path, &sharing, body, step, env.rt, report),
+        Statement::Require(capability) => require(capability),
+        Statement::Snapshot { field, name } => match env.rt.fields.get(&field) {
+            Some(f) => {
+                env.rt.interps.insert(name.clone(), Interpretation::new(f.state.clone()));
+                say!("📸 Snapshot {} as {}", field, name);
+            }
+            None => warn!("⚠️ Unknown field in Snapshot"),
+        },
+        Statement::Export { kind, name, path } => {
+            let field = env.rt.fields.get(&name).map(|f| f.state.as_slice());
+            export::export(report, step, kind, &name, &path, field);
+        }
+        Statement::Report { path } => {
+            let fields = env.rt.fields.iter().map(|(name, f)| (name.as_str(), f.state.as_slice()));
+            export::report(&export::RunSummary::new(step, fields, &report.traces, &env.rt.docs), path.as_deref());
+        }
+        Statement::Seed(seed) => {
+            env.rt.rng.reseed(seed);
+            say!("🎲 Seed {}", seed);
+        }
+        Statement::Repeat { count, body } => {
+            for _ in 0..count {
+                for stmt in &body {
+                    execute_statement(stmt.clone(), step, env, report);
+                }
+            }
+        }
+        Statement::If { condition, then, otherwise } => {
+            let holds = condition.eval(&lookup(&env.vars));
+            match holds {
+                Ok(holds) => {
+                    for stmt in if holds { then } else { otherwise } {
+                        execute_statement(stmt, step, env, report);
+                    }
+                }
+                Err(unknown) => warn!("⚠️ Unknown {} in If", unknown),
+            }
+        }
+    }
+}
@@ -0,0 +1,483 @@
+//! Static pass run between parsing and execution.
+//!
+//! Resolves what can be known without running the program: references to
+//! names that are never declared, interpretation/field size mismatches (the
+//! projection silently truncates to the shorter of the two), projections that
+//! cannot change anything, numeric options reading variables that are never
+//! bound, results that are produced but never read, and optional subsystems
+//! the script needs but this build lacks (see `crate::capability`).
+//!
+//! Errors found here stop a script before its first statement runs, rather
+//! than surfacing as `⚠️` lines halfway through; `check` reports them
+//! without optimizing.
+
+use super::export::{capability_of, ExportKind};
+use super::{Shared, Statement};
+use crate::capability::Capability;
+use crate::rundir::Checkpoint;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// Index of the statement in the program as parsed.
+    pub step: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: statement {}: {}", kind, self.step, self.message)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Optimized {
+    pub program: Vec<Statement>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Statements dropped because they have no effect.
+    pub removed: usize,
+    /// Optional subsystems the program uses that this build lacks; each also
+    /// has an error in `diagnostics`.
+    pub missing: Vec<Capability>,
+}
+
+impl Optimized {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.severity == Severity::Error)
+    }
+}
+
+/// Names declared so far and results not yet consumed, with the step that produced them.
+#[derive(Default)]
+struct Checker {
+    diagnostics: Vec<Diagnostic>,
+    removed: usize,
+    /// Sizes by name; `None` for a size computed from variables, such as
+    /// `field psi n`, which is only known when the program runs.
+    field_sizes: HashMap<String, Option<usize>>,
+    interp_sizes: HashMap<String, Option<usize>>,
+    unused_fields: HashMap<String, usize>,
+    unused_interps: HashMap<String, usize>,
+    traces: HashMap<String, usize>,
+    read_traces: HashSet<String>,
+    /// Fields projected into or steered, whose telemetry (`psi.distance`,
+    /// `psi.trajectory`, `psi[0]`, ...) may be exported as traces.
+    recorded: HashSet<String>,
+    meanings: HashMap<String, usize>,
+    logged_meanings: HashSet<String>,
+    vars: HashSet<String>,
+    consts: HashSet<String>,
+    missing: BTreeSet<Capability>,
+}
+
+/// Every problem `optimize` would report, leaving the program as it is.
+pub fn check(program: &[Statement]) -> Vec<Diagnostic> {
+    optimize(program.to_vec()).diagnostics
+}
+
+/// Check `program` and drop statements with no effect.
+pub fn optimize(program: Vec<Statement>) -> Optimized {
+    optimize_bound(program, &[])
+}
+
+/// Like `optimize`, with the `bound` variables counting as bound before the
+/// first statement, as they are in a program compiled with `vm::compile_bound`.
+pub fn optimize_bound(program: Vec<Statement>, bound: &[String]) -> Optimized {
+    let mut checker = Checker { vars: bound.iter().cloned().collect(), ..Default::default() };
+    let program = program
+        .into_iter()
+        .enumerate()
+        .filter_map(|(step, stmt)| checker.statement(step, stmt))
+        .collect();
+    checker.finish(program)
+}
+
+impl Checker {
+    fn diag(&mut self, step: usize, severity: Severity, message: String) {
+        self.diagnostics.push(Diagnostic { step, severity, message });
+    }
+
+    /// Note that `usage` needs `capability`, an error if this build lacks it.
+    fn require(&mut self, step: usize, capability: Capability, usage: String) {
+        if !capability.available() {
+            self.missing.insert(capability);
+            let message = format!(
+                "{} needs {} (feature `{}`), which this build does not include",
+                usage,
+                capability.description(),
+                capability
+            );
+            self.diag(step, Severity::Error, message);
+        }
+    }
+
+    /// Bind a variable, which must not name a constant.
+    fn bind(&mut self, step: usize, name: &str) {
+        if self.consts.contains(name) {
+            self.diag(step, Severity::Error, format!("constant {} cannot be reassigned", name));
+        }
+        self.vars.insert(name.to_string());
+    }
+
+    /// Check one statement; `None` if it is removed. Statements nested in a
+    /// block are reported at the step of the top-level statement.
+    fn statement(&mut self, step: usize, stmt: Statement) -> Option<Statement> {
+        for var in stmt.numbers().into_iter().flat_map(|e| e.fields()) {
+            if !self.vars.contains(var) {
+                self.diag(step, Severity::Error, format!("unknown variable {}", var));
+            }
+        }
+        let stmt = match stmt {
+            Statement::Repeat { count: 0, .. } => {
+                self.diag(step, Severity::Warning, "repeat 0 never runs its body; removed".to_string());
+                self.removed += 1;
+                return None;
+            }
+            Statement::Repeat { count, body } => {
+                // Checked as one pass over the body; later iterations see the same names.
+                let body = body.into_iter().filter_map(|s| self.statement(step, s)).collect();
+                return Some(Statement::Repeat { count, body });
+            }
+            Statement::If { condition, then, otherwise } => {
+                for name in condition.numbers().into_iter().flat_map(|e| e.fields()) {
+                    self.read_traces.insert(name.clone());
+                }
+                // Branches are checked one after the other, as if both ran.
+                let then = then.into_iter().filter_map(|s| self.statement(step, s)).collect();
+                let otherwise = otherwise.into_iter().filter_map(|s| self.statement(step, s)).collect();
+                return Some(Statement::If { condition, then, otherwise });
+            }
+            other => other,
+        };
+        if let Statement::Export { path, .. } = &stmt {
+            if let Some(capability) = capability_of(path) {
+                self.require(step, capability, format!("export to {}", path));
+            }
+        }
+        match &stmt {
+            Statement::Field { name, size, .. } => {
+                if let Some(prev) = self.unused_fields.insert(name.clone(), step) {
+                    self.diag(prev, Severity::Warning, format!("field {} is redeclared at statement {} before use", name, step));
+                }
+                // `validate` has checked that a literal size is a whole number.
+                self.field_sizes.insert(name.clone(), size.as_literal().map(|n| n as usize));
+            }
+            Statement::FieldFromCheckpoint { name, path } => match Checkpoint::load(Path::new(path), name) {
+                Ok(checkpoint) => {
+                    if let Some(prev) = self.unused_fields.insert(name.clone(), step) {
+                        self.diag(prev, Severity::Warning, format!("field {} is redeclared at statement {} before use", name, step));
+                    }
+                    self.field_sizes.insert(name.clone(), Some(checkpoint.state.len()));
+                }
+                Err(e) => self.diag(step, Severity::Error, format!("field {}: cannot read checkpoint: {}", name, e)),
+            },
+            Statement::DeriveField { name, expr } => {
+                for f in expr.fields() {
+                    self.unused_fields.remove(f);
+                }
+                // Sizes can only be compared once every operand's is known.
+                if expr.fields().iter().any(|f| self.field_sizes.get(*f) == Some(&None)) {
+                    self.unused_fields.insert(name.clone(), step);
+                    self.field_sizes.insert(name.clone(), None);
+                    return Some(stmt);
+                }
+                let size_of =
+                    |f: &String| self.field_sizes.get(f).copied().or_else(|| self.vars.contains(f).then_some(None));
+                let size = expr.size(&size_of);
+                match size {
+                    Ok(Some(size)) => {
+                        self.unused_fields.insert(name.clone(), step);
+                        self.field_sizes.insert(name.clone(), Some(size));
+                    }
+                    Ok(None) => self.diag(step, Severity::Error, format!("field {}: expression must involve at least one field", name)),
+                    Err(e) => self.diag(step, Severity::Error, format!("field {}: {}", name, e)),
+                }
+            }
+            Statement::SliceField { name, source, start, end } => {
+                self.unused_fields.remove(source);
+                match self.field_sizes.get(source).copied() {
+                    None => self.diag(step, Severity::Error, format!("unknown field {}", source)),
+                    Some(Some(size)) if *end > size => self.diag(
+                        step,
+                        Severity::Error,
+                        format!("field {}: slice ends at {} but field {} has size {}", name, end, source, size),
+                    ),
+                    Some(_) => {
+                        self.unused_fields.insert(name.clone(), step);
+                        self.field_sizes.insert(name.clone(), Some(end - start));
+                    }
+                }
+            }
+            Statement::ConcatFields { name, parts } => {
+                for part in parts {
+                    self.unused_fields.remove(part);
+                }
+                match parts.iter().find(|p| !self.field_sizes.contains_key(*p)) {
+                    Some(missing) => self.diag(step, Severity::Error, format!("unknown field {}", missing)),
+                    None => {
+                        let size = parts.iter().map(|p| self.field_sizes[p]).sum();
+                        self.unused_fields.insert(name.clone(), step);
+                        self.field_sizes.insert(name.clone(), size);
+                    }
+                }
+            }
+            Statement::Interpretation { name, values, .. } => {
+                self.unused_interps.insert(name.clone(), step);
+                self.interp_sizes.insert(name.clone(), Some(values.len()));
+            }
+            Statement::GenerateInterpretation { name, generator } => {
+                self.unused_interps.insert(name.clone(), step);
+                self.interp_sizes.insert(name.clone(), Some(generator.size()));
+            }
+            Statement::Project { target, interp, steps, .. } => {
+                self.unused_fields.remove(target);
+                self.unused_interps.remove(interp);
+                self.check_refs(step, target, interp, Severity::Error);
+                self.recorded.insert(target.clone());
+                if steps.as_ref().and_then(|s| s.as_literal()) == Some(0.0) {
+                    self.diag(step, Severity::Warning, format!("projection into {} has 0 steps; removed", target));
+                    self.removed += 1;
+                    return None;
+                }
+            }
+            Statement::ProjectMany { target, interps, steps, .. } => {
+                self.unused_fields.remove(target);
+                for (interp, _) in interps {
+                    self.unused_interps.remove(interp);
+                    self.check_refs(step, target, interp, Severity::Error);
+                }
+                self.recorded.insert(target.clone());
+                if *steps == 0 {
+                    self.diag(step, Severity::Warning, format!("projection into {} has 0 steps; removed", target));
+                    self.removed += 1;
+                    return None;
+                }
+            }
+            Statement::Morph { target, from, to, steps, .. } => {
+                self.unused_fields.remove(target);
+                for interp in [from, to] {
+                    self.unused_interps.remove(interp);
+                    self.check_refs(step, target, interp, Severity::Error);
+                }
+                self.recorded.insert(target.clone());
+                if *steps == 0 {
+                    self.diag(step, Severity::Warning, format!("morph of {} has 0 steps; removed", target));
+                    self.removed += 1;
+                    return None;
+                }
+            }
+            Statement::TraceDistance { name, field, interp } => {
+                self.unused_fields.remove(field);
+                self.unused_interps.remove(interp);
+                self.check_refs(step, field, interp, Severity::Warning);
+                self.traces.insert(name.clone(), step);
+                self.bind(step, name);
+            }
+            Statement::Let { name, field, interp, .. } => {
+                self.unused_fields.remove(field);
+                self.unused_interps.remove(interp);
+                self.check_refs(step, field, interp, Severity::Warning);
+                self.bind(step, name);
+            }
+            Statement::Const { name, .. } => {
+                if self.vars.contains(name) {
+                    self.diag(step, Severity::Error, format!("constant {} is already defined", name));
+                }
+                self.consts.insert(name.clone());
+                self.vars.insert(name.clone());
+            }
+            Statement::Assign { name, .. } => {
+                self.bind(step, name);
+            }
+            Statement::Meaning { name, trace_cmp, .. } => {
+                if !self.traces.contains_key(trace_cmp) && !self.vars.contains(trace_cmp) {
+                    self.diag(step, Severity::Error, format!("meaning {} compares unknown trace {}", name, trace_cmp));
+                }
+                self.read_traces.insert(trace_cmp.clone());
+                self.meanings.insert(name.clone(), step);
+                self.bind(step, name);
+            }
+            Statement::LogCoherence(name) => {
+                self.unused_fields.remove(name);
+                if !self.field_sizes.contains_key(name) {
+                    self.diag(step, Severity::Error, format!("unknown field {}", name));
+                }
+            }
+            Statement::LogMeaning(name) => {
+                if !self.meanings.contains_key(name) {
+                    self.diag(step, Severity::Error, format!("unknown meaning {}", name));
+                }
+                self.logged_meanings.insert(name.clone());
+            }
+            Statement::Steer { field, interp, .. } => {
+                self.unused_fields.remove(field);
+                self.unused_interps.remove(interp);
+                self.check_refs(step, field, interp, Severity::Error);
+                self.recorded.insert(field.clone());
+            }
+            Statement::Perturb { field, .. } | Statement::Decay { field, .. } => {
+                self.unused_fields.remove(field);
+                if !self.field_sizes.contains_key(field) {
+                    self.diag(step, Severity::Error, format!("unknown field {}", field));
+                }
+            }
+            Statement::Shock { field, end, .. } => {
+                self.unused_fields.remove(field);
+                match self.field_sizes.get(field).copied() {
+                    None => self.diag(step, Severity::Error, format!("unknown field {}", field)),
+                    Some(Some(size)) if *end > size => self.diag(
+                        step,
+                        Severity::Error,
+                        format!("shock indices end at {} but field {} has size {}", end, field, size),
+                    ),
+                    _ => {}
+                }
+            }
+            Statement::AddFields { left, right, into } => {
+                self.unused_fields.remove(left);
+                self.unused_fields.remove(right);
+                match (self.field_sizes.get(left).copied(), self.field_sizes.get(right).copied()) {
+                    (Some(Some(a)), Some(Some(b))) if a != b => self.diag(
+                        step,
+                        Severity::Error,
+                        format!("cannot add {} (size {}) and {} (size {})", left, a, right, b),
+                    ),
+                    (Some(a), Some(b)) => {
+                        self.unused_fields.insert(into.clone(), step);
+                        self.field_sizes.insert(into.clone(), a.or(b));
+                    }
+                    (a, _) => {
+                        let missing = if a.is_none() { left } else { right };
+                        self.diag(step, Severity::Error, format!("unknown field {}", missing));
+                    }
+                }
+            }
+            Statement::Scale { field, .. } | Statement::Normalize { field } => {
+                self.unused_fields.remove(field);
+                if !self.field_sizes.contains_key(field) {
+                    self.diag(step, Severity::Error, format!("unknown field {}", field));
+                }
+            }
+            Statement::Snapshot { field, name } => {
+                self.unused_fields.remove(field);
+                match self.field_sizes.get(field).copied() {
+                    Some(size) => {
+                        self.unused_interps.insert(name.clone(), step);
+                        self.interp_sizes.insert(name.clone(), size);
+                    }
+                    None => self.diag(step, Severity::Error, format!("unknown field {}", field)),
+                }
+            }
+            Statement::Export { kind: ExportKind::Field, name, .. } => {
+                self.unused_fields.remove(name);
+                if !self.field_sizes.contains_key(name) {
+                    self.diag(step, Severity::Error, format!("unknown field {}", name));
+                }
+            }
+            Statement::Export { kind: ExportKind::Trace, name, .. } | Statement::Assert { trace: name, .. } => {
+                let telemetry = name.split(['.', '[']).next().is_some_and(|f| f != name && self.recorded.contains(f));
+                if !self.traces.contains_key(name) && !self.vars.contains(name) && !telemetry {
+                    self.diag(step, Severity::Error, format!("unknown trace {}", name));
+                }
+                self.read_traces.insert(name.clone());
+            }
+            Statement::ExpressSymbol { into_field, .. } => {
+                self.unused_fields.remove(into_field);
+                if !self.field_sizes.contains_key(into_field) {
+                    self.diag(step, Severity::Error, format!("unknown field {}", into_field));
+                }
+            }
+            Statement::Report { .. } => {
+                // Every field and every trace so far is summarized.
+                self.unused_fields.clear();
+                self.read_traces.extend(self.traces.keys().cloned());
+            }
+            Statement::Repeat { .. } | Statement::If { .. } => unreachable!("handled above"),
+            Statement::Include(path) => {
+                self.diag(step, Severity::Error, format!("include {} was not resolved", path));
+            }
+            Statement::Run { path, sharing, body } => {
+                // The script is checked on its own, seeing the names this one
+                // shares with it; the names it declares in them are known after it.
+                let fields = sharing.contains(&Shared::Fields);
+                let interps = sharing.contains(&Shared::Interpretations);
+                let mut script = Checker::default();
+                if fields {
+                    script.field_sizes = self.field_sizes.clone();
+                    self.unused_fields.clear();
+                }
+                if interps {
+                    script.interp_sizes = self.interp_sizes.clone();
+                    self.unused_interps.clear();
+                }
+                for stmt in body.clone() {
+                    script.statement(step, stmt);
+                }
+                for d in script.diagnostics {
+                    self.diag(step, d.severity, format!("in {}: {}", path, d.message));
+                }
+                self.missing.extend(script.missing);
+                if fields {
+                    self.field_sizes = script.field_sizes;
+                }
+                if interps {
+                    self.interp_sizes = script.interp_sizes;
+                }
+            }
+            Statement::Require(capability) => self.require(step, *capability, "the script".to_string()),
+            Statement::NarrateReturn { .. } | Statement::Modulate { .. } | Statement::Level { .. } | Statement::Seed(_) => {}
+        }
+        Some(stmt)
+    }
+
+    fn finish(mut self, program: Vec<Statement>) -> Optimized {
+        // Traces are reported even when nothing reads them, so they are only noted, not removed.
+        // `let` variables are not flagged: field expressions may read them too.
+        for (name, step) in std::mem::take(&mut self.traces) {
+            if !self.read_traces.contains(&name) {
+                self.diag(step, Severity::Warning, format!("trace {} is never compared by a meaning or condition, or exported", name));
+            }
+        }
+        for (name, step) in std::mem::take(&mut self.meanings) {
+            if !self.logged_meanings.contains(&name) {
+                self.diag(step, Severity::Warning, format!("meaning {} is never logged", name));
+            }
+        }
+        for (name, step) in std::mem::take(&mut self.unused_fields) {
+            self.diag(step, Severity::Warning, format!("field {} is never used", name));
+        }
+        for (name, step) in std::mem::take(&mut self.unused_interps) {
+            self.diag(step, Severity::Warning, format!("interpretation {} is never used", name));
+        }
+        self.diagnostics.sort_by_key(|d| d.step);
+        Optimized { program, diagnostics: self.diagnostics, removed: self.removed, missing: self.missing.into_iter().collect() }
+    }
+
+    /// Check that `field` and `interp` exist; a size mismatch between them is
+    /// reported with `mismatch`, an error where the field is projected into.
+    fn check_refs(&mut self, step: usize, field: &str, interp: &str, mismatch: Severity) {
+        let message = match (self.field_sizes.get(field), self.interp_sizes.get(interp)) {
+            (None, _) => (Severity::Error, format!("unknown field {}", field)),
+            (_, None) => (Severity::Error, format!("unknown interpretation {}", interp)),
+            (Some(Some(f)), Some(Some(i))) if f != i => (
+                mismatch,
+                format!("field {} has size {} but interpretation {} has {} values", field, f, interp, i),
+            ),
+            _ => return,
+        };
+        self.diag(step, message.0, message.1);
+    }
+}
@@ -0,0 +1,805 @@
+//! Bytecode compilation of SPTL programs.
+//!
+//! `compile` resolves every field, interpretation and variable name to a slot
+//! index and pre-formats the messages of print-only statements, so `Vm::run` executes a
+//! flat instruction list without name lookups or string building. Behavior,
+//! including warnings for names not yet declared, matches `execute_program_into`
+//! (and, with `compile_in` and `Vm::run_in`, `execute_program_in`).
+
+use super::encode::Encoding;
+use super::export::{self, export, ExportKind, RunSummary};
+use super::expr::FieldExpr;
+use super::expr::Value;
+use super::generate::Generator;
+use super::{
+    apply_projection, bind_metric, build_level, check_assertion, decay_field, derived_field, eval_count, evaluate_meaning, express, expressed_pattern, log_meaning, morph, project_many, require, restore_field, steer, unknown_variable, Comparison, Condition, Metric,
+    EvalError, ProjectParams, ProjectSettings, ProjectionTarget, Shared, Statement, SteerSettings, run_script,
+};
+use crate::capability::Capability;
+use crate::condition::Unknown;
+use crate::events::Event;
+use crate::interpretation::Interpretation;
+use crate::perturb::{perturb, shock};
+use crate::recursion::RecursionLevel;
+use crate::report::RunReport;
+use crate::runtime::Runtime;
+use crate::console;
+use crate::{say, warn};
+use crate::substrate::Substrate;
+use crate::trace::trace_distance;
+use crate::visualize::print_vector;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub enum Instr {
+    NewField { slot: usize, size: FieldExpr<usize>, doc: Option<String> },
+    /// Restores field `name` into `slot` from the checkpoint at `path`.
+    RestoreField { slot: usize, name: String, path: String },
+    DeriveField { slot: usize, expr: FieldExpr<Operand> },
+    SliceField { slot: usize, source: usize, start: usize, end: usize },
+    ConcatFields { slot: usize, parts: Vec<usize> },
+    Let { var: usize, metric: Metric, field: usize, interp: usize },
+    Assign { var: usize, value: FieldExpr<usize> },
+    LoadInterp { slot: usize, values: Vec<FieldExpr<usize>>, doc: Option<String> },
+    GenerateInterp { slot: usize, generator: Generator<usize> },
+    Project { field: usize, interp: usize, params: ProjectParams<usize> },
+    ProjectMany {
+        field: usize,
+        interps: Vec<(usize, f64)>,
+        alternate: bool,
+        alpha: FieldExpr<usize>,
+        noise: FieldExpr<usize>,
+        steps: usize,
+    },
+    Morph { field: usize, from: usize, to: usize, alpha: FieldExpr<usize>, noise: FieldExpr<usize>, steps: usize },
+    /// Records trace `name` and binds it to `var`.
+    Trace { name: usize, var: usize, field: usize, interp: usize },
+    LogField { field: usize },
+    Express { token: String, field: usize, encoding: Encoding, weight: FieldExpr<usize>, blend: bool },
+    Steer { field: usize, interp: usize, target: FieldExpr<usize>, settings: SteerSettings },
+    Perturb { field: usize, amplitude: FieldExpr<usize> },
+    Shock { field: usize, start: usize, end: usize, value: FieldExpr<usize> },
+    AddFields { left: usize, right: usize, into: usize },
+    Scale { field: usize, factor: FieldExpr<usize> },
+    Decay { field: usize, rate: FieldExpr<usize>, steps: usize },
+    Normalize { field: usize },
+    /// Judges meaning `var` by the value of variable `trace`.
+    Meaning { var: usize, trace: usize, threshold: FieldExpr<usize> },
+    LogMeaning(String),
+    /// Checks the recorded trace `trace` against `bound`.
+    Assert { trace: String, cmp: Comparison, bound: FieldExpr<usize>, tolerance: f64 },
+    Modulate { token: String, intensity: FieldExpr<usize> },
+    Seed(u64),
+    /// Copies field `field` into interpretation slot `interp`.
+    Snapshot { field: usize, interp: usize, name: String },
+    /// `field` is the slot of the exported field; `None` for traces and unknown fields.
+    Export { kind: ExportKind, name: String, path: String, field: Option<usize> },
+    Report { path: Option<String> },
+    Print(String),
+    Warn(String),
+    Require(Capability),
+    Level { level: RecursionLevel, name: String, body: Vec<Statement> },
+    /// Runs `body` with `run_script`, on the slots' fields and interpretations.
+    Run { path: String, sharing: Vec<Shared>, body: Vec<Statement> },
+    /// The body is compiled twice: names a first pass declares late in the
+    /// body are already known to `rest`, as they are to later iterations in
+    /// `execute_program_into`.
+    Repeat { count: usize, first: Vec<Instr>, rest: Vec<Instr> },
+    If { condition: Condition<usize>, then: Vec<Instr>, otherwise: Vec<Instr> },
+}
+
+/// Operand of a compiled field expression.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operand {
+    Field(usize),
+    Var(usize),
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operand::Field(slot) => write!(f, "field #{}", slot),
+            Operand::Var(slot) => write!(f, "variable #{}", slot),
+        }
+    }
+}
+
+/// A compiled program: one instruction per source statement, so instruction
+/// index equals the statement step recorded in reports.
+#[derive(Debug, Clone)]
+pub struct Bytecode {
+    pub instrs: Vec<Instr>,
+    /// `{:?}` of each source statement, written to the journal.
+    pub journal: Vec<String>,
+    pub field_names: Vec<String>,
+    pub interp_names: Vec<String>,
+    pub trace_names: Vec<String>,
+    pub var_names: Vec<String>,
+}
+
+/// Name → slot table; slots are assigned in declaration order and reused on redeclaration.
+#[derive(Default, Clone)]
+struct Slots {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+}
+
+impl Slots {
+    fn declare(&mut self, name: &str) -> usize {
+        if let Some(&i) = self.index.get(name) {
+            return i;
+        }
+        self.names.push(name.to_string());
+        self.index.insert(name.to_string(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    fn get(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+}
+
+/// Warning for a numeric option naming a variable that is never bound before it.
+fn unknown_var(name: &str, statement: &str) -> Instr {
+    Instr::Warn(format!("⚠️ Unknown variable {} in {}", name, statement))
+}
+
+/// Name tables shared by a program and the blocks nested in it.
+#[derive(Default)]
+struct Compiler {
+    fields: Slots,
+    interps: Slots,
+    traces: Slots,
+    vars: Slots,
+}
+
+pub fn compile(program: Vec<Statement>) -> Bytecode {
+    compile_with(program, Compiler::default())
+}
+
+/// Compile a program to run on `rt` with `Vm::run_in`: the fields and
+/// interpretations `rt` already holds count as declared.
+pub fn compile_in(program: Vec<Statement>, rt: &Runtime) -> Bytecode {
+    compile_bound(program, rt, &[])
+}
+
+/// Like `compile_in`, leaving the `bound` variables to be set per run by
+/// `Vm::run_bound`. A sweep over `$name` parameters that only appear in
+/// numeric expressions compiles once and binds each point's values.
+pub fn compile_bound(program: Vec<Statement>, rt: &Runtime, bound: &[String]) -> Bytecode {
+    let mut compiler = Compiler::default();
+    for name in bound {
+        compiler.vars.declare(name);
+    }
+    for name in rt.fields.keys().collect::<BTreeSet<_>>() {
+        compiler.fields.declare(name);
+    }
+    for name in rt.interps.keys().collect::<BTreeSet<_>>() {
+        compiler.interps.declare(name);
+    }
+    compile_with(program, compiler)
+}
+
+fn compile_with(program: Vec<Statement>, mut compiler: Compiler) -> Bytecode {
+    let mut instrs = Vec::with_capacity(program.len());
+    let mut journal = Vec::with_capacity(program.len());
+    for stmt in program {
+        journal.push(format!("{:?}", stmt));
+        instrs.push(compiler.statement(stmt));
+    }
+
+    Bytecode {
+        instrs,
+        journal,
+        field_names: compiler.fields.names,
+        interp_names: compiler.interps.names,
+        trace_names: compiler.traces.names,
+        var_names: compiler.vars.names,
+    }
+}
+
+impl Compiler {
+    fn block(&mut self, body: Vec<Statement>) -> Vec<Instr> {
+        body.into_iter().map(|stmt| self.statement(stmt)).collect()
+    }
+
+    fn statement(&mut self, stmt: Statement) -> Instr {
+        match stmt {
+            Statement::Field { name, size, doc } => match size.resolve(&mut |v: &String| self.vars.get(v)) {
+                Ok(size) => Instr::NewField { slot: self.fields.declare(&name), size, doc },
+                Err(unknown) => unknown_var(&unknown, "Field"),
+            },
+            Statement::FieldFromCheckpoint { name, path } => {
+                Instr::RestoreField { slot: self.fields.declare(&name), name, path }
+            }
+            Statement::DeriveField { name, expr } => {
+                // Resolve before declaring, so `field a = a * 2` reads the previous `a`.
+                let resolved = expr.resolve(&mut |f: &String| {
+                    self.fields.get(f).map(Operand::Field).or_else(|| self.vars.get(f).map(Operand::Var))
+                });
+                match resolved {
+                    Ok(expr) => Instr::DeriveField { slot: self.fields.declare(&name), expr },
+                    Err(unknown) => Instr::Warn(format!("⚠️ field {}: unknown field {}", name, unknown)),
+                }
+            }
+            Statement::SliceField { name, source, start, end } => match self.fields.get(&source) {
+                Some(source) => Instr::SliceField { slot: self.fields.declare(&name), source, start, end },
+                None => Instr::Warn("⚠️ Unknown field in Slice".to_string()),
+            },
+            Statement::ConcatFields { name, parts } => {
+                match parts.iter().map(|p| self.fields.get(p)).collect::<Option<Vec<_>>>() {
+                    Some(parts) => Instr::ConcatFields { slot: self.fields.declare(&name), parts },
+                    None => Instr::Warn("⚠️ Unknown field in Concat".to_string()),
+                }
+            }
+            Statement::Interpretation { name, values, doc } => {
+                let resolved = values.into_iter().map(|value| value.resolve(&mut |v: &String| self.vars.get(v)));
+                match resolved.collect::<Result<Vec<_>, _>>() {
+                    Ok(values) => Instr::LoadInterp { slot: self.interps.declare(&name), values, doc },
+                    Err(unknown) => unknown_var(&unknown, "Interpretation"),
+                }
+            }
+            Statement::GenerateInterpretation { name, generator } => {
+                match generator.resolve(&mut |v: &String| self.vars.get(v)) {
+                    Ok(generator) => Instr::GenerateInterp { slot: self.interps.declare(&name), generator },
+                    Err(unknown) => unknown_var(&unknown, "Interpretation"),
+                }
+            }
+            Statement::Project { target, interp, alpha, noise, steps, tolerance, until, record, log_every } => {
+                match (self.fields.get(&target), self.interps.get(&interp)) {
+                    (Some(field), Some(interp)) => {
+                        let params = ProjectParams { alpha, noise, steps, tolerance, until, record, log_every };
+                        match params.resolve(&mut |v: &String| self.vars.get(v)) {
+                            Ok(params) => Instr::Project { field, interp, params },
+                            Err(unknown) => unknown_var(&unknown, "Project"),
+                        }
+                    }
+                    _ => Instr::Warn("⚠️ Unknown field or interpretation in Project".to_string()),
+                }
+            }
+            Statement::ProjectMany { target, interps, alternate, alpha, noise, steps } => {
+                let slots: Option<Vec<(usize, f64)>> =
+                    interps.iter().map(|(name, weight)| self.interps.get(name).map(|slot| (slot, *weight))).collect();
+                match (self.fields.get(&target), slots) {
+                    (Some(field), Some(interps)) => {
+                        let mut lookup = |v: &String| self.vars.get(v);
+                        match (alpha.resolve(&mut lookup), noise.resolve(&mut lookup)) {
+                            (Ok(alpha), Ok(noise)) => Instr::ProjectMany { field, interps, alternate, alpha, noise, steps },
+                            (Err(unknown), _) | (_, Err(unknown)) => unknown_var(&unknown, "Project"),
+                        }
+                    }
+                    _ => Instr::Warn("⚠️ Unknown field or interpretation in Project".to_string()),
+                }
+            }
+            Statement::Morph { target, from, to, alpha, noise, steps } => {
+                match (self.fields.get(&target), self.interps.get(&from), self.interps.get(&to)) {
+                    (Some(field), Some(from), Some(to)) => {
+                        let mut lookup = |v: &String| self.vars.get(v);
+                        match (alpha.resolve(&mut lookup), noise.resolve(&mut lookup)) {
+                            (Ok(alpha), Ok(noise)) => Instr::Morph { field, from, to, alpha, noise, steps },
+                            (Err(unknown), _) | (_, Err(unknown)) => unknown_var(&unknown, "Morph"),
+                        }
+                    }
+                    _ => Instr::Warn("⚠️ Unknown field or interpretation in Morph".to_string()),
+                }
+            }
+            Statement::TraceDistance { name, field, interp } => match (self.fields.get(&field), self.interps.get(&interp)) {
+                (Some(field), Some(interp)) => {
+                    Instr::Trace { name: self.traces.declare(&name), var: self.vars.declare(&name), field, interp }
+                }
+                _ => Instr::Warn("⚠️ Unknown field or interpretation in TraceDistance".to_string()),
+            },
+            Statement::Let { name, metric, field, interp } => match (self.fields.get(&field), self.interps.get(&interp)) {
+                (Some(field), Some(interp)) => Instr::Let { var: self.vars.declare(&name), metric, field, interp },
+                _ => Instr::Warn("⚠️ Unknown field or interpretation in Let".to_string()),
+            },
+            Statement::Assign { name, value } => match value.resolve(&mut |v: &String| self.vars.get(v)) {
+                // Resolved before declaring, so `let a = a + 1` reads the previous `a`.
+                Ok(value) => Instr::Assign { var: self.vars.declare(&name), value },
+                Err(unknown) => unknown_var(&unknown, "Let"),
+            },
+            Statement::Const { name, value } => Instr::Assign { var: self.vars.declare(&name), value: FieldExpr::Scalar(value) },
+            Statement::Meaning { name, trace_cmp, threshold } => match self.vars.get(&trace_cmp) {
+                Some(trace) => match threshold.resolve(&mut |v: &String| self.vars.get(v)) {
+                    Ok(threshold) => Instr::Meaning { var: self.vars.declare(&name), trace, threshold },
+                    Err(unknown) => unknown_var(&unknown, "Meaning"),
+                },
+                None => Instr::Warn(format!("⚠️ Unknown trace {} in Meaning", trace_cmp)),
+            },
+            Statement::NarrateReturn { tokens } => Instr::Print(format!("🗣 {}", tokens.join(" "))),
+            Statement::LogCoherence(name) => match self.fields.get(&name) {
+                Some(field) => Instr::LogField { field },
+                None => Instr::Warn("⚠️ Unknown field in LogCoherence".to_string()),
+            },
+            Statement::LogMeaning(name) => Instr::LogMeaning(name),
+            Statement::Assert { trace, cmp, bound, tolerance } => match bound.resolve(&mut |v: &String| self.vars.get(v)) {
+                Ok(bound) => Instr::Assert { trace, cmp, bound, tolerance },
+                Err(unknown) => unknown_var(&unknown, "Assert"),
+            },
+            Statement::ExpressSymbol { token, into_field, encoding, weight, blend } => match self.fields.get(&into_field) {
+                Some(field) => match weight.resolve(&mut |v: &String| self.vars.get(v)) {
+                    Ok(weight) => Instr::Express { token, field, encoding, weight, blend },
+                    Err(unknown) => unknown_var(&unknown, "ExpressSymbol"),
+                },
+                None => Instr::Warn("⚠️ Unknown field in ExpressSymbol".to_string()),
+            },
+            Statement::Modulate { token, intensity } => match intensity.resolve(&mut |v: &String| self.vars.get(v)) {
+                Ok(intensity) => Instr::Modulate { token, intensity },
+                Err(unknown) => unknown_var(&unknown, "Modulate"),
+            },
+            Statement::Level { level, name, body } => Instr::Level { level, name, body },
+            Statement::Steer { field, interp, target, settings } => match (self.fields.get(&field), self.interps.get(&interp)) {
+                (Some(field), Some(interp)) => match target.resolve(&mut |v: &String| self.vars.get(v)) {
+                    Ok(target) => Instr::Steer { field, interp, target, settings },
+                    Err(unknown) => unknown_var(&unknown, "Steer"),
+                },
+                _ => Instr::Warn("⚠️ Unknown field or interpretation in Steer".to_string()),
+            },
+            Statement::Perturb { field, amplitude } => match self.fields.get(&field) {
+                Some(field) => match amplitude.resolve(&mut |v: &String| self.vars.get(v)) {
+                    Ok(amplitude) => Instr::Perturb { field, amplitude },
+                    Err(unknown) => unknown_var(&unknown, "Perturb"),
+                },
+                None => Instr::Warn("⚠️ Unknown field in Perturb".to_string()),
+            },
+            Statement::Shock { field, start, end, value } => match self.fields.get(&field) {
+                Some(field) => match value.resolve(&mut |v: &String| self.vars.get(v)) {
+                    Ok(value) => Instr::Shock { field, start, end, value },
+                    Err(unknown) => unknown_var(&unknown, "Shock"),
+                },
+                None => Instr::Warn("⚠️ Unknown field in Shock".to_string()),
+            },
+            Statement::Seed(seed) => Instr::Seed(seed),
+            Statement::Require(capability) => Instr::Require(capability),
+            Statement::Snapshot { field, name } => match self.fields.get(&field) {
+                Some(field) => Instr::Snapshot { field, interp: self.interps.declare(&name), name },
+                None => Instr::Warn("⚠️ Unknown field in Snapshot".to_string()),
+            },
+            Statement::Export { kind, name, path } => {
+                let field = if kind == ExportKind::Field { self.fields.get(&name) } else { None };
+                Instr::Export { kind, name, path, field }
+            }
+            Statement::Report { path } => Instr::Report { path },
+            Statement::Include(path) => Instr::Warn(format!("⚠️ include {} was not resolved before execution", path)),
+            Statement::Run { path, sharing, body } => {
+                // Names the script declares in a shared kind are known to
+                // the statements after it, as they are to `execute_program_into`.
+                let mut script = Compiler { fields: self.fields.clone(), interps: self.interps.clone(), ..Compiler::default() };
+                script.block(body.clone());
+                if sharing.contains(&Shared::Fields) {
+                    for name in &script.fields.names {
+                        self.fields.declare(name);
+                    }
+                }
+                if sharing.contains(&Shared::Interpretations) {
+                    for name in &script.interps.names {
+                        self.interps.declare(name);
+                    }
+                }
+                Instr::Run { path, sharing, body }
+            }
+            Statement::AddFields { left, right, into } => match (self.fields.get(&left), self.fields.get(&right)) {
+                (Some(left), Some(right)) => Instr::AddFields { left, right, into: self.fields.declare(&into) },
+                _ => Instr::Warn("⚠️ Unknown field in Add".to_string()),
+            },
+            Statement::Scale { field, factor } => match self.fields.get(&field) {
+                Some(field) => match factor.resolve(&mut |v: &String| self.vars.get(v)) {
+                    Ok(factor) => Instr::Scale { field, factor },
+                    Err(unknown) => unknown_var(&unknown, "Scale"),
+                },
+                None => Instr::Warn("⚠️ Unknown field in Scale".to_string()),
+            },
+            Statement::Decay { field, rate, steps } => match self.fields.get(&field) {
+                Some(field) => match rate.resolve(&mut |v: &String| self.vars.get(v)) {
+                    Ok(rate) => Instr::Decay { field, rate, steps },
+                    Err(unknown) => unknown_var(&unknown, "Decay"),
+                },
+                None => Instr::Warn("⚠️ Unknown field in Decay".to_string()),
+            },
+            Statement::Normalize { field } => match self.fields.get(&field) {
+                Some(field) => Instr::Normalize { field },
+                None => Instr::Warn("⚠️ Unknown field in Normalize".to_string()),
+            },
+            Statement::Repeat { count, body } => {
+                let first = self.block(body.clone());
+                let rest = if count > 1 { self.block(body) } else { Vec::new() };
+                Instr::Repeat { count, first, rest }
+            }
+            Statement::If { condition, then, otherwise } => {
+                match condition.resolve(&mut |v: &String| self.vars.get(v)) {
+                    Ok(condition) => Instr::If { condition, then: self.block(then), otherwise: self.block(otherwise) },
+                    Err(unknown) => unknown_var(&unknown, "If"),
+                }
+            }
+        }
+    }
+}
+
+/// Executes compiled bytecode. Reusable across runs of the same program.
+pub struct Vm<'a> {
+    code: &'a Bytecode,
+}
+
+impl<'a> Vm<'a> {
+    pub fn new(code: &'a Bytecode) -> Self {
+        Vm { code }
+    }
+
+    pub fn run(&self, report: &mut RunReport) {
+        self.run_in(&mut Runtime::default(), report);
+    }
+
+    /// Run on `rt`, which the program was compiled for with `compile_in`:
+    /// slots start out holding its fields and interpretations, and fields
+    /// are written back when the program ends.
+    pub fn run_in(&self, rt: &mut Runtime, report: &mut RunReport) {
+        self.run_bound(rt, report, &BTreeMap::new());
+    }
+
+    /// `run_in` with each variable in `bindings` set before the first
+    /// instruction; for programs compiled with `compile_bound`.
+    pub fn run_bound(&self, rt: &mut Runtime, report: &mut RunReport, bindings: &BTreeMap<String, f64>) {
+        let code = self.code;
+        let mut machine = Machine {
+            fields: code.field_names.iter().map(|name| rt.fields.get(name).map(|f| Substrate::clone(f))).collect(),
+            interps: code.interp_names.iter().map(|name| rt.interps.get(name).cloned()).collect(),
+            vars: code.var_names.iter().map(|name| bindings.get(name).copied()).collect(),
+            rt,
+        };
+        for (step, instr) in code.instrs.iter().enumerate() {
+            report.log(format!("[{}] {}", step, code.journal[step]));
+            machine.exec(code, instr, step, report);
+        }
+        console::finish();
+
+        let Machine { fields, interps, rt, .. } = machine;
+        for (name, field) in code.field_names.iter().zip(fields) {
+            if let Some(field) = field {
+                rt.fields.insert(name.clone(), Arc::new(field));
+            }
+        }
+        for (name, interp) in code.interp_names.iter().zip(interps) {
+            if let Some(interp) = interp {
+                rt.interps.insert(name.clone(), interp);
+            }
+        }
+        report.fields = rt.field_states();
+        report.rng_draws = rt.rng.draws();
+        report.docs = rt.docs.clone();
+    }
+}
+
+/// Slot contents of one run, and the runtime it runs on.
+struct Machine<'r> {
+    fields: Vec<Option<Substrate>>,
+    interps: Vec<Option<Interpretation>>,
+    vars: Vec<Option<f64>>,
+    rt: &'r mut Runtime,
+}
+
+impl Machine<'_> {
+    /// Write the fields and interpretations in slots to the runtime.
+    fn store(&mut self, code: &Bytecode) {
+        for (name, field) in code.field_names.iter().zip(&self.fields) {
+            if let Some(field) = field {
+                self.rt.fields.insert(name.clone(), Arc::new(field.clone()));
+            }
+        }
+        for (name, interp) in code.interp_names.iter().zip(&self.interps) {
+            if let Some(interp) = interp {
+                self.rt.interps.insert(name.clone(), interp.clone());
+            }
+        }
+    }
+
+    /// Fill the slots from the runtime's fields and interpretations.
+    fn load(&mut self, code: &Bytecode) {
+        self.fields = code.field_names.iter().map(|name| self.rt.fields.get(name).map(|f| Substrate::clone(f))).collect();
+        self.interps = code.interp_names.iter().map(|name| self.rt.interps.get(name).cloned()).collect();
+    }
+
+    /// Execute one instruction. Instructions nested in a block report the
+    /// step of the top-level instruction containing them.
+    fn exec(&mut self, code: &Bytecode, instr: &Instr, step: usize, report: &mut RunReport) {
+        match instr {
+            Instr::NewField { slot, size, doc } => match eval_count(size, "size", &|v: &usize| self.vars[*v]) {
+                Ok(size) => {
+                    self.rt.document(&code.field_names[*slot], doc.as_deref());
+                    self.fields[*slot] = Some(Substrate::new(size));
+                }
+                Err(EvalError::Unknown(unknown)) => unknown_variable(&code.var_names[*unknown], "Field"),
+                Err(EvalError::NotWhole(e)) => warn!("⚠️ field {}: {}", code.field_names[*slot], e),
+            },
+            Instr::RestoreField { slot, name, path } => {
+                if let Some(field) = restore_field(name, path) {
+                    self.fields[*slot] = Some(field);
+                }
+            }
+            Instr::DeriveField { slot, expr } => {
+                let result = expr.eval(&|op: &Operand| match *op {
+                    Operand::Field(f) => self.fields[f].as_ref().map(|s| Value::Vector(s.state.clone())),
+                    Operand::Var(v) => self.vars[v].map(Value::Scalar),
+                });
+                match derived_field(&code.field_names[*slot], result) {
+                    Ok(field) => self.fields[*slot] = Some(field),
+                    Err(e) => warn!("⚠️ {}", e),
+                }
+            }
+            Instr::SliceField { slot, source, start, end } => match &self.fields[*source] {
+                Some(field) => match field.slice(*start..*end) {
+                    Ok(slice) => {
+                        let names = &code.field_names;
+                        say!("✂️ Sliced {}[{}..{}] into {}", names[*source], start, end, names[*slot]);
+                        self.fields[*slot] = Some(slice);
+                    }
+                    Err(e) => warn!("⚠️ field {}: {}", code.field_names[*slot], e),
+                },
+                None => warn!("⚠️ Unknown field in Slice"),
+            },
+            Instr::ConcatFields { slot, parts } => {
+                match parts.iter().map(|p| self.fields[*p].as_ref()).collect::<Option<Vec<_>>>() {
+                    Some(fields) => {
+                        let joined = Substrate::concat(&fields);
+                        let names: Vec<&str> = parts.iter().map(|p| code.field_names[*p].as_str()).collect();
+                        let name = &code.field_names[*slot];
+                        say!("🔗 Concatenated {} into {} ({} elements)", names.join(", "), name, joined.state.len());
+                        self.fields[*slot] = Some(joined);
+                    }
+                    None => warn!("⚠️ Unknown field in Concat"),
+                }
+            }
+            Instr::Let { var, metric, field, interp } => match (&self.fields[*field], &self.interps[*interp]) {
+                (Some(f), Some(i)) => {
+                    let value = metric.compute(f, i);
+                    bind_metric(report, step, &code.var_names[*var], value);
+                    self.rt.measurements.insert(code.var_names[*var].clone(), value);
+                    self.vars[*var] = Some(value);
+                }
+                _ => warn!("⚠️ Unknown field or interpretation in Let"),
+            },
+            Instr::Assign { var, value } => {
+                let result = value.scalar(&|v: &usize| self.vars[*v]);
+                match result {
+                    Ok(value) => self.vars[*var] = Some(value),
+                    Err(unknown) => unknown_variable(&code.var_names[*unknown], "Let"),
+                }
+            }
+            Instr::LoadInterp { slot, values, doc } => {
+                match values.iter().map(|value| value.scalar(&|v: &usize| self.vars[*v])).collect::<Result<Vec<_>, _>>() {
+                    Ok(values) => {
+                        self.rt.document(&code.interp_names[*slot], doc.as_deref());
+                        self.interps[*slot] = Some(Interpretation::new(values));
+                    }
+                    Err(unknown) => unknown_variable(&code.var_names[*unknown], "Interpretation"),
+                }
+            }
+            Instr::GenerateInterp { slot, generator } => match generator.generate(&|v: &usize| self.vars[*v], &mut self.rt.rng) {
+                Ok(values) => self.interps[*slot] = Some(Interpretation::new(values)),
+                Err(unknown) => unknown_variable(&code.var_names[*unknown], "Interpretation"),
+            },
+            // A declared slot is still empty when the statement filling it was
+            // skipped by a branch or failed; that reads as an unknown name.
+            Instr::Project { field, interp, params } => match (&mut self.fields[*field], &self.interps[*interp]) {
+                (Some(target), Some(interp)) => match params.evaluate(&|v: &usize| self.vars[*v]) {
+                    Ok(settings) => apply_projection(report, step, &code.field_names[*field], target, interp, &settings, self.rt.rng.at("project")),
+                    Err(EvalError::Unknown(unknown)) => unknown_variable(&code.var_names[*unknown], "Project"),
+                    Err(EvalError::NotWhole(e)) => warn!("⚠️ project: {}", e),
+                },
+                _ => warn!("⚠️ Unknown field or interpretation in Project"),
+            },
+            Instr::ProjectMany { field, interps, alternate, alpha, noise, steps } => {
+                let resolved: Option<Vec<_>> = interps
+                    .iter()
+                    .map(|(slot, weight)| self.interps[*slot].as_ref().map(|i| (code.interp_names[*slot].as_str(), i, *weight)))
+                    .collect();
+                let settings = (alpha.scalar(&|v: &usize| self.vars[*v]), noise.scalar(&|v: &usize| self.vars[*v]));
+                match (&mut self.fields[*field], resolved, settings) {
+                    (Some(target), Some(interps), (Ok(alpha), Ok(noise))) => {
+                        let settings = ProjectSettings::fixed(alpha, noise, *steps);
+                        let target = ProjectionTarget { name: &code.field_names[*field], field: target };
+                        project_many(report, step, target, &interps, *alternate, &settings, self.rt.rng.at("project"));
+                    }
+                    (_, _, (Err(unknown), _) | (_, Err(unknown))) => unknown_variable(&code.var_names[*unknown], "Project"),
+                    _ => warn!("⚠️ Unknown field or interpretation in Project"),
+                }
+            }
+            Instr::Morph { field, from, to, alpha, noise, steps } => {
+                let settings = (alpha.scalar(&|v: &usize| self.vars[*v]), noise.scalar(&|v: &usize| self.vars[*v]));
+                match (&mut self.fields[*field], &self.interps[*from], &self.interps[*to], settings) {
+                    (Some(target), Some(from), Some(to), (Ok(alpha), Ok(noise))) => {
+                        let settings = ProjectSettings::fixed(alpha, noise, *steps);
+                        let target = ProjectionTarget { name: &code.field_names[*field], field: target };
+                        morph(report, step, target, from, to, &settings, self.rt.rng.at("project"));
+                    }
+                    (.., (Err(unknown), _) | (_, Err(unknown))) => unknown_variable(&code.var_names[*unknown], "Morph"),
+                    _ => warn!("⚠️ Unknown field or interpretation in Morph"),
+                }
+            }
+            Instr::Trace { name, var, field, interp } => match (&self.fields[*field], &self.interps[*interp]) {
+                (Some(f), Some(i)) => {
+                    let result = trace_distance(f, i);
+                    let name = &code.trace_names[*name];
+                    say!("Trace {} = {:.4}", name, result);
+                    report.record(step, name, result);
+                    self.rt.measurements.insert(name.clone(), result);
+                    self.vars[*var] = Some(result);
+                    report.events.publish(Event::TraceComputed { name: name.clone(), value: result, tau: step as u64 });
+                }
+                _ => warn!("⚠️ Unknown field or interpretation in TraceDistance"),
+            },
+            Instr::LogField { field } => match &self.fields[*field] {
+                Some(f) => print_vector(&format!("Ψ[{}]", code.field_names[*field]), &f.state),
+                None => warn!("⚠️ Unknown field in LogCoherence"),
+            },
+            Instr::Express { token, field, encoding, weight, blend } => match &mut self.fields[*field] {
+                Some(f) => match weight.scalar(&|v: &usize| self.vars[*v]) {
+                    Ok(weight) => {
+                        let weight = weight * self.rt.gain(token);
+                        express(f, token, *encoding, weight, *blend, &mut self.rt.symbols);
+                        let into_field = &code.field_names[*field];
+                        say!("➕ Expressed {} into {}", token, into_field);
+                        self.rt.provenance.activated(into_field, &expressed_pattern(token, *encoding), self.rt.tau);
+                        report.events.publish(Event::SymbolExpressed {
+                            source: into_field.clone(),
+                            token: token.clone(),
+                            tau: step as u64,
+                        });
+                    }
+                    Err(unknown) => unknown_variable(&code.var_names[*unknown], "ExpressSymbol"),
+                },
+                None => warn!("⚠️ Unknown field in ExpressSymbol"),
+            },
+            Instr::Steer { field, interp, target, settings } => match (&mut self.fields[*field], &self.interps[*interp]) {
+                (Some(f), Some(i)) => match target.scalar(&|v: &usize| self.vars[*v]) {
+                    Ok(target) => {
+                        let steered = ProjectionTarget { name: &code.field_names[*field], field: f };
+                        steer(report, step, steered, i, target, settings, self.rt.rng.at("steer"))
+                    }
+                    Err(unknown) => unknown_variable(&code.var_names[*unknown], "Steer"),
+                },
+                _ => warn!("⚠️ Unknown field or interpretation in Steer"),
+            },
+            Instr::Perturb { field, amplitude } => match &mut self.fields[*field] {
+                Some(f) => match amplitude.scalar(&|v: &usize| self.vars[*v]) {
+                    Ok(amplitude) => {
+                        perturb(f, amplitude, self.rt.rng.at("perturb"));
+                        say!("🌪 Perturbed {} with noise {}", code.field_names[*field], amplitude);
+                    }
+                    Err(unknown) => unknown_variable(&code.var_names[*unknown], "Perturb"),
+                },
+                None => warn!("⚠️ Unknown field in Perturb"),
+            },
+            Instr::Shock { field, start, end, value } => match &mut self.fields[*field] {
+                Some(f) => match value.scalar(&|v: &usize| self.vars[*v]) {
+                    Ok(value) => match shock(f, *start..*end, value) {
+                        Ok(()) => say!("⚡ Shocked {}[{}..{}] = {}", code.field_names[*field], start, end, value),
+                        Err(e) => warn!("⚠️ {}", e),
+                    },
+                    Err(unknown) => unknown_variable(&code.var_names[*unknown], "Shock"),
+                },
+                None => warn!("⚠️ Unknown field in Shock"),
+            },
+            Instr::AddFields { left, right, into } => match (&self.fields[*left], &self.fields[*right]) {
+                (Some(a), Some(b)) => match a.add(b) {
+                    Ok(sum) => {
+                        let names = &code.field_names;
+                        say!("➕ Added {} + {} into {}", names[*left], names[*right], names[*into]);
+                        self.fields[*into] = Some(sum);
+                    }
+                    Err(e) => warn!("⚠️ {}", e),
+                },
+                _ => warn!("⚠️ Unknown field in Add"),
+            },
+            Instr::Scale { field, factor } => match &mut self.fields[*field] {
+                Some(f) => match factor.scalar(&|v: &usize| self.vars[*v]) {
+                    Ok(factor) => {
+                        f.scale(factor);
+                        say!("✖️ Scaled {} by {}", code.field_names[*field], factor);
+                    }
+                    Err(unknown) => unknown_variable(&code.var_names[*unknown], "Scale"),
+                },
+                None => warn!("⚠️ Unknown field in Scale"),
+            },
+            Instr::Decay { field, rate, steps } => match &mut self.fields[*field] {
+                Some(f) => match rate.scalar(&|v: &usize| self.vars[*v]) {
+                    Ok(rate) => decay_field(f, &code.field_names[*field], rate, *steps),
+                    Err(unknown) => unknown_variable(&code.var_names[*unknown], "Decay"),
+                },
+                None => warn!("⚠️ Unknown field in Decay"),
+            },
+            Instr::Normalize { field } => match &mut self.fields[*field] {
+                Some(f) => {
+                    let norm = f.normalize();
+                    say!("📐 Normalized {} (norm was {:.4})", code.field_names[*field], norm);
+                }
+                None => warn!("⚠️ Unknown field in Normalize"),
+            },
+            Instr::Meaning { var, trace, threshold } => {
+                let trace_cmp = &code.var_names[*trace];
+                let result = match self.vars[*trace] {
+                    Some(value) => threshold
+                        .scalar(&|v: &usize| self.vars[*v])
+                        .map(|threshold| Some(evaluate_meaning(report, &code.var_names[*var], trace_cmp, value, threshold))),
+                    None => Ok(None),
+                };
+                match result {
+                    Ok(Some(holds)) => self.vars[*var] = Some(holds),
+                    Ok(None) => warn!("⚠️ Unknown trace {} in Meaning", trace_cmp),
+                    Err(unknown) => unknown_variable(&code.var_names[*unknown], "Meaning"),
+                }
+            }
+            Instr::LogMeaning(name) => log_meaning(report, name),
+            Instr::Assert { trace, cmp, bound, tolerance } => match bound.scalar(&|v: &usize| self.vars[*v]) {
+                Ok(bound) => check_assertion(report, step, trace, *cmp, bound, *tolerance),
+                Err(unknown) => unknown_variable(&code.var_names[*unknown], "Assert"),
+            },
+            Instr::Modulate { token, intensity } => match intensity.scalar(&|v: &usize| self.vars[*v]) {
+                Ok(intensity) => {
+                    self.rt.modulate(token, intensity);
+                    say!("🎛 Modulated {} @ {:.2}", token, intensity);
+                }
+                Err(unknown) => unknown_variable(&code.var_names[*unknown], "Modulate"),
+            },
+            Instr::Snapshot { field, interp, name } => match &self.fields[*field] {
+                Some(f) => {
+                    self.interps[*interp] = Some(Interpretation::new(f.state.clone()));
+                    say!("📸 Snapshot {} as {}", code.field_names[*field], name);
+                }
+                None => warn!("⚠️ Unknown field in Snapshot"),
+            },
+            Instr::Export { kind, name, path, field } => {
+                let field = field.and_then(|slot| self.fields[slot].as_ref()).map(|f| f.state.as_slice());
+                export(report, step, *kind, name, path, field);
+            }
+            Instr::Report { path } => {
+                let fields = code
+                    .field_names
+                    .iter()
+                    .zip(&self.fields)
+                    .filter_map(|(name, f)| f.as_ref().map(|f| (name.as_str(), f.state.as_slice())));
+                let summary = RunSummary::new(step, fields, &report.traces, &self.rt.docs);
+                export::report(&summary, path.as_deref());
+            }
+            Instr::Seed(seed) => {
+                self.rt.rng.reseed(*seed);
+                say!("🎲 Seed {}", seed);
+            }
+            Instr::Print(msg) => say!("{}", msg),
+            Instr::Warn(msg) => warn!("{}", msg),
+            Instr::Require(capability) => require(*capability),
+            Instr::Level { level, name, body } => match build_level(*level, name, body.clone(), &self.rt.ontology) {
+                Ok(obj) => {
+                    say!("🧬 Level {} {} with {} parts", obj.level_name(), name, obj.subobjects.len());
+                    self.rt.hierarchies.insert(name.clone(), obj);
+                }
+                Err(e) => warn!("⚠️ {}", e),
+            },
+            Instr::Run { path, sharing, body } => {
+                self.store(code);
+                run_script(path, sharing, body.clone(), step, self.rt, report);
+                self.load(code);
+            }
+            Instr::Repeat { count, first, rest } => {
+                for i in 0..*count {
+                    for instr in if i == 0 { first } else { rest } {
+                        self.exec(code, instr, step, report);
+                    }
+                }
+            }
+            Instr::If { condition, then, otherwise } => {
+                match condition.eval(&|v: &usize| self.vars[*v]) {
+                    Ok(holds) => {
+                        for instr in if holds { then } else { otherwise } {
+                            self.exec(code, instr, step, report);
+                        }
+                    }
+                    Err(Unknown::Value(var)) => unknown_variable(&code.var_names[*var], "If"),
+                    Err(Unknown::Agent(agent)) => warn!("⚠️ Unknown agent {} in If", agent),
+                    Err(Unknown::Group(group)) => warn!("⚠️ Unknown group {} in If", group),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Statistical summaries of repeated stochastic runs.
+
+use crate::report::RunReport;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Two-sided 95% Student-t critical values for 1..=30 degrees of freedom.
+const T_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+    2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+    2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+];
+
+fn t_critical(df: usize) -> f64 {
+    match df {
+        0 => f64::NAN,
+        1..=30 => T_95[df - 1],
+        _ => 1.96,
+    }
+}
+
+/// Sample statistics with a 95% confidence interval for the mean.
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub n: usize,
+    pub mean: f64,
+    pub stddev: f64,
+    pub ci_low: f64,
+    pub ci_high: f64,
+}
+
+impl Summary {
+    pub fn of(values: &[f64]) -> Self {
+        let n = values.len();
+        let mean = values.iter().sum::<f64>() / n.max(1) as f64;
+        let stddev = if n > 1 {
+            (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        let half = if n > 1 { t_critical(n - 1) * stddev / (n as f64).sqrt() } else { 0.0 };
+        Summary { n, mean, stddev, ci_low: mean - half, ci_high: mean + half }
+    }
+}
+
+/// Aggregate of many runs of the same script.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RepeatSummary {
+    pub runs: usize,
+    /// Per-trace statistics over the runs that produced the trace.
+    pub traces: BTreeMap<String, Summary>,
+    /// Per-meaning fraction of runs in which it held.
+    pub meanings: BTreeMap<String, f64>,
+}
+
+impl RepeatSummary {
+    pub fn from_reports(reports: &[RunReport]) -> Self {
+        let mut traces: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+        let mut meanings: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+        for report in reports {
+            for (name, value) in &report.traces {
+                traces.entry(name.clone()).or_default().push(*value);
+            }
+            for (name, holds) in &report.meanings {
+                let entry = meanings.entry(name.clone()).or_default();
+                entry.0 += *holds as usize;
+                entry.1 += 1;
+            }
+        }
+        RepeatSummary {
+            runs: reports.len(),
+            traces: traces.into_iter().map(|(k, v)| (k, Summary::of(&v))).collect(),
+            meanings: meanings.into_iter().map(|(k, (t, n))| (k, t as f64 / n as f64)).collect(),
+        }
+    }
+
+    pub fn print(&self) {
+        println!("📊 {} runs", self.runs);
+        for (name, s) in &self.traces {
+            println!(
+                "  trace {:<16} mean={:.4} sd={:.4} 95% CI=[{:.4}, {:.4}] (n={})",
+                name, s.mean, s.stddev, s.ci_low, s.ci_high, s.n
+            );
+        }
+        for (name, freq) in &self.meanings {
+            println!("  meaning {:<14} true in {:.1}% of runs", name, freq * 100.0);
+        }
+    }
+}
@@ -23,11 +23,13 @@
 //!
 
 use std::collections::HashMap;
+use std::ops::Range;
 use rayon::prelude::*; // For parallelism
 use crate::symbol::Symbol;
+use serde::{Deserialize, Serialize};
 
 /// Represents a symbolic pattern (e.g., a bitstring, glyph, etc).
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Pattern(pub String);
 
 impl Pattern {
@@ -39,7 +41,7 @@ impl Pattern {
 
 /// The substrate (●) is a field of activations for patterns.
 /// It is always in flux: activations rise upon projection and decay over τ.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Substrate {
     /// Activation level for each pattern present in the substrate.
     pub activations: HashMap<Pattern, f64>,
@@ -48,8 +50,13 @@ pub struct Substrate {
 impl Substrate {
     /// Project a symbol into the substrate, increasing its activation.
     pub fn project(&mut self, symbol: &Symbol) {
+        self.project_with_gain(symbol, 1.0);
+    }
+
+    /// Project a symbol, increasing its activation by `gain`.
+    pub fn project_with_gain(&mut self, symbol: &Symbol, gain: f64) {
         let ent = self.activations.entry(symbol.pattern.clone()).or_insert(0.0);
-        *ent += 1.0;
+        *ent += gain;
     }
 
     /// Decay all activations multiplicatively, removing those below threshold.
@@ -60,4 +67,55 @@ impl Substrate {
         });
         self.activations.retain(|_, v| *v > 0.01);
     }
-}
\ No newline at end of file
+}
+
+/// Vector operations on a field's state, for composing fields from SPTL.
+impl Substrate {
+    /// Element-wise sum with a field of the same size.
+    pub fn add(&self, other: &Substrate) -> Result<Substrate, String> {
+        if self.state.len() != other.state.len() {
+            return Err(format!("cannot add fields of sizes {} and {}", self.state.len(), other.state.len()));
+        }
+        let mut sum = Substrate::new(self.state.len());
+        sum.state = self.state.iter().zip(&other.state).map(|(a, b)| a + b).collect();
+        Ok(sum)
+    }
+
+    /// A copy of the elements in `range`.
+    pub fn slice(&self, range: Range<usize>) -> Result<Substrate, String> {
+        if range.start >= range.end || range.end > self.state.len() {
+            return Err(format!("cannot slice {}..{} of a field of size {}", range.start, range.end, self.state.len()));
+        }
+        let mut slice = Substrate::new(range.len());
+        slice.state = self.state[range].to_vec();
+        Ok(slice)
+    }
+
+    /// The elements of `parts`, one field after another.
+    pub fn concat(parts: &[&Substrate]) -> Substrate {
+        let state: Vec<f64> = parts.iter().flat_map(|p| p.state.iter().copied()).collect();
+        let mut joined = Substrate::new(state.len());
+        joined.state = state;
+        joined
+    }
+
+    /// Multiply every element by `factor`.
+    pub fn scale(&mut self, factor: f64) {
+        self.state.iter_mut().for_each(|s| *s *= factor);
+    }
+
+    /// Relax toward zero: each of `steps` steps removes the fraction `rate`
+    /// of every element.
+    pub fn decay_state(&mut self, rate: f64, steps: usize) {
+        self.scale((1.0 - rate).powi(steps.min(i32::MAX as usize) as i32));
+    }
+
+    /// Scale to unit Euclidean norm and return the norm it had. A zero field is left as is.
+    pub fn normalize(&mut self) -> f64 {
+        let norm = self.state.iter().map(|s| s * s).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            self.state.iter_mut().for_each(|s| *s /= norm);
+        }
+        norm
+    }
+}
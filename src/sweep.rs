@@ -0,0 +1,90 @@
+//! Parameter sweeps over a script, resumable from an existing run directory.
+//!
+//! Each parameter point runs in its own sub-directory of the sweep root
+//! (`point-alpha=0.1,steps=20/`) following the run directory convention.
+//! Re-launching a sweep with the same root skips every point whose manifest
+//! is already marked completed.
+
+use crate::rundir::{RunDir, MANIFEST_FILE};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// One assignment of values to the swept parameters.
+pub type SweepPoint = BTreeMap<String, String>;
+
+/// Cartesian product of `name -> values` axes.
+pub fn expand_grid(axes: &[(String, Vec<String>)]) -> Vec<SweepPoint> {
+    let mut points = vec![SweepPoint::new()];
+    for (name, values) in axes {
+        points = points.into_iter()
+            .flat_map(|p| values.iter().map(move |v| {
+                let mut p = p.clone();
+                p.insert(name.clone(), v.clone());
+                p
+            }))
+            .collect();
+    }
+    points
+}
+
+/// Directory holding the run for one point.
+pub fn point_dir(root: &Path, point: &SweepPoint) -> PathBuf {
+    let label: Vec<String> = point.iter()
+        .map(|(k, v)| format!("{}={}", k, v.replace(['/', '\\'], "_")))
+        .collect();
+    root.join(format!("point-{}", label.join(",")))
+}
+
+/// Points under `root` whose run finished, read back from their manifests.
+pub fn completed_points(root: &Path) -> Vec<SweepPoint> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    entries.filter_map(|e| e.ok())
+        .filter(|e| e.path().join(MANIFEST_FILE).is_file())
+        .filter_map(|e| RunDir::open(e.path()).ok())
+        .filter(|run| run.manifest.completed)
+        .map(|run| run.manifest.params)
+        .collect()
+}
+
+#[derive(Debug, Default)]
+pub struct SweepSummary {
+    pub ran: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+/// Run every point not already completed under `root`.
+/// `run` receives the point and the directory its artifacts belong in.
+pub fn run_sweep(
+    root: &Path,
+    points: &[SweepPoint],
+    mut run: impl FnMut(&SweepPoint, &Path) -> io::Result<()>,
+) -> io::Result<SweepSummary> {
+    fs::create_dir_all(root)?;
+    let done = completed_points(root);
+    let mut summary = SweepSummary::default();
+    for point in points {
+        // Manifests may also carry fixed bindings, so match on the swept values only.
+        if done.iter().any(|d| point.iter().all(|(k, v)| d.get(k) == Some(v))) {
+            summary.skipped += 1;
+            continue;
+        }
+        let dir = point_dir(root, point);
+        match run(point, &dir) {
+            Ok(()) => summary.ran += 1,
+            Err(e) => {
+                eprintln!("⚠️ Sweep point {:?} failed: {}", point, e);
+                summary.failed += 1;
+            }
+        }
+    }
+    println!(
+        "Sweep: {} run, {} already completed, {} failed",
+        summary.ran, summary.skipped, summary.failed
+    );
+    Ok(summary)
+}
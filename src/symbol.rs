@@ -8,11 +8,15 @@
 //! See SPTL-Specification-Harmonization.md for more.
 
 use crate::substrate::Pattern;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
 
 /// A symbolic sign: a token and a pattern.
 /// Signs are not static; their identity emerges from cycles of expression, projection, and interpretation.
 /// If it participates in the say → project → interpret loop and survives tick, it is a sign.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Symbol {
     /// The sign's token (e.g. word, name, identifier).
     pub token: String,
@@ -36,25 +40,101 @@ impl Symbol {
     }
 }
 
+/// Interned identity of a symbol (token + pattern), cheap to copy and compare.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SymbolId(pub u32);
+
+fn interner() -> &'static Mutex<HashMap<Symbol, SymbolId>> {
+    static INTERNER: OnceLock<Mutex<HashMap<Symbol, SymbolId>>> = OnceLock::new();
+    INTERNER.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+impl Symbol {
+    /// Interned id of this symbol; equal symbols always share an id.
+    pub fn id(&self) -> SymbolId {
+        let mut table = interner().lock().unwrap();
+        let next = SymbolId(table.len() as u32);
+        *table.entry(self.clone()).or_insert(next)
+    }
+}
+
+/// How an interpretation classified the sign it was given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Classification {
+    /// The sign matched a known token → pattern entry.
+    Recognized,
+    /// The sign's pattern only partly matched the known entry for its token.
+    Approximate,
+    /// The sign did not match what the agent knows; recorded by the traces
+    /// of symbols with the sign's pattern.
+    Unrecognized,
+}
+
+/// Structured identity of an interpretation: what was interpreted and how.
+/// Two meanings express the same interpretant if these are equal, regardless of τ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interpretant {
+    pub symbol: SymbolId,
+    pub classification: Classification,
+}
+
 /// A meaning is an interpretation of a symbol at a recursion index (tau).
 /// Meaning is always situated in τ; it only exists as an interpretive event.
-#[derive(Debug, Clone)]
+/// Serialized without its interned id, which is only valid within one process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "MeaningRecord", from = "MeaningRecord")]
 pub struct Meaning {
     /// The sign/symbol being interpreted.
     pub sign: Symbol,
     /// The recursion/time index at which this meaning was created.
     pub tau: usize,
-    /// Human-readable description of the meaning.
-    pub description: String,
+    /// What the interpretation produced, compared directly by symmetry detection.
+    pub interpretant: Interpretant,
+}
+
+/// On-disk form of a `Meaning`; the interpretant's symbol id is re-interned on load.
+#[derive(Serialize, Deserialize)]
+struct MeaningRecord {
+    sign: Symbol,
+    tau: usize,
+    classification: Classification,
+}
+
+impl From<Meaning> for MeaningRecord {
+    fn from(m: Meaning) -> Self {
+        MeaningRecord { sign: m.sign, tau: m.tau, classification: m.interpretant.classification }
+    }
+}
+
+impl From<MeaningRecord> for Meaning {
+    fn from(r: MeaningRecord) -> Self {
+        Meaning::new(&r.sign, r.tau, r.classification)
+    }
 }
 
 impl Meaning {
     /// Create a new meaning from a symbol and recursion index.
     pub fn from_symbol(symbol: &Symbol, tau: usize) -> Self {
+        Meaning::new(symbol, tau, Classification::Recognized)
+    }
+
+    /// Create a meaning with an explicit classification.
+    pub fn new(symbol: &Symbol, tau: usize, classification: Classification) -> Self {
         Meaning {
             sign: symbol.clone(),
             tau,
-            description: format!("Interpretation of '{}' at τ={}", symbol.token, tau),
+            interpretant: Interpretant { symbol: symbol.id(), classification },
         }
     }
-}
\ No newline at end of file
+
+    /// Human-readable description, generated on demand.
+    pub fn description(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for Meaning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Interpretation of '{}' at τ={}", self.sign.token, self.tau)
+    }
+}
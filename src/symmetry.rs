@@ -1,6 +1,6 @@
 //! Symmetry, attractor, and differentiation detection for SPTL agents.
 
-use crate::agents::Agent;
+use crate::agents::{pattern_similarity, Agent};
 
 /// Returns true if all symbols' interpretant histories have stabilized (ΔΠ(s, τ) = 0 for last N steps).
 pub fn detect_symmetry(agent: &Agent, window: usize) -> bool {
@@ -10,8 +10,8 @@ pub fn detect_symmetry(agent: &Agent, window: usize) -> bool {
             return false;
         }
         let last = &meanings[meanings.len() - window..];
-        let first_desc = &last[0].description;
-        if !last.iter().all(|m| &m.description == first_desc) {
+        let first = last[0].interpretant;
+        if !last.iter().all(|m| m.interpretant == first) {
             return false;
         }
     }
@@ -29,7 +29,7 @@ pub fn detect_differentiation(agent: &Agent, window: usize) -> bool {
         let mut iter = last.iter();
         let mut prev = iter.next().unwrap();
         for m in iter {
-            if m.description != prev.description {
+            if m.interpretant != prev.interpretant {
                 return true;
             }
             prev = m;
@@ -41,4 +41,58 @@ pub fn detect_differentiation(agent: &Agent, window: usize) -> bool {
 /// Returns true if all memory traces have stabilized their interpretants (symmetry/attractor).
 pub fn detect_attractor(agent: &Agent, window: usize) -> bool {
     detect_symmetry(agent, window)
-}
\ No newline at end of file
+}
+/// Recent interpretants a trace must agree on to count as stable in `consensus`.
+pub const CONSENSUS_WINDOW: usize = 3;
+
+/// Whether `agent` holds `token` stably: its trace is at least as stable as
+/// the agent's coherence threshold and shows no differentiation over the
+/// last `window` interpretants.
+pub fn interprets_stably(agent: &Agent, token: &str, window: usize) -> bool {
+    let Some(trace) = agent.memory.traces.iter().find(|t| t.symbol.token == token) else {
+        return false;
+    };
+    let recent = &trace.interpretants[trace.interpretants.len().saturating_sub(window)..];
+    trace.stability >= agent.coherence_threshold && recent.windows(2).all(|w| w[0].interpretant == w[1].interpretant)
+}
+
+/// How many members of a group agree on a token.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Consensus {
+    /// Members that stably interpret the token with the pattern most of them share.
+    pub agreeing: usize,
+    pub members: usize,
+}
+
+impl Consensus {
+    /// Agreeing share of the group, 0 for an empty one.
+    pub fn share(&self) -> f64 {
+        if self.members == 0 {
+            0.0
+        } else {
+            self.agreeing as f64 / self.members as f64
+        }
+    }
+}
+
+/// Cross-agent agreement on `token`: of the members that interpret it stably,
+/// the largest set understanding one member's pattern, each by its own
+/// `min_similarity`. Members without a symbol table never agree.
+pub fn consensus(members: &[Option<&Agent>], token: &str, window: usize) -> Consensus {
+    let stable: Vec<&Agent> = members
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|a| a.symbol_table.contains_key(token) && interprets_stably(a, token, window))
+        .collect();
+    let agreeing = stable
+        .iter()
+        .map(|reference| {
+            let pattern = &reference.symbol_table[token];
+            let understands = |a: &&Agent| pattern_similarity(&a.symbol_table[token], pattern) >= a.reinforcement.min_similarity;
+            stable.iter().filter(|a| understands(a)).count()
+        })
+        .max()
+        .unwrap_or(0);
+    Consensus { agreeing, members: members.len() }
+}
@@ -0,0 +1,148 @@
+//! Built-in experiment templates for `sptl new experiment --template <name>`.
+//!
+//! Each template is a runnable set of files: an SPTL program (`main.sptl`), a
+//! narrative script (`story.narr`), and projection defaults (`sptl.toml`).
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const TEMPLATE_NAMES: &[&str] = &["naming-game", "drift", "coupled-fields"];
+
+const CONFIG: &str = "\
+# Projection defaults for this experiment.
+alpha = 0.3
+noise = 0.05
+steps = 20
+";
+
+const NAMING_GAME_SPTL: &str = "\
+# Naming game: a shared field converges on the population's agreed name.
+field lexicon 8
+interpretation name_a = [1, 0, 1, 0, 1, 0, 1, 0]
+interpretation name_b = [0, 1, 0, 1, 0, 1, 0, 1]
+
+project lexicon <- name_a { alpha: 0.3, noise: 0.05, steps: 20 }
+trace agree_a = distance(lexicon, name_a)
+trace agree_b = distance(lexicon, name_b)
+meaning settled = below(agree_a, 0.5)
+
+logcoherence lexicon
+logmeaning settled
+";
+
+const NAMING_GAME_NARR: &str = "\
+# Naming game: speakers propose names, hearers adopt what they understand.
+at τ=0:
+  create agent alice 64 0.2
+  create agent bob 64 0.2
+  create agent carol 64 0.2
+  alice says: kiki → 10101010
+
+repeat 3 times:
+  bob hears: kiki → 10101010
+  carol hears: kiki → 10101010
+  bob says: kiki → 10101010
+  tick 1
+
+at τ=5:
+  if carol knows kiki:
+    carol says: kiki → 10101010
+  assert carol knows kiki
+";
+
+const DRIFT_SPTL: &str = "\
+# Drift: a noisy projection wanders away from its interpretation.
+field psi 8
+interpretation origin = [1, 1, 1, 1, 0, 0, 0, 0]
+
+project psi <- origin { alpha: 0.3, noise: 0.05, steps: 20 }
+trace settled = distance(psi, origin)
+
+project psi <- origin { alpha: 0.05, noise: 0.8, steps: 50 }
+trace drifted = distance(psi, origin)
+
+logcoherence psi
+";
+
+const DRIFT_NARR: &str = "\
+# Drift: each retelling mutates the sign a little further.
+at τ=0:
+  create agent teller 32 0.1
+  create agent listener 32 0.1
+  teller says: origin → 11110000
+
+repeat 3 times:
+  listener hears: origin → 11110000
+  teller says: origin* → 11110001
+  tick 2
+
+at τ=10:
+  assert listener knows origin
+";
+
+const COUPLED_FIELDS_SPTL: &str = "\
+# Coupled fields: two fields pulled toward a shared and a private interpretation.
+field left 8
+field right 8
+interpretation shared = [1, 0, 1, 0, 1, 0, 1, 0]
+interpretation private = [0, 0, 0, 0, 1, 1, 1, 1]
+
+project left <- shared { alpha: 0.3, noise: 0.05, steps: 20 }
+project right <- shared { alpha: 0.3, noise: 0.05, steps: 10 }
+project right <- private { alpha: 0.1, noise: 0.05, steps: 10 }
+
+trace left_shared = distance(left, shared)
+trace right_shared = distance(right, shared)
+trace right_private = distance(right, private)
+
+logcoherence left
+logcoherence right
+";
+
+const COUPLED_FIELDS_NARR: &str = "\
+# Coupled fields: two agents in one cell share signs across molecules.
+at τ=0:
+  create molecule M1 from atoms A1 A2
+  create molecule M2 from atoms A3 A4
+  create cell C from molecules M1 M2
+  create agent left 64 0.2 in A1
+  create agent right 64 0.2 in A3
+  left says: pulse → 10101010
+  right hears: pulse → 10101010
+  tick 1
+
+at τ=2:
+  copy agent left from A1 to A3
+  tick 1
+";
+
+/// Files making up a template, as (relative path, contents).
+pub fn template_files(name: &str) -> Option<Vec<(&'static str, &'static str)>> {
+    let (sptl, narr) = match name {
+        "naming-game" => (NAMING_GAME_SPTL, NAMING_GAME_NARR),
+        "drift" => (DRIFT_SPTL, DRIFT_NARR),
+        "coupled-fields" => (COUPLED_FIELDS_SPTL, COUPLED_FIELDS_NARR),
+        _ => return None,
+    };
+    Some(vec![("main.sptl", sptl), ("story.narr", narr), ("sptl.toml", CONFIG)])
+}
+
+/// Write a template into `dir`, refusing to overwrite existing files.
+pub fn scaffold(name: &str, dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let files = template_files(name).ok_or_else(|| io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("unknown template '{}' (available: {})", name, TEMPLATE_NAMES.join(", ")),
+    ))?;
+    fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    for (rel, contents) in files {
+        let path = dir.join(rel);
+        if path.exists() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", path.display())));
+        }
+        fs::write(&path, contents)?;
+        written.push(path);
+    }
+    Ok(written)
+}
@@ -0,0 +1,25 @@
+use sptl_spi::actors::{ActorRuntime, AgentMessage};
+use sptl_spi::agents::Agent;
+use sptl_spi::substrate::Pattern;
+
+#[test]
+fn test_actor_express_reaches_listener() {
+    let agents = vec![Agent::new("alice", 16, 0.1), Agent::new("bob", 16, 0.1)];
+    let runtime = ActorRuntime::start(agents, 2);
+    runtime
+        .send(
+            "alice",
+            AgentMessage::Express {
+                token: "fire".to_string(),
+                pattern: Pattern::new("101"),
+                tau: 0,
+                to: vec!["bob".to_string()],
+            },
+        )
+        .unwrap();
+    assert!(runtime.send("carol", AgentMessage::Tick { decay: 0.1 }).is_err());
+
+    let agents = runtime.shutdown();
+    let alice = agents.iter().find(|a| a.id == "alice").unwrap();
+    assert!(alice.symbol_table.contains_key("fire"));
+}
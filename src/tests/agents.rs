@@ -0,0 +1,32 @@
+use sptl_spi::agents::Agent;
+use sptl_spi::narrative::parser::parse_script;
+use sptl_spi::narrative::runner::{execute_script, ScriptContext};
+use sptl_spi::substrate::Pattern;
+
+#[test]
+fn test_agent_transplant_between_runs() {
+    let dir = std::env::temp_dir().join(format!("sptl-agents-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("run1/agents/alice.json");
+
+    let first = format!(
+        "at τ=0:\n  create agent alice 16 0.1\n  alice says: fire → 1010\nat τ=3:\n  alice says: water → 0110\n  save agent alice to \"{}\"\n",
+        path.display()
+    );
+    execute_script(&parse_script(&first), &mut ScriptContext::default());
+
+    let saved = Agent::load(&path).unwrap();
+    assert_eq!(saved.symbol_table["water"], Pattern::new("0110"));
+    assert_eq!(saved.memory.traces[1].tau_index, 3);
+
+    // A later scenario picks the agent up, under a new name, with its experience intact.
+    let second = format!("at τ=0:\n  load agent alice2 from \"{}\"\n", path.display());
+    let mut ctx = ScriptContext::default();
+    execute_script(&parse_script(&second), &mut ctx);
+    let state = &ctx.world.agents["alice2"];
+    assert_eq!(state.memory, vec!["fire".to_string(), "water".to_string()]);
+    let agent = state.agent.as_ref().unwrap();
+    assert_eq!(agent.id, "alice2");
+    assert_eq!(agent.symbol_table, saved.symbol_table);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,55 @@
+use sptl_spi::batch::{run, run_all, RunConfig};
+use sptl_spi::cli::Engine;
+
+const SCRIPT: &str = "field psi 4\ninterpretation I = [1 0 1 0]\n\
+                      project psi <- I { alpha: $alpha noise: 0 steps: 5 }\ntrace d = trace_distance(psi, I)";
+
+#[test]
+fn test_batch_runs_are_isolated_and_ordered() {
+    let mut configs: Vec<RunConfig> =
+        [0.05, 0.1, 0.2].iter().map(|alpha| RunConfig::new(SCRIPT).param("alpha", alpha)).collect();
+    configs.push(RunConfig::new(SCRIPT).param("alpha", 0.1).engine(Engine::Ast));
+    configs.push(RunConfig::new("field psi size"));
+
+    let reports = run_all(configs);
+    assert_eq!(reports.len(), 5);
+    let d: Vec<f64> = reports[..4].iter().map(|r| r.traces["d"]).collect();
+    // A stronger pull ends closer; runs do not share fields.
+    assert!(d[0] > d[1] && d[1] > d[2]);
+    assert_eq!(d[1], d[3]);
+    assert!(reports[..4].iter().all(|r| r.errors.is_empty()));
+    assert_eq!(reports[4].errors.len(), 1);
+    assert!(reports[4].traces.is_empty());
+}
+
+#[test]
+fn test_shared_compilation_matches_separate_runs() {
+    let noisy = "field psi 4\ninterpretation I = [1 0 1 0]\nlet a = $alpha * 2\n\
+                 project psi <- I { alpha: a noise: $noise steps: 5 }\ntrace d = trace_distance(psi, I)";
+    let point = |alpha: f64| RunConfig::new(noisy).param("alpha", alpha).param("noise", 0.05).seed(7);
+    let reports = run_all(vec![point(0.05), point(0.1)]);
+    for (report, alpha) in reports.iter().zip([0.05, 0.1]) {
+        assert_eq!(report.traces["d"], run(point(alpha)).traces["d"]);
+    }
+
+    // A parameter used as a step count cannot be left open; each run parses it.
+    let steps = "field psi 4\ninterpretation I = [1 0 1 0]\n\
+                 project psi <- I { alpha: 0.1 noise: 0 steps: $n }\ntrace d = trace_distance(psi, I)";
+    let reports = run_all(vec![RunConfig::new(steps).param("n", 2), RunConfig::new(steps).param("n", 8)]);
+    assert!(reports.iter().all(|r| r.errors.is_empty()));
+    assert!(reports[0].traces["d"] > reports[1].traces["d"]);
+}
+
+#[test]
+fn test_audited_runs_count_rng_draws_per_site() {
+    let script = "field psi 4\ninterpretation I = [1 0 1 0]\n\
+                  project psi <- I { alpha: 0.1 noise: 0.05 steps: 5 }\nperturb psi noise 0.1";
+    let audited = |seed: u64| RunConfig::new(script).seed(seed).audit_rng();
+    let reports = run_all(vec![audited(1), audited(2).engine(Engine::Ast)]);
+    for report in &reports {
+        // One draw per element per projection step, and per element perturbed.
+        assert_eq!(report.rng_draws["project"], 20);
+        assert_eq!(report.rng_draws["perturb"], 4);
+    }
+    assert!(run(RunConfig::new(script).seed(1)).rng_draws.is_empty());
+}
@@ -0,0 +1,83 @@
+use sptl_spi::condition::Condition;
+use sptl_spi::narrative::ast::{Action, Block};
+use sptl_spi::narrative::build::Script;
+use sptl_spi::narrative::parser::parse_script;
+use sptl_spi::sptl::build::{var, Program};
+use sptl_spi::sptl::{parse_source, Comparison, RecordKind};
+use std::collections::BTreeMap;
+
+#[test]
+fn test_program_builder_matches_parsed_source() {
+    let source = "\
+field psi 4
+interpretation I = [1, 0, 1, 0]
+let a = 0.2
+project psi <- I { alpha: a, steps: 20 } record trajectory every 5 steps
+project psi <- I { alpha: 0.1, until: dist < 0.01 }
+trace d = trace_distance(psi, I)
+if d > 0.5 {
+    repeat 2 {
+        perturb psi noise 0.1
+    }
+}
+assert trace d < 0.5 within 0.01
+";
+    let built = Program::new()
+        .field("psi", 4)
+        .interpretation("I", vec![1.0, 0.0, 1.0, 0.0])
+        .assign("a", 0.2)
+        .project("psi", "I", |p| p.alpha(var("a")).steps(20).record(RecordKind::Trajectory, 5))
+        .project("psi", "I", |p| p.alpha(0.1).until(0.01))
+        .trace("d", "psi", "I")
+        .if_then(Condition::parse("d > 0.5").unwrap(), |p| p.repeat(2, |p| p.perturb("psi", 0.1)))
+        .assert_trace("d", Comparison::Less, 0.5, 0.01)
+        .build();
+    let parsed = parse_source(source, &BTreeMap::new()).unwrap();
+    assert_eq!(serde_json::to_string(&built).unwrap(), serde_json::to_string(&parsed).unwrap());
+}
+
+/// The parsed blocks without the `Source` markers the parser adds.
+fn without_sources(blocks: Vec<Block>) -> Vec<Block> {
+    fn strip(actions: Vec<Action>) -> Vec<Action> {
+        actions
+            .into_iter()
+            .filter(|a| !matches!(a, Action::Source { .. }))
+            .map(|a| match a {
+                Action::Conditional(condition, then, otherwise) => {
+                    Action::Conditional(condition, strip(then), strip(otherwise))
+                }
+                other => other,
+            })
+            .collect()
+    }
+    blocks
+        .into_iter()
+        .map(|b| match b {
+            Block::AtTau(tau, actions) => Block::AtTau(tau, strip(actions)),
+            Block::Repeat(n, actions) => Block::Repeat(n, strip(actions)),
+            other => other,
+        })
+        .collect()
+}
+
+#[test]
+fn test_script_builder_matches_parsed_script() {
+    let script = "\
+at τ=0:
+  create agent alice 16 0.1
+  field F 4
+  alice says: fire → 1010
+repeat 3 times:
+  alice projects: fire into F
+  if alice knows fire:
+    tick 1
+";
+    let built = Script::new()
+        .at(0, |a| a.agent("alice", 16, 0.1).field("F", 4).say("alice", "fire", "1010"))
+        .repeat(3, |a| {
+            a.project("alice", "fire", Some("F")).if_then(Condition::parse("alice knows fire").unwrap(), |a| a.tick(1))
+        })
+        .build();
+    let parsed = without_sources(parse_script(script));
+    assert_eq!(serde_json::to_string(&built).unwrap(), serde_json::to_string(&parsed).unwrap());
+}
@@ -0,0 +1,44 @@
+use sptl_spi::config::Config;
+use sptl_spi::sptl::cache::{AstCache, GRAMMAR_VERSION};
+use sptl_spi::sptl::{parse_source, Statement};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn entries(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir).unwrap().map(|e| e.unwrap().path()).collect();
+    paths.sort();
+    paths
+}
+
+fn json(program: &[Statement]) -> String {
+    serde_json::to_string(program).unwrap()
+}
+
+#[test]
+fn test_cache_entries_are_invalidated_by_source_and_grammar_changes() {
+    let dir = std::env::temp_dir().join(format!("sptl-cache-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let (cache, params, config) = (AstCache::new(&dir), BTreeMap::new(), Config::default());
+    let source = "field psi 4\n";
+    let parsed = cache.parse(source, &params, &config).unwrap();
+    let [entry] = entries(&dir).try_into().unwrap();
+
+    // An entry is the grammar version and the program; plant a different
+    // program under this source's key to see when the cache is used.
+    let planted = parse_source("field phi 8\n", &params).unwrap();
+    let write = |version: u32| fs::write(&entry, bincode::serialize(&(version, &planted)).unwrap()).unwrap();
+    write(GRAMMAR_VERSION);
+    assert_eq!(json(&cache.parse(source, &params, &config).unwrap()), json(&planted));
+
+    // Another source has its own entry.
+    let changed = "field psi 16\n";
+    assert_eq!(json(&cache.parse(changed, &params, &config).unwrap()), json(&parse_source(changed, &params).unwrap()));
+    assert_eq!(entries(&dir).len(), 2);
+
+    // An entry from another grammar is parsed again and replaced.
+    write(GRAMMAR_VERSION - 1);
+    assert_eq!(json(&cache.parse(source, &params, &config).unwrap()), json(&parsed));
+    assert_eq!(json(&cache.parse(source, &params, &config).unwrap()), json(&parsed));
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,127 @@
+use sptl_spi::condition::{Condition, Unknown};
+use sptl_spi::narrative::parser::parse_script;
+use sptl_spi::narrative::runner::{execute_script, ScriptContext};
+use std::collections::HashMap;
+
+#[test]
+fn test_one_condition_language_for_sptl_and_narrative() {
+    // SPTL scope: `let` variables, traces and meanings, no agents.
+    let vars: HashMap<String, f64> = [("d".to_string(), 0.05)].into();
+    let lookup = |name: &String| vars.get(name).copied();
+    assert_eq!(Condition::parse("d * 2 < 0.2").unwrap().eval(&lookup), Ok(true));
+    assert_eq!(Condition::parse("e > 1").unwrap().eval(&lookup), Err(Unknown::Value(&"e".to_string())));
+    let knows = Condition::parse("alice knows fire").unwrap();
+    assert_eq!(knows.eval(&lookup), Err(Unknown::Agent("alice")));
+    assert!(Condition::parse("d").is_none());
+
+    // Narrative scope: the same conditions over agents, variables and τ.
+    let script = "at τ=4:\n  create agent alice 16 0.1\n  alice says: fire → 1010\n  let count = 7\n";
+    let mut ctx = ScriptContext::default();
+    execute_script(&parse_script(script), &mut ctx);
+    assert_eq!(knows.eval(&ctx), Ok(true));
+    assert_eq!(Condition::parse("alice memory contains water").unwrap().eval(&ctx), Ok(false));
+    assert_eq!(Condition::parse("count > tau + 2").unwrap().eval(&ctx), Ok(true));
+    assert_eq!(Condition::parse("bob knows fire").unwrap().eval(&ctx), Err(Unknown::Agent("bob")));
+}
+
+#[test]
+fn test_explain_shows_values_read_and_the_failing_comparison() {
+    let vars: HashMap<String, f64> = [("d".to_string(), 0.5), ("a".to_string(), 0.2)].into();
+    let lookup = |name: &String| vars.get(name).copied();
+    assert_eq!(
+        Condition::parse("d < a * 2").unwrap().explain(&lookup),
+        ["d < a * 2 does not hold", "  d = 0.5", "  a * 2 = 0.4", "    a = 0.2", "  0.5 < 0.4 is false"]
+    );
+    assert_eq!(
+        Condition::parse("d < e + 1").unwrap().explain(&lookup),
+        ["d < e + 1 cannot be evaluated: unknown variable e", "  d = 0.5", "  e + 1 is unknown: it reads e", "    e is unknown"]
+    );
+    assert_eq!(Condition::parse("alice knows fire").unwrap().explain(&lookup)[1], "  there is no agent alice");
+}
+
+#[test]
+fn test_consensus_needs_a_quorum_sharing_one_pattern() {
+    let script = "at τ=0:\n  create agent alice 16 0.1\n  create agent bob 16 0.1\n  create agent carol 16 0.1\n  alice says: fire → 1010\n  bob says: fire → 1010\n  carol says: fire → 0101\n  group learners = alice bob carol\n";
+    let mut ctx = ScriptContext::default();
+    execute_script(&parse_script(script), &mut ctx);
+    // Two of three share a pattern; carol's differs in every position.
+    let majority = Condition::parse("consensus on \"fire\" among group learners quorum 0.6").unwrap();
+    assert_eq!(majority.eval(&ctx), Ok(true));
+    assert_eq!(Condition::parse("consensus on fire among group learners quorum 0.8").unwrap().eval(&ctx), Ok(false));
+    assert_eq!(Condition::parse("consensus on water among group learners quorum 0.1").unwrap().eval(&ctx), Ok(false));
+    assert_eq!(
+        Condition::parse("consensus on fire among group others quorum 0.5").unwrap().eval(&ctx),
+        Err(Unknown::Group("others"))
+    );
+    assert_eq!(Condition::parse(&majority.to_string()).unwrap(), majority);
+    assert_eq!(majority.explain(&ctx)[1], "  2 of 3 in learners agree on fire (0.67)");
+}
+
+#[test]
+fn test_narrative_if_runs_one_branch_of_elif_and_else() {
+    let branch = |count: u32| {
+        let script = format!(
+            "at τ=0:\n  let count = {}\n  if count > 5:\n    let branch = high\n  elif count > 2:\n    let branch = middle\n  else:\n    let branch = low\n  let after = yes\n",
+            count
+        );
+        let mut ctx = ScriptContext::default();
+        execute_script(&parse_script(&script), &mut ctx);
+        assert_eq!(ctx.world.vars["after"], "yes");
+        ctx.world.vars["branch"].clone()
+    };
+    assert_eq!(branch(9), "high");
+    assert_eq!(branch(3), "middle");
+    assert_eq!(branch(1), "low");
+}
+
+#[test]
+fn test_comparisons_cover_equality_and_agent_activations() {
+    let values = HashMap::from([("d".to_string(), 0.5)]);
+    let lookup = |name: &String| values.get(name).copied();
+    for (text, holds) in [("d >= 0.5", true), ("d <= 0.4", false), ("d == 0.5", true), ("d != 0.5", false), ("d * 2 >= 1", true)] {
+        let condition = Condition::parse(text).unwrap();
+        assert_eq!(condition.eval(&lookup), Ok(holds), "{}", text);
+        assert_eq!(Condition::parse(&condition.to_string()).unwrap(), condition);
+    }
+
+    let script = "at τ=0:\n  create agent alice 16 0.1\n  let count = 4\n  tick 10\n";
+    let mut ctx = ScriptContext::default();
+    execute_script(&parse_script(script), &mut ctx);
+    std::sync::Arc::make_mut(ctx.world.agents.get_mut("alice").unwrap()).activation.insert("fire".to_string(), 0.75);
+    for (text, holds) in [
+        ("tau >= 10", true),
+        ("$count > 3", true),
+        ("$count == 4", true),
+        ("alice activation fire > 0.5", true),
+        ("alice activation water > 0", false),
+    ] {
+        assert_eq!(Condition::parse(text).unwrap().eval(&ctx), Ok(holds), "{}", text);
+    }
+    assert_eq!(Condition::parse("bob activation fire > 0.5").unwrap().eval(&ctx), Err(Unknown::Agent("bob")));
+}
+
+#[test]
+fn test_and_or_not_combine_conditions_with_parentheses() {
+    let script = "at τ=0:\n  create agent alice 16 0.1\n  create agent bob 16 0.1\n  alice says: fire → 1010\n  let n = 0\n\
+                  while not bob knows fire and n < 3:\n  let n = 3\n\
+                  at τ=0:\n  if alice knows fire and (bob knows fire or n >= 3):\n    let seen = yes\n";
+    let mut ctx = ScriptContext::default();
+    execute_script(&parse_script(script), &mut ctx);
+    assert_eq!(ctx.world.vars["seen"], "yes");
+
+    for (text, holds) in [
+        ("alice knows fire and bob knows fire", false),
+        ("alice knows fire or bob knows fire", true),
+        ("not bob knows fire", true),
+        ("not (alice knows fire or bob knows fire)", false),
+        ("bob knows fire or alice knows fire and not bob knows fire", true),
+        ("(bob knows fire or alice knows fire) and bob knows fire", false),
+    ] {
+        let condition = Condition::parse(text).unwrap();
+        assert_eq!(condition.eval(&ctx), Ok(holds), "{}", text);
+        assert_eq!(condition.to_string(), text);
+    }
+    // `and` stops at the first part that does not hold, so carol is never asked about.
+    assert_eq!(Condition::parse("bob knows fire and carol knows fire").unwrap().eval(&ctx), Ok(false));
+    assert_eq!(Condition::parse("alice knows fire and carol knows fire").unwrap().eval(&ctx), Err(Unknown::Agent("carol")));
+}
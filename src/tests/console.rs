@@ -0,0 +1,28 @@
+use sptl_spi::console::{Console, Throttle};
+
+fn feed(console: &mut Console, lines: &[&str]) -> Vec<String> {
+    lines.iter().flat_map(|line| console.line(line.to_string())).collect()
+}
+
+#[test]
+fn test_unthrottled_console_prints_everything() {
+    let mut console = Console::default();
+    assert_eq!(feed(&mut console, &["a", "a", "b"]), ["a", "a", "b"]);
+    assert!(console.finish().is_empty());
+}
+
+#[test]
+fn test_repeats_are_summarized() {
+    let mut console = Console::new(Throttle { repeat_every: Some(3), lines_per_tau: None });
+    let printed = feed(&mut console, &["tick", "tick", "tick", "tick", "tick", "tock"]);
+    assert_eq!(printed, ["tick", "… repeated 3 time(s): tick", "… repeated 1 time(s): tick", "tock"]);
+}
+
+#[test]
+fn test_lines_per_tau_are_capped() {
+    let mut console = Console::new(Throttle { repeat_every: None, lines_per_tau: Some(2) });
+    assert_eq!(feed(&mut console, &["a", "b", "c", "d"]), ["a", "b"]);
+    assert_eq!(console.set_tau(1), ["… 2 more line(s) at τ=0 not shown"]);
+    assert_eq!(feed(&mut console, &["e"]), ["e"]);
+    assert!(console.finish().is_empty());
+}
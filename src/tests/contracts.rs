@@ -0,0 +1,60 @@
+use sptl_spi::narrative::contract::{check, ResourceKind};
+use sptl_spi::narrative::parser::parse_script;
+use sptl_spi::narrative::runner::{execute_script, ScriptContext};
+
+const LIBRARY: &str = "\
+macro introduce(a, b):
+  requires agent $a exists
+  creates agent $b
+  create agent $b 16 0.1
+  $b says: hello → 1010
+";
+
+#[test]
+fn test_macro_contracts_parse_and_pass_when_met() {
+    let script = format!("{}at τ=0:\n  create agent alice 16 0.1\n  introduce(alice, bob)\n", LIBRARY);
+    let blocks = parse_script(&script);
+    let contract = blocks.iter().find_map(|b| match b {
+        sptl_spi::narrative::ast::Block::MacroDef { contract, .. } => Some(contract.clone()),
+        _ => None,
+    });
+    let contract = contract.unwrap();
+    assert_eq!(contract.requires[0].kind, ResourceKind::Agent);
+    assert_eq!(contract.creates[0].name, "$b");
+    assert!(check(&blocks).is_empty());
+
+    let mut ctx = ScriptContext::default();
+    execute_script(&blocks, &mut ctx);
+    assert_eq!(ctx.world.agents["bob"].memory, ["hello"]);
+}
+
+#[test]
+fn test_macro_contracts_are_checked_before_running() {
+    let script = format!(
+        "macro broken(x):\n  requires field $y\n  creates group $x\n  log nothing\n{}at τ=0:\n  introduce(carol, dave)\n  introduce(carol)\n  missing(1)\n",
+        LIBRARY
+    );
+    let problems = check(&parse_script(&script));
+    assert_eq!(
+        problems,
+        [
+            "macro broken: `requires field $y` names $y, which is not a parameter of broken",
+            "macro broken: declares `creates group $x`, but nothing in its body creates it",
+            "line 11: introduce(carol, dave): requires agent carol, but nothing in the script creates it",
+            "line 12: introduce(carol): introduce expects 2 argument(s), got 1",
+            "line 13: missing(1): no macro named missing",
+        ]
+    );
+}
+
+#[test]
+fn test_unmet_precondition_stops_the_call() {
+    // A custom action might create carol, so only the runner can tell.
+    let script = format!("{}at τ=0:\n  custom noop\n  introduce(carol, dave)\n", LIBRARY);
+    let blocks = parse_script(&script);
+    assert!(check(&blocks).is_empty());
+    let mut ctx = ScriptContext::default();
+    ctx.register_action("noop", |_, _| Ok(()));
+    execute_script(&blocks, &mut ctx);
+    assert!(!ctx.world.agents.contains_key("dave"));
+}
@@ -0,0 +1,35 @@
+use sptl_spi::narrative::parser::parse_script;
+use sptl_spi::narrative::runner::{execute_script, ScriptContext};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_custom_actions_run_host_closures_on_the_world() {
+    let script = "\
+when alice.reading > 0.4 then: alice says: warm → 11
+at τ=2:
+  create agent alice 16 0.1
+  let who = alice
+  custom sensor_read $who
+  custom sensor_read bob
+  custom unregistered alice
+";
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&calls);
+    let mut ctx = ScriptContext::default();
+    ctx.register_action("sensor_read", move |world, args| {
+        seen.lock().unwrap().push(args.to_vec());
+        let [agent] = args else { return Err("expects one agent".to_string()) };
+        if !world.agents.contains_key(agent) {
+            return Err(format!("no agent {}", agent));
+        }
+        world.measurements.insert(format!("{}.reading", agent), 0.1 * world.tau as f64 + 0.3);
+        Ok(())
+    });
+    execute_script(&parse_script(script), &mut ctx);
+
+    assert_eq!(*calls.lock().unwrap(), [vec!["alice".to_string()], vec!["bob".to_string()]]);
+    assert!((ctx.world.measurements["alice.reading"] - 0.5).abs() < 1e-9);
+    assert!(!ctx.world.measurements.contains_key("bob.reading"));
+    // The reading is visible to the rest of the script like any measurement.
+    assert_eq!(ctx.world.agents["alice"].memory, ["warm"]);
+}
@@ -0,0 +1,36 @@
+use sptl_spi::batch::{differential, run, RunConfig};
+use sptl_spi::cli::Engine;
+
+/// Scripts covering every statement whose effect shows up in a report.
+const SCRIPTS: &[&str] = &[
+    "field psi 8\ninterpretation I = gaussian(size: 8, mean: 0.5, std: 0.2)\n\
+     project psi <- I { alpha: 0.2, noise: 0.05, steps: 10, log_every: 5 }\n\
+     expresssymbol \"fire\" into psi { weight: 0.1 }\n\
+     trace d = trace_distance(psi, I)\nlet c = coherence(psi, I)\nassert trace d < 5",
+    "field psi 4\ninterpretation I = [1 0 1 0]\n\
+     project psi <- I { alpha: 0.3, noise: 0.01, until: dist < 0.05, max_steps: 200 }\n\
+     perturb psi noise 0.1\nshock psi indices [1..3] value 2\nscale psi 0.5\ndecay psi 0.1 steps 3\n\
+     normalize psi\ntrace d = trace_distance(psi, I)\nmeaning near = below(d, 1)",
+    "field psi 4\ninterpretation A = [1 0 0 0]\ninterpretation B = [0 0 0 1]\n\
+     project psi <- [A: 2, B: 1] { alpha: 0.5, noise: 0.02, steps: 4 }\n\
+     morph psi from A to B { alpha: 0.4, noise: 0.02, steps: 6 }\n\
+     steer psi toward B keep coherence > 0.9 using alpha in [0.01, 0.5] for 20 steps\n\
+     trace d = trace_distance(psi, B)",
+    "field a 4\nfield b 4\ninterpretation I = random(size: 4, low: -1, high: 1)\n\
+     repeat 3 {\nproject a <- I { alpha: 0.2, noise: 0.1, steps: 2 }\nperturb b noise 0.2\n}\n\
+     add a b into c\nfield d = a * 0.5 + b\nfield head = c[0..2]\nfield e = concat(head, d)\nfield f = e[1..4]\n\
+     trace t = trace_distance(a, I)\n\
+     if t < 1 { let branch = 1 } else { let branch = 2 }\nfield g = d + branch",
+];
+
+#[test]
+fn test_engines_agree_on_every_script() {
+    for (i, script) in SCRIPTS.iter().enumerate() {
+        let ast = run(RunConfig::new(*script).engine(Engine::Ast).seed(1));
+        assert!(ast.errors.is_empty(), "script {} does not run: {:?}", i, ast.errors);
+        for seed in [1, 7, 42] {
+            let differences = differential(RunConfig::new(*script).seed(seed));
+            assert!(differences.is_empty(), "script {} with seed {}: {:#?}", i, seed, differences);
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use sptl_spi::recursion::{CategoryObject, RecursionLevel};
+
+#[test]
+fn test_emergence_score() {
+    let a1 = CategoryObject::new(RecursionLevel::Atom, "A1");
+    let a2 = CategoryObject::new(RecursionLevel::Atom, "A2");
+    // Parts alone have nothing to explain.
+    assert_eq!(a1.emergence_score(), 0.0);
+
+    let m = CategoryObject::from_parts(RecursionLevel::Molecule, "M", vec![a1, a2]).unwrap();
+    // The bond and formula are introduced by the molecule, not by its atoms.
+    let score = m.emergence_score();
+    assert!(score > 0.0 && score <= 1.0);
+    assert_eq!(m.emergence_by_node().len(), 1);
+
+    // Parts from the wrong level are rejected.
+    let p = CategoryObject::new(RecursionLevel::Particle, "P");
+    assert!(CategoryObject::from_parts(RecursionLevel::Molecule, "M2", vec![p]).is_err());
+}
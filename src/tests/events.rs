@@ -0,0 +1,37 @@
+use sptl_spi::events::{Event, EventBus};
+use std::sync::{Arc, Mutex};
+
+fn created(name: &str, tau: u64) -> Event {
+    Event::AgentCreated { name: name.to_string(), tau }
+}
+
+#[test]
+fn test_subscribers_see_events_in_order_until_they_unsubscribe() {
+    let mut bus = EventBus::new();
+    assert!(bus.is_empty());
+    // Both callbacks log to one list, so it shows the order they were called in.
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let subscribe = |bus: &mut EventBus, who: &'static str| {
+        let log = Arc::clone(&log);
+        bus.subscribe(move |e| {
+            if let Event::AgentCreated { name, .. } = e {
+                log.lock().unwrap().push(format!("{} saw {}", who, name));
+            }
+        })
+    };
+    let first = subscribe(&mut bus, "first");
+    let (_, rx) = bus.subscribe_channel();
+    subscribe(&mut bus, "second");
+
+    bus.publish(created("alice", 0));
+    bus.publish(created("bob", 1));
+    assert_eq!(*log.lock().unwrap(), ["first saw alice", "second saw alice", "first saw bob", "second saw bob"]);
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), [created("alice", 0), created("bob", 1)]);
+
+    assert!(bus.unsubscribe(first));
+    assert!(!bus.unsubscribe(first));
+    bus.publish(created("carol", 2));
+    assert_eq!(log.lock().unwrap()[4..], ["second saw carol"]);
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), [created("carol", 2)]);
+    assert!(!bus.is_empty());
+}
@@ -0,0 +1,38 @@
+use sptl_spi::sptl::format::format_program;
+use sptl_spi::sptl::parse_source;
+use std::collections::BTreeMap;
+
+const MESSY: &str = "field psi 4   // the field\ninterpretation I=[1,0,1,0]\nlet a = (0.2+0.1)*1\n\
+                     project psi<-I{steps:5,alpha:a*2-0.3}record trajectory every 2\n\
+                     trace d = trace_distance( psi , I )\nif d > 0.5 { scale psi -1 } else if alice knows fire { normalize psi }\n\
+                     else { repeat 2 { shock psi indices 0..=1 value 2 } }\nnarratereturn \"say \\\"hi\\\"\"\n\
+                     export trace d to \"out/d.csv\"";
+
+const CANONICAL: &str = "field psi 4
+interpretation I = [1, 0, 1, 0]
+let a = (0.2 + 0.1) * 1
+project psi <- I { alpha: a * 2 - 0.3, noise: 0, steps: 5 } record trajectory every 2 steps
+trace d = trace_distance(psi, I)
+if d > 0.5 {
+    scale psi -1
+} else if alice knows fire {
+    normalize psi
+} else {
+    repeat 2 {
+        shock psi indices [0..2] value 2
+    }
+}
+narratereturn \"say \\\"hi\\\"\"
+export trace d to \"out/d.csv\"
+";
+
+#[test]
+fn test_fmt_prints_canonical_source_that_parses_back() {
+    let program = parse_source(MESSY, &BTreeMap::new()).unwrap();
+    let formatted = format_program(&program, None);
+    assert_eq!(formatted, CANONICAL);
+
+    let reparsed = parse_source(&formatted, &BTreeMap::new()).unwrap();
+    assert_eq!(serde_json::to_string(&reparsed).unwrap(), serde_json::to_string(&program).unwrap());
+    assert_eq!(format_program(&reparsed, None), formatted);
+}
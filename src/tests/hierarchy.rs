@@ -0,0 +1,53 @@
+use sptl_spi::agents::Agent;
+use sptl_spi::recursion::{CategoryObject, RecursionLevel, TickMode};
+use sptl_spi::substrate::Pattern;
+
+/// A root with `width` chains of `depth` nodes, each with an active pattern and an agent memory.
+fn forest(width: usize, depth: usize) -> CategoryObject {
+    let node = |id: String| {
+        let mut obj = CategoryObject::new(RecursionLevel::Atom, &id);
+        obj.substrate.activations.insert(Pattern::new(&id), 1.0);
+        let mut agent = Agent::new(format!("agent-{}", id), 16, 0.1);
+        agent.express_symbol(&id, Pattern::new("101"), 0);
+        obj.agents.push(agent);
+        obj
+    };
+    let mut root = node("root".to_string());
+    for w in 0..width {
+        let mut chain = node(format!("{}-{}", w, depth));
+        for d in (1..depth).rev() {
+            let mut parent = node(format!("{}-{}", w, d));
+            parent.subobjects.push(Box::new(chain));
+            chain = parent;
+        }
+        root.subobjects.push(Box::new(chain));
+    }
+    root
+}
+
+/// Every object's activations and agent stabilities, in pre-order.
+fn state(obj: &CategoryObject) -> Vec<(String, Vec<f64>, Vec<f64>)> {
+    obj.fold(Vec::new(), |mut out, obj, _| {
+        let activations = obj.substrate.activations.values().copied().collect();
+        let stabilities = obj.agents.iter().flat_map(|a| a.memory.traces.iter().map(|t| t.stability)).collect();
+        out.push((obj.id.clone(), activations, stabilities));
+        out
+    })
+}
+
+#[test]
+fn test_grained_ticks_agree_with_full_ticks() {
+    let ticked = |mode: TickMode| {
+        let mut obj = forest(3, 5);
+        for _ in 0..4 {
+            obj.tick(mode);
+        }
+        state(&obj)
+    };
+    let full = ticked(TickMode::Flattened);
+    assert_eq!(full.len(), 16);
+    // Grains below, between and above the subtree sizes split the tree differently.
+    for grain in [0, 2, 5, 64] {
+        assert_eq!(ticked(TickMode::Recursive { grain }), full, "grain {}", grain);
+    }
+}
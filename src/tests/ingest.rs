@@ -0,0 +1,25 @@
+use sptl_spi::ingest::{corpus_script, pattern_for, words, IngestOptions};
+use sptl_spi::narrative::ast::Block;
+use sptl_spi::narrative::runner::{execute_script, ScriptContext};
+
+#[test]
+fn test_corpus_replays_as_say_and_interpret_events() {
+    let text = "The fire spreads. The fire's warmth, the water!";
+    assert_eq!(words(text), ["the", "fire", "spreads", "the", "fire's", "warmth", "the", "water"]);
+    assert_eq!(pattern_for("fire", 12), pattern_for("fire", 12));
+    assert_ne!(pattern_for("fire", 12), pattern_for("water", 12));
+    assert_eq!(pattern_for("fire", 100).len(), 100);
+
+    let options = IngestOptions { agents: 2, tokens_per_tick: 3, ..Default::default() };
+    let blocks = corpus_script(text, &options);
+    // Readers at τ=0, then eight words at three per τ.
+    assert_eq!(blocks.len(), 4);
+    assert!(matches!(blocks.last(), Some(Block::AtTau(3, actions)) if actions.len() == 4));
+
+    let mut ctx = ScriptContext::default();
+    execute_script(&blocks, &mut ctx);
+    assert_eq!(ctx.world.tau, 3);
+    // reader0 speaks the even words and interprets the odd ones.
+    assert_eq!(ctx.world.agents["reader0"].memory.len(), 8);
+    assert!(ctx.world.agents["reader1"].memory.contains(&"water".to_string()));
+}
@@ -0,0 +1,104 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use sptl_spi::condition::Condition;
+use sptl_spi::json::JsonProgram;
+use sptl_spi::sptl::{parse_source, Statement};
+use std::collections::BTreeMap;
+
+/// The complete examples in the JSON block under `heading` in the schema
+/// documentation; examples with `<placeholders>` are left out.
+fn documented(heading: &str) -> Vec<String> {
+    let docs: Vec<&str> = include_str!("../json.rs").lines().filter_map(|line| line.strip_prefix("//!")).collect();
+    let start = docs.iter().position(|line| line.trim_start().starts_with(heading)).unwrap();
+    let block = docs[start..].iter().skip_while(|line| line.trim() != "```json").skip(1);
+    let mut examples: Vec<String> = Vec::new();
+    for line in block.take_while(|line| line.trim() != "```") {
+        // Continuation lines are indented past the example they belong to.
+        match examples.last_mut() {
+            Some(example) if line.starts_with("  ") => example.push_str(line),
+            _ if !line.trim().is_empty() => examples.push(line.to_string()),
+            _ => {}
+        }
+    }
+    examples.retain(|example| !example.contains('<'));
+    examples
+}
+
+/// Whether `written` says everything `example` does; a `null` in the
+/// example may be left out.
+fn covers(written: &Value, example: &Value) -> bool {
+    match (written, example) {
+        (Value::Object(written), Value::Object(example)) => example.iter().all(|(key, value)| match written.get(key) {
+            Some(w) => covers(w, value),
+            None => value.is_null(),
+        }),
+        (Value::Array(written), Value::Array(example)) => {
+            written.len() == example.len() && written.iter().zip(example).all(|(w, e)| covers(w, e))
+        }
+        (Value::Number(written), Value::Number(example)) => written.as_f64() == example.as_f64(),
+        _ => written == example,
+    }
+}
+
+/// Every documented example parses as a `T`, writes back what it says, and
+/// reads back as the same value.
+fn check_examples<T: Serialize + DeserializeOwned>(heading: &str) -> usize {
+    let examples = documented(heading);
+    for example in &examples {
+        let parsed: T = serde_json::from_str(example).unwrap_or_else(|e| panic!("{}: {}", example, e));
+        let written = serde_json::to_value(&parsed).unwrap();
+        assert!(covers(&written, &serde_json::from_str(example).unwrap()), "{} was written as {}", example, written);
+        let reread: T = serde_json::from_value(written.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&reread).unwrap(), written, "{}", example);
+    }
+    examples.len()
+}
+
+#[test]
+fn test_documented_statements_and_conditions_round_trip() {
+    assert!(check_examples::<Statement>("SPTL statements") >= 30);
+    assert!(check_examples::<Condition>("Conditions") >= 5);
+}
+
+#[test]
+fn test_parsed_program_round_trips_through_json() {
+    let source = "field psi 4 \"the field\"\ninterpretation seed = [1 0 1 0]\nlet a = 0.2\n\
+                  project psi <- seed { alpha: a * 2, noise: 0, steps: 5 } record trajectory every 1 steps\n\
+                  trace d = trace_distance(psi, seed)\nif d < 0.5 and not d < 0.1 { scale psi 0.5 }";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    let json = JsonProgram::sptl(program.clone()).to_json();
+    let JsonProgram::Sptl { version, statements } = JsonProgram::from_json(&json).unwrap() else {
+        panic!("{} read back as a narrative", json);
+    };
+    assert_eq!(version, sptl_spi::json::JSON_FORMAT_VERSION);
+    assert_eq!(serde_json::to_value(&statements).unwrap(), serde_json::to_value(&program).unwrap());
+    assert_eq!(
+        serde_json::to_value(&statements[0]).unwrap(),
+        serde_json::json!({ "Field": { "name": "psi", "size": 4.0, "doc": "the field" } })
+    );
+
+    let newer = json.replacen("\"version\": 1", "\"version\": 2", 1);
+    assert!(JsonProgram::from_json(&newer).is_err());
+}
+
+#[test]
+fn test_json_statements_are_checked_like_parsed_ones() {
+    let program = |statement: &str| format!("{{ \"format\": \"sptl\", \"version\": 1, \"statements\": [{}] }}", statement);
+    let project = r#"{ "Project": { "target": "psi", "interp": "seed", "alpha": 0.3, "noise": 0.0, "steps": 20, "tolerance": 0.0001,
+        "until": null, "record": null, "log_every": 5 } }"#;
+    assert!(JsonProgram::from_json(&program(project)).is_ok());
+    let rejected = [
+        project.replace("\"log_every\": 5", "\"log_every\": 0"),
+        project.replace("\"record\": null", "\"record\": { \"kind\": \"Trajectory\", \"every\": 0 }"),
+        r#"{ "ProjectMany": { "target": "psi", "interps": [], "alternate": true, "alpha": 0.3, "noise": 0.0, "steps": 5 } }"#.to_string(),
+        r#"{ "ProjectMany": { "target": "psi", "interps": [["a", 0.0], ["b", 0.0]], "alternate": false, "alpha": 0.3, "noise": 0.0,
+            "steps": 5 } }"#
+            .to_string(),
+        r#"{ "GenerateInterpretation": { "name": "h", "generator": { "OneHot": { "size": 4, "index": 4 } } } }"#.to_string(),
+        format!(r#"{{ "Repeat": {{ "count": 2, "body": [{}] }} }}"#, project.replace("\"log_every\": 5", "\"log_every\": 0")),
+    ];
+    for statement in rejected {
+        assert!(JsonProgram::from_json(&program(&statement)).is_err(), "{}", statement);
+    }
+}
@@ -0,0 +1,61 @@
+use sptl_spi::lexicon::{Lexicon, LEXICON_VERSION};
+use sptl_spi::narrative::parser::parse_script;
+use sptl_spi::narrative::runner::{execute_script, ScriptContext};
+use sptl_spi::substrate::Pattern;
+
+#[test]
+fn test_population_lexicon_round_trips_into_new_agents() {
+    let dir = std::env::temp_dir().join(format!("sptl-lexicon-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("vocab.json");
+
+    // alice and bob agree on fire; carol uses another pattern for it.
+    let first = format!(
+        "at τ=0:\n  create agent alice 16 0.1\n  create agent bob 16 0.1\n  create agent carol 16 0.1\n  \
+         alice says: fire → 1010\n  bob says: fire → 1010\n  carol says: fire → 0001\n  carol says: water → 0110\n  \
+         save lexicon to \"{}\"\n",
+        path.display()
+    );
+    execute_script(&parse_script(&first), &mut ScriptContext::default());
+
+    let lexicon = Lexicon::load(&path).unwrap();
+    assert_eq!(lexicon.version, LEXICON_VERSION);
+    let tokens: Vec<_> = lexicon.entries.iter().map(|e| (e.token.as_str(), e.pattern.as_str(), e.speakers)).collect();
+    assert_eq!(tokens, [("fire", "1010", 2), ("water", "0110", 1)]);
+    assert_eq!(lexicon.entries[0].stability, 1.0);
+
+    let second = format!("at τ=5:\n  create agent dave 16 0.1\n  load lexicon \"{}\" into dave\n", path.display());
+    let mut ctx = ScriptContext::default();
+    execute_script(&parse_script(&second), &mut ctx);
+    let state = &ctx.world.agents["dave"];
+    assert_eq!(state.memory, vec!["fire".to_string(), "water".to_string()]);
+    let agent = state.agent.as_ref().unwrap();
+    assert_eq!(agent.symbol_table["fire"], Pattern::new("1010"));
+    assert_eq!(agent.memory.traces[1].tau_index, 5);
+    assert_eq!(Lexicon::of_agent(agent).entries.len(), 2);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_pinned_symbols_survive_decay_eviction_and_forgetting() {
+    let script = "at τ=0:\n  create agent alice 2 0.1\n  alice says: fire → 1010\n  pin fire in alice\n  \
+                  alice says: water → 0110\n  alice says: earth → 0011\n  perturb agent alice forget 1.0\n";
+    let mut ctx = ScriptContext::default();
+    execute_script(&parse_script(script), &mut ctx);
+    let state = &ctx.world.agents["alice"];
+    assert_eq!(state.memory, vec!["fire".to_string()]);
+
+    // At capacity, earth evicted water rather than the older, pinned fire.
+    let mut agent = state.agent.clone().unwrap();
+    let remembered: Vec<_> = agent.memory.traces.iter().map(|t| t.symbol.token.as_str()).collect();
+    assert_eq!(remembered, ["fire", "earth"]);
+    agent.decay_memory(1.0);
+    assert_eq!(agent.memory.traces.len(), 1);
+    assert_eq!(agent.memory.traces[0].stability, 1.0);
+
+    let pinned: Vec<_> = Lexicon::of_agent(&agent).entries.iter().map(|e| (e.token.clone(), e.pinned)).collect();
+    assert_eq!(pinned, [("earth".to_string(), false), ("fire".to_string(), true), ("water".to_string(), false)]);
+    assert!(agent.memory.unpin("fire"));
+    agent.decay_memory(1.0);
+    assert!(agent.memory.traces.is_empty());
+}
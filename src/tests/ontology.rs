@@ -0,0 +1,61 @@
+use sptl_spi::config::Config;
+use sptl_spi::interpretation::Interpretation;
+use sptl_spi::narrative::parser::parse_script;
+use sptl_spi::narrative::runner::{execute_script, ScriptContext};
+use sptl_spi::ontology::Ontology;
+use sptl_spi::recursion::RecursionLevel;
+use std::sync::Arc;
+
+const LANGUAGE: &str = r#"
+[[level]]
+name = "word"
+report = ["energy"]
+
+[[level]]
+name = "sentence"
+report = ["parts"]
+aggregate = { energy = "mean" }
+
+[[level]]
+name = "discourse"
+aggregate = { parts = "sum" }
+"#;
+
+#[test]
+fn test_ontology_replaces_the_level_ladder() {
+    let ontology = Ontology::parse(LANGUAGE).unwrap();
+    let sentence = ontology.level("sentences").unwrap();
+    assert_eq!(sentence, RecursionLevel::Defined { rank: 1, top: 2 });
+    assert_eq!(sentence.below(), ontology.level("word"));
+    assert_eq!(sentence.above(), ontology.level("discourse"));
+    assert_eq!(ontology.level("discourse").unwrap().above(), None);
+    assert_eq!(ontology.level("atom"), None);
+
+    let mut ctx = ScriptContext::default();
+    ctx.world.ontology = Some(Arc::new(ontology));
+    let blocks = parse_script("at τ=0:\n  create sentence S1 from words w1 w2\n  create sentence S2 from w3\n  create discourse D from sentences S1 S2\n");
+    execute_script(&blocks, &mut ctx);
+
+    let d = &ctx.world.hierarchies["D"];
+    assert_eq!(d.level_name(), "discourse");
+    let Some(Interpretation::Level(interp)) = d.interpret() else {
+        panic!("expected an ontology-defined interpretation");
+    };
+    // S2 names no level after `from`, so its one word is a part.
+    assert_eq!(interp.values["sum parts"], 3.0);
+    assert_eq!(interp.constituents[0].values["parts"], 2.0);
+    assert!(interp.constituents[0].values.contains_key("mean energy"));
+}
+
+#[test]
+fn test_ontology_is_read_from_sptl_toml() {
+    let dir = std::env::temp_dir().join(format!("sptl-ontology-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("language.toml"), LANGUAGE).unwrap();
+    std::fs::write(dir.join("sptl.toml"), "ontology = \"language.toml\"\n").unwrap();
+    let config = Config::for_script(&dir.join("x.sptl"));
+    assert_eq!(config.level("word"), Some(RecursionLevel::Defined { rank: 0, top: 2 }));
+    assert_eq!(config.level("cell"), None);
+    assert!(Ontology::parse("[[level]]\nname = \"a\"\n[[level]]\nname = \"A\"\n").is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
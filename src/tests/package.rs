@@ -0,0 +1,48 @@
+use sptl_spi::package::{Contents, Package, PackageManifest};
+use std::collections::BTreeMap;
+use std::fs;
+
+#[test]
+fn test_package_round_trips_text_and_binary_files() {
+    let dir = std::env::temp_dir().join(format!("sptl-package-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let (source, out) = (dir.join("experiment"), dir.join("extracted"));
+    fs::create_dir_all(source.join("lib")).unwrap();
+    fs::write(source.join("main.sptl"), "include \"lib/fields.sptl\"\n").unwrap();
+    fs::write(source.join("lib/fields.sptl"), "field psi 4\n").unwrap();
+    fs::write(source.join("lib/weights.bin"), [0xff, 0x00, 0xfe]).unwrap();
+
+    let pkg = Package::from_dir(&source, None, Some(7)).unwrap();
+    assert_eq!(pkg.manifest.entry, "main.sptl");
+    assert_eq!(pkg.files["lib/weights.bin"], Contents::Bytes(vec![0xff, 0x00, 0xfe]));
+    let path = dir.join("experiment.sptlpkg");
+    pkg.write(&path).unwrap();
+
+    let read = Package::read(&path).unwrap();
+    assert_eq!(read.manifest.seed, Some(7));
+    assert_eq!(read.files, pkg.files);
+    assert_eq!(read.extract(&out).unwrap(), out.join("main.sptl"));
+    for rel in ["main.sptl", "lib/fields.sptl", "lib/weights.bin"] {
+        assert_eq!(fs::read(out.join(rel)).unwrap(), fs::read(source.join(rel)).unwrap(), "{}", rel);
+    }
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_extract_refuses_paths_outside_the_directory() {
+    let dir = std::env::temp_dir().join(format!("sptl-package-unsafe-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let outside = std::env::temp_dir().join(format!("sptl-package-escaped-{}", std::process::id()));
+    for rel in [outside.to_string_lossy().into_owned(), "../escaped.sptl".to_string(), "./main.sptl".to_string()] {
+        let files = BTreeMap::from([
+            ("main.sptl".to_string(), Contents::Text("field psi 4\n".to_string())),
+            (rel.clone(), Contents::Text("field evil 1\n".to_string())),
+        ]);
+        let manifest = PackageManifest { version: env!("CARGO_PKG_VERSION").to_string(), seed: None, entry: "main.sptl".to_string() };
+        let err = Package { manifest, files }.extract(&dir).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData, "{}", rel);
+        assert!(!outside.exists());
+        assert!(!dir.join("main.sptl").exists(), "{}: wrote files before refusing", rel);
+    }
+    let _ = fs::remove_dir_all(&dir);
+}
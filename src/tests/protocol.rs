@@ -0,0 +1,35 @@
+use sptl_spi::events::Event;
+use sptl_spi::protocol::{check, Violation};
+
+fn expressed(agent: &str, token: &str, tau: u64) -> Event {
+    Event::SymbolExpressed { source: agent.to_string(), token: token.to_string(), tau }
+}
+
+fn projected(agent: &str, token: &str, tau: u64) -> Event {
+    Event::SymbolProjected { agent: agent.to_string(), token: token.to_string(), tau }
+}
+
+fn interpreted(agent: &str, token: &str, tau: u64) -> Event {
+    Event::SymbolInterpreted { agent: agent.to_string(), token: token.to_string(), tau }
+}
+
+#[test]
+fn test_protocol_violations_are_reported_per_agent() {
+    let events = vec![
+        expressed("alice", "fire", 0),
+        projected("alice", "fire", 1),
+        interpreted("bob", "fire", 2),
+        expressed("alice", "water", 3),
+        expressed("bob", "earth", 3),
+        projected("bob", "earth", 4),
+        interpreted("carol", "wind", 5),
+    ];
+    let report = check(events);
+    assert_eq!(report.completed, 1);
+    assert_eq!(report.violations["alice"], vec![Violation::NeverProjected { token: "water".to_string(), tau: 3 }]);
+    assert_eq!(report.violations["bob"], vec![Violation::NeverInterpreted { token: "earth".to_string(), tau: 4 }]);
+    assert_eq!(report.violations["carol"], vec![Violation::InterpretedUnexpressed { token: "wind".to_string(), tau: 5 }]);
+
+    let in_order = check(vec![expressed("a", "x", 0), projected("a", "x", 0), interpreted("b", "x", 0)]);
+    assert!(in_order.is_conformant());
+}
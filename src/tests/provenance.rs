@@ -0,0 +1,49 @@
+use sptl_spi::narrative::parser::parse_script;
+use sptl_spi::narrative::runner::{execute_script, ScriptContext};
+use sptl_spi::provenance::Question;
+use sptl_spi::substrate::Pattern;
+
+#[test]
+fn test_why_traces_state_back_to_script_lines() {
+    let script = "\
+at τ=0:
+  create agent alice 16 0.1
+  field F 4
+  alice says: fire → 1010
+at τ=3:
+  alice projects: fire into F
+  tick 1
+  alice projects: fire into F
+";
+    let mut ctx = ScriptContext { script: Some("story.narr".to_string()), ..ScriptContext::default() };
+    execute_script(&parse_script(script), &mut ctx);
+    assert_eq!(ctx.world.fields["F"].activations[&Pattern::new("1010")], 2.0);
+
+    let causes: Vec<String> = ctx.world.provenance.why_pattern("F", "1010").iter().map(|c| c.to_string()).collect();
+    assert_eq!(
+        causes,
+        ["τ=3 story.narr:6: alice projects: fire into F", "τ=4 story.narr:8: alice projects: fire into F"]
+    );
+    let question = Question::parse("token fire in alice").unwrap();
+    assert_eq!(question.answer(&ctx.world.provenance), ["token fire in alice was caused by:", "  τ=0 story.narr:4: alice says: fire → 1010"]);
+    assert_eq!(Question::parse("pattern 0101 in F").unwrap().answer(&ctx.world.provenance), ["Nothing recorded for pattern 0101 in F."]);
+}
+
+#[test]
+fn test_describe_shows_doc_strings() {
+    let script = "\
+macro greet(a) \"say hello as a\":
+  $a says: hello → 1010
+at τ=0:
+  create agent alice 16 0.1 \"the first speaker\"
+  field F size 4 \"visual cortex analogue\"
+  greet(alice)
+  describe alice
+  describe greet
+";
+    let mut ctx = ScriptContext::default();
+    execute_script(&parse_script(script), &mut ctx);
+    assert_eq!(ctx.world.describe("alice"), ["agent alice (1 memories): \"the first speaker\""]);
+    assert_eq!(ctx.world.describe("F"), ["field F (4 elements): \"visual cortex analogue\""]);
+    assert_eq!(ctx.macros["greet"].doc.as_deref(), Some("say hello as a"));
+}
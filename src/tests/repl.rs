@@ -0,0 +1,90 @@
+use sptl_spi::config::Config;
+use sptl_spi::repl::{Repl, Reply};
+use std::collections::BTreeMap;
+
+#[test]
+fn test_repl_keeps_state_between_lines() {
+    let mut params = BTreeMap::new();
+    params.insert("size".to_string(), "4".to_string());
+    let mut repl = Repl::new(params, Config::default());
+    for line in ["field psi $size", "interpretation I = [1 1 1 1]", "let a = 0.5"] {
+        assert_eq!(repl.eval(line), Reply::Ready);
+    }
+    assert_eq!(repl.eval("repeat 2 {  // two half steps"), Reply::More);
+    assert_eq!(repl.eval("    project psi <- I { alpha: a, noise: 0, steps: 1 }"), Reply::More);
+    assert_eq!(repl.eval("}"), Reply::Ready);
+    assert_eq!(repl.eval("trace d = trace_distance(psi, I)"), Reply::Ready);
+    assert_eq!(repl.env.rt.field_states()["psi"], vec![0.75; 4]);
+    assert!((repl.env.report.traces["d"] - 0.5).abs() < 1e-9);
+
+    // A line that does not parse changes nothing.
+    assert_eq!(repl.eval("field"), Reply::Ready);
+    assert_eq!(repl.eval(":reset"), Reply::Ready);
+    assert!(repl.env.rt.fields.is_empty() && repl.env.vars.is_empty());
+    assert_eq!(repl.eval(":quit"), Reply::Quit);
+}
+
+#[test]
+fn test_repl_set_tunes_the_paused_session() {
+    let mut repl = Repl::new(BTreeMap::new(), Config::default());
+    repl.eval("field psi 4");
+    repl.eval("interpretation I = [1 1 1 1]");
+    repl.eval(":set alpha 0.5");
+    repl.eval(":set steps 1");
+    repl.eval("project psi <- I { }");
+    assert_eq!(repl.env.rt.field_states()["psi"], vec![0.5; 4]);
+
+    repl.eval("let a = 0.1");
+    repl.eval(":set a 0.5");
+    repl.eval("project psi <- I { alpha: a }");
+    assert_eq!(repl.env.rt.field_states()["psi"], vec![0.75; 4]);
+    assert!(repl.env.report.journal.iter().any(|line| line.ends_with("set alpha = 0.5")));
+
+    repl.eval(":set decay 0.5");
+    repl.eval("decay psi");
+    assert_eq!(repl.env.rt.field_states()["psi"], vec![0.375; 4]);
+
+    // The saved script replays the tuned session with the default settings.
+    use sptl_spi::sptl::{execute_program, parse_source};
+    let path = std::env::temp_dir().join(format!("sptl-repl-{}.sptl", std::process::id()));
+    assert_eq!(repl.eval(&format!(":save {}", path.display())), Reply::Ready);
+    let saved = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    let replayed = execute_program(parse_source(&saved, &BTreeMap::new()).unwrap());
+    assert_eq!(replayed.fields["psi"], vec![0.375; 4]);
+}
+
+#[test]
+fn test_bench_times_copies_of_the_session_state() {
+    use sptl_spi::bench::Bench;
+    let mut repl = Repl::new(BTreeMap::new(), Config::default());
+    repl.eval("field psi 4");
+    repl.eval("interpretation I = [1 1 1 1]");
+
+    let bench = Bench::parse(&["project", "psi", "I", "steps=500", "noise=0"], 0.1, 0.05).unwrap();
+    assert_eq!(bench, Bench::Project { field: "psi".into(), interp: "I".into(), steps: 500, alpha: 0.1, noise: 0.0 });
+    let measurement = bench.run(&repl.env.rt).unwrap();
+    assert_eq!(measurement.steps, 500);
+    assert!(measurement.per_second() > 0.0);
+    // The session's field is untouched.
+    assert_eq!(repl.env.rt.field_states()["psi"], vec![0.0; 4]);
+
+    let tick = Bench::parse(&["tick", "population=10", "n=3"], 0.1, 0.0).unwrap();
+    assert_eq!(tick.run(&repl.env.rt).unwrap().label, "tick 10 agents");
+    // The session has no agents of its own.
+    assert!(Bench::parse(&["tick"], 0.1, 0.0).unwrap().run(&repl.env.rt).is_err());
+    assert!(Bench::parse(&["project", "psi", "I", "gain=2"], 0.1, 0.0).is_err());
+    assert_eq!(repl.eval(":bench project psi I steps=10"), Reply::Ready);
+}
+
+#[test]
+fn test_doc_strings_are_described_and_reported() {
+    let mut repl = Repl::new(BTreeMap::new(), Config::default());
+    repl.eval("field psi size 4 \"the agent's belief state\"");
+    repl.eval("interpretation I = [1 1 1 1]");
+    assert_eq!(repl.env.rt.describe("psi"), ["field psi (4 elements): \"the agent's belief state\""]);
+    assert_eq!(repl.env.rt.describe("I"), ["interpretation I (4 values): no description"]);
+    assert!(repl.env.rt.describe("nothing").is_empty());
+    assert_eq!(repl.eval(":describe psi"), Reply::Ready);
+    assert_eq!(repl.env.report.docs["psi"], "the agent's belief state");
+}
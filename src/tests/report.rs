@@ -0,0 +1,36 @@
+use sptl_spi::report::{read_series, ReportStream, RunReport, PLOT_POINTS};
+use sptl_spi::sptl::{execute_program, execute_program_into, parse_source};
+use std::collections::BTreeMap;
+use std::fs;
+
+#[test]
+fn test_streamed_report_writes_what_an_in_memory_one_keeps() {
+    let dir = std::env::temp_dir().join(format!("sptl-report-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let source = "field psi 4\ninterpretation seed = [1 1 1 1]\n\
+                  project psi <- seed { alpha: 0.5, noise: 0, steps: 3 } record trajectory every 1 steps\n\
+                  trace t = trace_distance(psi, seed)";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    let in_memory = execute_program(program.clone());
+
+    // A capacity of 2 flushes several times during the run.
+    let (journal, telemetry) = (dir.join("journal.log"), dir.join("telemetry.csv"));
+    let mut streamed = RunReport { stream: Some(ReportStream::create(&journal, &telemetry, 2).unwrap()), ..RunReport::default() };
+    execute_program_into(program, &mut streamed);
+    streamed.stream.take().unwrap().flush().unwrap();
+    assert!(streamed.journal.is_empty() && streamed.telemetry.is_empty());
+    assert_eq!(streamed.traces, in_memory.traces);
+
+    let journaled: Vec<String> = fs::read_to_string(&journal).unwrap().lines().map(String::from).collect();
+    assert_eq!(journaled, in_memory.journal);
+    let expected = dir.join("expected.csv");
+    in_memory.write_telemetry_csv(&expected).unwrap();
+    assert_eq!(fs::read_to_string(&telemetry).unwrap(), fs::read_to_string(&expected).unwrap());
+
+    // Three half steps from zero: the trajectory halves from a distance of 1.
+    let series = read_series(&telemetry, PLOT_POINTS).unwrap();
+    assert_eq!(series["psi.trajectory"], [1.0, 0.5, 0.25]);
+    assert_eq!(series, in_memory.series(PLOT_POINTS));
+    let _ = fs::remove_dir_all(&dir);
+}
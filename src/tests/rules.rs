@@ -0,0 +1,35 @@
+use sptl_spi::condition::Condition;
+use sptl_spi::narrative::ast::{Action, Rule};
+use sptl_spi::narrative::parser::parse_script;
+use sptl_spi::narrative::runner::{execute_script, ScriptContext};
+
+#[test]
+fn test_rules_fire_by_priority_and_respect_refractory() {
+    let script = "\
+when alice knows fire then bob interprets: low
+when alice knows fire priority 2 then:
+  bob interprets: high
+when alice knows fire refractory 2 then carol interprets: again
+at τ=0:
+  create agent alice 16 0.1
+  alice says: fire → 1010
+repeat 4 times:
+  tick 1
+";
+    let mut ctx = ScriptContext::default();
+    execute_script(&parse_script(script), &mut ctx);
+    let memory = |agent: &str| ctx.world.agents[agent].memory.clone();
+    // Higher priority first, once per τ for τ = 0..=4.
+    assert_eq!(memory("bob")[..2], ["high".to_string(), "low".to_string()]);
+    assert_eq!(memory("bob").len(), 10);
+    // Refractory 2: τ = 0, 2, 4.
+    assert_eq!(memory("carol").len(), 3);
+    assert_eq!(ctx.rules.last_fired(2), Some(4));
+
+    // Rules can also be added from Rust.
+    let mut ctx = ScriptContext::default();
+    let condition = Condition::parse("tau > 1").unwrap();
+    ctx.add_rule(Rule::new(condition, vec![Action::Log("tau".to_string())]).refractory(10));
+    execute_script(&parse_script("at τ=0:\n  tick 1\n  tick 1\n  tick 1\n"), &mut ctx);
+    assert_eq!(ctx.rules.last_fired(0), Some(2));
+}
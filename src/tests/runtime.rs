@@ -0,0 +1,53 @@
+use sptl_spi::narrative::parser::parse_script;
+use sptl_spi::narrative::runner::{execute_script, ScriptContext};
+use sptl_spi::report::RunReport;
+use sptl_spi::runtime::Runtime;
+use sptl_spi::sptl::{parse_source, vm};
+use std::collections::BTreeMap;
+
+#[test]
+fn test_narrative_and_sptl_share_one_runtime() {
+    let dir = std::env::temp_dir().join(format!("sptl-runtime-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("pull.sptl");
+    std::fs::write(&script, "project F <- I { alpha: 1.0 noise: 0 steps: 1 }\ntrace d = trace_distance(F, I)\n").unwrap();
+
+    // The narrative declares the field; the SPTL script projects into it and
+    // leaves its trace where the narrative can read it.
+    let narrative = format!(
+        "at τ=0:\n  field F 4\n  interpretation I = [1, 1, 1, 1]\n  run \"{}\"\n  assert d < 0.001\n",
+        script.display()
+    );
+    let mut ctx = ScriptContext::default();
+    execute_script(&parse_script(&narrative), &mut ctx);
+    assert_eq!(ctx.world.fields["F"].state, vec![1.0; 4]);
+    assert_eq!(ctx.world.measurements["d"], 0.0);
+    let _ = std::fs::remove_dir_all(&dir);
+
+    // The bytecode engine sees the same runtime.
+    let mut rt = Runtime::default();
+    rt.fields = ctx.world.fields.clone();
+    rt.interps = ctx.world.interps.clone();
+    let program = parse_source("scale F 2\ntrace e = trace_distance(F, I)", &BTreeMap::new()).unwrap();
+    let mut report = RunReport::default();
+    vm::Vm::new(&vm::compile_in(program, &rt)).run_in(&mut rt, &mut report);
+    assert_eq!(report.fields["F"], vec![2.0; 4]);
+    assert_eq!(rt.measurements["e"], 2.0);
+    // The narrative's copy is untouched: fields are copied on write.
+    assert_eq!(ctx.world.fields["F"].state, vec![1.0; 4]);
+}
+
+#[test]
+fn test_modulate_scales_activations_and_later_projections() {
+    use sptl_spi::substrate::Pattern;
+    let script = "at τ=0:\n  create agent alice 16 0.1\n  field F 4\n  alice says: fire → 1010\n  alice projects: fire into F\n";
+    let mut ctx = ScriptContext::default();
+    execute_script(&parse_script(script), &mut ctx);
+    let pattern = Pattern::new("1010");
+    assert_eq!(ctx.world.fields["F"].activations[&pattern], 1.0);
+
+    ctx.world.modulate("fire", 3.0);
+    assert_eq!(ctx.world.fields["F"].activations[&pattern], 3.0);
+    execute_script(&parse_script("at τ=1:\n  alice projects: fire into F\n"), &mut ctx);
+    assert_eq!(ctx.world.fields["F"].activations[&pattern], 6.0);
+}
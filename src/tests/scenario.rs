@@ -0,0 +1,42 @@
+use sptl_spi::narrative::parser::parse_script;
+use sptl_spi::narrative::runner::{execute_script, ScriptContext};
+
+const SCRIPT: &str = "\
+at τ=0:
+  seed 7
+  create agent agent2 16 0.5
+  random agents 5 mem 32 coh 0.1..0.4 group learners
+  random symbols 3 bits 8 into learners
+";
+
+fn run(script: &str) -> ScriptContext {
+    let mut ctx = ScriptContext::default();
+    execute_script(&parse_script(script), &mut ctx);
+    ctx
+}
+
+#[test]
+fn test_random_scenarios_are_fixed_by_the_seed() {
+    let ctx = run(SCRIPT);
+    // agent2 was taken, so numbering skips it.
+    assert_eq!(ctx.world.groups["learners"], ["agent1", "agent3", "agent4", "agent5", "agent6"]);
+    let patterns: Vec<String> = ctx.world.groups["learners"]
+        .iter()
+        .map(|name| {
+            let agent = ctx.world.agents[name].agent.as_ref().unwrap();
+            assert!((0.1..=0.4).contains(&agent.coherence_threshold));
+            assert_eq!(agent.memory.max_traces, 32);
+            assert_eq!(agent.symbol_table.len(), 3);
+            agent.symbol_table["sym2"].0.clone()
+        })
+        .collect();
+    // Every recipient is given the same vocabulary.
+    assert!(patterns.iter().all(|p| p.len() == 8 && *p == patterns[0]));
+    assert!(ctx.world.agents["agent2"].agent.as_ref().unwrap().symbol_table.is_empty());
+
+    let again = run(SCRIPT);
+    let threshold = |ctx: &ScriptContext| ctx.world.agents["agent6"].agent.as_ref().unwrap().coherence_threshold;
+    assert_eq!(threshold(&again), threshold(&ctx));
+    let reseeded = run(&SCRIPT.replace("seed 7", "seed 8"));
+    assert_ne!(threshold(&reseeded), threshold(&ctx));
+}
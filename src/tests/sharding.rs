@@ -0,0 +1,36 @@
+use sptl_spi::agents::Agent;
+use sptl_spi::sharding::ShardedPopulation;
+use sptl_spi::substrate::Pattern;
+
+/// Run 8 agents for 20 τ, a different one speaking each τ.
+fn run(shards: usize, sync_interval: usize) -> ShardedPopulation {
+    let agents = (0..8).map(|i| Agent::new(format!("a{}", i), 16, 0.1)).collect();
+    let mut pop = ShardedPopulation::new(agents, shards, sync_interval);
+    let patterns = ["1010", "0110", "1111"];
+    for tau in 0..20 {
+        pop.express(&format!("a{}", tau % 8), &format!("w{}", tau % 3), Pattern::new(patterns[tau % 3])).unwrap();
+        pop.step();
+    }
+    pop
+}
+
+#[test]
+fn test_sharded_run_matches_the_unsharded_one() {
+    let unsharded = run(1, 1);
+    let sharded = run(4, 10);
+    assert_eq!(sharded.syncs, 2);
+    assert_eq!(sharded.substrate.activations.len(), 3);
+    for (pattern, value) in &unsharded.substrate.activations {
+        let merged = sharded.substrate.activations[pattern];
+        assert!((merged - value).abs() < 1e-9, "{:?}: {} sharded, {} unsharded", pattern, merged, value);
+    }
+}
+
+#[test]
+fn test_sync_decays_each_projection_once() {
+    let mut pop = ShardedPopulation::new(vec![Agent::new("a", 16, 0.1), Agent::new("b", 16, 0.1)], 2, 10);
+    pop.express("a", "fire", Pattern::new("1010")).unwrap();
+    pop.run(10);
+    let value = pop.substrate.activations[&Pattern::new("1010")];
+    assert!((value - 0.95f64.powi(10)).abs() < 1e-12);
+}
@@ -0,0 +1,64 @@
+use sptl_spi::config::Config;
+use sptl_spi::repl::{Repl, Reply};
+use sptl_spi::shared::{Role, SharedSession};
+use std::collections::BTreeMap;
+
+#[test]
+fn test_observers_see_what_the_controller_changes() {
+    let session = SharedSession::new(Repl::new(BTreeMap::new(), Config::default()));
+    let (alice, role, _alice_rx) = session.hub().attach("alice");
+    assert_eq!(role, Role::Controller);
+    let (bob, role, bob_rx) = session.hub().attach("bob");
+    assert_eq!(role, Role::Observer);
+
+    session.submit(alice, "field psi 4");
+    session.submit(alice, "interpretation I = [1 1 1 1]");
+    session.submit(alice, "project psi <- I { alpha: 0.5, noise: 0, steps: 1 }");
+    let seen: Vec<String> = bob_rx.try_iter().collect();
+    assert_eq!(
+        seen,
+        [
+            "* bob joined as observer",
+            "alice> field psi 4",
+            "  field psi created (4 elements)",
+            "alice> interpretation I = [1 1 1 1]",
+            "alice> project psi <- I { alpha: 0.5, noise: 0, steps: 1 }",
+            "  field psi changed",
+        ]
+    );
+
+    // Observers may query and talk but not change the session.
+    assert_eq!(session.submit(bob, "field phi 4"), Reply::Ready);
+    assert!(!session.repl().env.rt.fields.contains_key("phi"));
+    session.submit(bob, ":fields");
+    session.submit(bob, ":say looks converged");
+    let seen: Vec<String> = bob_rx.try_iter().collect();
+    assert_eq!(seen, ["You are observing; only the controller can change the session.", "psi (4)", "bob: looks converged"]);
+
+    session.submit(alice, ":pass bob");
+    {
+        let hub = session.hub();
+        assert_eq!((hub.role(alice), hub.role(bob)), (Some(Role::Observer), Some(Role::Controller)));
+    }
+    assert_eq!(session.submit(bob, ":quit"), Reply::Quit);
+    assert_eq!(session.hub().controller(), Some(alice));
+}
+
+#[test]
+fn test_entries_broadcast_what_they_print_without_holding_up_the_hub() {
+    let session = SharedSession::new(Repl::new(BTreeMap::new(), Config::default()));
+    let (alice, _, alice_rx) = session.hub().attach("alice");
+    let (bob, _, bob_rx) = session.hub().attach("bob");
+    session.submit(alice, ":show psi");
+    let seen: Vec<String> = alice_rx.try_iter().collect();
+    assert_eq!(seen, ["* alice joined as controller", "* bob joined as observer", "alice> :show psi", "  Nothing named psi."]);
+
+    // While an entry holds the session, talk and queries still go through.
+    let running = session.repl();
+    assert_eq!(session.submit(bob, ":who"), Reply::Ready);
+    assert_eq!(session.submit(bob, ":fields"), Reply::Ready);
+    assert_eq!(session.submit(bob, ":say still waiting"), Reply::Ready);
+    drop(running);
+    let seen: Vec<String> = bob_rx.try_iter().collect();
+    assert_eq!(seen, ["* bob joined as observer", "alice> :show psi", "  Nothing named psi.", "alice (controller), bob", "bob: still waiting"]);
+}
@@ -0,0 +1,45 @@
+use sptl_spi::agents::{Agent, ReinforcementPolicy};
+use sptl_spi::similarity::SymbolIndex;
+use sptl_spi::substrate::Pattern;
+use sptl_spi::symbol::{Classification, Symbol};
+use std::collections::HashMap;
+
+fn bits(i: usize) -> Pattern {
+    Pattern::new(&format!("{:016b}", i))
+}
+
+#[test]
+fn test_nearest_symbols_matches_a_full_scan() {
+    let mut agent = Agent::new("a", 16, 0.1);
+    for i in 0..5000 {
+        agent.learn_symbol(&format!("w{}", i), bits(i * 7));
+    }
+    let query = bits((7 * 1234) ^ 0b100);
+    let nearest = agent.nearest_symbols(&query, 3);
+    assert_eq!(nearest[0].0, "w1234");
+    assert!((nearest[0].1 - 15.0 / 16.0).abs() < 1e-9);
+    assert_eq!(nearest.len(), 3);
+
+    // A stale index still answers correctly from the table.
+    let mut table = HashMap::new();
+    table.insert("fire".to_string(), Pattern::new("1010"));
+    assert_eq!(SymbolIndex::default().nearest(&table, &Pattern::new("1011"), 1), vec![("fire".to_string(), 0.75)]);
+}
+
+#[test]
+fn test_fuzzy_policy_grounds_unknown_tokens_by_pattern() {
+    let policy = ReinforcementPolicy { min_similarity: 0.75, ..ReinforcementPolicy::default() };
+    let mut agent = Agent::new("a", 16, 0.1).with_reinforcement(policy);
+    agent.express_symbol("fire", Pattern::new("10101010"), 0);
+    agent.express_symbol("water", Pattern::new("01010101"), 0);
+
+    let meaning = agent.interpret_symbol(&Symbol::new("flame", Pattern::new("10101011")), 1).unwrap();
+    assert_eq!(meaning.sign.token, "fire");
+    assert_eq!(meaning.interpretant.classification, Classification::Approximate);
+    assert!(agent.interpret_symbol(&Symbol::new("mud", Pattern::new("11110000")), 1).is_none());
+
+    // The exact-match default never guesses at unknown tokens.
+    let mut strict = Agent::new("b", 16, 0.1);
+    strict.express_symbol("fire", Pattern::new("10101010"), 0);
+    assert!(strict.interpret_symbol(&Symbol::new("flame", Pattern::new("10101010")), 1).is_none());
+}
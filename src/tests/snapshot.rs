@@ -0,0 +1,42 @@
+use sptl_spi::narrative::parser::parse_script;
+use sptl_spi::narrative::runner::{execute_script, ScriptContext};
+use std::thread;
+
+#[test]
+fn test_snapshots_keep_the_state_of_their_tick() {
+    let mut ctx = ScriptContext::default();
+    let snapshots = ctx.observe();
+    execute_script(&parse_script("at τ=0:\n  field F 4\n  perturb F noise 0.5\n  tick 1\n"), &mut ctx);
+    let first = snapshots.latest();
+    assert_eq!((first.epoch, first.tau), (1, 1));
+    let before = first.field("F").unwrap().to_vec();
+
+    execute_script(&parse_script("at τ=1:\n  perturb F noise 0.5\n  tick 1\n"), &mut ctx);
+    let second = snapshots.latest();
+    assert_eq!((second.epoch, second.tau), (2, 2));
+    assert_ne!(second.field("F").unwrap(), before.as_slice());
+    // The earlier view is untouched by the tick after it.
+    assert_eq!(first.field("F").unwrap(), before.as_slice());
+}
+
+#[test]
+fn test_readers_see_whole_ticks_while_the_world_runs() {
+    let mut ctx = ScriptContext::default();
+    let snapshots = ctx.observe();
+    let reader = {
+        let snapshots = snapshots.clone();
+        thread::spawn(move || {
+            let mut last = 0;
+            while last < 200 {
+                let snapshot = snapshots.latest();
+                // Every tick advances τ by one, so a torn view would show up here.
+                assert_eq!(snapshot.tau, snapshot.epoch);
+                assert!(snapshot.epoch >= last);
+                last = snapshot.epoch;
+            }
+        })
+    };
+    execute_script(&parse_script("at τ=0:\n  field F 8\nrepeat 200 times:\n  perturb F noise 0.1\n  tick 1\n"), &mut ctx);
+    reader.join().unwrap();
+    assert_eq!(snapshots.latest().epoch, 200);
+}
@@ -0,0 +1,766 @@
+use sptl_spi::sptl::{parse_source, Statement};
+use std::collections::BTreeMap;
+
+fn project_of(source: &str) -> Option<(f64, f64, Option<f64>)> {
+    match parse_source(source, &BTreeMap::new()).ok()?.pop()? {
+        Statement::Project { alpha, noise, steps, .. } => {
+            Some((alpha.as_literal()?, noise.as_literal()?, steps.map(|s| s.as_literal()).transpose()?))
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn test_project_options_any_spacing_and_order() {
+    let expected = Some((0.3, 0.05, Some(20.0)));
+    assert_eq!(project_of("project psi <- seed { alpha: 0.3 noise: 0.05 steps: 20 }"), expected);
+    assert_eq!(project_of("project psi <- seed { alpha:0.3 noise:0.05 steps:20 }"), expected);
+    assert_eq!(project_of("project psi <- seed { steps : 20, alpha: 0.3, noise:0.05 }"), expected);
+}
+
+#[test]
+fn test_project_options_rejects_unknown_and_duplicate_keys() {
+    assert_eq!(project_of("project psi <- seed { alpha: 0.3 noise: 0.05 steps: 20 gain: 2 }"), None);
+    assert_eq!(project_of("project psi <- seed { alpha: 0.3 alpha: 0.4 noise: 0.05 steps: 20 }"), None);
+}
+
+#[test]
+fn test_project_optional_noise_and_auto_steps() {
+    assert_eq!(project_of("project psi <- seed { alpha: 0.3 }"), Some((0.3, 0.0, None)));
+    assert_eq!(project_of("project psi <- seed { alpha: 0.3 steps: auto }"), Some((0.3, 0.0, None)));
+    assert_eq!(project_of("project psi <- seed { alpha: 0.3 steps: soon }"), None);
+}
+
+#[test]
+fn test_project_until_with_step_limit() {
+    let program = parse_source("project psi <- seed { alpha: 0.3 until: dist < 0.01 max_steps: 500 }", &BTreeMap::new()).unwrap();
+    match program.as_slice() {
+        [Statement::Project { steps, until, .. }] => {
+            assert_eq!(until.as_ref().and_then(|u| u.as_literal()), Some(0.01));
+            assert_eq!(steps.as_ref().and_then(|s| s.as_literal()), Some(500.0));
+        }
+        other => panic!("expected one project statement, got {:?}", other),
+    }
+    // `max_steps` only makes sense with a convergence target.
+    assert_eq!(project_of("project psi <- seed { alpha: 0.3 max_steps: 500 }"), None);
+}
+
+#[test]
+fn test_field_expression_precedence() {
+    use sptl_spi::sptl::expr::{BinOp, FieldExpr};
+    let program = parse_source("field c = a + 0.5 * b", &BTreeMap::new()).unwrap();
+    let expected = FieldExpr::Binary(
+        BinOp::Add,
+        Box::new(FieldExpr::Field("a".to_string())),
+        Box::new(FieldExpr::Binary(
+            BinOp::Mul,
+            Box::new(FieldExpr::Scalar(0.5)),
+            Box::new(FieldExpr::Field("b".to_string())),
+        )),
+    );
+    match program.as_slice() {
+        [Statement::DeriveField { name, expr }] => {
+            assert_eq!(name, "c");
+            assert_eq!(*expr, expected);
+        }
+        other => panic!("expected one derived field, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_errors_carry_position_and_continue() {
+    let source = "field psi 16\nfield broken size\nfield ok 4\nbogus thing\n";
+    let errors = parse_source(source, &BTreeMap::new()).unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert_eq!((errors[0].line, errors[0].column, errors[0].token.as_str()), (2, 14, "size"));
+    assert_eq!((errors[1].line, errors[1].column, errors[1].token.as_str()), (4, 1, "bogus"));
+}
+
+#[test]
+fn test_comments_are_ignored() {
+    let source = "# header\nfield psi 16 // trailing\n/* block\n   field gone 4 */ field phi 8\n";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    assert_eq!(program.len(), 2);
+    assert!(matches!(&program[1], Statement::Field { name, size, .. } if name == "phi" && size.as_literal() == Some(8.0)));
+
+    // A block comment left open is reported where it opens.
+    let errors = parse_source("field psi 16
+field phi 8 /* never closed
+field gone 4
+", &BTreeMap::new()).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!((errors[0].line, errors[0].column, errors[0].message.as_str()), (2, 13, "unterminated block comment"));
+}
+
+#[test]
+fn test_let_variables_in_numeric_options() {
+    use sptl_spi::sptl::execute_program;
+    let source = "field psi 4\ninterpretation seed = [1 1 1 1]\nlet a = 0.3\nlet b = (a+0.2)*2\n\
+                  project psi <- seed { alpha: a*b - 0.3, noise: 0, steps: 1 }\ntrace t = trace_distance(psi, seed)";
+    let report = execute_program(parse_source(source, &BTreeMap::new()).unwrap());
+    // One step at alpha 0.3 * 1.0 - 0.3 = 0 leaves psi at zero, a distance of 2 from seed.
+    assert!((report.traces["t"] - 2.0).abs() < 1e-9);
+
+    // Field sizes, bracketed values and step counts take expressions too,
+    // in both engines; a size that is not a whole number makes no field.
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::vm;
+    let source = "let n = 2\nlet v = 0.5\nfield psi n * 2\nfield odd n / 4\ninterpretation seed = [v * 2, 1 -1 (v + 0.5)]\n\
+                  project psi <- seed { alpha: 0.5, noise: 0, steps: n / 2 }\ntrace t = trace_distance(psi, seed)";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    let mut compiled = RunReport::default();
+    vm::Vm::new(&vm::compile(program.clone())).run(&mut compiled);
+    for report in [execute_program(program), compiled] {
+        // One half step from zero toward [1 1 -1 1].
+        assert_eq!(report.fields["psi"], [0.5, 0.5, -0.5, 0.5]);
+        assert!((report.traces["t"] - 1.0).abs() < 1e-9);
+        assert!(!report.fields.contains_key("odd"));
+    }
+    assert!(parse_source("field psi 2.5", &BTreeMap::new()).is_err());
+}
+
+#[test]
+fn test_repeat_runs_body_in_both_engines() {
+    use sptl_spi::sptl::{execute_program, vm};
+    use sptl_spi::report::RunReport;
+    let source = "field psi 4\ninterpretation seed = [1 1 1 1]\n\
+                  repeat 2 { project psi <- seed { alpha: 0.5, noise: 0, steps: 1 } }\n\
+                  trace t = trace_distance(psi, seed)";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    assert_eq!(program.len(), 4);
+    // Two half steps from zero leave every element at 0.75.
+    let ast = execute_program(program.clone());
+    assert!((ast.traces["t"] - 0.5).abs() < 1e-9);
+    let mut report = RunReport::default();
+    vm::Vm::new(&vm::compile(program)).run(&mut report);
+    assert!((report.traces["t"] - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_if_branches_on_trace_value() {
+    use sptl_spi::sptl::execute_program;
+    let source = "field psi 4\ninterpretation seed = [1 1 1 1]\ntrace d = trace_distance(psi, seed)\n\
+                  if d < 1 { let branch = 1 } else if d > 3 { let branch = 2 } else { let branch = 3 }\n\
+                  field out = psi + branch";
+    let report = execute_program(parse_source(source, &BTreeMap::new()).unwrap());
+    // psi starts at zero, so d = 2 and neither comparison holds.
+    assert_eq!(report.fields["out"], vec![3.0; 4]);
+    assert!(parse_source("if d { field x 4 }", &BTreeMap::new()).is_err());
+}
+
+#[test]
+fn test_meaning_evaluates_threshold() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::{execute_program_into, vm};
+    let source = "field psi 4\ninterpretation seed = [1 1 1 1]\ntrace d = trace_distance(psi, seed)\n\
+                  meaning near = below(d, 1)\nmeaning far = below(d, 3)\nlogmeaning far\n\
+                  if far > 0 { let x = 5 } else { let x = 7 }\nfield out = psi + x";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    let mut ast = RunReport::default();
+    execute_program_into(program.clone(), &mut ast);
+    let mut bytecode = RunReport::default();
+    vm::Vm::new(&vm::compile(program)).run(&mut bytecode);
+    for report in [ast, bytecode] {
+        // d = 2: below 3 but not below 1.
+        assert!(!report.meanings["near"]);
+        assert!(report.meanings["far"]);
+        assert_eq!(report.fields["out"], vec![5.0; 4]);
+    }
+}
+
+#[test]
+fn test_include_splices_relative_files_and_rejects_cycles() {
+    use sptl_spi::sptl::resolve_includes;
+    let dir = std::env::temp_dir().join(format!("sptl-include-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("lib")).unwrap();
+    std::fs::write(dir.join("lib/common.sptl"), "field psi 4\ninclude \"seed.sptl\"").unwrap();
+    std::fs::write(dir.join("lib/seed.sptl"), "interpretation seed = [1 1 1 1]").unwrap();
+    let main = dir.join("main.sptl");
+    let params = BTreeMap::new();
+    let config = Default::default();
+
+    let program = parse_source("include \"lib/common.sptl\"\nrepeat 2 { import lib/seed.sptl }", &params).unwrap();
+    let program = resolve_includes(program, &main, &params, &config).unwrap();
+    assert_eq!(program.len(), 3);
+    assert!(matches!(&program[0], Statement::Field { name, .. } if name == "psi"));
+    assert!(matches!(&program[1], Statement::Interpretation { name, .. } if name == "seed"));
+    assert!(matches!(&program[2], Statement::Repeat { body, .. } if body.len() == 1));
+
+    std::fs::write(dir.join("lib/seed.sptl"), "include common.sptl").unwrap();
+    let program = parse_source("include lib/common.sptl", &params).unwrap();
+    let errors = resolve_includes(program, &main, &params, &config).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].message.starts_with("include cycle"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_const_names_numbers_once() {
+    use sptl_spi::sptl::{execute_program, optimize::optimize};
+    let source = "const ETA 0.5\nconst CALM = 3\nfield psi 4\ninterpretation seed = [1 1 1 1]\n\
+                  project psi <- seed { alpha: ETA, noise: 0, steps: 1 }\ntrace d = trace_distance(psi, seed)\n\
+                  meaning calm = below(d, CALM)";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    assert!(!optimize(program.clone()).has_errors());
+    let report = execute_program(program);
+    // One half step from zero: every element at 0.5, a distance of 1.
+    assert!((report.traces["d"] - 1.0).abs() < 1e-9);
+    assert!(report.meanings["calm"]);
+
+    let reassigned = parse_source(&format!("{}\nlet ETA = 0.1", source), &BTreeMap::new()).unwrap();
+    assert!(optimize(reassigned).has_errors());
+}
+
+#[test]
+fn test_field_arithmetic_statements() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::{execute_program_into, vm};
+    let source = "field psi 4\nshock psi indices [0..1] value 3\nfield chi 4\nshock chi indices [1..2] value 4\n\
+                  add psi chi into omega\nscale psi 2\nnormalize omega";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    let mut ast = RunReport::default();
+    execute_program_into(program.clone(), &mut ast);
+    let mut bytecode = RunReport::default();
+    vm::Vm::new(&vm::compile(program)).run(&mut bytecode);
+    for report in [ast, bytecode] {
+        assert_eq!(report.fields["psi"], vec![6.0, 0.0, 0.0, 0.0]);
+        // [3 4 0 0] has norm 5.
+        assert_eq!(report.fields["omega"], vec![0.6, 0.8, 0.0, 0.0]);
+    }
+    assert!(parse_source("add psi chi omega", &BTreeMap::new()).is_err());
+}
+
+#[test]
+fn test_seed_makes_noisy_runs_reproducible() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::{execute_program_into, vm};
+    let source = "seed 42\nfield psi 4\ninterpretation I = [1 0 1 0]\n\
+                  project psi <- I { alpha: 0.3 noise: 0.2 steps: 5 }\nperturb psi noise 0.5";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    assert!(matches!(program[0], Statement::Seed(42)));
+    let run = |bytecode: bool| {
+        let mut report = RunReport::default();
+        if bytecode {
+            vm::Vm::new(&vm::compile(program.clone())).run(&mut report);
+        } else {
+            execute_program_into(program.clone(), &mut report);
+        }
+        report.fields["psi"].clone()
+    };
+    let first = run(false);
+    assert_eq!(run(false), first);
+    // Both engines draw the same noise in the same order.
+    assert_eq!(run(true), first);
+}
+
+#[test]
+fn test_export_writes_traces_and_fields() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::{execute_program_into, vm};
+    let dir = std::env::temp_dir().join(format!("sptl-export-{}", std::process::id()));
+    let source = format!(
+        "field psi 2\ninterpretation I = [1 1]\nrepeat 2 {{\n  project psi <- I {{ alpha: 0.5 noise: 0 steps: 1 }}\n  \
+         trace d = trace_distance(psi, I)\n}}\nexport trace d to \"{0}/d.csv\"\nexport field psi to \"{0}/psi.json\"",
+        dir.display()
+    );
+    let program = parse_source(&source, &BTreeMap::new()).unwrap();
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+
+    execute_program_into(program.clone(), &mut RunReport::default());
+    let d = read("d.csv");
+    let lines: Vec<&str> = d.lines().collect();
+    assert_eq!(lines[0], "step,value");
+    assert_eq!(lines.len(), 3);
+    let psi: Vec<f64> = serde_json::from_str(&read("psi.json")).unwrap();
+    assert_eq!(psi, vec![0.75, 0.75]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    vm::Vm::new(&vm::compile(program)).run(&mut RunReport::default());
+    assert_eq!(read("d.csv"), d);
+
+    assert!(parse_source("field psi 2\nexport field psi to \"psi.txt\"", &BTreeMap::new()).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_snapshot_keeps_a_field_state_for_later_comparison() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::{execute_program_into, vm};
+    let source = "field psi 4\nshock psi indices [0..4] value 1\nsnapshot psi as psi_t0\n\
+                  shock psi indices [0..1] value 3\ntrace drift = distance(psi, psi_t0)\n\
+                  project psi <- psi_t0 { alpha: 1 noise: 0 steps: 1 }\ntrace back = distance(psi, psi_t0)";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    let mut ast = RunReport::default();
+    execute_program_into(program.clone(), &mut ast);
+    let mut bytecode = RunReport::default();
+    vm::Vm::new(&vm::compile(program)).run(&mut bytecode);
+    for report in [ast, bytecode] {
+        assert_eq!(report.traces["drift"], 2.0);
+        assert_eq!(report.traces["back"], 0.0);
+        assert_eq!(report.fields["psi"], vec![1.0; 4]);
+    }
+}
+
+#[test]
+fn test_proc_calls_expand_with_arguments() {
+    let source = "field psi 4\ninterpretation seed = [1 0 1 0]\n\
+                  proc warmup(field, n) {\n  project field <- seed { alpha: 0.3 steps: n }\n}\n\
+                  warmup(psi, 20)\nrepeat 2 { warmup(psi,5) }";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    assert_eq!(program.len(), 4);
+    match &program[2] {
+        Statement::Project { target, steps, .. } => {
+            assert_eq!((target.as_str(), steps.as_ref().and_then(|s| s.as_literal())), ("psi", Some(20.0)))
+        }
+        other => panic!("expected a projection, found {:?}", other),
+    }
+    match &program[3] {
+        Statement::Repeat { body, .. } => {
+            assert!(matches!(&body[0], Statement::Project { steps: Some(steps), .. } if steps.as_literal() == Some(5.0)))
+        }
+        other => panic!("expected a repeat, found {:?}", other),
+    }
+
+    let errors = parse_source("proc one(a) { normalize a }\none(x, y)", &BTreeMap::new()).unwrap_err();
+    assert!(errors[0].message.contains("expects 1 argument"));
+    let errors = parse_source("proc forever() { forever() }\nforever()", &BTreeMap::new()).unwrap_err();
+    assert!(errors[0].message.contains("call itself"));
+}
+
+#[test]
+fn test_quoted_strings_are_single_tokens() {
+    let source = "narratereturn \"the field converged\" \"# not a comment, \\\"quoted\\\"\" // a comment\n\
+                  field d 2\nexport field d to \"my results/d.csv\"";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    match &program[0] {
+        Statement::NarrateReturn { tokens } => {
+            assert_eq!(tokens, &["the field converged", "# not a comment, \"quoted\""]);
+        }
+        other => panic!("expected narratereturn, found {:?}", other),
+    }
+    assert!(matches!(&program[2], Statement::Export { path, .. } if path == "my results/d.csv"));
+
+    let errors = parse_source("narratereturn \"never closed", &BTreeMap::new()).unwrap_err();
+    assert!(errors[0].message.contains("unterminated string"));
+}
+
+#[test]
+fn test_lexer_needs_no_spaces_around_punctuation() {
+    let source = "field psi 4\ninterpretation I=[1,0,1,-1]\nproject psi<-I{alpha:0.3,noise:0,steps:2}\n\
+                  trace d=trace_distance(psi,I)\nshock psi indices [0..2] value -1e-1";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    let values = |s: &Statement| match s {
+        Statement::Interpretation { values, .. } => values.iter().map(|v| v.as_literal()).collect::<Option<Vec<f64>>>(),
+        _ => None,
+    };
+    assert_eq!(values(&program[1]), Some(vec![1.0, 0.0, 1.0, -1.0]));
+    assert_eq!(project_of(&source.lines().take(3).collect::<Vec<_>>().join("\n")), Some((0.3, 0.0, Some(2.0))));
+    assert!(matches!(&program[3], Statement::TraceDistance { field, interp, .. } if field == "psi" && interp == "I"));
+    assert!(matches!(&program[4], Statement::Shock { start: 0, end: 2, value, .. } if value.as_literal() == Some(-0.1)));
+}
+
+#[test]
+fn test_static_check_reports_problems_before_running() {
+    use sptl_spi::sptl::optimize::{check, Severity};
+    let source = "field psi 4\ninterpretation short = [1 0]\nproject psi <- short { alpha: 0.3 steps: 2 }\n\
+                  trace d = trace_distance(psi, missing)\nexport trace psi.distance to \"psi.csv\"\n\
+                  export trace t to \"t.csv\"";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    let errors: Vec<(usize, String)> = check(&program)
+        .into_iter()
+        .filter(|d| d.severity == Severity::Error)
+        .map(|d| (d.step, d.message))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (2, "field psi has size 4 but interpretation short has 2 values".to_string()),
+            (3, "unknown interpretation missing".to_string()),
+            (5, "unknown trace t".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_assert_trace_records_expected_and_actual() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::{execute_program_into, vm};
+    let source = "field psi 4\ninterpretation seed = [1 1 1 1]\ntrace d = trace_distance(psi, seed)\n\
+                  assert trace d < 2 within 1e-3\nassert trace d > 2.5\nassert trace missing < 1";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    let mut ast = RunReport::default();
+    execute_program_into(program.clone(), &mut ast);
+    let mut bytecode = RunReport::default();
+    vm::Vm::new(&vm::compile(program)).run(&mut bytecode);
+    for report in [ast, bytecode] {
+        // d = 2: the tolerance lets `d < 2` pass.
+        let outcomes: Vec<(bool, Option<f64>)> = report.assertions.iter().map(|a| (a.passed, a.actual)).collect();
+        assert_eq!(outcomes, [(true, Some(2.0)), (false, Some(2.0)), (false, None)]);
+        assert_eq!((report.assertions[0].expected, report.assertions[0].tolerance), (2.0, 1e-3));
+        assert_eq!(report.failed_assertions(), 2);
+        assert_eq!(
+            report.failure_summary(),
+            ["step 4: assert trace d > 2.5: d = 2.000000", "step 5: assert trace missing < 1: missing was never recorded"]
+        );
+    }
+    assert!(parse_source("assert trace d = 2", &BTreeMap::new()).is_err());
+}
+
+#[test]
+fn test_decay_relaxes_fields_in_both_engines() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::{execute_program_into, vm};
+    let source = "field psi 2\ninterpretation I = [1 1]\nproject psi <- I { alpha: 1 noise: 0 steps: 1 }\n\
+                  let r = 0.5\ndecay psi r steps 2\nfield once = psi\ndecay once 0.5";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    assert!(matches!(&program[4], Statement::Decay { steps: 2, .. }));
+    let mut ast = RunReport::default();
+    execute_program_into(program.clone(), &mut ast);
+    let mut bytecode = RunReport::default();
+    vm::Vm::new(&vm::compile(program)).run(&mut bytecode);
+    for report in [ast, bytecode] {
+        assert_eq!(report.fields["psi"], vec![0.25; 2]);
+        assert_eq!(report.fields["once"], vec![0.125; 2]);
+    }
+}
+
+#[test]
+fn test_trace_coherence_feeds_later_statements() {
+    use sptl_spi::sptl::execute_program;
+    let source = "field psi 2\ninterpretation I = [1 0]\nproject psi <- I { alpha: 1 noise: 0 steps: 1 }\n\
+                  trace c = coherence(psi, I)\nmeaning aligned = below(c, 2)\n\
+                  if c > 0.5 { let x = 1 } else { let x = 0 }\nfield out = psi * x";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    assert!(matches!(&program[3], Statement::Let { name, .. } if name == "c"));
+    let report = execute_program(program);
+    assert!((report.traces["c"] - 1.0).abs() < 1e-9);
+    assert!(report.meanings["aligned"]);
+    assert_eq!(report.fields["out"], vec![1.0, 0.0]);
+}
+
+#[test]
+fn test_field_from_checkpoint_restores_one_field() {
+    use sptl_spi::rundir::{Checkpoint, CHECKPOINT_DIR};
+    use sptl_spi::sptl::execute_program;
+    let run = std::env::temp_dir().join(format!("sptl-ckpt-{}", std::process::id()));
+    std::fs::create_dir_all(run.join(CHECKPOINT_DIR)).unwrap();
+    let checkpoint = Checkpoint { field: "psi".to_string(), state: vec![0.5, 0.25] };
+    std::fs::write(run.join(CHECKPOINT_DIR).join("psi.ckpt"), serde_json::to_string(&checkpoint).unwrap()).unwrap();
+
+    let source = format!(
+        "field psi from checkpoint \"{}\"\nfield chi from checkpoint \"{}\"\ninterpretation I = [1 1]\n\
+         project psi <- I {{ alpha: 0.5 noise: 0 steps: 1 }}",
+        run.display(),
+        run.join(CHECKPOINT_DIR).join("psi.ckpt").display()
+    );
+    let report = execute_program(parse_source(&source, &BTreeMap::new()).unwrap());
+    assert_eq!(report.fields["psi"], vec![0.75, 0.625]);
+    assert_eq!(report.fields["chi"], vec![0.5, 0.25]);
+
+    let missing = parse_source("field psi from checkpoint \"no/such.ckpt\"", &BTreeMap::new()).unwrap();
+    assert!(sptl_spi::sptl::optimize::optimize(missing).has_errors());
+    std::fs::remove_dir_all(run).unwrap();
+}
+
+#[test]
+fn test_project_many_alternates_or_mixes_in_both_engines() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::format::format_program;
+    use sptl_spi::sptl::{execute_program_into, vm};
+    let source = "field psi 2\nfield mix 2\ninterpretation A = [1 0]\ninterpretation B = [0 1]\n\
+                  project psi <- [A, B] { alternate, alpha: 1, noise: 0, steps: 3 }\n\
+                  project mix <- [A: 3, B: 1] { alpha: 1 noise: 0 steps: 1 }";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    assert!(matches!(&program[4], Statement::ProjectMany { alternate: true, steps: 3, .. }));
+    let formatted = format_program(&program, None);
+    assert!(formatted.contains("project mix <- [A: 3, B: 1] { alpha: 1, noise: 0, steps: 1 }"));
+    assert_eq!(format_program(&parse_source(&formatted, &BTreeMap::new()).unwrap(), None), formatted);
+
+    let mut ast = RunReport::default();
+    execute_program_into(program.clone(), &mut ast);
+    let mut bytecode = RunReport::default();
+    vm::Vm::new(&vm::compile(program)).run(&mut bytecode);
+    for report in [ast, bytecode] {
+        // A, B, A: the last step wins with a full pull.
+        assert_eq!(report.fields["psi"], vec![1.0, 0.0]);
+        assert_eq!(report.fields["mix"], vec![0.75, 0.25]);
+        assert_eq!(report.traces["psi.A.distance"], 0.0);
+        assert!(report.traces["psi.B.distance"] > 0.0);
+    }
+
+    for bad in ["[A] { alpha: 1, steps: 1 }", "[A: 2, B] { alternate, alpha: 1, steps: 1 }", "[A, B] { alpha: 1 }"] {
+        let source = format!("field psi 2\ninterpretation A = [1 0]\ninterpretation B = [0 1]\nproject psi <- {}", bad);
+        assert!(parse_source(&source, &BTreeMap::new()).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn test_interpretation_generators_in_both_engines() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::format::format_program;
+    use sptl_spi::sptl::{execute_program_into, vm};
+    let source = "field psi 8\nlet amp = 2\ninterpretation g = gaussian(size: 8, mean: 1, std: 0.5, seed: 7)\n\
+                  interpretation h = one_hot(size: 8, index: 2)\ninterpretation s = sine(size: 8, amplitude: amp)\n\
+                  interpretation r = random(size: 8, low: -1, high: 1)\n\
+                  project psi <- h { alpha: 1 noise: 0 steps: 1 }\n\
+                  trace d = trace_distance(psi, g)\ntrace e = trace_distance(psi, s)";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    let formatted = format_program(&program, None);
+    assert!(formatted.contains("interpretation s = sine(size: 8, periods: 1, amplitude: amp, phase: 0)"));
+    assert_eq!(format_program(&parse_source(&formatted, &BTreeMap::new()).unwrap(), None), formatted);
+
+    let mut ast = RunReport::default();
+    execute_program_into(program.clone(), &mut ast);
+    let mut bytecode = RunReport::default();
+    vm::Vm::new(&vm::compile(program)).run(&mut bytecode);
+    let mut one_hot = vec![0.0; 8];
+    one_hot[2] = 1.0;
+    for report in [&ast, &bytecode] {
+        assert_eq!(report.fields["psi"], one_hot);
+        // The sine peaks at 2 on index 2: (1-2)², plus 2 or 4 at every other non-zero.
+        assert!((report.traces["e"] - 13f64.sqrt()).abs() < 1e-9);
+    }
+    // A seeded generator gives the same values whatever else draws noise.
+    assert_eq!(ast.traces["d"], bytecode.traces["d"]);
+
+    for bad in ["one_hot(size: 4, index: 4)", "noise(size: 4)", "sine(size: 4, seed: 1)", "gaussian(std: 1)"] {
+        assert!(parse_source(&format!("interpretation I = {}", bad), &BTreeMap::new()).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn test_check_lists_capabilities_the_build_lacks() {
+    use sptl_spi::capability::Capability;
+    use sptl_spi::sptl::optimize::optimize;
+    let source = "require gpu\nfield psi 4\ninterpretation I = [1 0 1 0]\nproject psi <- I { alpha: 0.3 steps: 2 }\n\
+                  export trace psi.distance to \"plots/d.png\"\nexport field psi to \"psi.csv\"";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    assert!(matches!(program[0], Statement::Require(Capability::Gpu)));
+    let optimized = optimize(program);
+    let lacking: Vec<Capability> = [Capability::Plots, Capability::Gpu].into_iter().filter(|c| !c.available()).collect();
+    assert_eq!(optimized.missing, lacking);
+    assert_eq!(optimized.has_errors(), !lacking.is_empty());
+
+    assert!(parse_source("require teleport", &BTreeMap::new()).is_err());
+    assert!(parse_source("export trace d to \"d.xlsx\"", &BTreeMap::new()).is_err());
+}
+
+#[test]
+fn test_morph_drifts_the_target_in_both_engines() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::format::format_program;
+    use sptl_spi::sptl::{execute_program_into, vm};
+    let source = "field psi 2\ninterpretation A = [0 0]\ninterpretation B = [1 1]\n\
+                  morph psi from A to B { alpha: 0.5 noise: 0 steps: 4 }";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    let formatted = format_program(&program, None);
+    assert!(formatted.contains("morph psi from A to B { alpha: 0.5, noise: 0, steps: 4 }"));
+
+    let mut ast = RunReport::default();
+    execute_program_into(program.clone(), &mut ast);
+    let mut bytecode = RunReport::default();
+    vm::Vm::new(&vm::compile(program)).run(&mut bytecode);
+    for report in [ast, bytecode] {
+        // Targets 0.25, 0.5, 0.75, 1 pulled halfway each step; projecting B
+        // throughout would have reached 0.9375.
+        assert_eq!(report.fields["psi"], vec![0.765625; 2]);
+        assert!((report.traces["psi.distance"] - 0.234375 * 2f64.sqrt()).abs() < 1e-12);
+    }
+    assert!(parse_source("field psi 2\nmorph psi from A to B { alpha: 0.5 }", &BTreeMap::new()).is_err());
+}
+
+#[test]
+fn test_trace_dispatches_on_the_metric_name() {
+    use sptl_spi::sptl::execute_program;
+    let source = "field psi 4\ninterpretation seed = [1 2 3 -1]\ntrace d = trace_distance(psi, seed)\n\
+                  trace c = coherence(psi, seed)\ntrace m = manhattan(psi, seed)\ntrace r = rmse(psi, seed)";
+    let report = execute_program(parse_source(source, &BTreeMap::new()).unwrap());
+    // psi is all zeros: no direction to be coherent with, and 1 + 4 + 9 + 1 = 15 squared.
+    assert!((report.traces["d"] - 15f64.sqrt()).abs() < 1e-9);
+    assert_eq!(report.traces["c"], 0.0);
+    assert!((report.traces["m"] - 7.0).abs() < 1e-9);
+    assert!((report.traces["r"] - (15.0f64 / 4.0).sqrt()).abs() < 1e-9);
+
+    let errors = parse_source("trace x = euclid(psi, seed)", &BTreeMap::new()).unwrap_err();
+    assert!(errors[0].message.starts_with("unknown metric `euclid`"), "{}", errors[0].message);
+}
+
+#[test]
+fn test_project_log_every_journals_progress_in_both_engines() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::{execute_program, vm};
+    let source = "field psi 4\ninterpretation seed = [1 1 1 1]\n\
+                  project psi <- seed { alpha: 0.5, noise: 0, steps: 4, log_every: 2 }";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    let progress = |report: &RunReport| -> Vec<String> {
+        let lines = report.journal.iter().filter(|line| line.contains(" step "));
+        lines.map(|line| line.split_once("] ").unwrap().1.to_string()).collect()
+    };
+    // Each half step halves the distance from 2.
+    let expected = ["psi step 2: dist = 0.5000, coherence = 1.0000", "psi step 4: dist = 0.1250, coherence = 1.0000"];
+    assert_eq!(progress(&execute_program(program.clone())), expected);
+    let mut report = RunReport::default();
+    vm::Vm::new(&vm::compile(program)).run(&mut report);
+    assert_eq!(progress(&report), expected);
+
+    assert!(parse_source(&source.replace("log_every: 2", "log_every: 0"), &BTreeMap::new()).is_err());
+}
+
+#[test]
+fn test_field_slices_and_concatenations_in_both_engines() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::{execute_program, vm};
+    let source = "field psi 4\ninterpretation seed = [1 2 3 4]\n\
+                  project psi <- seed { alpha: 1, noise: 0, steps: 1 }\n\
+                  field mid = psi[1..3]\nfield twice = concat(mid, mid)";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    assert!(matches!(&program[3], Statement::SliceField { source, start: 1, end: 3, .. } if source == "psi"));
+    let ast = execute_program(program.clone());
+    let mut report = RunReport::default();
+    vm::Vm::new(&vm::compile(program)).run(&mut report);
+    for fields in [&ast.fields, &report.fields] {
+        assert_eq!(fields["mid"], [2.0, 3.0]);
+        assert_eq!(fields["twice"], [2.0, 3.0, 2.0, 3.0]);
+    }
+
+    assert!(parse_source("field psi 4\nfield empty = psi[2..2]", &BTreeMap::new()).is_err());
+}
+
+#[test]
+fn test_expresssymbol_writes_into_the_field_in_both_engines() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::format::format_program;
+    use sptl_spi::sptl::{execute_program, vm};
+    let source = "field psi 4\nexpresssymbol \"1010\" into psi\n\
+                  expresssymbol \"a\" into psi { encoding: onehot, weight: 2 }\n\
+                  expresssymbol \"b\" into psi { encoding: onehot, weight: 0.5, blend }";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    // Bits add 1010; `a` is the first registered token and `b` the second.
+    let expected = [1.5, 0.5, 0.5, 0.0];
+    assert_eq!(execute_program(program.clone()).fields["psi"], expected);
+    let mut report = RunReport::default();
+    vm::Vm::new(&vm::compile(program.clone())).run(&mut report);
+    assert_eq!(report.fields["psi"], expected);
+
+    let formatted = format_program(&program, None);
+    assert!(formatted.contains("expresssymbol \"1010\" into psi\n"));
+    assert!(formatted.contains("expresssymbol \"b\" into psi { encoding: onehot, weight: 0.5, blend }"));
+    assert!(parse_source("field psi 4\nexpresssymbol \"a\" into psi { encoding: morse }", &BTreeMap::new()).is_err());
+}
+
+#[test]
+fn test_modulate_scales_later_expressions_in_both_engines() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::{execute_program, vm};
+    let source = "field psi 4\nexpresssymbol \"1010\" into psi\nlet half = 0.5\n\
+                  modulate \"1010\" intensity half\nexpresssymbol \"1010\" into psi";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    let expected = [1.5, 0.0, 1.5, 0.0];
+    assert_eq!(execute_program(program.clone()).fields["psi"], expected);
+    let mut report = RunReport::default();
+    vm::Vm::new(&vm::compile(program)).run(&mut report);
+    assert_eq!(report.fields["psi"], expected);
+}
+
+#[test]
+fn test_execution_env_continues_after_the_host_changes_state() {
+    use sptl_spi::sptl::ExecutionEnv;
+    let mut env = ExecutionEnv::new();
+    env.exec_source("field psi 4\ninterpretation I = [1, 1, 1, 1]\nscale psi 2").unwrap();
+    assert_eq!(env.field("psi").unwrap(), [0.0; 4]);
+
+    env.field_mut("psi").unwrap().state = vec![0.5; 4];
+    env.set_var("k", 2.0);
+    env.exec_source("scale psi k\ntrace d = trace_distance(psi, I)").unwrap();
+    assert_eq!(env.field("psi").unwrap(), [1.0; 4]);
+    assert_eq!(env.trace("d"), Some(0.0));
+    assert_eq!(env.var("d"), Some(0.0));
+    assert_eq!(env.step, 5);
+    assert_eq!(env.into_report().fields["psi"], [1.0; 4]);
+}
+
+#[test]
+fn test_report_summarizes_fields_and_traces_in_both_engines() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::format::format_program;
+    use sptl_spi::sptl::{execute_program, vm};
+    let dir = std::env::temp_dir().join(format!("sptl-report-{}", std::process::id()));
+    let (ast_path, vm_path) = (dir.join("ast.json"), dir.join("vm.json"));
+    let source = |path: &std::path::Path| {
+        format!(
+            "field psi 4\ninterpretation I = [1, 0, 1, 0]\nexpresssymbol \"1010\" into psi\n\
+             trace d = trace_distance(psi, I)\nreport to \"{}\"",
+            path.display()
+        )
+    };
+    let program = parse_source(&source(&ast_path), &BTreeMap::new()).unwrap();
+    assert!(format_program(&program, None).ends_with(&format!("report to \"{}\"\n", ast_path.display())));
+    execute_program(program);
+    let program = parse_source(&source(&vm_path), &BTreeMap::new()).unwrap();
+    vm::Vm::new(&vm::compile(program)).run(&mut RunReport::default());
+
+    let written = std::fs::read_to_string(&ast_path).unwrap();
+    assert_eq!(written, std::fs::read_to_string(&vm_path).unwrap());
+    let summary: serde_json::Value = serde_json::from_str(&written).unwrap();
+    assert_eq!(summary["fields"][0]["mean"], 0.5);
+    assert_eq!(summary["fields"][0]["variance"], 0.25);
+    assert_eq!(summary["fields"][0]["max"], 1.0);
+    assert_eq!(summary["traces"]["d"], 0.0);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(parse_source("report to \"summary.txt\"", &BTreeMap::new()).is_err());
+}
+
+#[test]
+fn test_project_stops_early_once_close_enough_in_both_engines() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::{execute_program, vm};
+    let source = "field psi 4\ninterpretation seed = [1, 0, 1, 0]\n\
+                  project psi <- seed { alpha: 0.3, noise: 0.01, steps: 1000, until: distance < 0.05 }\n\
+                  project psi <- seed { alpha: 0.3, steps: 2, until: trace_distance < 0.0001 }";
+    let program = parse_source(source, &BTreeMap::new()).unwrap();
+    let ast = execute_program(program.clone());
+    let mut report = RunReport::default();
+    vm::Vm::new(&vm::compile(program)).run(&mut report);
+    for report in [ast, report] {
+        let steps: Vec<f64> = report.telemetry.iter().filter(|row| row.name == "psi.steps").map(|row| row.value).collect();
+        let converged: Vec<f64> = report.telemetry.iter().filter(|row| row.name == "psi.converged").map(|row| row.value).collect();
+        assert!(steps[0] < 1000.0, "ran the whole budget: {:?}", steps);
+        assert_eq!(steps[1], 2.0);
+        assert_eq!(converged, [1.0, 0.0]);
+    }
+}
+
+#[test]
+fn test_run_shares_fields_but_not_variables_in_both_engines() {
+    use sptl_spi::report::RunReport;
+    use sptl_spi::sptl::{execute_program, optimize, resolve_includes, vm};
+    let dir = std::env::temp_dir().join(format!("sptl-run-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    // The stage binds its own `a` and adds a field the pipeline uses afterwards.
+    std::fs::write(
+        dir.join("stage.sptl"),
+        "let a = 0.5\ninterpretation I = [1 1 1 1]\nproject psi <- I { alpha: a, noise: 0, steps: 1 }\nfield out 4",
+    )
+    .unwrap();
+    let source = "let a = 0\nfield psi 4\ninterpretation J = [1 1 1 1]\n\
+                  run \"stage.sptl\" sharing fields\nrun \"stage.sptl\" sharing fields\n\
+                  project psi <- J { alpha: a, noise: 0, steps: 1 }\nnormalize out";
+    let params = BTreeMap::new();
+    let program = parse_source(source, &params).unwrap();
+    let program = resolve_includes(program, &dir.join("main.sptl"), &params, &Default::default()).unwrap();
+    assert!(matches!(&program[3], Statement::Run { body, .. } if body.len() == 4));
+    assert!(!optimize::optimize(program.clone()).has_errors());
+
+    let ast = execute_program(program.clone());
+    let mut report = RunReport::default();
+    vm::Vm::new(&vm::compile(program)).run(&mut report);
+    for report in [ast, report] {
+        // Each run moved psi halfway to I with its own `a`; the last
+        // projection used this script's `a = 0`.
+        assert_eq!(report.fields["psi"], vec![0.75; 4]);
+        assert!(report.fields.contains_key("out"));
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
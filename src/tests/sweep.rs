@@ -0,0 +1,29 @@
+use sptl_spi::rundir::RunDir;
+use sptl_spi::sweep::{expand_grid, point_dir, run_sweep};
+
+#[test]
+fn test_sweep_skips_completed_points() {
+    let root = std::env::temp_dir().join(format!("sptl-sweep-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let points = expand_grid(&[
+        ("alpha".to_string(), vec!["0.1".to_string(), "0.2".to_string()]),
+        ("steps".to_string(), vec!["10".to_string()]),
+    ]);
+    assert_eq!(points.len(), 2);
+
+    // Finish only the first point, as if the sweep had been interrupted.
+    let mut run = RunDir::create(point_dir(&root, &points[0])).unwrap();
+    run.manifest.params = points[0].clone();
+    run.manifest.completed = true;
+    run.write_manifest().unwrap();
+
+    let mut ran = Vec::new();
+    let summary = run_sweep(&root, &points, |p, _| {
+        ran.push(p.clone());
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(summary.skipped, 1);
+    assert_eq!(ran, vec![points[1].clone()]);
+    let _ = std::fs::remove_dir_all(&root);
+}
@@ -16,4 +16,45 @@ fn test_attractor_detection() {
     agent.interpret_symbol(&s2, tau + 5);
     // Attractor state should now be false
     assert!(!agent.is_attractor_state(3));
-}
\ No newline at end of file
+}
+#[test]
+fn test_partial_credit_reinforcement() {
+    use sptl_spi::agents::ReinforcementPolicy;
+    use sptl_spi::symbol::Classification;
+    let policy = ReinforcementPolicy { base: 0.4, min_similarity: 0.5, recency_bonus: 0.2, recency_half_life: 2.0 };
+    let mut agent = Agent::new("a", 16, 0.1).with_reinforcement(policy);
+    let s = agent.express_symbol("foo", Pattern::new("1010"), 0);
+    agent.memory.traces[0].stability = 0.0;
+
+    // Three of four characters match, two τ after expression: 0.4 * 0.75 + 0.2 * 0.5.
+    let close = Symbol::new("foo", Pattern::new("1011"));
+    let meaning = agent.interpret_symbol(&close, 2).unwrap();
+    assert_eq!(meaning.interpretant.classification, Classification::Approximate);
+    assert!((agent.memory.traces[0].stability - 0.4).abs() < 1e-9);
+
+    // Below the similarity floor the sign is not understood at all.
+    assert!(agent.interpret_symbol(&Symbol::new("foo", Pattern::new("0101")), 2).is_none());
+
+    // The default policy keeps the exact-match, flat +0.1 behavior.
+    let mut strict = Agent::new("b", 16, 0.1);
+    strict.express_symbol("foo", Pattern::new("1010"), 0);
+    assert!(strict.interpret_symbol(&close, 2).is_none());
+    assert!((strict.score_interpretation(&s, 7).unwrap() - 0.1).abs() < 1e-9);
+}
+
+#[test]
+fn test_consensus_counts_stable_members_understanding_one_pattern() {
+    use sptl_spi::agents::ReinforcementPolicy;
+    let lenient = ReinforcementPolicy { min_similarity: 0.75, ..ReinforcementPolicy::default() };
+    let mut agents: Vec<Agent> = (0..4).map(|i| Agent::new(format!("a{}", i), 16, 0.5).with_reinforcement(lenient)).collect();
+    for (agent, pattern) in agents.iter_mut().zip(["1010", "1011", "0101", "1010"]) {
+        agent.express_symbol("fire", Pattern::new(pattern), 0);
+    }
+    // a3's trace has decayed below its coherence threshold: not stable.
+    agents[3].memory.traces[0].stability = 0.2;
+    let members: Vec<Option<&Agent>> = agents.iter().map(Some).chain([None]).collect();
+    let c = symmetry::consensus(&members, "fire", symmetry::CONSENSUS_WINDOW);
+    assert_eq!((c.agreeing, c.members), (2, 5));
+    assert!((c.share() - 0.4).abs() < 1e-9);
+    assert!(!symmetry::interprets_stably(&agents[3], "fire", symmetry::CONSENSUS_WINDOW));
+}
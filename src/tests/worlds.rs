@@ -0,0 +1,73 @@
+use sptl_spi::narrative::parser::parse_script;
+use sptl_spi::narrative::runner::{execute_script, ScriptContext};
+use sptl_spi::narrative::world::diff;
+use std::sync::Arc;
+
+#[test]
+fn test_world_fork_explores_two_futures() {
+    let script = "\
+at τ=0:
+  create agent alice 16 0.1
+  field F 4
+  interpretation I = 1 1 1 1
+  alice says: fire → 1010
+  world fork as W2
+at τ=5:
+  shock F indices [0..4] value 1.0
+  measure d = distance(F, I)
+  world switch W2
+  alice says: water → 0110
+  measure d = distance(F, I)
+";
+    let mut ctx = ScriptContext::default();
+    execute_script(&parse_script(script), &mut ctx);
+
+    assert_eq!(ctx.world_name, "W2");
+    let main = &ctx.worlds["main"];
+    // The fork kept the past: both worlds know fire, only W2 heard water.
+    assert_eq!(main.agents["alice"].memory, vec!["fire".to_string()]);
+    assert_eq!(ctx.world.agents["alice"].memory, vec!["fire".to_string(), "water".to_string()]);
+    // Only main was shocked onto the interpretation.
+    assert_eq!(main.measurements["d"], 0.0);
+    assert_eq!(ctx.world.measurements["d"], 2.0);
+
+    let lines = diff(main, &ctx.world);
+    assert!(lines.iter().any(|l| l.starts_with("d: 0.0000 → 2.0000")));
+    assert!(lines.iter().any(|l| l.starts_with("field F")));
+    assert!(lines.iter().any(|l| l.starts_with("agent alice: 1 → 2")));
+
+    // Untouched state stays shared between the worlds.
+    let mut again = ScriptContext::default();
+    execute_script(&parse_script("at τ=0:\n  field G 4\n  world fork as W3\n"), &mut again);
+    assert!(Arc::ptr_eq(&again.world.fields["G"], &again.worlds["W3"].fields["G"]));
+}
+
+#[test]
+fn test_worlds_are_isolated_unless_bridged() {
+    let script = "\
+at τ=0:
+  field F 4
+  shock F indices [0..4] value 2.0
+  world create W2
+  world switch W2
+  field G 4
+  world fork as W3
+  world switch main
+  bridge F@main -> G@W2 strength 0.5
+at τ=1:
+  tick 1
+";
+    let mut ctx = ScriptContext::default();
+    execute_script(&parse_script(script), &mut ctx);
+    // Half way on declaration, half of the rest on the tick.
+    assert_eq!(ctx.worlds["W2"].fields["G"].state, vec![1.5; 4]);
+    // W3 forked before the bridge and is not coupled to anything.
+    assert_eq!(ctx.worlds["W3"].fields["G"].state, vec![0.0; 4]);
+    assert_eq!(ctx.world.fields["F"].state, vec![2.0; 4]);
+
+    // A fork continues with noise of its own.
+    let script = "at τ=0:\n  field F 64\n  world fork as W2\n  perturb F noise 1.0\n  world switch W2\n  perturb F noise 1.0\n";
+    let mut ctx = ScriptContext::default();
+    execute_script(&parse_script(script), &mut ctx);
+    assert_ne!(ctx.world.fields["F"].state, ctx.worlds["main"].fields["F"].state);
+}
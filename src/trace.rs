@@ -18,4 +18,17 @@ pub fn coherence(a: &[f64], b: &[f64]) -> f64 {
     } else {
         dot / (mag_a * mag_b)
     }
-}
\ No newline at end of file
+}
+/// Sum of absolute differences (L1 distance).
+pub fn manhattan(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y).abs()).sum()
+}
+
+/// Root mean squared difference; 0 for empty states.
+pub fn rmse(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len().min(b.len());
+    if n == 0 {
+        return 0.0;
+    }
+    (a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum::<f64>() / n as f64).sqrt()
+}
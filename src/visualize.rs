@@ -1,4 +1,25 @@
+use crate::say;
+
 pub fn print_vector(name: &str, vec: &[f64]) {
     let body = vec.iter().map(|v| format!("{:.2}", v)).collect::<Vec<_>>().join(", ");
-    println!("{} = [{}]", name, body);
-}
\ No newline at end of file
+    say!("{} = [{}]", name, body);
+}
+/// Render a series as a small ASCII line plot (10 rows tall).
+pub fn render_plot(name: &str, values: &[f64]) -> String {
+    const ROWS: usize = 10;
+    let mut out = format!("{} ({} samples)\n", name, values.len());
+    if values.is_empty() {
+        return out;
+    }
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let span = if max > min { max - min } else { 1.0 };
+    for row in (0..ROWS).rev() {
+        let level = min + span * row as f64 / (ROWS - 1) as f64;
+        let line: String = values.iter()
+            .map(|v| if ((v - min) / span * (ROWS - 1) as f64).round() as usize == row { '*' } else { ' ' })
+            .collect();
+        out.push_str(&format!("{:>10.4} |{}\n", level, line));
+    }
+    out
+}